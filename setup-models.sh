@@ -31,7 +31,7 @@ echo "🚀 To run the API:"
 echo "   cargo run"
 echo ""
 echo "🧪 To test PII detection:"
-echo "   curl -X POST http://localhost:8000/api/pii/detect \\"
+echo "   curl -X POST http://localhost:8000/v1/pii/detect \\"
 echo "     -H 'Content-Type: application/json' \\"
 echo "     -d '{\"text\": \"My name is John Doe and my email is john@example.com\"}'"
 echo ""
@@ -0,0 +1,130 @@
+// Joining of model spans the token-level decoder split: the model sometimes
+// labels "John" and "Doe" as two person spans instead of "John Doe". Spans of
+// the same label separated by at most a few characters of whitespace or
+// punctuation are joined into one entity covering both.
+
+use std::str::FromStr;
+
+use crate::PiiEntity;
+
+// Confidence of a joined span, from the confidences of its parts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JoinedScore {
+    // The weakest part, so joining never makes a finding more certain
+    #[default]
+    Min,
+    Mean,
+}
+
+impl FromStr for JoinedScore {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "min" => Ok(JoinedScore::Min),
+            "mean" => Ok(JoinedScore::Mean),
+            other => Err(format!("unknown joined score {:?}, expected min or mean", other)),
+        }
+    }
+}
+
+// Whether the text between two spans can be bridged: at most `max_gap`
+// characters, none of them letters, digits or line breaks
+fn bridgeable(gap: &str, max_gap: usize) -> bool {
+    gap.chars().count() <= max_gap && gap.chars().all(|c| !c.is_alphanumeric() && c != '\n' && c != '\r')
+}
+
+// Join adjacent spans of the same label in `text`. The joined entity takes its
+// text from `text`, the attributes of all parts, and a confidence per `score`.
+pub fn join(text: &str, entities: &mut Vec<PiiEntity>, max_gap: usize, score: JoinedScore) {
+    entities.sort_by(|a, b| (&a.label, a.start, a.end).cmp(&(&b.label, b.start, b.end)));
+    // Joined entities with the number of parts and sum of their confidences
+    let mut joined: Vec<(PiiEntity, usize, f32)> = Vec::with_capacity(entities.len());
+    for entity in entities.drain(..) {
+        if let Some((last, parts, sum)) = joined.last_mut() {
+            let adjacent = last.label == entity.label
+                && last.end <= entity.start
+                && text.get(last.end..entity.start).is_some_and(|gap| bridgeable(gap, max_gap));
+            if adjacent {
+                last.end = entity.end;
+                last.confidence = last.confidence.min(entity.confidence);
+                last.attributes.extend(entity.attributes);
+                *parts += 1;
+                *sum += entity.confidence;
+                continue;
+            }
+        }
+        let confidence = entity.confidence;
+        joined.push((entity, 1, confidence));
+    }
+
+    entities.extend(joined.into_iter().map(|(mut entity, parts, sum)| {
+        if parts > 1 {
+            if let Some(span) = text.get(entity.start..entity.end) {
+                entity.text = span.to_string();
+            }
+            if score == JoinedScore::Mean {
+                entity.confidence = sum / parts as f32;
+            }
+        }
+        entity
+    }));
+    entities.sort_by(|a, b| (a.start, a.end, &a.label).cmp(&(b.start, b.end, &b.label)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_in;
+
+    #[test]
+    fn test_joins_split_names() {
+        let text = "Ask John Doe or mail jane@example.com";
+        let mut entities = vec![
+            entity_in(text, "Doe", "person", 0.7),
+            entity_in(text, "jane@example.com", "email", 0.9),
+            entity_in(text, "John", "person", 0.9),
+        ];
+        join(text, &mut entities, 1, JoinedScore::Min);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!((entities[0].text.as_str(), entities[0].start, entities[0].end), ("John Doe", 4, 12));
+        assert_eq!(entities[0].confidence, 0.7);
+        assert_eq!(entities[1].label, "email");
+    }
+
+    #[test]
+    fn test_mean_score() {
+        let text = "John Doe";
+        let mut entities = vec![entity_in(text, "John", "person", 0.9), entity_in(text, "Doe", "person", 0.7)];
+        join(text, &mut entities, 1, JoinedScore::Mean);
+        assert!((entities[0].confidence - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_keeps_spans_apart_across_words_labels_and_lines() {
+        let text = "John and Jane Roe\nDoe";
+        let mut entities = vec![
+            entity_in(text, "John", "person", 0.9),
+            entity_in(text, "Jane", "person", 0.9),
+            entity_in(text, "Roe", "organization", 0.9),
+            entity_in(text, "Doe", "person", 0.9),
+        ];
+        join(text, &mut entities, 5, JoinedScore::Min);
+        assert_eq!(entities.len(), 4);
+    }
+
+    #[test]
+    fn test_gap_limit() {
+        let text = "555 - 0100";
+        let parts = || vec![entity_in(text, "555", "phone", 0.8), entity_in(text, "0100", "phone", 0.8)];
+
+        let mut entities = parts();
+        join(text, &mut entities, 2, JoinedScore::Min);
+        assert_eq!(entities.len(), 2);
+
+        let mut entities = parts();
+        join(text, &mut entities, 3, JoinedScore::Min);
+        assert_eq!(entities[0].text, "555 - 0100");
+    }
+}
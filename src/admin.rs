@@ -0,0 +1,134 @@
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::inference::InferenceQueue;
+use crate::model_info::ModelInfo;
+use crate::pool::PoolSnapshot;
+
+// Grants access to /api/admin routes. When GLINER_ADMIN_TOKEN is set, requests
+// must carry it as `Authorization: Bearer <token>`; otherwise admin routes are
+// open and should be kept off public networks.
+pub struct AdminAuth;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminAuth {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let expected = request
+            .rocket()
+            .state::<Config>()
+            .and_then(|config| config.admin_token.as_deref());
+        let Some(expected) = expected else {
+            return request::Outcome::Success(AdminAuth);
+        };
+
+        let presented = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match presented {
+            Some(token) if constant_time_eq(token.trim().as_bytes(), expected.as_bytes()) => {
+                request::Outcome::Success(AdminAuth)
+            }
+            _ => request::Outcome::Error((Status::Unauthorized, ApiError::Unauthorized)),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrainStatus {
+    pub draining: bool,
+    // Whether new detection requests are still admitted
+    pub accepting: bool,
+    // Seconds of grace left before new detection requests are refused
+    pub grace_remaining_seconds: u64,
+    // Detection requests waiting for or running inference
+    pub in_flight: usize,
+}
+
+impl DrainStatus {
+    pub fn of(queue: &InferenceQueue) -> Self {
+        let remaining = queue
+            .drain_deadline()
+            .map(|deadline| deadline.saturating_duration_since(queue.clock().now()).as_secs())
+            .unwrap_or(0);
+        DrainStatus {
+            draining: queue.is_draining(),
+            accepting: queue.accepting(),
+            grace_remaining_seconds: remaining,
+            in_flight: queue.depth(),
+        }
+    }
+}
+
+// Served by GET /api/admin/stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStats {
+    pub uptime_secs: u64,
+    // Resident set size of the process; None where /proc is not available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rss_bytes: Option<u64>,
+    // Estimated from the model files: every session holds its own copy of the
+    // ONNX weights. None while no model is loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_memory_bytes: Option<u64>,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    // Model sessions currently checked out
+    pub in_flight_inferences: usize,
+}
+
+impl AdminStats {
+    pub fn of(uptime_secs: u64, queue: &InferenceQueue, pool: Option<PoolSnapshot>, info: Option<&ModelInfo>) -> Self {
+        AdminStats {
+            uptime_secs,
+            rss_bytes: process_rss(),
+            model_memory_bytes: info.map(model_footprint),
+            queue_depth: queue.depth(),
+            queue_capacity: queue.capacity(),
+            in_flight_inferences: pool.map(|pool| pool.size - pool.idle).unwrap_or(0),
+        }
+    }
+}
+
+pub fn model_footprint(info: &ModelInfo) -> u64 {
+    info.onnx.size_bytes * info.sessions.max(1) as u64 + info.tokenizer.size_bytes
+}
+
+fn process_rss() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status").ok().as_deref().and_then(parse_vm_rss)
+}
+
+// The `VmRSS:   123456 kB` line of /proc/self/status, in bytes
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.trim_start_matches("VmRSS:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tgliner-api\nVmPeak:\t  912340 kB\nVmRSS:\t  524288 kB\nThreads:\t12\n";
+        assert_eq!(parse_vm_rss(status), Some(512 * 1024 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tgliner-api\n"), None);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+}
@@ -0,0 +1,201 @@
+// Caller-facing labels mapped onto the labels the model and recognizers know,
+// so API consumers can use their own taxonomy: with GLINER_LABEL_ALIASES set to
+// "customer_name=person,msisdn=phone number", a request for "customer_name" has
+// the model look for "person" and gets its findings back as "customer_name".
+// GLiNER also does better with some phrasings than others, so the model can be
+// prompted with a richer phrase per label (GLINER_LABEL_PROMPTS, or a request's
+// `label_prompts`), e.g. "email address" for "email".
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::PiiEntity;
+
+// "label=value", both sides trimmed and non-empty
+fn pair(value: &str, expected: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((label, target)) if !label.trim().is_empty() && !target.trim().is_empty() => {
+            Ok((label.trim().to_string(), target.trim().to_string()))
+        }
+        _ => Err(format!("invalid entry {:?}, expected {}", value, expected)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelAlias {
+    pub label: String,
+    pub target: String,
+}
+
+impl FromStr for LabelAlias {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (label, target) = pair(value, "label=model label")?;
+        Ok(LabelAlias { label, target })
+    }
+}
+
+// The phrase the model is prompted with for a label, e.g. "email=email address";
+// findings are still reported under the label
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelPrompt {
+    pub label: String,
+    pub prompt: String,
+}
+
+impl FromStr for LabelPrompt {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (label, prompt) = pair(value, "label=prompt")?;
+        Ok(LabelPrompt { label, prompt })
+    }
+}
+
+// A requested label, the label it stands for and what the model is asked for
+#[derive(Debug, Clone)]
+struct Entry {
+    label: String,
+    target: String,
+    prompt: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LabelMap {
+    entries: Vec<Entry>,
+}
+
+impl LabelMap {
+    pub fn new(labels: &[&str], aliases: &[LabelAlias]) -> Self {
+        let entries = labels
+            .iter()
+            .map(|label| {
+                let target = aliases.iter().find(|alias| alias.label == *label).map_or(*label, |alias| alias.target.as_str());
+                Entry {
+                    label: label.to_string(),
+                    target: target.to_string(),
+                    prompt: target.to_string(),
+                }
+            })
+            .collect();
+        LabelMap { entries }
+    }
+
+    // Prompt the model with these phrases instead of the labels. `overrides`
+    // take precedence over `prompts`; either can be keyed by the requested
+    // label or the label it stands for.
+    pub fn with_prompts(mut self, prompts: &[LabelPrompt], overrides: Option<&HashMap<String, String>>) -> Self {
+        for entry in &mut self.entries {
+            let configured = |key: &str| prompts.iter().find(|prompt| prompt.label == key).map(|prompt| &prompt.prompt);
+            let prompt = [&entry.label, &entry.target]
+                .iter()
+                .find_map(|key| overrides.and_then(|overrides| overrides.get(key.as_str())))
+                .or_else(|| [&entry.label, &entry.target].iter().find_map(|key| configured(key)));
+            if let Some(prompt) = prompt.filter(|prompt| !prompt.trim().is_empty()) {
+                entry.prompt = prompt.trim().to_string();
+            }
+        }
+        self
+    }
+
+    // The aliases and prompts configured in GLINER_LABEL_ALIASES and
+    // GLINER_LABEL_PROMPTS, with a request's own prompts on top
+    pub fn resolve(labels: &[&str], config: &Config, overrides: Option<&HashMap<String, String>>) -> Self {
+        LabelMap::new(labels, &config.label_aliases).with_prompts(&config.label_prompts, overrides)
+    }
+
+    // Labels to run recognizers with, without duplicates
+    pub fn labels(&self) -> Vec<&str> {
+        unique(self.entries.iter().map(|entry| entry.target.as_str()))
+    }
+
+    // What to prompt the model with, without duplicates
+    pub fn prompts(&self) -> Vec<&str> {
+        unique(self.entries.iter().map(|entry| entry.prompt.as_str()))
+    }
+
+    // Put findings reported under a requested label or a prompt back under
+    // the label they stand for
+    pub fn canonicalize(&self, entities: &mut [PiiEntity]) {
+        for entity in entities {
+            let entry = self
+                .entries
+                .iter()
+                .find(|entry| entry.label == entity.label)
+                .or_else(|| self.entries.iter().find(|entry| entry.prompt == entity.label));
+            if let Some(entry) = entry {
+                entity.label = entry.target.clone();
+            }
+        }
+    }
+
+    // Report findings under the label that was requested; when several
+    // requested labels stand for the same one, the first of them
+    pub fn restore(&self, entities: &mut [PiiEntity]) {
+        for entity in entities {
+            let entry = self
+                .entries
+                .iter()
+                .find(|entry| entry.target == entity.label)
+                .or_else(|| self.entries.iter().find(|entry| entry.prompt == entity.label));
+            if let Some(entry) = entry {
+                entity.label = entry.label.clone();
+            }
+        }
+    }
+}
+
+fn unique<'a>(items: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen: Vec<&str> = Vec::new();
+    for item in items {
+        if !seen.contains(&item) {
+            seen.push(item);
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_aliases_apply_on_input_and_reverse_on_output() {
+        let aliases: Vec<LabelAlias> = vec!["customer_name=person".parse().unwrap(), "msisdn = phone number".parse().unwrap()];
+        let map = LabelMap::new(&["customer_name", "email", "msisdn", "person"], &aliases);
+        assert_eq!(map.labels(), vec!["person", "email", "phone number"]);
+
+        let mut entities = vec![entity("person", 0, 4, 0.9), entity("phone number", 0, 4, 0.9), entity("email", 0, 4, 0.9), entity("date of birth", 0, 4, 0.9)];
+        map.restore(&mut entities);
+        let labels: Vec<&str> = entities.iter().map(|entity| entity.label.as_str()).collect();
+        assert_eq!(labels, vec!["customer_name", "msisdn", "email", "date of birth"]);
+
+        map.canonicalize(&mut entities);
+        assert_eq!(entities[1].label, "phone number");
+    }
+
+    #[test]
+    fn test_prompts_are_sent_to_the_model_and_reported_as_labels() {
+        let prompts: Vec<LabelPrompt> = vec!["email=email address".parse().unwrap(), "person=full name of a person".parse().unwrap()];
+        let aliases: Vec<LabelAlias> = vec!["customer_name=person".parse().unwrap()];
+        let overrides = HashMap::from([("customer_name".to_string(), "customer's name".to_string())]);
+        let map = LabelMap::new(&["email", "customer_name", "phone"], &aliases).with_prompts(&prompts, Some(&overrides));
+        assert_eq!(map.prompts(), vec!["email address", "customer's name", "phone"]);
+        assert_eq!(map.labels(), vec!["email", "person", "phone"]);
+
+        let mut entities = vec![entity("email address", 0, 4, 0.9), entity("customer's name", 0, 4, 0.9)];
+        map.canonicalize(&mut entities);
+        assert_eq!((entities[0].label.as_str(), entities[1].label.as_str()), ("email", "person"));
+        map.restore(&mut entities);
+        assert_eq!(entities[1].label, "customer_name");
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_target() {
+        assert!("customer_name".parse::<LabelAlias>().is_err());
+        assert!("customer_name=".parse::<LabelAlias>().is_err());
+    }
+}
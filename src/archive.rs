@@ -0,0 +1,259 @@
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use crate::error::ApiError;
+use crate::extract::{self, DocumentFormat, ExtractedText};
+
+// Archive formats the file scan endpoint unpacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let lower = filename.to_ascii_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            return Some(ArchiveFormat::TarGz);
+        }
+        match Path::new(&lower).extension()?.to_str()? {
+            "zip" => Some(ArchiveFormat::Zip),
+            "tar" => Some(ArchiveFormat::Tar),
+            _ => None,
+        }
+    }
+}
+
+// Bounds on what is unpacked from one upload, to contain archive bombs
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+    pub max_members: usize,
+    // Largest single member read; bigger ones are skipped
+    pub max_member_bytes: u64,
+    // Most bytes unpacked from the whole archive
+    pub max_total_bytes: u64,
+}
+
+// A member whose text was extracted
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveMember {
+    pub path: String,
+    pub format: DocumentFormat,
+    pub text: ExtractedText,
+}
+
+// A member that was not scanned, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedMember {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveContents {
+    pub members: Vec<ArchiveMember>,
+    pub skipped: Vec<SkippedMember>,
+}
+
+impl ArchiveContents {
+    // Extract a member's text, or record why it is skipped
+    fn add(&mut self, path: String, bytes: Vec<u8>) {
+        let skip = |reason: String| SkippedMember { path: path.clone(), reason };
+        let Some(format) = DocumentFormat::from_filename(&path) else {
+            let reason = match ArchiveFormat::from_filename(&path) {
+                Some(_) => "nested archives are not scanned",
+                None => "unsupported file type",
+            };
+            self.skipped.push(skip(reason.to_string()));
+            return;
+        };
+        match extract::extract_text(format, bytes) {
+            Ok(text) => self.members.push(ArchiveMember { path, format, text }),
+            Err(ApiError::InvalidInput(message)) => self.skipped.push(skip(message)),
+            Err(e) => self.skipped.push(skip(e.to_string())),
+        }
+    }
+}
+
+// Tracks the limits while members are read
+struct Budget {
+    limits: ArchiveLimits,
+    members: usize,
+    total_bytes: u64,
+}
+
+impl Budget {
+    fn count_member(&mut self) -> Result<(), ApiError> {
+        self.members += 1;
+        if self.members > self.limits.max_members {
+            return Err(ApiError::PayloadTooLarge(format!(
+                "archive has more than {} members",
+                self.limits.max_members
+            )));
+        }
+        Ok(())
+    }
+
+    // Read a member, up to the per-member limit; None when it is larger
+    fn read(&mut self, reader: impl Read) -> Result<Option<Vec<u8>>, ApiError> {
+        let mut bytes = Vec::new();
+        reader
+            .take(self.limits.max_member_bytes + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| ApiError::InvalidInput(format!("failed to read archive member: {}", e)))?;
+        self.total_bytes += bytes.len() as u64;
+        if self.total_bytes > self.limits.max_total_bytes {
+            return Err(ApiError::PayloadTooLarge(format!(
+                "archive unpacks to more than {} bytes",
+                self.limits.max_total_bytes
+            )));
+        }
+        Ok((bytes.len() as u64 <= self.limits.max_member_bytes).then_some(bytes))
+    }
+}
+
+// Unpack an archive and extract the text of every supported member. Members
+// that can't be scanned are listed with a reason instead of failing the upload;
+// exceeding the member count or total size limit does fail it.
+pub fn read_archive(format: ArchiveFormat, bytes: &[u8], limits: ArchiveLimits) -> Result<ArchiveContents, ApiError> {
+    let mut budget = Budget {
+        limits,
+        members: 0,
+        total_bytes: 0,
+    };
+    match format {
+        ArchiveFormat::Zip => read_zip(bytes, &mut budget),
+        ArchiveFormat::Tar => read_tar(Cursor::new(bytes), &mut budget),
+        ArchiveFormat::TarGz => read_tar(GzDecoder::new(Cursor::new(bytes)), &mut budget),
+    }
+}
+
+fn too_large(limits: &ArchiveLimits) -> String {
+    format!("member is larger than {} bytes", limits.max_member_bytes)
+}
+
+fn read_zip(bytes: &[u8], budget: &mut Budget) -> Result<ArchiveContents, ApiError> {
+    let invalid = |e: zip::result::ZipError| ApiError::InvalidInput(format!("failed to read zip archive: {}", e));
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(invalid)?;
+    let mut contents = ArchiveContents::default();
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(invalid)?;
+        if entry.is_dir() {
+            continue;
+        }
+        budget.count_member()?;
+        let path = entry.name().to_string();
+        match budget.read(entry)? {
+            Some(bytes) => contents.add(path, bytes),
+            None => contents.skipped.push(SkippedMember { path, reason: too_large(&budget.limits) }),
+        }
+    }
+    Ok(contents)
+}
+
+fn read_tar(reader: impl Read, budget: &mut Budget) -> Result<ArchiveContents, ApiError> {
+    let invalid = |e: std::io::Error| ApiError::InvalidInput(format!("failed to read tar archive: {}", e));
+    let mut archive = tar::Archive::new(reader);
+    let mut contents = ArchiveContents::default();
+    for entry in archive.entries().map_err(invalid)? {
+        let entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        budget.count_member()?;
+        let path = entry.path().map_err(invalid)?.to_string_lossy().into_owned();
+        match budget.read(entry)? {
+            Some(bytes) => contents.add(path, bytes),
+            None => contents.skipped.push(SkippedMember { path, reason: too_large(&budget.limits) }),
+        }
+    }
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const LIMITS: ArchiveLimits = ArchiveLimits {
+        max_members: 10,
+        max_member_bytes: 1024,
+        max_total_bytes: 4096,
+    };
+
+    fn zip_of(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_of(files: &[(&str, &str)]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_archive_format_from_filename() {
+        assert_eq!(ArchiveFormat::from_filename("export.ZIP"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_filename("dump.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_filename("dump.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_filename("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_zip_members_extracted_or_skipped() {
+        let bytes = zip_of(&[("a/notes.txt", "Jane Roe"), ("setup.exe", "MZ"), ("inner.zip", "PK")]);
+        let contents = read_archive(ArchiveFormat::Zip, &bytes, LIMITS).unwrap();
+
+        assert_eq!(contents.members.len(), 1);
+        assert_eq!(contents.members[0].path, "a/notes.txt");
+        assert_eq!(contents.members[0].text.sections, vec!["Jane Roe"]);
+        let reasons: Vec<&str> = contents.skipped.iter().map(|skipped| skipped.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["unsupported file type", "nested archives are not scanned"]);
+    }
+
+    #[test]
+    fn test_tar_gz_members() {
+        let bytes = tar_gz_of(&[("one.md", "# Jane"), ("two.txt", "John Doe")]);
+        let contents = read_archive(ArchiveFormat::TarGz, &bytes, LIMITS).unwrap();
+        let paths: Vec<&str> = contents.members.iter().map(|member| member.path.as_str()).collect();
+        assert_eq!(paths, vec!["one.md", "two.txt"]);
+    }
+
+    #[test]
+    fn test_limits() {
+        let big = "x".repeat(2000);
+        let contents = read_archive(ArchiveFormat::Zip, &zip_of(&[("big.txt", &big)]), LIMITS).unwrap();
+        assert_eq!(contents.skipped[0].reason, "member is larger than 1024 bytes");
+
+        let many: Vec<(String, &str)> = (0..11).map(|index| (format!("{}.txt", index), "a")).collect();
+        let many: Vec<(&str, &str)> = many.iter().map(|(name, content)| (name.as_str(), *content)).collect();
+        assert!(matches!(
+            read_archive(ArchiveFormat::Zip, &zip_of(&many), LIMITS),
+            Err(ApiError::PayloadTooLarge(_))
+        ));
+
+        let files = [("1.txt", "a".repeat(1000)), ("2.txt", "b".repeat(1000)), ("3.txt", "c".repeat(1000)), ("4.txt", "d".repeat(1000)), ("5.txt", "e".repeat(1000))];
+        let files: Vec<(&str, &str)> = files.iter().map(|(name, content)| (*name, content.as_str())).collect();
+        assert!(matches!(
+            read_archive(ArchiveFormat::Zip, &zip_of(&files), LIMITS),
+            Err(ApiError::PayloadTooLarge(_))
+        ));
+    }
+}
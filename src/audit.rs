@@ -0,0 +1,408 @@
+// Audit trail of detection calls: one event per call with who called, what was
+// found and how long it took, as evidence of what was scanned and when. Events
+// never hold submitted text or finding text, only counts and a hash of the
+// findings' labels and offsets.
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::request::{self, FromRequest, Request};
+use rocket::Response;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::logging::{self, RequestId};
+use crate::model_info::unix_now;
+use crate::shadow::ShadowDiff;
+use crate::PiiEntity;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    // Unix seconds when the response was sent
+    pub timestamp: u64,
+    pub request_id: String,
+    pub method: String,
+    // Matched route, e.g. "/v1/pii/detect?<fields>"
+    pub route: String,
+    pub status: u16,
+    // Fingerprint of the credential the caller presented, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<String>,
+    pub latency_ms: f64,
+    // None when the call failed, or its results were streamed after the response started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub findings: Option<AuditFindings>,
+    // Set on the separate event recorded when the shadow model disagreed with
+    // the primary on this request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<ShadowDiff>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditFindings {
+    pub documents: usize,
+    pub total_entities: usize,
+    pub label_counts: BTreeMap<String, usize>,
+    // SHA-256 over every finding's document index, label and offsets, to show
+    // later that a stored result is the one that was returned
+    pub result_sha256: String,
+}
+
+impl AuditFindings {
+    // Summary of per-document findings, in document order
+    pub fn of<'a, D>(documents: impl IntoIterator<Item = D>) -> Self
+    where
+        D: IntoIterator<Item = &'a PiiEntity>,
+    {
+        let mut findings = AuditFindings::default();
+        let mut hasher = Sha256::new();
+        for (index, entities) in documents.into_iter().enumerate() {
+            findings.documents += 1;
+            for entity in entities {
+                findings.total_entities += 1;
+                *findings.label_counts.entry(entity.label.clone()).or_default() += 1;
+                hasher.update(format!("{}\t{}\t{}\t{}\n", index, entity.label, entity.start, entity.end));
+            }
+        }
+        findings.result_sha256 = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+        findings
+    }
+}
+
+// Destination for audit events. A sink failing is logged and does not fail the call.
+pub trait AuditSink: Send + Sync {
+    fn write(&self, event: &AuditEvent) -> io::Result<()>;
+
+    // Delete events from before `cutoff` (Unix seconds), returning how many were
+    // removed. Sinks that hand events off, like stdout, keep nothing to delete.
+    fn purge(&self, _cutoff: u64) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+// One JSON object per line on stdout, for collectors tailing the container output
+pub struct StdoutSink;
+
+impl AuditSink for StdoutSink {
+    fn write(&self, event: &AuditEvent) -> io::Result<()> {
+        let line = serde_json::to_string(event)?;
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", line)?;
+        stdout.flush()
+    }
+}
+
+// One JSON object per line appended to a file
+pub struct FileSink {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl FileSink {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(FileSink { path, file: Mutex::new(file) })
+    }
+}
+
+impl AuditSink for FileSink {
+    fn write(&self, event: &AuditEvent) -> io::Result<()> {
+        let line = serde_json::to_string(event)?;
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", line)
+    }
+
+    // Rewrite the file without the expired events. Writes wait meanwhile; lines
+    // that cannot be read as events are kept.
+    fn purge(&self, cutoff: u64) -> io::Result<u64> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let contents = std::fs::read_to_string(&self.path)?;
+        let mut kept = String::with_capacity(contents.len());
+        let mut removed = 0;
+        for line in contents.lines() {
+            match serde_json::from_str::<AuditEvent>(line) {
+                Ok(event) if event.timestamp < cutoff => removed += 1,
+                _ => {
+                    kept.push_str(line);
+                    kept.push('\n');
+                }
+            }
+        }
+        if removed > 0 {
+            let temporary = self.path.with_extension("purge");
+            std::fs::write(&temporary, kept)?;
+            std::fs::rename(&temporary, &self.path)?;
+            *file = OpenOptions::new().append(true).open(&self.path)?;
+        }
+        Ok(removed)
+    }
+}
+
+// The configured sinks, plus the most recent events kept in memory for the
+// admin query endpoint
+pub struct AuditLog {
+    sinks: Vec<Box<dyn AuditSink>>,
+    recent: Mutex<VecDeque<AuditEvent>>,
+    capacity: usize,
+}
+
+pub type AuditState = Arc<AuditLog>;
+
+impl AuditLog {
+    pub fn new(sinks: Vec<Box<dyn AuditSink>>, capacity: usize) -> Self {
+        AuditLog {
+            sinks,
+            recent: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+            capacity,
+        }
+    }
+
+    // Audit log for GLINER_AUDIT_SINK: "off", "memory" (recent events only),
+    // "stdout" or "file" (appending to GLINER_AUDIT_FILE)
+    pub fn from_config(config: &Config) -> Result<Option<Self>, String> {
+        let sink: Box<dyn AuditSink> = match config.audit_sink.as_str() {
+            "off" => return Ok(None),
+            "memory" => return Ok(Some(AuditLog::new(Vec::new(), config.audit_recent))),
+            "stdout" => Box::new(StdoutSink),
+            "file" => {
+                let path = config
+                    .audit_file
+                    .as_deref()
+                    .ok_or_else(|| "GLINER_AUDIT_SINK=file needs GLINER_AUDIT_FILE".to_string())?;
+                Box::new(FileSink::open(path).map_err(|e| format!("could not open audit file {}: {}", path, e))?)
+            }
+            "database" => return Err("GLINER_AUDIT_SINK=database needs the persistence feature".to_string()),
+            other => return Err(format!("unknown audit sink {:?}, expected off, memory, stdout, file or database", other)),
+        };
+        Ok(Some(AuditLog::new(vec![sink], config.audit_recent)))
+    }
+
+    pub fn record(&self, event: AuditEvent) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.write(&event) {
+                tracing::error!(request_id = %event.request_id, error = %e, "Failed to write audit event");
+            }
+        }
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == self.capacity {
+            recent.pop_front();
+        }
+        if self.capacity > 0 {
+            recent.push_back(event);
+        }
+    }
+
+    // Drop events from before `cutoff` (Unix seconds) from memory and the sinks,
+    // returning how many were removed (memory holds copies of the newest ones)
+    pub fn purge(&self, cutoff: u64) -> io::Result<u64> {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let before = recent.len();
+        recent.retain(|event| event.timestamp >= cutoff);
+        let mut removed = (before - recent.len()) as u64;
+        drop(recent);
+        for sink in &self.sinks {
+            removed = removed.max(sink.purge(cutoff)?);
+        }
+        Ok(removed)
+    }
+
+    // Newest first, optionally only those at or after `since` (Unix seconds)
+    pub fn recent(&self, limit: usize, since: Option<u64>) -> Vec<AuditEvent> {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent
+            .iter()
+            .rev()
+            .filter(|event| since.is_none_or(|since| event.timestamp >= since))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+// Marks a request as a detection call to audit. Handlers take it as a guard and
+// report what they found; the `AuditRecorder` fairing writes the event once the
+// response status is known.
+#[derive(Default)]
+pub struct AuditTrail {
+    // Set when a handler took the guard; requests to other routes are not audited
+    requested: AtomicBool,
+    // Set when the findings themselves are persisted, not just their summary
+    keep_entities: AtomicBool,
+    // Set for calls in privacy mode, whose findings are persisted without text
+    private: AtomicBool,
+    findings: Mutex<Option<AuditFindings>>,
+    entities: Mutex<Option<Vec<Vec<PiiEntity>>>>,
+}
+
+impl AuditTrail {
+    pub fn findings<'a, D>(&self, documents: impl IntoIterator<Item = D>)
+    where
+        D: IntoIterator<Item = &'a PiiEntity>,
+    {
+        let documents: Vec<Vec<&PiiEntity>> = documents.into_iter().map(|entities| entities.into_iter().collect()).collect();
+        *self.findings.lock().unwrap_or_else(|e| e.into_inner()) = Some(AuditFindings::of(documents.iter().map(|entities| entities.iter().copied())));
+        if self.keep_entities.load(Ordering::Relaxed) {
+            let owned = documents.into_iter().map(|entities| entities.into_iter().cloned().collect()).collect();
+            *self.entities.lock().unwrap_or_else(|e| e.into_inner()) = Some(owned);
+        }
+    }
+
+    // The findings reported by the handler, per document, when they are kept
+    pub fn take_entities(&self) -> Option<Vec<Vec<PiiEntity>>> {
+        self.entities.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    pub fn set_private(&self, private: bool) {
+        self.private.store(private, Ordering::Relaxed);
+    }
+
+    pub fn private(&self) -> bool {
+        self.private.load(Ordering::Relaxed)
+    }
+
+    pub fn requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+
+    pub fn summary(&self) -> Option<AuditFindings> {
+        self.findings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r AuditTrail {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let trail = request.local_cache(AuditTrail::default);
+        trail.requested.store(true, Ordering::Relaxed);
+        #[cfg(feature = "persistence")]
+        if request.rocket().state::<Option<crate::persistence::ResultStoreState>>().is_some_and(Option::is_some) {
+            trail.keep_entities.store(true, Ordering::Relaxed);
+        }
+        request::Outcome::Success(trail)
+    }
+}
+
+// Fingerprint of the bearer token or API key a request carries, so calls can be
+// attributed to a credential without storing it
+pub fn caller_key(request: &Request<'_>) -> Option<String> {
+    let headers = request.headers();
+    let credential = headers
+        .get_one("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| headers.get_one("X-Api-Key"))?;
+    Some(format!("sha256:{}", logging::text_hash(credential.trim())))
+}
+
+pub struct AuditRecorder;
+
+#[rocket::async_trait]
+impl Fairing for AuditRecorder {
+    fn info(&self) -> Info {
+        Info {
+            name: "Audit recorder",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(log) = request.rocket().state::<Option<AuditState>>().and_then(Option::as_ref) else {
+            return;
+        };
+        let trail = request.local_cache(AuditTrail::default);
+        if !trail.requested() {
+            return;
+        }
+        let success = response.status().class().is_success();
+        let findings = trail.summary();
+        log.record(AuditEvent {
+            timestamp: unix_now(),
+            request_id: RequestId::of(request).0,
+            method: request.method().to_string(),
+            route: request.route().map(|route| route.uri.to_string()).unwrap_or_default(),
+            status: response.status().code,
+            caller: caller_key(request),
+            latency_ms: logging::elapsed_ms(request),
+            findings: findings.filter(|_| success),
+            shadow: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{audit_event, entity_at};
+
+    #[test]
+    fn test_findings_summary_has_no_text() {
+        let documents = vec![
+            vec![entity_at("secret", "email", 0, 0.9), entity_at("secret", "person", 10, 0.9)],
+            vec![entity_at("secret", "email", 3, 0.9)],
+        ];
+        let findings = AuditFindings::of(&documents);
+        assert_eq!(findings.documents, 2);
+        assert_eq!(findings.total_entities, 3);
+        assert_eq!(findings.label_counts["email"], 2);
+        assert!(!serde_json::to_string(&findings).unwrap().contains("secret"));
+
+        // The hash changes with the results
+        let moved = vec![
+            vec![entity_at("secret", "email", 0, 0.9), entity_at("secret", "person", 11, 0.9)],
+            vec![entity_at("secret", "email", 3, 0.9)],
+        ];
+        assert_ne!(AuditFindings::of(&moved).result_sha256, findings.result_sha256);
+        assert_eq!(AuditFindings::of(&documents), findings);
+    }
+
+    #[test]
+    fn test_recent_events_are_bounded_and_newest_first() {
+        let log = AuditLog::new(Vec::new(), 2);
+        for timestamp in [10, 20, 30] {
+            log.record(audit_event(timestamp));
+        }
+        let recent: Vec<u64> = log.recent(10, None).iter().map(|event| event.timestamp).collect();
+        assert_eq!(recent, vec![30, 20]);
+        assert_eq!(log.recent(10, Some(25)).len(), 1);
+        assert_eq!(log.recent(1, None)[0].timestamp, 30);
+    }
+
+    #[test]
+    fn test_file_sink_appends_json_lines() {
+        let path = std::env::temp_dir().join(format!("gliner-audit-{}.jsonl", std::process::id()));
+        let sink = FileSink::open(path.to_str().unwrap()).unwrap();
+        sink.write(&audit_event(1)).unwrap();
+        sink.write(&audit_event(2)).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let events: Vec<AuditEvent> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].timestamp, 2);
+    }
+
+    #[test]
+    fn test_file_sink_purge_keeps_newer_events() {
+        let path = std::env::temp_dir().join(format!("gliner-audit-purge-{}.jsonl", std::process::id()));
+        let sink = FileSink::open(path.to_str().unwrap()).unwrap();
+        for timestamp in [10, 20, 30] {
+            sink.write(&audit_event(timestamp)).unwrap();
+        }
+        assert_eq!(sink.purge(20).unwrap(), 1);
+        // Writing still appends to the rewritten file
+        sink.write(&audit_event(40)).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let timestamps: Vec<u64> = contents
+            .lines()
+            .map(|line| serde_json::from_str::<AuditEvent>(line).unwrap().timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![20, 30, 40]);
+    }
+}
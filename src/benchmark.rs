@@ -0,0 +1,201 @@
+// Throughput and latency of the loaded model for POST /api/admin/benchmark:
+// generated texts (see `synthetic`) of each requested length go through the
+// model in batches of each requested size, straight to inference without
+// chunking, pre-pass or recognizers, so the numbers are the model's own.
+
+use gliner::model::input::text::TextInput;
+use serde::{Deserialize, Serialize};
+
+use crate::chunking::estimate_tokens;
+use crate::detection::DetectionError;
+use crate::error::ApiError;
+use crate::pool::Model;
+use crate::synthetic::{self, SeededRng};
+use crate::timing;
+
+// Limits on one run, so a benchmark can't hold a session for long
+pub const MAX_TEXT_CHARS: usize = 10_000;
+pub const MAX_BATCH_SIZE: usize = 64;
+pub const MAX_ITERATIONS: usize = 100;
+pub const MAX_CASES: usize = 32;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BenchmarkRequest {
+    // Text lengths in characters
+    pub lengths: Vec<usize>,
+    pub batch_sizes: Vec<usize>,
+    // Timed batches per length and batch size, after one untimed warmup batch
+    pub iterations: usize,
+    // Labels to detect; defaults to the default labels
+    pub labels: Option<Vec<String>>,
+    pub seed: u64,
+}
+
+impl Default for BenchmarkRequest {
+    fn default() -> Self {
+        BenchmarkRequest {
+            lengths: vec![100, 500, 1500],
+            batch_sizes: vec![1, 8],
+            iterations: 10,
+            labels: None,
+            seed: 0,
+        }
+    }
+}
+
+impl BenchmarkRequest {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.lengths.is_empty() || self.batch_sizes.is_empty() || self.iterations == 0 {
+            return Err(ApiError::InvalidInput("lengths, batch_sizes and iterations must not be empty".to_string()));
+        }
+        if let Some(length) = self.lengths.iter().find(|&&length| length == 0 || length > MAX_TEXT_CHARS) {
+            return Err(ApiError::InvalidInput(format!("length {} is out of range, expected 1 to {}", length, MAX_TEXT_CHARS)));
+        }
+        if let Some(size) = self.batch_sizes.iter().find(|&&size| size == 0 || size > MAX_BATCH_SIZE) {
+            return Err(ApiError::InvalidInput(format!("batch size {} is out of range, expected 1 to {}", size, MAX_BATCH_SIZE)));
+        }
+        if self.iterations > MAX_ITERATIONS {
+            return Err(ApiError::InvalidInput(format!("iterations is {}, the limit is {}", self.iterations, MAX_ITERATIONS)));
+        }
+        if self.lengths.len() * self.batch_sizes.len() > MAX_CASES {
+            return Err(ApiError::InvalidInput(format!("at most {} length and batch size combinations per run", MAX_CASES)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Latency {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Latency {
+    pub fn of(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(f64::total_cmp);
+        Latency {
+            p50_ms: percentile(&samples, 50.0),
+            p90_ms: percentile(&samples, 90.0),
+            p99_ms: percentile(&samples, 99.0),
+            max_ms: samples.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+// Nearest-rank percentile of sorted samples
+pub fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkCase {
+    pub length: usize,
+    pub batch_size: usize,
+    pub iterations: usize,
+    // Estimated words per text, as the GLiNER splitter counts them
+    pub tokens_per_text: f64,
+    pub texts_per_sec: f64,
+    pub tokens_per_sec: f64,
+    // Per batch
+    pub latency: Latency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub model: String,
+    pub labels: Vec<String>,
+    pub cases: Vec<BenchmarkCase>,
+    pub total_ms: f64,
+}
+
+// `count` generated texts of at most `length` characters, cut at a word break
+pub fn texts(length: usize, count: usize, seed: u64) -> Vec<String> {
+    let mut rng = SeededRng::new(seed);
+    (0..count)
+        .map(|_| {
+            let mut text = String::new();
+            while text.chars().count() < length {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(&synthetic::document(&mut rng, 4).text);
+            }
+            let cut = text.char_indices().nth(length).map_or(text.len(), |(index, _)| index);
+            let cut = match text[..cut].rfind(' ') {
+                Some(space) if cut < text.len() && space > 0 => space,
+                _ => cut,
+            };
+            text.truncate(cut);
+            text
+        })
+        .collect()
+}
+
+// Time `iterations` batches of `batch_size` texts of `length` characters
+pub fn run_case(model: &Model, length: usize, batch_size: usize, iterations: usize, labels: &[&str], seed: u64) -> Result<BenchmarkCase, DetectionError> {
+    let batch = texts(length, batch_size, seed);
+    let batch: Vec<&str> = batch.iter().map(String::as_str).collect();
+    let tokens: usize = batch.iter().map(|text| estimate_tokens(text)).sum();
+
+    model.inference(TextInput::from_str(&batch, labels)?)?;
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let started = std::time::Instant::now();
+        model.inference(TextInput::from_str(&batch, labels)?)?;
+        samples.push(timing::millis(started.elapsed()));
+    }
+
+    let seconds = samples.iter().sum::<f64>() / 1000.0;
+    let per_sec = |count: usize| if seconds > 0.0 { (count * iterations) as f64 / seconds } else { 0.0 };
+    Ok(BenchmarkCase {
+        length,
+        batch_size,
+        iterations,
+        tokens_per_text: tokens as f64 / batch_size as f64,
+        texts_per_sec: per_sec(batch_size),
+        tokens_per_sec: per_sec(tokens),
+        latency: Latency::of(samples),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texts_fit_the_length() {
+        for length in [1, 40, 500, 3000] {
+            let generated = texts(length, 3, 7);
+            assert_eq!(generated.len(), 3);
+            for text in &generated {
+                let chars = text.chars().count();
+                assert!(chars <= length && chars > 0, "{} chars for {}", chars, length);
+            }
+        }
+        assert!(texts(500, 1, 7)[0].chars().count() > 400);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let latency = Latency::of((1..=100).rev().map(f64::from).collect());
+        assert_eq!((latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.max_ms), (50.0, 90.0, 99.0, 100.0));
+        assert_eq!(Latency::of(vec![4.0]).p99_ms, 4.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_request_limits() {
+        assert!(BenchmarkRequest::default().validate().is_ok());
+        let request = BenchmarkRequest { batch_sizes: vec![0], ..BenchmarkRequest::default() };
+        assert!(request.validate().is_err());
+        let request = BenchmarkRequest { lengths: vec![MAX_TEXT_CHARS + 1], ..BenchmarkRequest::default() };
+        assert!(request.validate().is_err());
+    }
+}
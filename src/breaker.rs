@@ -0,0 +1,170 @@
+// Circuit breaker in front of the model: when too many recent inferences fail,
+// new work is refused with 503 "circuit_open" instead of being sent to sessions
+// that are likely wedged, until the model has been reloaded and passed a
+// self-test (see `health::spawn_recovery`).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+use crate::config::Config;
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakerSettings {
+    // Recent outcomes the failure rate is taken over; 0 disables the breaker
+    pub window: usize,
+    // Outcomes needed in the window before it can trip
+    pub min_requests: usize,
+    // Share of failures in the window that trips it
+    pub failure_rate: f64,
+}
+
+impl BreakerSettings {
+    pub fn from_config(config: &Config) -> Self {
+        BreakerSettings {
+            window: config.breaker_window,
+            min_requests: config.breaker_min_requests.clamp(1, config.breaker_window.max(1)),
+            failure_rate: config.breaker_failure_rate,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        BreakerSettings {
+            window: 0,
+            min_requests: 1,
+            failure_rate: 1.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct BreakerState {
+    // Most recent last; true for a success
+    outcomes: VecDeque<bool>,
+    open: bool,
+}
+
+pub struct CircuitBreaker {
+    settings: BreakerSettings,
+    state: Mutex<BreakerState>,
+    tripped: Notify,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: BreakerSettings) -> Self {
+        CircuitBreaker {
+            settings,
+            state: Mutex::new(BreakerState::default()),
+            tripped: Notify::new(),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(BreakerSettings::disabled())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.lock().open
+    }
+
+    // Count the outcome of an inference. Only model failures count against the
+    // model; bad input, timeouts and refusals say nothing about its health.
+    pub fn observe<T>(&self, result: &Result<T, ApiError>) {
+        match result {
+            Ok(_) => self.record(true),
+            Err(ApiError::InferenceFailed(_)) => self.record(false),
+            Err(_) => {}
+        }
+    }
+
+    fn record(&self, success: bool) {
+        if self.settings.window == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        if state.open {
+            return;
+        }
+        state.outcomes.push_back(success);
+        while state.outcomes.len() > self.settings.window {
+            state.outcomes.pop_front();
+        }
+        let failures = state.outcomes.iter().filter(|&&success| !success).count();
+        let total = state.outcomes.len();
+        if total >= self.settings.min_requests && failures as f64 >= self.settings.failure_rate * total as f64 {
+            state.open = true;
+            state.outcomes.clear();
+            tracing::error!(failures, total, "Circuit breaker opened: inference is failing repeatedly");
+            self.tripped.notify_one();
+        }
+    }
+
+    // Let work through again
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.open = false;
+        state.outcomes.clear();
+    }
+
+    // Wait until the breaker opens
+    pub async fn tripped(&self) {
+        self.tripped.notified().await;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(BreakerSettings {
+            window: 10,
+            min_requests: 4,
+            failure_rate: 0.5,
+        })
+    }
+
+    fn failure() -> Result<(), ApiError> {
+        Err(ApiError::InferenceFailed("session error".to_string()))
+    }
+
+    #[test]
+    fn test_opens_at_the_failure_rate() {
+        let breaker = breaker();
+        breaker.observe(&Ok(()));
+        breaker.observe(&failure());
+        breaker.observe(&Ok(()));
+        assert!(!breaker.is_open());
+        breaker.observe(&failure());
+        assert!(breaker.is_open());
+
+        breaker.close();
+        assert!(!breaker.is_open());
+        breaker.observe(&failure());
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_other_errors_do_not_count() {
+        let breaker = breaker();
+        for _ in 0..10 {
+            breaker.observe::<()>(&Err(ApiError::InvalidInput("empty text".to_string())));
+            breaker.observe::<()>(&Err(ApiError::Overloaded { queue_depth: 1, capacity: 1 }));
+        }
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_disabled_never_opens() {
+        let breaker = CircuitBreaker::disabled();
+        for _ in 0..10 {
+            breaker.observe(&failure());
+        }
+        assert!(!breaker.is_open());
+    }
+}
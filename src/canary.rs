@@ -0,0 +1,105 @@
+// Canary rollout: a second model, GLINER_CANARY_MODEL, is loaded next to the
+// primary one and serves GLINER_CANARY_PERCENT of single-text detections.
+// Responses and metrics name the model that served them, so a new model version
+// can be compared on a slice of production traffic before it replaces the old.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::loading;
+use crate::service::ModelState;
+
+pub struct Canary {
+    model: String,
+    percent: f64,
+    model_state: ModelState,
+    // Set once the canary is loaded and warmed up; until then all traffic stays on the primary
+    ready: AtomicBool,
+    requests: AtomicU64,
+}
+
+impl Canary {
+    pub fn new(model: &str, percent: f64) -> Self {
+        Canary {
+            model: model.to_string(),
+            percent: percent.clamp(0.0, 100.0),
+            model_state: Arc::new(Mutex::new(None)),
+            ready: AtomicBool::new(false),
+            requests: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .canary_model
+            .as_deref()
+            .map(|model| Canary::new(model, config.canary_percent))
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn model_state(&self) -> &ModelState {
+        &self.model_state
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    // Whether the next request goes to the canary
+    pub fn pick(&self) -> bool {
+        self.is_ready() && take_share(&self.requests, self.percent)
+    }
+
+    // Load and warm up the canary model with the primary's settings
+    pub async fn load(&self, config: &Config) {
+        match loading::load_secondary(config, &self.model).await {
+            Ok(pool) => {
+                *self.model_state.lock().await = Some(pool);
+                self.ready.store(true, Ordering::Relaxed);
+                tracing::info!(model = %self.model, percent = self.percent, "Canary model ready");
+            }
+            Err(e) => tracing::error!(model = %self.model, error = %e, "Failed to load the canary model; all traffic stays on the primary"),
+        }
+    }
+}
+
+pub type CanaryState = Arc<Option<Canary>>;
+
+// Whether the request counted by `counter` falls in a `percent` share of traffic.
+// Requests are split by count rather than at random, so any window of 100
+// requests holds the share give or take one.
+pub fn take_share(counter: &AtomicU64, percent: f64) -> bool {
+    let n = counter.fetch_add(1, Ordering::Relaxed) as f64;
+    ((n + 1.0) * percent / 100.0).floor() > (n * percent / 100.0).floor()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_follows_the_percentage() {
+        let canary = Canary::new("gliner-canary", 10.0);
+        canary.ready.store(true, Ordering::Relaxed);
+        let picked = (0..1000).filter(|_| canary.pick()).count();
+        assert_eq!(picked, 100);
+
+        let quarter = Canary::new("gliner-canary", 25.0);
+        quarter.ready.store(true, Ordering::Relaxed);
+        let picked: Vec<bool> = (0..4).map(|_| quarter.pick()).collect();
+        assert_eq!(picked.iter().filter(|&&canary| canary).count(), 1);
+    }
+
+    #[test]
+    fn test_no_traffic_until_loaded() {
+        let canary = Canary::new("gliner-canary", 100.0);
+        assert!(!canary.pick());
+        canary.ready.store(true, Ordering::Relaxed);
+        assert!(canary.pick());
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::PiiEntity;
+
+// Label of card findings, matching the "credit card number" label of the pci-dss profile
+pub const LABEL: &str = "credit card number";
+
+// Digits in a card number (ISO/IEC 7812); Maestro issues numbers as short as 12
+pub const LENGTHS: RangeInclusive<usize> = 12..=19;
+
+// Issuer identification ranges, as (low, high, network): a card belongs to a
+// network when its leading digits, cut to the length of `low`, fall within the
+// range. The longest matching prefix wins, so co-branded sub-ranges go first.
+const BIN_RANGES: &[(&str, &str, &str)] = &[
+    ("622126", "622925", "discover"),
+    ("2200", "2204", "mir"),
+    ("2221", "2720", "mastercard"),
+    ("3528", "3589", "jcb"),
+    ("5018", "5018", "maestro"),
+    ("5020", "5020", "maestro"),
+    ("5038", "5038", "maestro"),
+    ("5893", "5893", "maestro"),
+    ("6011", "6011", "discover"),
+    ("6304", "6304", "maestro"),
+    ("6759", "6759", "maestro"),
+    ("6761", "6763", "maestro"),
+    ("300", "305", "diners"),
+    ("644", "649", "discover"),
+    ("34", "34", "amex"),
+    ("36", "36", "diners"),
+    ("37", "37", "amex"),
+    ("38", "39", "diners"),
+    ("51", "55", "mastercard"),
+    ("62", "62", "unionpay"),
+    ("65", "65", "discover"),
+    ("4", "4", "visa"),
+];
+
+// Card network for a number's leading digits, from the embedded BIN table
+pub fn network(digits: &str) -> Option<&'static str> {
+    BIN_RANGES
+        .iter()
+        .filter(|(low, _, _)| digits.len() >= low.len())
+        .find(|(low, high, _)| {
+            let prefix = &digits[..low.len()];
+            *low <= prefix && prefix <= *high
+        })
+        .map(|(_, _, network)| *network)
+}
+
+pub fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(index, byte)| {
+            let digit = (byte - b'0') as u32;
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// The digits of a card number written with spaces or dashes, if it is one
+fn card_digits(text: &str) -> Option<String> {
+    if !text.chars().all(|c| c.is_ascii_digit() || c == ' ' || c == '-') {
+        return None;
+    }
+    let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+    LENGTHS.contains(&digits.len()).then_some(digits)
+}
+
+// Network and masked form of a Luhn-valid card number, e.g. "visa ************1111"
+pub fn attributes(text: &str) -> Option<BTreeMap<String, String>> {
+    let digits = card_digits(text.trim())?;
+    if !luhn_valid(&digits) {
+        return None;
+    }
+    let network = network(&digits).unwrap_or("unknown");
+    let last4 = &digits[digits.len() - 4..];
+    Some(BTreeMap::from([
+        ("card_network".to_string(), network.to_string()),
+        ("card_last4".to_string(), last4.to_string()),
+        ("normalized".to_string(), format!("{} {}{}", network, "*".repeat(digits.len() - 4), last4)),
+    ]))
+}
+
+// Enrich card number findings that pass the Luhn check; others are left as found
+pub fn enrich_entities(entities: &mut [PiiEntity]) {
+    for entity in entities.iter_mut().filter(|entity| entity.label == LABEL) {
+        if let Some(attributes) = attributes(&entity.text) {
+            entity.attributes.extend(attributes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_at;
+
+    #[test]
+    fn test_networks_from_bin_ranges() {
+        assert_eq!(network("4111111111111111"), Some("visa"));
+        assert_eq!(network("5500000000000004"), Some("mastercard"));
+        assert_eq!(network("2223000048400011"), Some("mastercard"));
+        assert_eq!(network("378282246310005"), Some("amex"));
+        assert_eq!(network("6011111111111117"), Some("discover"));
+        assert_eq!(network("6221260000000000"), Some("discover"));
+        assert_eq!(network("6200000000000005"), Some("unionpay"));
+        assert_eq!(network("3530111333300000"), Some("jcb"));
+        assert_eq!(network("9999999999999999"), None);
+    }
+
+    #[test]
+    fn test_attributes_mask_all_but_network_and_last4() {
+        let found = attributes("4111 1111 1111 1111").unwrap();
+        assert_eq!(found["card_network"], "visa");
+        assert_eq!(found["normalized"], "visa ************1111");
+
+        assert!(attributes("4111 1111 1111 1112").is_none());
+        assert!(attributes("call 4111111111111111").is_none());
+    }
+
+    #[test]
+    fn test_shortest_card_numbers() {
+        let found = attributes("5018 0000 0009").unwrap();
+        assert_eq!(found["card_network"], "maestro");
+        assert_eq!(found["normalized"], "maestro ********0009");
+        // Luhn-valid, but one digit short
+        assert!(attributes("5018 0000 007").is_none());
+    }
+
+    #[test]
+    fn test_enrich_only_card_findings() {
+        let card = "378282246310005";
+        let mut entities = vec![entity_at(card, LABEL, 0, 0.8), entity_at(card, "phone number", 0, 0.8)];
+        enrich_entities(&mut entities);
+        assert_eq!(entities[0].attributes["normalized"], "amex ***********0005");
+        assert!(entities[1].attributes.is_empty());
+    }
+}
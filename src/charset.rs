@@ -0,0 +1,148 @@
+// Character encoding of uploaded text files. Files that are not UTF-8 (exports
+// from older Windows tools, UTF-16 files written by Excel or PowerShell) are
+// transcoded to UTF-8 before detection instead of being rejected, and the
+// encoding that was detected is reported with the results.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+    #[serde(rename = "iso-8859-1")]
+    Latin1,
+}
+
+// Bytes sampled to recognise UTF-16 without a byte order mark
+const SAMPLE_BYTES: usize = 4096;
+
+// Windows-1252 characters for bytes 0x80-0x9F, which are C1 control codes in
+// Latin-1. The five unassigned bytes keep their control code.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+// Work out the encoding of a text file and decode it. UTF-8 files are passed
+// through unchanged, byte order mark included, so their offsets stay
+// file-relative; other encodings are decoded without their byte order mark, and
+// offsets refer to the decoded text.
+pub fn decode(bytes: Vec<u8>) -> Result<(String, TextEncoding), ApiError> {
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        return Ok((decode_utf16(rest, u16::from_le_bytes)?, TextEncoding::Utf16Le));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        return Ok((decode_utf16(rest, u16::from_be_bytes)?, TextEncoding::Utf16Be));
+    }
+    match utf16_without_bom(&bytes) {
+        Some(TextEncoding::Utf16Le) => return Ok((decode_utf16(&bytes, u16::from_le_bytes)?, TextEncoding::Utf16Le)),
+        Some(TextEncoding::Utf16Be) => return Ok((decode_utf16(&bytes, u16::from_be_bytes)?, TextEncoding::Utf16Be)),
+        _ => {}
+    }
+    let bytes = match String::from_utf8(bytes) {
+        Ok(text) => return Ok((text, TextEncoding::Utf8)),
+        Err(e) => e.into_bytes(),
+    };
+    // Legacy single-byte text has no NULs; binary files usually do
+    if bytes.contains(&0) {
+        return Err(ApiError::InvalidInput("file is not text in a supported encoding".to_string()));
+    }
+    // Latin-1 has only control codes at 0x80-0x9F, so text using them was
+    // written as Windows-1252
+    if bytes.iter().any(|byte| (0x80..0xa0).contains(byte)) {
+        let text = bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9f => WINDOWS_1252[(byte - 0x80) as usize],
+                _ => byte as char,
+            })
+            .collect();
+        return Ok((text, TextEncoding::Windows1252));
+    }
+    Ok((bytes.iter().map(|&byte| byte as char).collect(), TextEncoding::Latin1))
+}
+
+// UTF-16 text that is mostly Latin script has a NUL in every other byte: the
+// high byte of each code unit
+fn utf16_without_bom(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.len() < 4 || bytes.len() % 2 == 1 {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(SAMPLE_BYTES)];
+    let pairs = sample.len() / 2;
+    let (mut even, mut odd) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even += usize::from(pair[0] == 0);
+        odd += usize::from(pair[1] == 0);
+    }
+    if odd * 10 >= pairs * 4 && even * 10 <= pairs {
+        Some(TextEncoding::Utf16Le)
+    } else if even * 10 >= pairs * 4 && odd * 10 <= pairs {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<String, ApiError> {
+    if bytes.len() % 2 == 1 {
+        return Err(ApiError::InvalidInput("file is UTF-16 text with an odd number of bytes".to_string()));
+    }
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|e| ApiError::InvalidInput(format!("file is not valid UTF-16 text: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, bom: &[u8], unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut bytes = bom.to_vec();
+        bytes.extend(text.encode_utf16().flat_map(unit));
+        bytes
+    }
+
+    #[test]
+    fn test_utf8_is_passed_through() {
+        let bytes = "\u{feff}Zoë Roe".as_bytes().to_vec();
+        assert_eq!(decode(bytes).unwrap(), ("\u{feff}Zoë Roe".to_string(), TextEncoding::Utf8));
+    }
+
+    #[test]
+    fn test_utf16_with_and_without_bom() {
+        let text = "Name: Zoë Roe, zoe@example.com";
+        assert_eq!(decode(utf16(text, &[0xff, 0xfe], u16::to_le_bytes)).unwrap(), (text.to_string(), TextEncoding::Utf16Le));
+        assert_eq!(decode(utf16(text, &[0xfe, 0xff], u16::to_be_bytes)).unwrap(), (text.to_string(), TextEncoding::Utf16Be));
+        assert_eq!(decode(utf16(text, &[], u16::to_le_bytes)).unwrap(), (text.to_string(), TextEncoding::Utf16Le));
+        assert_eq!(decode(utf16(text, &[], u16::to_be_bytes)).unwrap(), (text.to_string(), TextEncoding::Utf16Be));
+    }
+
+    #[test]
+    fn test_single_byte_encodings() {
+        // "Zoë Müller" in Latin-1
+        let latin1 = b"Zo\xeb M\xfcller".to_vec();
+        assert_eq!(decode(latin1).unwrap(), ("Zoë Müller".to_string(), TextEncoding::Latin1));
+
+        // Curly quotes and the euro sign only exist in Windows-1252
+        let windows = b"\x93Zo\xeb\x94 paid \x80100".to_vec();
+        assert_eq!(decode(windows).unwrap(), ("“Zoë” paid €100".to_string(), TextEncoding::Windows1252));
+    }
+
+    #[test]
+    fn test_rejects_binary_and_broken_utf16() {
+        assert!(decode(vec![0x89, b'P', b'N', b'G', 0x00, 0x00, 0x01]).is_err());
+        assert!(decode(vec![0xff, 0xfe, 0x00]).is_err());
+        // Unpaired surrogate
+        assert!(decode(vec![0xff, 0xfe, 0x00, 0xd8, b'a', 0x00]).is_err());
+    }
+}
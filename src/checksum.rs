@@ -0,0 +1,349 @@
+// Check digits of structured identifiers, used to re-score findings: a card
+// number must pass Luhn, an IBAN mod-97 (ISO 7064), and a US social security
+// number must have a valid area, group and serial; the identifiers of the
+// country recognizer packs (see `packs`) have their own. Findings that pass can have
+// their confidence raised (GLINER_CHECKSUM_VALID_BOOST), and those that fail
+// kept, downgraded or dropped (GLINER_CHECKSUM_INVALID), which cuts false
+// positives on random digit strings.
+
+use std::str::FromStr;
+
+use crate::card;
+use crate::config::Config;
+use crate::PiiEntity;
+
+// What to do with a finding whose checksum fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidAction {
+    Keep,
+    // Multiply the confidence by GLINER_CHECKSUM_DOWNGRADE
+    Downgrade,
+    Drop,
+}
+
+impl FromStr for InvalidAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "keep" => Ok(InvalidAction::Keep),
+            "downgrade" => Ok(InvalidAction::Downgrade),
+            "drop" => Ok(InvalidAction::Drop),
+            other => Err(format!("unknown action {:?}, expected keep, downgrade or drop", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChecksumPolicy {
+    pub valid_boost: f32,
+    pub invalid: InvalidAction,
+    pub downgrade: f32,
+}
+
+impl ChecksumPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        ChecksumPolicy {
+            valid_boost: config.checksum_valid_boost.max(0.0),
+            invalid: config.checksum_invalid,
+            downgrade: config.checksum_downgrade.clamp(0.0, 1.0),
+        }
+    }
+
+    // Whether re-scoring changes anything
+    pub fn is_off(&self) -> bool {
+        self.valid_boost == 0.0 && self.invalid == InvalidAction::Keep
+    }
+}
+
+// The check for findings of a label, if it has one
+pub fn validator(label: &str) -> Option<fn(&str) -> bool> {
+    match label.to_ascii_lowercase().as_str() {
+        card::LABEL | "credit card" | "card number" => Some(card_valid),
+        "iban" | "iban code" => Some(iban_valid),
+        "social security number" | "ssn" => Some(ssn_valid),
+        "employer identification number" => Some(ein_valid),
+        "national insurance number" => Some(nino_valid),
+        "nhs number" => Some(nhs_valid),
+        "german tax id" => Some(steuer_id_valid),
+        "aadhaar number" => Some(aadhaar_valid),
+        "pan number" => Some(pan_valid),
+        _ => None,
+    }
+}
+
+// Whether a value passes the check of its label; None when the label has no
+// check
+pub fn check(label: &str, value: &str) -> Option<bool> {
+    validator(label).map(|valid| valid(value))
+}
+
+// Re-score the findings of labels with a check, marking each with a `checksum`
+// attribute of "valid" or "invalid". Findings already marked are left alone, so
+// re-scoring twice changes nothing.
+pub fn rescore(entities: &mut Vec<PiiEntity>, policy: &ChecksumPolicy) {
+    if policy.is_off() {
+        return;
+    }
+    entities.retain_mut(|entity| {
+        if entity.attributes.contains_key("checksum") {
+            return true;
+        }
+        let Some(valid) = check(&entity.label, &entity.text) else { return true };
+        entity.attributes.insert("checksum".to_string(), if valid { "valid" } else { "invalid" }.to_string());
+        match (valid, policy.invalid) {
+            (true, _) => entity.confidence = (entity.confidence + policy.valid_boost).min(1.0),
+            (false, InvalidAction::Keep) => {}
+            (false, InvalidAction::Downgrade) => entity.confidence *= policy.downgrade,
+            (false, InvalidAction::Drop) => return false,
+        }
+        true
+    });
+}
+
+// The digits of `text` when it holds only digits and the separators given
+pub fn digits(text: &str, separators: &[char]) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit() || separators.contains(&c)) {
+        return None;
+    }
+    Some(text.chars().filter(char::is_ascii_digit).collect())
+}
+
+pub fn card_valid(text: &str) -> bool {
+    digits(text, &[' ', '-']).is_some_and(|digits| card::LENGTHS.contains(&digits.len()) && card::luhn_valid(&digits))
+}
+
+// ISO 13616: country code, two check digits and up to 30 letters or digits,
+// valid when the rearranged number is 1 mod 97
+pub fn iban_valid(text: &str) -> bool {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    let bytes = compact.as_bytes();
+    if !(15..=34).contains(&bytes.len())
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..4].iter().all(u8::is_ascii_digit)
+        || !bytes.iter().all(u8::is_ascii_alphanumeric)
+    {
+        return false;
+    }
+    mod97(&compact[4..], &compact[..4]) == 1
+}
+
+// Remainder mod 97 of `head` followed by `tail`, with letters as 10 to 35
+pub fn mod97(head: &str, tail: &str) -> u32 {
+    head.chars().chain(tail.chars()).fold(0u32, |remainder, c| match c.to_digit(36) {
+        Some(value) if value >= 10 => (remainder * 100 + value) % 97,
+        Some(value) => (remainder * 10 + value) % 97,
+        None => remainder,
+    })
+}
+
+// US SSN: area not 000, 666 or 9xx, group not 00, serial not 0000
+pub fn ssn_valid(text: &str) -> bool {
+    let Some(digits) = digits(text, &['-', ' ']) else { return false };
+    if digits.len() != 9 {
+        return false;
+    }
+    let (area, group, serial) = (&digits[..3], &digits[3..5], &digits[5..]);
+    area != "000" && area != "666" && !area.starts_with('9') && group != "00" && serial != "0000"
+}
+
+// US EIN: nine digits written 12-3456789, with a prefix the IRS assigns
+pub fn ein_valid(text: &str) -> bool {
+    const UNASSIGNED: &[&str] = &["00", "07", "08", "09", "17", "18", "19", "28", "29", "49", "69", "70", "78", "79", "89", "96", "97"];
+    digits(text, &['-']).is_some_and(|digits| digits.len() == 9 && !UNASSIGNED.contains(&&digits[..2]))
+}
+
+// UK National Insurance number: two prefix letters (not D, F, I, Q, U or V,
+// nor O second, nor a reserved pair), six digits and a suffix A to D
+pub fn nino_valid(text: &str) -> bool {
+    const RESERVED: &[&str] = &["BG", "GB", "KN", "NK", "NT", "TN", "ZZ"];
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    let chars: Vec<char> = compact.chars().collect();
+    chars.len() == 9
+        && chars[0].is_ascii_uppercase()
+        && chars[1].is_ascii_uppercase()
+        && !"DFIQUV".contains(chars[0])
+        && !"DFIOQUV".contains(chars[1])
+        && !RESERVED.contains(&&compact[..2])
+        && chars[2..8].iter().all(char::is_ascii_digit)
+        && ('A'..='D').contains(&chars[8])
+}
+
+// UK NHS number: ten digits, the last a mod-11 check of the first nine
+// weighted 10 down to 2
+pub fn nhs_valid(text: &str) -> bool {
+    let Some(digits) = digits(text, &[' ', '-']) else { return false };
+    if digits.len() != 10 {
+        return false;
+    }
+    let values: Vec<u32> = digits.bytes().map(|byte| (byte - b'0') as u32).collect();
+    let sum: u32 = values[..9].iter().zip((2..=10).rev()).map(|(digit, weight)| digit * weight).sum();
+    match 11 - sum % 11 {
+        11 => values[9] == 0,
+        10 => false,
+        check => values[9] == check,
+    }
+}
+
+// German tax identification number (Steuer-ID): eleven digits, not starting
+// with 0, where one of the first ten digits appears two or three times and the
+// others at most once, and the last is an ISO 7064 MOD 11,10 check digit
+pub fn steuer_id_valid(text: &str) -> bool {
+    let Some(digits) = digits(text, &[' ']) else { return false };
+    if digits.len() != 11 || digits.starts_with('0') {
+        return false;
+    }
+    let values: Vec<u32> = digits.bytes().map(|byte| (byte - b'0') as u32).collect();
+    let mut counts = [0usize; 10];
+    for &digit in &values[..10] {
+        counts[digit as usize] += 1;
+    }
+    let repeated: Vec<usize> = counts.iter().copied().filter(|&count| count > 1).collect();
+    if !matches!(repeated.as_slice(), [2] | [3]) {
+        return false;
+    }
+    let mut product = 10;
+    for &digit in &values[..10] {
+        let sum = match (digit + product) % 10 {
+            0 => 10,
+            sum => sum,
+        };
+        product = (sum * 2) % 11;
+    }
+    let check = match 11 - product {
+        10 => 0,
+        check => check,
+    };
+    values[10] == check
+}
+
+const VERHOEFF_D: [[u8; 10]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 2, 3, 4, 0, 6, 7, 8, 9, 5],
+    [2, 3, 4, 0, 1, 7, 8, 9, 5, 6],
+    [3, 4, 0, 1, 2, 8, 9, 5, 6, 7],
+    [4, 0, 1, 2, 3, 9, 5, 6, 7, 8],
+    [5, 9, 8, 7, 6, 0, 4, 3, 2, 1],
+    [6, 5, 9, 8, 7, 1, 0, 4, 3, 2],
+    [7, 6, 5, 9, 8, 2, 1, 0, 4, 3],
+    [8, 7, 6, 5, 9, 3, 2, 1, 0, 4],
+    [9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+];
+const VERHOEFF_P: [[u8; 10]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+    [1, 5, 7, 6, 2, 8, 3, 0, 9, 4],
+    [5, 8, 0, 3, 7, 9, 6, 1, 4, 2],
+    [8, 9, 1, 6, 0, 4, 3, 5, 2, 7],
+    [9, 4, 5, 8, 3, 0, 2, 7, 1, 6],
+    [4, 2, 8, 6, 5, 7, 3, 9, 0, 1],
+    [2, 7, 9, 3, 8, 0, 6, 4, 1, 5],
+    [7, 0, 4, 6, 9, 1, 3, 2, 5, 8],
+];
+const VERHOEFF_INV: [u8; 10] = [0, 4, 3, 2, 1, 5, 6, 7, 8, 9];
+
+// Verhoeff checksum over ASCII digits; `shift` is 1 when computing the check
+// digit for `digits`, 0 when validating digits that end with one
+fn verhoeff(digits: &str, shift: usize) -> u8 {
+    digits.bytes().rev().enumerate().fold(0, |check, (index, byte)| {
+        VERHOEFF_D[check as usize][VERHOEFF_P[(index + shift) % 8][(byte - b'0') as usize] as usize]
+    })
+}
+
+pub fn verhoeff_valid(digits: &str) -> bool {
+    verhoeff(digits, 0) == 0
+}
+
+// The Verhoeff check digit to append to `digits`
+pub fn verhoeff_digit(digits: &str) -> u8 {
+    VERHOEFF_INV[verhoeff(digits, 1) as usize]
+}
+
+// Indian Aadhaar number: twelve digits, not starting with 0 or 1, the last a
+// Verhoeff check digit
+pub fn aadhaar_valid(text: &str) -> bool {
+    digits(text, &[' ', '-']).is_some_and(|digits| {
+        digits.len() == 12 && !digits.starts_with(['0', '1']) && verhoeff_valid(&digits)
+    })
+}
+
+// Indian PAN: five letters, four digits and a letter, the fourth letter the
+// holder type (P for a person, C for a company, ...). There is no check digit.
+pub fn pan_valid(text: &str) -> bool {
+    let chars: Vec<char> = text.trim().chars().collect();
+    chars.len() == 10
+        && chars[..5].iter().all(char::is_ascii_uppercase)
+        && "PCHABGJLFT".contains(chars[3])
+        && chars[5..9].iter().all(char::is_ascii_digit)
+        && chars[9].is_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_at;
+
+    #[test]
+    fn test_checks() {
+        assert!(card_valid("4111 1111 1111 1111"));
+        assert!(!card_valid("4111 1111 1111 1112"));
+        assert!(!card_valid("4111"));
+        assert!(card_valid("5018 0000 0009"));
+        assert!(!card_valid("5018 0000 007"));
+        assert!(iban_valid("GB82 WEST 1234 5698 7654 32"));
+        assert!(iban_valid("de89370400440532013000"));
+        assert!(!iban_valid("GB82 WEST 1234 5698 7654 33"));
+        assert!(ssn_valid("123-45-6789"));
+        assert!(!ssn_valid("666-45-6789"));
+        assert!(!ssn_valid("123-00-6789"));
+        assert_eq!(check("person", "Jane Roe"), None);
+    }
+
+    #[test]
+    fn test_country_identifier_checks() {
+        assert!(ein_valid("12-3456789"));
+        assert!(!ein_valid("07-3456789"));
+        assert!(nino_valid("AB 12 34 56 C"));
+        assert!(!nino_valid("GB123456A"));
+        assert!(!nino_valid("AB123456E"));
+        assert!(nhs_valid("943 476 5919"));
+        assert!(!nhs_valid("943 476 5918"));
+        assert!(steuer_id_valid("86095742719"));
+        assert!(!steuer_id_valid("86095742718"));
+        assert!(!steuer_id_valid("12345678903"));
+        assert_eq!(verhoeff_digit("236"), 3);
+        assert!(verhoeff_valid("2363"));
+        let aadhaar = format!("23412341234{}", verhoeff_digit("23412341234"));
+        assert!(aadhaar_valid(&aadhaar));
+        assert!(!aadhaar_valid("123412341234"));
+        assert!(pan_valid("ABCPE1234F"));
+        assert!(!pan_valid("ABCXE1234F"));
+    }
+
+    #[test]
+    fn test_rescore_boosts_and_drops() {
+        let policy = ChecksumPolicy { valid_boost: 0.2, invalid: InvalidAction::Drop, downgrade: 0.5 };
+        let mut entities = vec![
+            entity_at("4111 1111 1111 1111", "credit card number", 0, 0.7),
+            entity_at("1234 5678 9012 3456", "credit card number", 0, 0.9),
+            entity_at("Jane Roe", "person", 0, 0.6),
+        ];
+        rescore(&mut entities, &policy);
+        assert_eq!(entities.len(), 2);
+        assert!((entities[0].confidence - 0.9).abs() < 1e-6);
+        assert_eq!(entities[0].attributes["checksum"], "valid");
+        assert!(entities[1].attributes.is_empty());
+
+        rescore(&mut entities, &policy);
+        assert!((entities[0].confidence - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rescore_downgrades() {
+        let policy = ChecksumPolicy { valid_boost: 0.0, invalid: InvalidAction::Downgrade, downgrade: 0.5 };
+        let mut entities = vec![entity_at("GB82 WEST 1234 5698 7654 33", "iban", 0, 0.8)];
+        rescore(&mut entities, &policy);
+        assert!((entities[0].confidence - 0.4).abs() < 1e-6);
+        assert_eq!(entities[0].attributes["checksum"], "invalid");
+    }
+}
@@ -0,0 +1,254 @@
+// A slice of one document sent to the model as its own sequence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub document: usize,
+    // Byte offset of the chunk within its document
+    pub offset: usize,
+    pub text: String,
+}
+
+// Split a document into chunks of at most `max_chars` characters, breaking on
+// whitespace where possible and repeating up to `overlap` characters between
+// consecutive chunks so entities on a boundary are seen whole at least once
+pub fn split_into_chunks(document: usize, text: &str, max_chars: usize, overlap: usize) -> Vec<Chunk> {
+    let max_chars = max_chars.max(1);
+    let overlap = overlap.min(max_chars / 2);
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+    if chars.len() <= max_chars {
+        return vec![Chunk {
+            document,
+            offset: 0,
+            text: text.to_string(),
+        }];
+    }
+
+    let byte_at = |index: usize| if index < chars.len() { chars[index].0 } else { text.len() };
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    loop {
+        let mut end = (start + max_chars).min(chars.len());
+        if end < chars.len() {
+            if let Some(space) = (start + 1..end).rev().find(|&i| chars[i].1.is_whitespace()) {
+                end = space;
+            }
+        }
+
+        chunks.push(Chunk {
+            document,
+            offset: byte_at(start),
+            text: text[byte_at(start)..byte_at(end)].to_string(),
+        });
+
+        if end >= chars.len() {
+            break;
+        }
+
+        // Step back into the previous chunk, then forward to the next word start
+        let mut next = end.saturating_sub(overlap).max(start + 1);
+        while next < end && !chars[next - 1].1.is_whitespace() {
+            next += 1;
+        }
+        while next < chars.len() && chars[next].1.is_whitespace() {
+            next += 1;
+        }
+        if next >= chars.len() {
+            break;
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+// Pack chunks from every document into fixed-size inference batches, returning
+// chunk indices per batch. Scheduling chunks rather than documents keeps each
+// batch full when a request mixes short and long documents.
+pub fn schedule_batches(chunks: &[Chunk], batch_size: usize) -> Vec<Vec<usize>> {
+    let indices: Vec<usize> = (0..chunks.len()).collect();
+    indices
+        .chunks(batch_size.max(1))
+        .map(|batch| batch.to_vec())
+        .collect()
+}
+
+// Approximate the number of words the GLiNER splitter will produce: runs of
+// alphanumerics (joined by - or _) count as one token, other symbols count alone
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() || (in_word && (c == '-' || c == '_')) {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else {
+            in_word = false;
+            if !c.is_whitespace() {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// Like `schedule_batches`, but first groups chunks into length buckets (by
+// estimated token count) and sorts within each bucket, so every inference
+// batch holds sequences of similar length and little padding is wasted
+pub fn schedule_bucketed(chunks: &[Chunk], batch_size: usize, boundaries: &[usize]) -> Vec<Vec<usize>> {
+    if boundaries.is_empty() {
+        return schedule_batches(chunks, batch_size);
+    }
+
+    let mut boundaries = boundaries.to_vec();
+    boundaries.sort_unstable();
+
+    let mut buckets: Vec<Vec<(usize, usize)>> = vec![Vec::new(); boundaries.len() + 1];
+    for (index, chunk) in chunks.iter().enumerate() {
+        let tokens = estimate_tokens(&chunk.text);
+        let bucket = boundaries
+            .iter()
+            .position(|&boundary| tokens <= boundary)
+            .unwrap_or(boundaries.len());
+        buckets[bucket].push((tokens, index));
+    }
+
+    let mut batches = Vec::new();
+    for mut bucket in buckets {
+        bucket.sort_unstable();
+        for batch in bucket.chunks(batch_size.max(1)) {
+            batches.push(batch.iter().map(|&(_, index)| index).collect());
+        }
+    }
+    batches
+}
+
+// Token counts with and without the padding each batch is extended to
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PaddingStats {
+    pub real_tokens: usize,
+    pub padded_tokens: usize,
+}
+
+impl PaddingStats {
+    // Share of the computed sequence positions that hold real tokens
+    pub fn efficiency(&self) -> f64 {
+        if self.padded_tokens == 0 {
+            1.0
+        } else {
+            self.real_tokens as f64 / self.padded_tokens as f64
+        }
+    }
+}
+
+pub fn padding_stats(chunks: &[Chunk], batches: &[Vec<usize>]) -> PaddingStats {
+    let mut stats = PaddingStats::default();
+    for batch in batches {
+        let lengths: Vec<usize> = batch.iter().map(|&index| estimate_tokens(&chunks[index].text)).collect();
+        let longest = lengths.iter().copied().max().unwrap_or(0);
+        stats.real_tokens += lengths.iter().sum::<usize>();
+        stats.padded_tokens += longest * lengths.len();
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_text_is_single_chunk() {
+        let chunks = split_into_chunks(3, "John Doe", 100, 10);
+        assert_eq!(chunks, vec![Chunk { document: 3, offset: 0, text: "John Doe".to_string() }]);
+    }
+
+    #[test]
+    fn test_chunks_break_on_whitespace_and_keep_offsets() {
+        let text = "alpha beta gamma delta epsilon";
+        let chunks = split_into_chunks(0, text, 12, 0);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.text.chars().count() <= 12);
+            assert_eq!(&text[chunk.offset..chunk.offset + chunk.text.len()], chunk.text);
+        }
+        for chunk in &chunks[1..] {
+            assert!(text[..chunk.offset].ends_with(' '));
+        }
+    }
+
+    #[test]
+    fn test_chunks_overlap() {
+        let text = "one two three four five six seven eight nine ten";
+        let chunks = split_into_chunks(0, text, 16, 6);
+
+        for pair in chunks.windows(2) {
+            assert!(pair[1].offset < pair[0].offset + pair[0].text.len());
+        }
+        assert!(chunks.last().unwrap().text.ends_with("ten"));
+    }
+
+    #[test]
+    fn test_multibyte_text_splits_on_char_boundaries() {
+        let text = "Zoë Müller wohnt in Köln und schreibt an zoë@example.de";
+        let chunks = split_into_chunks(0, text, 10, 3);
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.offset..chunk.offset + chunk.text.len()], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_schedule_batches_spans_documents() {
+        let mut chunks = split_into_chunks(0, "short", 100, 0);
+        chunks.extend(split_into_chunks(1, "a much longer document that needs splitting", 10, 0));
+        chunks.extend(split_into_chunks(2, "tiny", 100, 0));
+
+        let batches = schedule_batches(&chunks, 4);
+        assert!(batches.iter().all(|batch| batch.len() <= 4));
+        assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), chunks.len());
+        assert_eq!(batches[0].len(), 4);
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens("John Doe"), 2);
+        assert_eq!(estimate_tokens("john@example.com"), 5);
+        assert_eq!(estimate_tokens("well-known  e_mail"), 2);
+        assert_eq!(estimate_tokens("   "), 0);
+    }
+
+    #[test]
+    fn test_bucketing_improves_padding_efficiency() {
+        let texts = [
+            "short one",
+            "this chunk is quite a lot longer than the others in the request",
+            "tiny",
+            "another long chunk with many words in it to pad against",
+        ];
+        let chunks: Vec<Chunk> = texts
+            .iter()
+            .enumerate()
+            .flat_map(|(document, text)| split_into_chunks(document, text, 200, 0))
+            .collect();
+
+        let plain = padding_stats(&chunks, &schedule_batches(&chunks, 2));
+        let bucketed_batches = schedule_bucketed(&chunks, 2, &[4, 64]);
+        let bucketed = padding_stats(&chunks, &bucketed_batches);
+
+        assert_eq!(plain.real_tokens, bucketed.real_tokens);
+        assert!(bucketed.efficiency() > plain.efficiency());
+        assert_eq!(bucketed_batches.iter().map(Vec::len).sum::<usize>(), chunks.len());
+    }
+
+    #[test]
+    fn test_bucketing_without_boundaries_keeps_order() {
+        let chunks: Vec<Chunk> = ["b c d", "a"]
+            .iter()
+            .enumerate()
+            .flat_map(|(document, text)| split_into_chunks(document, text, 100, 0))
+            .collect();
+        assert_eq!(schedule_bucketed(&chunks, 8, &[]), vec![vec![0, 1]]);
+    }
+}
@@ -0,0 +1,598 @@
+use serde::Serialize;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::conll;
+use crate::presidio;
+use crate::detection::DetectionError;
+use crate::diff::{self, DiffFinding};
+use crate::extract::{self, DocumentFormat, ExtractedText, SectionUnit};
+use crate::log_scan::{LineBatcher, LogFinding, LogLine, LogSummary, SummaryLine};
+use crate::recognizers::RecognizerOptions;
+use crate::output::OutputFormat;
+use crate::report::{self, ReportSection};
+use crate::sarif;
+use crate::service::{Detection, FileEntity, PiiEntity, PiiService};
+use crate::validation;
+
+pub const USAGE: &str = "Usage:
+  gliner-rs-api [serve]                      Run the HTTP server (default)
+  gliner-rs-api detect [OPTIONS]             Detect PII in text read from stdin
+  gliner-rs-api scan-dir <DIR> [OPTIONS]     Scan every supported file under DIR
+  gliner-rs-api scan-diff [REPO] [OPTIONS]   Scan the lines added by the changes staged
+                                             in REPO, or by a unified diff on stdin
+  gliner-rs-api scan-log [FILE] [OPTIONS]    Scan a log file (or stdin) line by line
+  gliner-rs-api kafka [--profile <NAME>]     Scan messages from a Kafka topic into another
+                                             (GLINER_KAFKA_* settings, `kafka` feature)
+
+Options:
+  --profile <NAME>     Label profile to detect and redact with
+  --fail-on-findings   Exit with status 1 when any entity is found
+  --format <FORMAT>    json (default), sarif, html, conll, or presidio (detect only)
+
+Results are printed as JSON: one object for detect and scan-diff, one line per
+file for scan-dir, and one line per line with findings plus a summary line for
+scan-log. With --format sarif or html, detect and scan-dir print one SARIF 2.1.0
+log or one HTML report instead; with --format conll or presidio, detect prints
+IOB2 tags or Presidio analyzer results.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Serve,
+    Detect(ScanOptions),
+    ScanDir(PathBuf, ScanOptions),
+    // Repository whose staged changes to scan; None reads a diff from stdin
+    ScanDiff(Option<PathBuf>, ScanOptions),
+    // Log file to scan; None reads stdin
+    ScanLog(Option<PathBuf>, ScanOptions),
+    // Consume and scan Kafka messages until interrupted
+    Kafka(ScanOptions),
+}
+
+// Output formats scan-dir can write; conll and presidio describe a single text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirFormat {
+    Json,
+    Sarif,
+    Html,
+}
+
+const DETECT_ONLY_FORMATS: &str = "conll and presidio output are only available for detect";
+
+fn dir_format(format: OutputFormat) -> Result<DirFormat, DetectionError> {
+    match format {
+        OutputFormat::Json => Ok(DirFormat::Json),
+        OutputFormat::Sarif => Ok(DirFormat::Sarif),
+        OutputFormat::Html => Ok(DirFormat::Html),
+        OutputFormat::Conll | OutputFormat::Presidio => Err(DETECT_ONLY_FORMATS.into()),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    pub profile: Option<String>,
+    pub fail_on_findings: bool,
+    pub format: OutputFormat,
+}
+
+// Parse the arguments after the program name
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(Command::Serve);
+    };
+
+    let mut options = ScanOptions::default();
+    let mut positional = Vec::new();
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let name = rest.next().ok_or("--profile needs a profile name")?;
+                options.profile = Some(name.clone());
+            }
+            "--fail-on-findings" => options.fail_on_findings = true,
+            "--format" => {
+                let format = rest.next().ok_or("--format needs json, sarif, html, conll or presidio")?;
+                options.format = OutputFormat::parse(format)
+                    .map_err(|_| format!("unknown format {}, expected json, sarif, html, conll or presidio", format))?;
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            value => positional.push(value),
+        }
+    }
+
+    match (command.as_str(), positional.as_slice()) {
+        ("serve", []) => Ok(Command::Serve),
+        ("detect", []) => Ok(Command::Detect(options)),
+        ("scan-dir", _) if matches!(options.format, OutputFormat::Conll | OutputFormat::Presidio) => {
+            Err(DETECT_ONLY_FORMATS.to_string())
+        }
+        ("scan-dir", [dir]) => Ok(Command::ScanDir(PathBuf::from(dir), options)),
+        ("scan-dir", _) => Err("scan-dir takes exactly one directory".to_string()),
+        ("scan-diff" | "scan-log" | "kafka", _) if options.format != OutputFormat::Json => {
+            Err(format!("{} only prints JSON", command))
+        }
+        ("scan-diff", []) => Ok(Command::ScanDiff(None, options)),
+        ("scan-diff", [repo]) => Ok(Command::ScanDiff(Some(PathBuf::from(repo)), options)),
+        ("scan-diff", _) => Err("scan-diff takes at most one repository".to_string()),
+        ("scan-log", []) => Ok(Command::ScanLog(None, options)),
+        ("scan-log", [file]) => Ok(Command::ScanLog(Some(PathBuf::from(file)), options)),
+        ("scan-log", _) => Err("scan-log takes at most one file".to_string()),
+        ("kafka", []) if options.fail_on_findings => Err("kafka does not exit on findings".to_string()),
+        ("kafka", []) => Ok(Command::Kafka(options)),
+        ("serve" | "detect" | "kafka", _) => Err(format!("{} takes no positional arguments", command)),
+        _ => Err(format!("unknown command {}", command)),
+    }
+}
+
+// The service and the options of a one-off run
+struct Scanner {
+    service: PiiService,
+    profile: Option<String>,
+}
+
+impl Scanner {
+    async fn load(config: Config, profile: Option<&str>) -> Result<Self, DetectionError> {
+        let service = PiiService::load(config).await?;
+        // Fail on an unknown profile before anything is scanned
+        service.profile(profile)?;
+        Ok(Scanner {
+            service,
+            profile: profile.map(str::to_string),
+        })
+    }
+
+    fn detect(&self, texts: &[&str]) -> Result<Vec<Detection>, DetectionError> {
+        Ok(self.service.detect_batch(texts, self.profile.as_deref(), &RecognizerOptions::default())?)
+    }
+}
+
+#[derive(Serialize)]
+struct DetectOutput {
+    entities: Vec<PiiEntity>,
+    total_entities: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redacted_text: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DiffOutput {
+    findings: Vec<DiffFinding>,
+    total_entities: usize,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum FileOutput {
+    Scanned {
+        path: String,
+        format: DocumentFormat,
+        entities: Vec<FileEntity>,
+        total_entities: usize,
+    },
+    Failed {
+        path: String,
+        error: String,
+    },
+}
+
+// Run a one-off command, returning the process exit status
+pub async fn run(command: Command, config: Config) -> Result<i32, DetectionError> {
+    match command {
+        Command::Serve => Err("serve is not a CLI scan command".into()),
+        Command::Detect(options) => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            let scanner = Scanner::load(config, options.profile.as_deref()).await?;
+
+            let (Detection { entities, redacted_text }, text) = tokio::task::spawn_blocking(move || {
+                scanner.detect(&[text.as_str()]).map(|mut detections| (detections.remove(0), text))
+            })
+            .await??;
+            let found = !entities.is_empty();
+            if options.format == OutputFormat::Sarif {
+                let findings: Vec<sarif::Finding> = entities
+                    .iter()
+                    .map(|entity| sarif::Finding {
+                        uri: "stdin",
+                        entity,
+                        text: Some(&text),
+                        section: None,
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&sarif::log(&findings))?);
+                return Ok(exit_status(found, &options));
+            }
+            if options.format == OutputFormat::Conll {
+                print!("{}", conll::to_conll(&text, &entities));
+                return Ok(exit_status(found, &options));
+            }
+            if options.format == OutputFormat::Presidio {
+                println!("{}", serde_json::to_string_pretty(&presidio::results(&text, &entities))?);
+                return Ok(exit_status(found, &options));
+            }
+            if options.format == OutputFormat::Html {
+                let section = ReportSection {
+                    heading: None,
+                    text: &text,
+                    entities: entities.iter().collect(),
+                };
+                print!("{}", report::report("stdin", &[section]));
+                return Ok(exit_status(found, &options));
+            }
+            let output = DetectOutput {
+                total_entities: entities.len(),
+                entities,
+                redacted_text,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            Ok(exit_status(found, &options))
+        }
+        Command::ScanDir(dir, options) => {
+            let format = dir_format(options.format)?;
+            let scanner = Scanner::load(config, options.profile.as_deref()).await?;
+            let found = tokio::task::spawn_blocking(move || scan_dir(&scanner, &dir, format)).await??;
+            Ok(exit_status(found, &options))
+        }
+        Command::ScanDiff(repo, options) => {
+            let diff = match repo {
+                Some(repo) => staged_diff(&repo)?,
+                None => {
+                    let mut diff = String::new();
+                    std::io::stdin().read_to_string(&mut diff)?;
+                    diff
+                }
+            };
+            let blocks = diff::added_blocks(&diff);
+            let findings = if blocks.is_empty() {
+                Vec::new()
+            } else {
+                let scanner = Scanner::load(config, options.profile.as_deref()).await?;
+                tokio::task::spawn_blocking(move || {
+                    let texts: Vec<&str> = blocks.iter().map(|block| block.text.as_str()).collect();
+                    let detections = scanner.detect(&texts)?;
+                    let results = detections.into_iter().map(|detection| detection.entities).collect();
+                    Ok::<_, DetectionError>(diff::findings(&blocks, results))
+                })
+                .await??
+            };
+            let found = !findings.is_empty();
+            let output = DiffOutput {
+                total_entities: findings.len(),
+                findings,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            Ok(exit_status(found, &options))
+        }
+        Command::ScanLog(file, options) => {
+            let scanner = Scanner::load(config, options.profile.as_deref()).await?;
+            let summary = tokio::task::spawn_blocking(move || -> Result<LogSummary, DetectionError> {
+                match file {
+                    Some(file) => scan_log(&scanner, std::io::BufReader::new(std::fs::File::open(file)?)),
+                    None => scan_log(&scanner, std::io::stdin().lock()),
+                }
+            })
+            .await??;
+            Ok(exit_status(summary.total_hits > 0, &options))
+        }
+        #[cfg(feature = "kafka")]
+        Command::Kafka(options) => {
+            crate::kafka::run(config, options.profile).await?;
+            Ok(0)
+        }
+        #[cfg(not(feature = "kafka"))]
+        Command::Kafka(_) => Err("kafka mode needs a build with the kafka feature".into()),
+    }
+}
+
+// Scan a log in batches of lines, printing a JSON line per line with findings
+// as each batch completes, then the summary. Lines too long to scan are
+// reported on stderr and counted as skipped.
+fn scan_log(scanner: &Scanner, mut reader: impl BufRead) -> Result<LogSummary, DetectionError> {
+    let config = scanner.service.config();
+    let mut batcher = LineBatcher::new(config.max_batch_documents);
+    let mut summary = LogSummary::default();
+    let mut stdout = std::io::stdout().lock();
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        let finished = reader.read_until(b'\n', &mut buffer)? == 0;
+        let batch = if finished {
+            batcher.finish()
+        } else {
+            let line = String::from_utf8_lossy(buffer.strip_suffix(b"\n").unwrap_or(&buffer[..])).into_owned();
+            match batcher.push(line) {
+                Some(batch) => batch,
+                None => continue,
+            }
+        };
+
+        let mut lines: Vec<LogLine> = Vec::with_capacity(batch.len());
+        for line in batch {
+            match validation::validate_text(&line.text, config) {
+                Ok(()) => lines.push(line),
+                Err(e) => {
+                    summary.lines_skipped += 1;
+                    eprintln!("Skipping line {}: {}", line.line, e);
+                }
+            }
+        }
+        if !lines.is_empty() {
+            let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+            for (line, detection) in lines.iter().zip(scanner.detect(&texts)?) {
+                summary.record(&detection.entities);
+                if !detection.entities.is_empty() {
+                    let finding = LogFinding {
+                        line: line.line,
+                        entities: detection.entities,
+                    };
+                    writeln!(stdout, "{}", serde_json::to_string(&finding)?)?;
+                }
+            }
+        }
+        if finished {
+            break;
+        }
+    }
+    summary.lines_read = batcher.lines_read();
+    writeln!(stdout, "{}", serde_json::to_string(&SummaryLine { summary: summary.clone() })?)?;
+    Ok(summary)
+}
+
+// Changes staged in a git repository, as a unified diff without context lines
+fn staged_diff(repo: &Path) -> Result<String, DetectionError> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["diff", "--cached", "--no-color", "--no-ext-diff", "--unified=0"])
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git diff failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn exit_status(found: bool, options: &ScanOptions) -> i32 {
+    if found && options.fail_on_findings {
+        1
+    } else {
+        0
+    }
+}
+
+// Scan files one at a time, printing a JSON line per file, or one SARIF log or
+// HTML report at the end (files that could not be read are then reported on
+// stderr); returns whether anything was found
+fn scan_dir(scanner: &Scanner, dir: &Path, output_format: DirFormat) -> Result<bool, DetectionError> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut found = false;
+    let mut scanned = Vec::new();
+    let mut stdout = std::io::stdout().lock();
+    for (path, format) in files {
+        let display = path.display().to_string();
+        if output_format != DirFormat::Json {
+            match scan_file(scanner, &path, format) {
+                Ok((entities, text)) => {
+                    found |= !entities.is_empty();
+                    scanned.push((artifact_uri(dir, &path), entities, text));
+                }
+                Err(e) => eprintln!("Skipping {}: {}", display, e),
+            }
+            continue;
+        }
+        let output = match scan_file(scanner, &path, format) {
+            Ok((entities, _)) => {
+                found |= !entities.is_empty();
+                FileOutput::Scanned {
+                    path: display,
+                    format,
+                    total_entities: entities.len(),
+                    entities,
+                }
+            }
+            Err(e) => FileOutput::Failed {
+                path: display,
+                error: e.to_string(),
+            },
+        };
+        writeln!(stdout, "{}", serde_json::to_string(&output)?)?;
+    }
+
+    match output_format {
+        DirFormat::Json => {}
+        DirFormat::Sarif => {
+            // Lines and columns are only given for files scanned as a whole
+            let wholes: Vec<Option<String>> = scanned
+                .iter()
+                .map(|(_, _, text)| (text.unit == SectionUnit::File).then(|| text.sections.concat()))
+                .collect();
+            let findings: Vec<sarif::Finding> = scanned
+                .iter()
+                .zip(&wholes)
+                .flat_map(|((uri, entities, _), whole)| sarif::file_findings(uri, entities, whole.as_deref()))
+                .collect();
+            writeln!(stdout, "{}", serde_json::to_string_pretty(&sarif::log(&findings))?)?;
+        }
+        DirFormat::Html => {
+            let sections: Vec<ReportSection> = scanned
+                .iter()
+                .flat_map(|(uri, entities, text)| report::file_sections(Some(uri), text, entities))
+                .collect();
+            write!(stdout, "{}", report::report(&dir.display().to_string(), &sections))?;
+        }
+    }
+    Ok(found)
+}
+
+// Path of a scanned file relative to the scanned directory, with forward
+// slashes, as SARIF consumers expect
+fn artifact_uri(dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    let components: Vec<String> = relative.components().map(|part| part.as_os_str().to_string_lossy().into_owned()).collect();
+    components.join("/")
+}
+
+// Entities found in a file, with the text they were found in
+fn scan_file(scanner: &Scanner, path: &Path, format: DocumentFormat) -> Result<(Vec<FileEntity>, ExtractedText), DetectionError> {
+    let extracted = extract::extract_text(format, std::fs::read(path)?)?;
+    let (unit, sections, parts) = (extracted.unit, &extracted.sections, &extracted.parts);
+    let scanned: Vec<usize> = (0..sections.len()).filter(|&index| !sections[index].trim().is_empty()).collect();
+    let texts: Vec<&str> = scanned.iter().map(|&index| sections[index].as_str()).collect();
+
+    let mut entities = Vec::new();
+    for (&section, detection) in scanned.iter().zip(scanner.detect(&texts)?) {
+        entities.extend(detection.entities.into_iter().map(|entity| FileEntity {
+            entity,
+            page: (unit == SectionUnit::Page).then_some(section + 1),
+            paragraph: (unit == SectionUnit::Paragraph).then_some(section),
+            part: parts.get(section).cloned(),
+            bbox: None,
+        }));
+    }
+    Ok((entities, extracted))
+}
+
+// Files under `dir` in a format text can be extracted from, skipping hidden
+// entries and not following symlinks
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, DocumentFormat)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if file_type.is_file() {
+            let format = path.to_str().and_then(DocumentFormat::from_filename);
+            if let Some(format) = format.filter(|format| *format != DocumentFormat::Image) {
+                files.push((path, format));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse_args(&[]), Ok(Command::Serve));
+        assert_eq!(parse_args(&args(&["serve"])), Ok(Command::Serve));
+        assert_eq!(
+            parse_args(&args(&["detect", "--profile", "gdpr"])),
+            Ok(Command::Detect(ScanOptions {
+                profile: Some("gdpr".to_string()),
+                fail_on_findings: false,
+                format: OutputFormat::Json,
+            }))
+        );
+        assert_eq!(
+            parse_args(&args(&["scan-dir", "--fail-on-findings", "docs", "--format", "sarif"])),
+            Ok(Command::ScanDir(
+                PathBuf::from("docs"),
+                ScanOptions {
+                    profile: None,
+                    fail_on_findings: true,
+                    format: OutputFormat::Sarif,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_args(&args(&["detect", "--format", "presidio"])),
+            Ok(Command::Detect(ScanOptions {
+                format: OutputFormat::Presidio,
+                ..ScanOptions::default()
+            }))
+        );
+        assert_eq!(
+            parse_args(&args(&["scan-diff", "--fail-on-findings"])),
+            Ok(Command::ScanDiff(
+                None,
+                ScanOptions {
+                    fail_on_findings: true,
+                    ..ScanOptions::default()
+                }
+            ))
+        );
+        assert_eq!(
+            parse_args(&args(&["scan-log", "app.log", "--profile", "pci"])),
+            Ok(Command::ScanLog(
+                Some(PathBuf::from("app.log")),
+                ScanOptions {
+                    profile: Some("pci".to_string()),
+                    ..ScanOptions::default()
+                }
+            ))
+        );
+        assert_eq!(
+            parse_args(&args(&["scan-diff", "."])),
+            Ok(Command::ScanDiff(Some(PathBuf::from(".")), ScanOptions::default()))
+        );
+        assert_eq!(
+            parse_args(&args(&["kafka", "--profile", "gdpr"])),
+            Ok(Command::Kafka(ScanOptions {
+                profile: Some("gdpr".to_string()),
+                ..ScanOptions::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_args(&args(&["scan-dir"])).is_err());
+        assert!(parse_args(&args(&["detect", "--profile"])).is_err());
+        assert!(parse_args(&args(&["detect", "--verbose"])).is_err());
+        assert!(parse_args(&args(&["detect", "--format", "xml"])).is_err());
+        assert!(parse_args(&args(&["scan-diff", "--format", "sarif"])).is_err());
+        assert!(parse_args(&args(&["scan-log", "--format", "html"])).is_err());
+        assert!(parse_args(&args(&["scan-dir", "docs", "--format", "conll"])).is_err());
+        assert!(parse_args(&args(&["scan-dir", "docs", "--format", "presidio"])).is_err());
+        assert!(parse_args(&args(&["kafka", "logs"])).is_err());
+        assert!(parse_args(&args(&["kafka", "--fail-on-findings"])).is_err());
+        assert!(parse_args(&args(&["train"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_rejects_commands_it_cannot_run() {
+        assert!(run(Command::Serve, Config::default()).await.is_err());
+
+        // Refused before any model is loaded
+        let options = ScanOptions {
+            format: OutputFormat::Presidio,
+            ..ScanOptions::default()
+        };
+        let error = run(Command::ScanDir(PathBuf::from("docs"), options), Config::default()).await.unwrap_err();
+        assert_eq!(error.to_string(), DETECT_ONLY_FORMATS);
+    }
+
+    #[test]
+    fn test_collect_files_skips_unsupported_and_hidden() {
+        let root = std::env::temp_dir().join(format!("gliner-cli-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        for name in ["a.txt", "nested/b.md", "nested/c.bin", ".git/d.txt"] {
+            std::fs::write(root.join(name), "Jane Roe").unwrap();
+        }
+
+        let mut files = Vec::new();
+        collect_files(&root, &mut files).unwrap();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        let names: Vec<PathBuf> = files.iter().map(|(path, _)| path.strip_prefix(&root).unwrap().to_path_buf()).collect();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(names, vec![PathBuf::from("a.txt"), PathBuf::from("nested/b.md")]);
+    }
+}
@@ -0,0 +1,338 @@
+// Typed client for the v1 HTTP API, so Rust services calling a deployed
+// instance do not each hand-roll requests and error handling. Only built with
+// the `client` feature; it does not need the `server` one.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::ErrorResponse;
+use crate::logging::REQUEST_ID_HEADER;
+use crate::PiiEntity;
+
+// Body of POST /v1/pii/detect
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DetectRequest {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // Named label profile, e.g. "hipaa"; findings are also masked per the
+    // profile into `redacted_text`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    // BCP 47 locale of the text, e.g. "en-US"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+impl DetectRequest {
+    pub fn new(text: impl Into<String>) -> Self {
+        DetectRequest {
+            text: text.into(),
+            ..DetectRequest::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DetectResult {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub entities: Vec<PiiEntity>,
+    pub total_entities: usize,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub redacted_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchDocument {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+// Body of POST /v1/pii/detect/batch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchRequest {
+    pub documents: Vec<BatchDocument>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+impl BatchRequest {
+    pub fn new<S: Into<String>>(texts: impl IntoIterator<Item = S>) -> Self {
+        BatchRequest {
+            documents: texts
+                .into_iter()
+                .map(|text| BatchDocument {
+                    text: text.into(),
+                    id: None,
+                })
+                .collect(),
+            ..BatchRequest::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentResult {
+    pub index: usize,
+    #[serde(default)]
+    pub id: Option<String>,
+    pub entities: Vec<PiiEntity>,
+    pub total_entities: usize,
+    #[serde(default)]
+    pub redacted_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchResult {
+    pub results: Vec<DocumentResult>,
+    pub total_documents: usize,
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+// The server's `error_code` values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    ModelNotLoaded,
+    InvalidInput,
+    InferenceFailed,
+    PayloadTooLarge,
+    Timeout,
+    QueueFull,
+    Draining,
+    Unauthorized,
+    NotFound,
+    StorageFailed,
+    CircuitOpen,
+    // A code this client does not know yet
+    Other(String),
+}
+
+impl ErrorCode {
+    pub fn parse(code: &str) -> Self {
+        match code {
+            "model_not_loaded" => ErrorCode::ModelNotLoaded,
+            "invalid_input" => ErrorCode::InvalidInput,
+            "inference_failed" => ErrorCode::InferenceFailed,
+            "payload_too_large" => ErrorCode::PayloadTooLarge,
+            "timeout" => ErrorCode::Timeout,
+            "queue_full" => ErrorCode::QueueFull,
+            "draining" => ErrorCode::Draining,
+            "unauthorized" => ErrorCode::Unauthorized,
+            "not_found" => ErrorCode::NotFound,
+            "storage_failed" => ErrorCode::StorageFailed,
+            "circuit_open" => ErrorCode::CircuitOpen,
+            other => ErrorCode::Other(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    // The server answered with an error body
+    Api {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+        // Diagnostics such as a timeout's completed batches
+        details: Option<serde_json::Value>,
+        // The server's id for the failed request, to find it in its logs
+        request_id: Option<String>,
+    },
+    // The request could not be sent or its response not read
+    Http(reqwest::Error),
+    // The response did not have the expected shape
+    Decode(String),
+}
+
+impl ClientError {
+    // Whether the same request may succeed later, possibly on another instance
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Api { code, .. } => matches!(
+                code,
+                ErrorCode::ModelNotLoaded
+                    | ErrorCode::Timeout
+                    | ErrorCode::QueueFull
+                    | ErrorCode::Draining
+                    | ErrorCode::CircuitOpen
+            ),
+            ClientError::Http(e) => e.is_timeout() || e.is_connect(),
+            ClientError::Decode(_) => false,
+        }
+    }
+
+    // Error from a failed response's body, with the id from its `X-Request-Id`
+    // header when the body has none (e.g. from a proxy in front of the server)
+    fn from_body(status: u16, body: &[u8], request_id: Option<String>) -> Self {
+        match serde_json::from_slice::<ErrorResponse>(body) {
+            Ok(error) => ClientError::Api {
+                status,
+                code: ErrorCode::parse(&error.error_code),
+                message: error.message.unwrap_or_default(),
+                details: error.data,
+                request_id: error.request_id.or(request_id),
+            },
+            Err(_) => ClientError::Api {
+                status,
+                code: ErrorCode::Other(format!("http_{}", status)),
+                message: String::from_utf8_lossy(body).into_owned(),
+                details: None,
+                request_id,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Api { status, code, message, request_id: Some(request_id), .. } => {
+                write!(f, "server answered {} ({:?}) to request {}: {}", status, code, request_id, message)
+            }
+            ClientError::Api { status, code, message, .. } => write!(f, "server answered {} ({:?}): {}", status, code, message),
+            ClientError::Http(e) => write!(f, "request failed: {}", e),
+            ClientError::Decode(message) => write!(f, "unexpected response: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Http(e)
+    }
+}
+
+#[derive(Deserialize)]
+struct Envelope<T> {
+    data: Option<T>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GlinerClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl GlinerClient {
+    // Client for the instance at `base_url`, e.g. "http://127.0.0.1:8000"
+    pub fn new(base_url: impl Into<String>) -> Self {
+        GlinerClient::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    // Client reusing a configured reqwest client, e.g. one with timeouts or
+    // default headers
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        GlinerClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+        }
+    }
+
+    pub async fn detect(&self, request: &DetectRequest) -> Result<DetectResult, ClientError> {
+        self.post("/v1/pii/detect", request).await
+    }
+
+    pub async fn detect_batch(&self, request: &BatchRequest) -> Result<BatchResult, ClientError> {
+        self.post("/v1/pii/detect/batch", request).await
+    }
+
+    // The text with findings masked as the profile says
+    pub async fn redact(&self, text: impl Into<String>, profile: impl Into<String>) -> Result<String, ClientError> {
+        let request = DetectRequest {
+            profile: Some(profile.into()),
+            ..DetectRequest::new(text)
+        };
+        self.detect(&request)
+            .await?
+            .redacted_text
+            .ok_or_else(|| ClientError::Decode("response has no redacted_text".to_string()))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, ClientError> {
+        let response = self.http.post(self.url(path)).json(body).send().await?;
+        let status = response.status();
+        let request_id = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let bytes = response.bytes().await?;
+        if !status.is_success() {
+            return Err(ClientError::from_body(status.as_u16(), &bytes, request_id));
+        }
+        let envelope: Envelope<T> = serde_json::from_slice(&bytes).map_err(|e| ClientError::Decode(e.to_string()))?;
+        envelope.data.ok_or_else(|| ClientError::Decode("response has no data".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiError;
+
+    #[test]
+    fn test_every_server_error_code_is_known() {
+        let errors = [
+            ApiError::ModelNotLoaded,
+            ApiError::InvalidInput(String::new()),
+            ApiError::InferenceFailed(String::new()),
+            ApiError::PayloadTooLarge(String::new()),
+            ApiError::Timeout { timeout_ms: 0, elapsed_ms: 0, completed_batches: 0, total_batches: 0 },
+            ApiError::Overloaded { queue_depth: 0, capacity: 0 },
+            ApiError::Draining,
+            ApiError::Unauthorized,
+            ApiError::NotFound(String::new()),
+            ApiError::StorageFailed(String::new()),
+            ApiError::CircuitOpen,
+        ];
+        for error in errors {
+            assert!(!matches!(ErrorCode::parse(error.error_code()), ErrorCode::Other(_)), "{}", error.error_code());
+        }
+    }
+
+    #[test]
+    fn test_error_body_is_typed() {
+        let body = serde_json::to_vec(&ApiError::Overloaded { queue_depth: 8, capacity: 8 }.body()).unwrap();
+        let error = ClientError::from_body(429, &body, Some("6710a1f3-2a".to_string()));
+        assert!(error.is_retryable());
+        match error {
+            ClientError::Api { status, code, details, request_id, .. } => {
+                assert_eq!(status, 429);
+                assert_eq!(request_id.as_deref(), Some("6710a1f3-2a"));
+                assert_eq!(code, ErrorCode::QueueFull);
+                assert_eq!(details.unwrap()["capacity"], 8);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let error = ClientError::from_body(502, b"Bad Gateway", None);
+        assert!(matches!(error, ClientError::Api { code: ErrorCode::Other(ref code), .. } if code == "http_502"));
+    }
+
+    #[test]
+    fn test_detect_result_reads_the_detect_shape() {
+        let data = serde_json::json!({
+            "text": "Call Jane Roe",
+            "entities": [{"text": "Jane Roe", "label": "person", "sequence": 0, "probability": 0.9, "start": 5, "end": 13}],
+            "total_entities": 1,
+            "message": "PII detection completed successfully"
+        });
+        let result: DetectResult = serde_json::from_value(data).unwrap();
+        assert_eq!(result.entities[0].confidence, 0.9);
+        assert_eq!(GlinerClient::new("http://localhost:8000/").url("/v1/pii/detect"), "http://localhost:8000/v1/pii/detect");
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Source of time for the time-based parts of the service (drain grace periods,
+// retention ages and scan schedules). Production uses the system clock; tests swap in a TestClock and move
+// time forward explicitly instead of sleeping.
+pub trait Clock: Send + Sync {
+    // Monotonic time, for deadlines and elapsed-time checks
+    fn now(&self) -> Instant;
+    // Wall-clock Unix timestamp in seconds, for anything reported to clients
+    fn unix_now(&self) -> u64;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+// A clock that stands still until advanced. Both readings move together, so
+// monotonic deadlines and reported timestamps stay consistent.
+pub struct TestClock {
+    origin: Instant,
+    unix_origin: u64,
+    elapsed: Mutex<Duration>,
+}
+
+impl TestClock {
+    pub fn new() -> Arc<Self> {
+        Arc::new(TestClock {
+            origin: Instant::now(),
+            unix_origin: SystemClock.unix_now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        })
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) += by;
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    fn unix_now(&self) -> u64 {
+        self.unix_origin + self.elapsed().as_secs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_only_moves_when_advanced() {
+        let clock = TestClock::new();
+        let start = clock.now();
+        let unix_start = clock.unix_now();
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(clock.now() - start, Duration::from_secs(90));
+        assert_eq!(clock.unix_now() - unix_start, 90);
+    }
+}
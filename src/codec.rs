@@ -0,0 +1,227 @@
+use rocket::data::{self, Data, FromData, Limits};
+use rocket::http::{ContentType, MediaType, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::ApiError;
+
+// Body encodings the detection endpoints accept and answer with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl BodyFormat {
+    // Encoding named by a Content-Type or Accept media type; anything
+    // unrecognised is treated as JSON
+    pub fn of(media_type: &MediaType) -> Self {
+        if media_type.top() != "application" {
+            return BodyFormat::Json;
+        }
+        match media_type.sub().as_str().to_ascii_lowercase().as_str() {
+            "msgpack" | "x-msgpack" | "vnd.msgpack" => BodyFormat::MessagePack,
+            "cbor" => BodyFormat::Cbor,
+            _ => BodyFormat::Json,
+        }
+    }
+
+    // Protobuf bodies need a schema, so they go to the dedicated
+    // `protobuf` feature routes instead
+    pub fn is_protobuf(media_type: &MediaType) -> bool {
+        media_type.top() == "application" && matches!(media_type.sub().as_str(), "x-protobuf" | "protobuf")
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BodyFormat::Json => "JSON",
+            BodyFormat::MessagePack => "MessagePack",
+            BodyFormat::Cbor => "CBOR",
+        }
+    }
+
+    pub fn content_type(self) -> ContentType {
+        match self {
+            BodyFormat::Json => ContentType::JSON,
+            BodyFormat::MessagePack => ContentType::MsgPack,
+            BodyFormat::Cbor => ContentType::new("application", "cbor"),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, ApiError> {
+        let decoded = match self {
+            BodyFormat::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+            BodyFormat::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+            BodyFormat::Cbor => ciborium::from_reader(bytes).map_err(|e| e.to_string()),
+        };
+        decoded.map_err(|e| ApiError::InvalidInput(format!("request body is not valid {}: {}", self.name(), e)))
+    }
+
+    // Struct fields are encoded by name, so MessagePack and CBOR bodies have the
+    // same shape as the JSON ones
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            BodyFormat::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            BodyFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+            BodyFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| e.to_string())?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+// Whole request body, within the JSON size limit whatever its encoding
+async fn read_body(request: &Request<'_>, data: Data<'_>) -> Result<Vec<u8>, (Status, ApiError)> {
+    let limit = request.limits().get("json").unwrap_or(Limits::JSON);
+    match data.open(limit).into_bytes().await {
+        Ok(bytes) if bytes.is_complete() => Ok(bytes.into_inner()),
+        Ok(_) => Err((
+            Status::PayloadTooLarge,
+            ApiError::PayloadTooLarge(format!("request body exceeds {} bytes", limit.as_u64())),
+        )),
+        Err(e) => Err((Status::BadRequest, ApiError::InvalidInput(format!("failed to read request body: {}", e)))),
+    }
+}
+
+// Request body decoded according to its Content-Type: JSON, MessagePack or
+// CBOR
+pub struct Body<T>(pub T);
+
+impl<T> Body<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Body<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for Body<T> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        if request.content_type().is_some_and(|content_type| BodyFormat::is_protobuf(content_type.media_type())) {
+            return data::Outcome::Forward((data, Status::UnsupportedMediaType));
+        }
+        let format = request.content_type().map(|content_type| BodyFormat::of(content_type.media_type())).unwrap_or_default();
+        let bytes = match read_body(request, data).await {
+            Ok(bytes) => bytes,
+            Err(error) => return data::Outcome::Error(error),
+        };
+        match format.decode(&bytes) {
+            Ok(value) => data::Outcome::Success(Body(value)),
+            Err(error) => data::Outcome::Error((Status::UnprocessableEntity, error)),
+        }
+    }
+}
+
+// Response encoded in the caller's preferred Accept type: MessagePack, CBOR,
+// or JSON otherwise. Errors are always JSON.
+pub struct Encoded<T>(pub T);
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Encoded<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let format = request.accept().map(|accept| BodyFormat::of(accept.preferred().media_type())).unwrap_or_default();
+        if format == BodyFormat::Json {
+            return Json(self.0).respond_to(request);
+        }
+        let bytes = format.encode(&self.0).map_err(|e| {
+            tracing::error!(format = format.name(), error = %e, "Failed to encode response");
+            Status::InternalServerError
+        })?;
+        (format.content_type(), bytes).respond_to(request)
+    }
+}
+
+// Protobuf request body
+#[cfg(feature = "protobuf")]
+pub struct Protobuf<T>(pub T);
+
+#[cfg(feature = "protobuf")]
+impl<T> Protobuf<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "protobuf")]
+#[rocket::async_trait]
+impl<'r, T: prost::Message + Default> FromData<'r> for Protobuf<T> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let bytes = match read_body(request, data).await {
+            Ok(bytes) => bytes,
+            Err(error) => return data::Outcome::Error(error),
+        };
+        match T::decode(bytes.as_slice()) {
+            Ok(value) => data::Outcome::Success(Protobuf(value)),
+            Err(e) => {
+                let error = ApiError::InvalidInput(format!("request body is not a valid protobuf message: {}", e));
+                data::Outcome::Error((Status::UnprocessableEntity, error))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl<'r, T: prost::Message> Responder<'r, 'static> for Protobuf<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        (ContentType::new("application", "x-protobuf"), self.0.encode_to_vec()).respond_to(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PiiEntity;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_media_types() {
+        assert_eq!(BodyFormat::of(&MediaType::MsgPack), BodyFormat::MessagePack);
+        assert_eq!(BodyFormat::of(&MediaType::new("application", "x-msgpack")), BodyFormat::MessagePack);
+        assert_eq!(BodyFormat::of(&MediaType::new("application", "cbor")), BodyFormat::Cbor);
+        assert_eq!(BodyFormat::of(&MediaType::JSON), BodyFormat::Json);
+        assert_eq!(BodyFormat::of(&MediaType::Any), BodyFormat::Json);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_field_names() {
+        let mut attributes = BTreeMap::new();
+        attributes.insert("network".to_string(), "visa".to_string());
+        let entity = PiiEntity {
+            text: "4111 1111 1111 1111".to_string(),
+            label: "credit card number".to_string(),
+            confidence: 0.5,
+            start: 3,
+            end: 22,
+            attributes,
+        };
+        for format in [BodyFormat::Json, BodyFormat::MessagePack, BodyFormat::Cbor] {
+            let bytes = format.encode(&entity).unwrap();
+            let decoded: serde_json::Value = format.decode(&bytes).unwrap();
+            assert_eq!(decoded["label"], "credit card number", "{}", format.name());
+            assert_eq!(decoded["attributes"]["network"], "visa", "{}", format.name());
+        }
+    }
+
+    #[test]
+    fn test_decode_error_names_the_format() {
+        let error = BodyFormat::Cbor.decode::<serde_json::Value>(&[0xff]).unwrap_err();
+        assert!(error.to_string().contains("CBOR"));
+    }
+}
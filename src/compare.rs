@@ -0,0 +1,155 @@
+// Side-by-side comparison of two loaded models (the primary and a canary or
+// shadow model) on the same texts, served by POST /api/admin/compare to help
+// decide whether a model upgrade finds what the current model finds.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::PiiEntity;
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub texts: Vec<String>,
+    // Model names as GET /model, the canary and the shadow report them; the
+    // primary is compared against the other loaded model when left out
+    #[serde(default)]
+    pub model_a: Option<String>,
+    #[serde(default)]
+    pub model_b: Option<String>,
+    #[serde(default)]
+    pub labels: Option<Vec<String>>,
+}
+
+// A finding of either model, matched on label and offsets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparedEntity {
+    pub text: String,
+    pub label: String,
+    pub start: usize,
+    pub end: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_a: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_b: Option<f32>,
+    // score_b - score_a, when both found it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LabelAgreement {
+    pub matched: usize,
+    pub only_a: usize,
+    pub only_b: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonSummary {
+    pub matched: usize,
+    pub only_a: usize,
+    pub only_b: usize,
+    // Matched findings over all findings; 1.0 when neither model found anything
+    pub agreement: f64,
+    // Mean of |delta| over matched findings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean_abs_delta: Option<f64>,
+    pub by_label: BTreeMap<String, LabelAgreement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelComparison {
+    pub model_a: String,
+    pub model_b: String,
+    // Per text, in request order
+    pub documents: Vec<Vec<ComparedEntity>>,
+    pub summary: ComparisonSummary,
+}
+
+// Line up the findings of both models on one text, in text order
+pub fn side_by_side(a: &[PiiEntity], b: &[PiiEntity]) -> Vec<ComparedEntity> {
+    let mut merged: BTreeMap<(usize, usize, String), ComparedEntity> = BTreeMap::new();
+    for (entity, from_a) in a.iter().map(|entity| (entity, true)).chain(b.iter().map(|entity| (entity, false))) {
+        let compared = merged
+            .entry((entity.start, entity.end, entity.label.clone()))
+            .or_insert_with(|| ComparedEntity {
+                text: entity.text.clone(),
+                label: entity.label.clone(),
+                start: entity.start,
+                end: entity.end,
+                score_a: None,
+                score_b: None,
+                delta: None,
+            });
+        let score = if from_a { &mut compared.score_a } else { &mut compared.score_b };
+        *score = Some(score.map_or(entity.confidence, |seen: f32| seen.max(entity.confidence)));
+    }
+    merged
+        .into_values()
+        .map(|mut compared| {
+            compared.delta = compared.score_a.zip(compared.score_b).map(|(a, b)| b - a);
+            compared
+        })
+        .collect()
+}
+
+pub fn summarize(documents: &[Vec<ComparedEntity>]) -> ComparisonSummary {
+    let mut by_label: BTreeMap<String, LabelAgreement> = BTreeMap::new();
+    let mut deltas = Vec::new();
+    for compared in documents.iter().flatten() {
+        let counts = by_label.entry(compared.label.clone()).or_default();
+        match compared.delta {
+            Some(delta) => {
+                counts.matched += 1;
+                deltas.push(delta.abs() as f64);
+            }
+            None if compared.score_a.is_some() => counts.only_a += 1,
+            None => counts.only_b += 1,
+        }
+    }
+    let matched = by_label.values().map(|counts| counts.matched).sum();
+    let only_a = by_label.values().map(|counts| counts.only_a).sum();
+    let only_b = by_label.values().map(|counts| counts.only_b).sum();
+    let total = matched + only_a + only_b;
+    ComparisonSummary {
+        matched,
+        only_a,
+        only_b,
+        agreement: if total == 0 { 1.0 } else { matched as f64 / total as f64 },
+        mean_abs_delta: (!deltas.is_empty()).then(|| deltas.iter().sum::<f64>() / deltas.len() as f64),
+        by_label,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_side_by_side_with_deltas() {
+        let a = vec![entity("person", 0, 8, 0.9), entity("phone", 30, 42, 0.6)];
+        let b = vec![entity("person", 0, 8, 0.8), entity("email", 12, 28, 0.7)];
+        let compared = side_by_side(&a, &b);
+
+        assert_eq!(compared.len(), 3);
+        assert_eq!(compared[0].label, "person");
+        assert!((compared[0].delta.unwrap() + 0.1).abs() < 1e-6);
+        assert_eq!((compared[1].score_a, compared[1].score_b), (None, Some(0.7)));
+        assert_eq!((compared[2].score_a, compared[2].score_b), (Some(0.6), None));
+
+        let summary = summarize(&[compared]);
+        assert_eq!((summary.matched, summary.only_a, summary.only_b), (1, 1, 1));
+        assert!((summary.agreement - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.by_label["email"], LabelAgreement { matched: 0, only_a: 0, only_b: 1 });
+    }
+
+    #[test]
+    fn test_nothing_found_is_full_agreement() {
+        let summary = summarize(&[Vec::new()]);
+        assert_eq!(summary.agreement, 1.0);
+        assert!(summary.mean_abs_delta.is_none());
+    }
+}
@@ -0,0 +1,511 @@
+use std::str::FromStr;
+
+use crate::adjacent::JoinedScore;
+use crate::aliases::{LabelAlias, LabelPrompt};
+use crate::checksum::InvalidAction;
+use crate::discovery;
+use crate::language::LanguageModel;
+use crate::normalization::NormalizationForm;
+use crate::pipeline::{MergeStrategy, DETECTORS};
+
+// Default model served when GLINER_MODEL is not set
+pub const DEFAULT_MODEL: &str = "onnx-community/gliner-multitask-large-v0.5";
+
+// Sample text used to warm up the ONNX session before the instance reports ready
+pub const DEFAULT_WARMUP_TEXT: &str =
+    "My name is John Doe and my email is john@example.com. Call me at (555) 123-4567.";
+
+// Runtime configuration, read from GLINER_* environment variables
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub model_name: String,
+    // Start serving before the model is loaded, loading it in the background
+    pub lazy_load: bool,
+    // Hub missing model files are downloaded from, e.g. https://huggingface.co
+    pub model_hub_url: Option<String>,
+    // Second model serving a share (0-100) of single-text detections during a rollout
+    pub canary_model: Option<String>,
+    pub canary_percent: f64,
+    // Detect the language of single-text detections and report it
+    pub language_detection: bool,
+    // Models serving particular languages (ISO 639-3, or "*" for any other),
+    // e.g. "deu=urchade/gliner_multi-v2.1"; routing turns on detection
+    pub language_models: Vec<LanguageModel>,
+    // Model run after single-text detections, in the background, to record where
+    // it disagrees with the primary; on a share (0-100) of them
+    pub shadow_model: Option<String>,
+    pub shadow_percent: f64,
+    // Examples kept for fine-tuning (0 disables collection): a share (0-100) of
+    // detections with a finding scored below the confidence bound
+    pub feedback_capacity: usize,
+    pub feedback_confidence_below: f32,
+    pub feedback_sample_percent: f64,
+    // Findings scored in [min, max) wait for a reviewer; 0 capacity disables review
+    pub review_capacity: usize,
+    pub review_confidence_min: f32,
+    pub review_confidence_max: f32,
+    pub warmup_text: String,
+    pub warmup_runs: usize,
+    // Readiness self-test: latency above which the instance reports degraded,
+    // and how long a result is reused
+    pub self_test_max_ms: u64,
+    pub self_test_cache_secs: u64,
+    // Circuit breaker: recent inferences looked at (0 turns it off), how many
+    // are needed before it can open, the failure rate that opens it, and the
+    // wait between attempts to reload the model while it is open
+    pub breaker_window: usize,
+    pub breaker_min_requests: usize,
+    pub breaker_failure_rate: f64,
+    pub breaker_retry_secs: u64,
+    pub max_chunk_chars: usize,
+    pub chunk_overlap: usize,
+    pub inference_batch_size: usize,
+    // Upper token-count bounds of the length buckets chunks are grouped into
+    // before batching; empty disables bucketing
+    pub length_buckets: Vec<usize>,
+    pub max_text_chars: usize,
+    // Normalization of request text before detection (none, nfc or nfkc),
+    // removal of zero-width characters, and folding of look-alike letters
+    pub normalization_form: NormalizationForm,
+    pub strip_zero_width: bool,
+    pub fold_confusables: bool,
+    pub max_batch_documents: usize,
+    // Upper bound on JSON request bodies, enforced by Rocket before parsing
+    pub max_payload_bytes: u64,
+    // Upper bound on multipart file uploads
+    pub max_upload_bytes: u64,
+    // Most files unpacked from one uploaded archive, and the most bytes they may
+    // expand to in total (each member is also bound by max_upload_bytes)
+    pub max_archive_members: usize,
+    pub max_archive_bytes: u64,
+    // Most non-empty cells scanned from one CSV upload
+    pub max_csv_cells: usize,
+    // Upper bound on a streamed NDJSON request body
+    pub max_ndjson_bytes: u64,
+    // Upper bound on a streamed log file
+    pub max_log_bytes: u64,
+    // Most non-blank string values scanned from one JSON document
+    pub max_json_strings: usize,
+    // Report private, loopback and reserved IP addresses, not just public ones
+    pub ip_include_private: bool,
+    // Enrich valid card number findings with their network and a masked number
+    pub card_enrichment: bool,
+    // Checksum re-scoring of card numbers, IBANs and SSNs: added to the
+    // confidence of values that pass, and what happens to those that fail (with
+    // the factor a downgrade multiplies the confidence by)
+    pub checksum_valid_boost: f32,
+    pub checksum_invalid: InvalidAction,
+    pub checksum_downgrade: f32,
+    // Country identifier packs enabled for every request, e.g. ["uk", "de"]
+    pub recognizer_packs: Vec<String>,
+    // Detectors to run (see `pipeline::DETECTORS`) and how overlapping findings
+    // of different detectors are merged; `vote` keeps findings that at least
+    // `merge_min_votes` detectors agree on
+    pub detectors: Vec<String>,
+    pub merge_strategy: MergeStrategy,
+    pub merge_min_votes: usize,
+    // Join model spans of the same label at most this many characters apart
+    // (whitespace and punctuation only), e.g. "John" and "Doe"; None disables.
+    // The joined span's confidence is the min or mean of its parts.
+    pub join_max_gap: Option<usize>,
+    pub join_score: JoinedScore,
+    // Generic types POST /pii/discover asks the model for
+    pub discovery_labels: Vec<String>,
+    // JSON object of label to known terms, matched by the gazetteer detector
+    pub gazetteer_file: Option<String>,
+    // Caller-facing labels and the model labels they stand for, e.g.
+    // "customer_name=person"
+    pub label_aliases: Vec<LabelAlias>,
+    // Phrases the model is prompted with instead of a label, e.g.
+    // "email=email address"; findings keep the label
+    pub label_prompts: Vec<LabelPrompt>,
+    // WASM post-processing plugins run on every detection result, in order, and
+    // the instructions each run may execute
+    pub plugins: Vec<String>,
+    pub plugin_fuel: u64,
+    // Never echo submitted text: responses carry offsets and labels only, for
+    // every request rather than just those asking for it
+    pub privacy_mode: bool,
+    // OCR tools (used by builds with the `ocr` feature) for images and scanned
+    // PDFs: recognition languages as tesseract `-l` takes them, and the DPI PDF
+    // pages are rendered at
+    pub tesseract_path: String,
+    pub pdftoppm_path: String,
+    pub ocr_languages: String,
+    pub ocr_dpi: u32,
+    // First-pass screen deciding which chunks reach the main model: "off", "rules" or "model"
+    pub prepass: String,
+    pub prepass_model: Option<String>,
+    pub prepass_threshold: f32,
+    // Trade throughput for run-to-run reproducible results
+    pub deterministic: bool,
+    // Default per-request deadline, and the most a client may ask for via header
+    pub request_timeout_ms: u64,
+    pub max_request_timeout_ms: u64,
+    // Requests allowed to wait for or run inference at once before answering 429
+    pub max_queue_depth: usize,
+    // JSON array of extra label profiles, merged over the built-in presets
+    pub profiles_file: Option<String>,
+    // ONNX Runtime: intra-op threads (ORT default when unset), execution providers
+    // in order of preference, and allocator settings
+    pub threads: Option<usize>,
+    pub execution_providers: Vec<String>,
+    pub gpu_device_id: i32,
+    pub gpu_memory_limit: Option<usize>,
+    pub cpu_arena: bool,
+    // Independent ONNX sessions for the model, each serving one request at a time
+    pub sessions: usize,
+    // Sessions kept free of batch work so interactive requests don't wait
+    // behind bulk scans; batch work always gets at least one session
+    pub interactive_sessions: usize,
+    // Run as a supervisor keeping a primary worker and a warm standby process
+    pub supervisor: bool,
+    // Bearer token required by /api/admin routes; unset leaves them open
+    pub admin_token: Option<String>,
+    // How long a drained instance keeps admitting requests that were already routed to it
+    pub drain_grace_secs: u64,
+    // Peer instance to pull replicated configuration from, with its admin token
+    pub config_peer_url: Option<String>,
+    pub config_peer_token: Option<String>,
+    pub config_sync_interval_secs: u64,
+    // Log filter in RUST_LOG syntax, e.g. "debug" or "info,gliner_rs_api=debug",
+    // and the line format: "pretty" or "json"
+    pub log_level: String,
+    pub log_format: String,
+    // Where audit events of detection calls go: "off", "memory", "stdout", "file"
+    // (GLINER_AUDIT_FILE) or "database", and how many recent ones the admin endpoint keeps
+    pub audit_sink: String,
+    pub audit_file: Option<String>,
+    pub audit_recent: usize,
+    // PostgreSQL connection string for stored results, used by builds with the
+    // `persistence` feature
+    pub database_url: Option<String>,
+    // Time to live of stored results and of audit events, unset keeping them
+    // forever, and how often expired ones are swept
+    pub results_ttl_secs: Option<u64>,
+    pub audit_ttl_secs: Option<u64>,
+    pub retention_sweep_secs: u64,
+    // S3-compatible endpoint (e.g. MinIO) used instead of AWS for bucket/key scans,
+    // addressed path style
+    pub s3_endpoint: Option<String>,
+    // Buckets callers may name by bucket and key in /api/pii/scan/s3, which reads
+    // them with the service's own credentials; other objects need a presigned url
+    pub s3_allowed_buckets: Vec<String>,
+    // Blob endpoint including the account (e.g. Azurite's) used instead of
+    // https://{account}.blob.core.windows.net, by builds with the `azure` feature
+    pub azure_endpoint: Option<String>,
+    // Kafka consumer mode (`kafka` command, `kafka` feature): brokers, consumer
+    // group, topics, JSON pointer of the text to scan (unset scans the whole
+    // payload) and whether to "annotate" or "redact" messages
+    pub kafka_brokers: Option<String>,
+    pub kafka_group_id: String,
+    pub kafka_input_topic: Option<String>,
+    pub kafka_output_topic: Option<String>,
+    pub kafka_field: Option<String>,
+    pub kafka_action: String,
+    // Scheduled scans: runs kept per schedule, objects scanned per run, and the
+    // default url POSTed a notice when a run fails
+    pub schedule_history: usize,
+    pub schedule_max_objects: usize,
+    pub schedule_notify_url: Option<String>,
+    // Retries of an object that failed for a transient reason, and the wait
+    // before the first, doubled for each one after
+    pub schedule_retries: u32,
+    pub schedule_retry_backoff_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            model_name: DEFAULT_MODEL.to_string(),
+            lazy_load: false,
+            model_hub_url: None,
+            canary_model: None,
+            canary_percent: 5.0,
+            language_detection: false,
+            language_models: Vec::new(),
+            shadow_model: None,
+            shadow_percent: 100.0,
+            feedback_capacity: 0,
+            feedback_confidence_below: 0.7,
+            feedback_sample_percent: 10.0,
+            review_capacity: 0,
+            review_confidence_min: 0.5,
+            review_confidence_max: 0.7,
+            warmup_text: DEFAULT_WARMUP_TEXT.to_string(),
+            warmup_runs: 1,
+            self_test_max_ms: 1_000,
+            self_test_cache_secs: 10,
+            breaker_window: 20,
+            breaker_min_requests: 10,
+            breaker_failure_rate: 0.5,
+            breaker_retry_secs: 30,
+            max_chunk_chars: 1500,
+            chunk_overlap: 100,
+            inference_batch_size: 8,
+            length_buckets: vec![64, 128, 256],
+            max_text_chars: 100_000,
+            normalization_form: NormalizationForm::Nfc,
+            strip_zero_width: true,
+            fold_confusables: false,
+            max_batch_documents: 256,
+            max_payload_bytes: 5 * 1024 * 1024,
+            max_upload_bytes: 10 * 1024 * 1024,
+            max_archive_members: 1_000,
+            max_archive_bytes: 256 * 1024 * 1024,
+            max_csv_cells: 50_000,
+            max_ndjson_bytes: 1024 * 1024 * 1024,
+            max_log_bytes: 1024 * 1024 * 1024,
+            max_json_strings: 50_000,
+            ip_include_private: false,
+            card_enrichment: false,
+            checksum_valid_boost: 0.0,
+            checksum_invalid: InvalidAction::Keep,
+            checksum_downgrade: 0.5,
+            recognizer_packs: Vec::new(),
+            detectors: DETECTORS.iter().map(|name| name.to_string()).collect(),
+            merge_strategy: MergeStrategy::RulePriority,
+            merge_min_votes: 2,
+            join_max_gap: None,
+            join_score: JoinedScore::Min,
+            discovery_labels: discovery::DEFAULT_LABELS.iter().map(|label| label.to_string()).collect(),
+            gazetteer_file: None,
+            label_aliases: Vec::new(),
+            label_prompts: Vec::new(),
+            plugins: Vec::new(),
+            plugin_fuel: 50_000_000,
+            privacy_mode: false,
+            tesseract_path: "tesseract".to_string(),
+            pdftoppm_path: "pdftoppm".to_string(),
+            ocr_languages: "eng".to_string(),
+            ocr_dpi: 300,
+            prepass: "off".to_string(),
+            prepass_model: None,
+            prepass_threshold: 0.3,
+            deterministic: false,
+            request_timeout_ms: 30_000,
+            max_request_timeout_ms: 300_000,
+            max_queue_depth: 64,
+            profiles_file: None,
+            threads: None,
+            execution_providers: Vec::new(),
+            gpu_device_id: 0,
+            gpu_memory_limit: None,
+            cpu_arena: true,
+            sessions: 1,
+            interactive_sessions: 1,
+            supervisor: false,
+            admin_token: None,
+            drain_grace_secs: 30,
+            config_peer_url: None,
+            config_peer_token: None,
+            config_sync_interval_secs: 60,
+            log_level: "info,rocket=warn".to_string(),
+            log_format: "pretty".to_string(),
+            audit_sink: "off".to_string(),
+            audit_file: None,
+            audit_recent: 1_000,
+            database_url: None,
+            results_ttl_secs: None,
+            audit_ttl_secs: None,
+            retention_sweep_secs: 3_600,
+            s3_endpoint: None,
+            s3_allowed_buckets: Vec::new(),
+            azure_endpoint: None,
+            kafka_brokers: None,
+            kafka_group_id: "gliner-pii".to_string(),
+            kafka_input_topic: None,
+            kafka_output_topic: None,
+            kafka_field: None,
+            kafka_action: "annotate".to_string(),
+            schedule_history: 20,
+            schedule_max_objects: 1_000,
+            schedule_notify_url: None,
+            schedule_retries: 2,
+            schedule_retry_backoff_ms: 1_000,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let defaults = Config::default();
+        Config {
+            model_name: env_or("GLINER_MODEL", defaults.model_name),
+            lazy_load: env_or("GLINER_LAZY_LOAD", defaults.lazy_load),
+            model_hub_url: std::env::var("GLINER_MODEL_HUB_URL").ok().filter(|url| !url.is_empty()),
+            canary_model: std::env::var("GLINER_CANARY_MODEL").ok().filter(|model| !model.is_empty()),
+            canary_percent: env_or("GLINER_CANARY_PERCENT", defaults.canary_percent),
+            language_detection: env_or("GLINER_LANGUAGE_DETECTION", defaults.language_detection),
+            language_models: env_list("GLINER_LANGUAGE_MODELS", defaults.language_models),
+            shadow_model: std::env::var("GLINER_SHADOW_MODEL").ok().filter(|model| !model.is_empty()),
+            shadow_percent: env_or("GLINER_SHADOW_PERCENT", defaults.shadow_percent),
+            feedback_capacity: env_or("GLINER_FEEDBACK_CAPACITY", defaults.feedback_capacity),
+            feedback_confidence_below: env_or("GLINER_FEEDBACK_CONFIDENCE_BELOW", defaults.feedback_confidence_below),
+            feedback_sample_percent: env_or("GLINER_FEEDBACK_SAMPLE_PERCENT", defaults.feedback_sample_percent),
+            review_capacity: env_or("GLINER_REVIEW_CAPACITY", defaults.review_capacity),
+            review_confidence_min: env_or("GLINER_REVIEW_CONFIDENCE_MIN", defaults.review_confidence_min),
+            review_confidence_max: env_or("GLINER_REVIEW_CONFIDENCE_MAX", defaults.review_confidence_max),
+            warmup_text: env_or("GLINER_WARMUP_TEXT", defaults.warmup_text),
+            warmup_runs: env_or("GLINER_WARMUP_RUNS", defaults.warmup_runs),
+            self_test_max_ms: env_or("GLINER_SELF_TEST_MAX_MS", defaults.self_test_max_ms),
+            self_test_cache_secs: env_or("GLINER_SELF_TEST_CACHE_SECS", defaults.self_test_cache_secs),
+            breaker_window: env_or("GLINER_BREAKER_WINDOW", defaults.breaker_window),
+            breaker_min_requests: env_or("GLINER_BREAKER_MIN_REQUESTS", defaults.breaker_min_requests),
+            breaker_failure_rate: env_or("GLINER_BREAKER_FAILURE_RATE", defaults.breaker_failure_rate),
+            breaker_retry_secs: env_or("GLINER_BREAKER_RETRY_SECS", defaults.breaker_retry_secs),
+            max_chunk_chars: env_or("GLINER_MAX_CHUNK_CHARS", defaults.max_chunk_chars),
+            chunk_overlap: env_or("GLINER_CHUNK_OVERLAP", defaults.chunk_overlap),
+            inference_batch_size: env_or("GLINER_INFERENCE_BATCH_SIZE", defaults.inference_batch_size),
+            length_buckets: env_list("GLINER_LENGTH_BUCKETS", defaults.length_buckets),
+            max_text_chars: env_or("GLINER_MAX_TEXT_CHARS", defaults.max_text_chars),
+            normalization_form: env_or("GLINER_NORMALIZATION", defaults.normalization_form),
+            strip_zero_width: env_or("GLINER_STRIP_ZERO_WIDTH", defaults.strip_zero_width),
+            fold_confusables: env_or("GLINER_FOLD_CONFUSABLES", defaults.fold_confusables),
+            max_batch_documents: env_or("GLINER_MAX_BATCH_DOCUMENTS", defaults.max_batch_documents),
+            max_payload_bytes: env_or("GLINER_MAX_PAYLOAD_BYTES", defaults.max_payload_bytes),
+            max_upload_bytes: env_or("GLINER_MAX_UPLOAD_BYTES", defaults.max_upload_bytes),
+            max_archive_members: env_or("GLINER_MAX_ARCHIVE_MEMBERS", defaults.max_archive_members),
+            max_archive_bytes: env_or("GLINER_MAX_ARCHIVE_BYTES", defaults.max_archive_bytes),
+            max_csv_cells: env_or("GLINER_MAX_CSV_CELLS", defaults.max_csv_cells),
+            max_ndjson_bytes: env_or("GLINER_MAX_NDJSON_BYTES", defaults.max_ndjson_bytes),
+            max_log_bytes: env_or("GLINER_MAX_LOG_BYTES", defaults.max_log_bytes),
+            max_json_strings: env_or("GLINER_MAX_JSON_STRINGS", defaults.max_json_strings),
+            ip_include_private: env_or("GLINER_IP_INCLUDE_PRIVATE", defaults.ip_include_private),
+            card_enrichment: env_or("GLINER_CARD_ENRICHMENT", defaults.card_enrichment),
+            checksum_valid_boost: env_or("GLINER_CHECKSUM_VALID_BOOST", defaults.checksum_valid_boost),
+            checksum_invalid: env_or("GLINER_CHECKSUM_INVALID", defaults.checksum_invalid),
+            checksum_downgrade: env_or("GLINER_CHECKSUM_DOWNGRADE", defaults.checksum_downgrade),
+            recognizer_packs: env_list("GLINER_RECOGNIZER_PACKS", defaults.recognizer_packs),
+            detectors: env_list("GLINER_DETECTORS", defaults.detectors),
+            merge_strategy: env_or("GLINER_MERGE_STRATEGY", defaults.merge_strategy),
+            merge_min_votes: env_or("GLINER_MERGE_MIN_VOTES", defaults.merge_min_votes),
+            join_max_gap: env_opt("GLINER_JOIN_MAX_GAP"),
+            join_score: env_or("GLINER_JOIN_SCORE", defaults.join_score),
+            discovery_labels: env_list("GLINER_DISCOVERY_LABELS", defaults.discovery_labels),
+            gazetteer_file: env_opt("GLINER_GAZETTEER_FILE"),
+            label_aliases: env_list("GLINER_LABEL_ALIASES", defaults.label_aliases),
+            label_prompts: env_list("GLINER_LABEL_PROMPTS", defaults.label_prompts),
+            plugins: env_list("GLINER_PLUGINS", defaults.plugins),
+            plugin_fuel: env_or("GLINER_PLUGIN_FUEL", defaults.plugin_fuel),
+            privacy_mode: env_or("GLINER_PRIVACY_MODE", defaults.privacy_mode),
+            tesseract_path: env_or("GLINER_TESSERACT_PATH", defaults.tesseract_path),
+            pdftoppm_path: env_or("GLINER_PDFTOPPM_PATH", defaults.pdftoppm_path),
+            ocr_languages: env_or("GLINER_OCR_LANGUAGES", defaults.ocr_languages),
+            ocr_dpi: env_or("GLINER_OCR_DPI", defaults.ocr_dpi),
+            prepass: env_or("GLINER_PREPASS", defaults.prepass),
+            prepass_model: std::env::var("GLINER_PREPASS_MODEL").ok(),
+            prepass_threshold: env_or("GLINER_PREPASS_THRESHOLD", defaults.prepass_threshold),
+            deterministic: env_or("GLINER_DETERMINISTIC", defaults.deterministic),
+            request_timeout_ms: env_or("GLINER_REQUEST_TIMEOUT_MS", defaults.request_timeout_ms),
+            max_request_timeout_ms: env_or("GLINER_MAX_REQUEST_TIMEOUT_MS", defaults.max_request_timeout_ms),
+            max_queue_depth: env_or("GLINER_MAX_QUEUE_DEPTH", defaults.max_queue_depth),
+            profiles_file: std::env::var("GLINER_PROFILES_FILE").ok(),
+            threads: env_opt("GLINER_THREADS"),
+            execution_providers: env_list("GLINER_EXECUTION_PROVIDERS", defaults.execution_providers),
+            gpu_device_id: env_or("GLINER_GPU_DEVICE_ID", defaults.gpu_device_id),
+            gpu_memory_limit: env_opt("GLINER_GPU_MEMORY_LIMIT"),
+            cpu_arena: env_or("GLINER_CPU_ARENA", defaults.cpu_arena),
+            sessions: env_or("GLINER_SESSIONS", defaults.sessions).max(1),
+            interactive_sessions: env_or("GLINER_INTERACTIVE_SESSIONS", defaults.interactive_sessions),
+            supervisor: env_or("GLINER_SUPERVISOR", defaults.supervisor),
+            admin_token: std::env::var("GLINER_ADMIN_TOKEN").ok().filter(|token| !token.is_empty()),
+            drain_grace_secs: env_or("GLINER_DRAIN_GRACE_SECS", defaults.drain_grace_secs),
+            config_peer_url: std::env::var("GLINER_CONFIG_PEER_URL").ok(),
+            config_peer_token: std::env::var("GLINER_CONFIG_PEER_TOKEN").ok(),
+            config_sync_interval_secs: env_or("GLINER_CONFIG_SYNC_INTERVAL_SECS", defaults.config_sync_interval_secs),
+            log_level: env_or("GLINER_LOG_LEVEL", defaults.log_level),
+            log_format: env_or("GLINER_LOG_FORMAT", defaults.log_format),
+            audit_sink: env_or("GLINER_AUDIT_SINK", defaults.audit_sink),
+            audit_file: std::env::var("GLINER_AUDIT_FILE").ok(),
+            audit_recent: env_or("GLINER_AUDIT_RECENT", defaults.audit_recent),
+            database_url: std::env::var("GLINER_DATABASE_URL").ok(),
+            results_ttl_secs: env_opt("GLINER_RESULTS_TTL_SECS"),
+            audit_ttl_secs: env_opt("GLINER_AUDIT_TTL_SECS"),
+            retention_sweep_secs: env_or("GLINER_RETENTION_SWEEP_SECS", defaults.retention_sweep_secs),
+            s3_endpoint: std::env::var("GLINER_S3_ENDPOINT").ok(),
+            s3_allowed_buckets: env_list("GLINER_S3_ALLOWED_BUCKETS", defaults.s3_allowed_buckets),
+            azure_endpoint: std::env::var("GLINER_AZURE_ENDPOINT").ok(),
+            kafka_brokers: std::env::var("GLINER_KAFKA_BROKERS").ok(),
+            kafka_group_id: env_or("GLINER_KAFKA_GROUP_ID", defaults.kafka_group_id),
+            kafka_input_topic: std::env::var("GLINER_KAFKA_INPUT_TOPIC").ok(),
+            kafka_output_topic: std::env::var("GLINER_KAFKA_OUTPUT_TOPIC").ok(),
+            kafka_field: std::env::var("GLINER_KAFKA_FIELD").ok(),
+            kafka_action: env_or("GLINER_KAFKA_ACTION", defaults.kafka_action),
+            schedule_history: env_or("GLINER_SCHEDULE_HISTORY", defaults.schedule_history),
+            schedule_max_objects: env_or("GLINER_SCHEDULE_MAX_OBJECTS", defaults.schedule_max_objects),
+            schedule_notify_url: std::env::var("GLINER_SCHEDULE_NOTIFY_URL").ok(),
+            schedule_retries: env_or("GLINER_SCHEDULE_RETRIES", defaults.schedule_retries),
+            schedule_retry_backoff_ms: env_or("GLINER_SCHEDULE_RETRY_BACKOFF_MS", defaults.schedule_retry_backoff_ms),
+        }
+    }
+}
+
+impl Config {
+    pub fn tokenizer_path(&self) -> String {
+        tokenizer_path_for(&self.model_name)
+    }
+
+    pub fn onnx_path(&self) -> String {
+        onnx_path_for(&self.model_name)
+    }
+}
+
+// Local model files live under models/<model name>
+pub fn tokenizer_path_for(model_name: &str) -> String {
+    format!("models/{}/tokenizer.json", model_name)
+}
+
+pub fn onnx_path_for(model_name: &str) -> String {
+    format!("models/{}/model.onnx", model_name)
+}
+
+// Read an environment variable, falling back to the default when unset or unparsable.
+// Configuration is read before logging is set up, so warnings go straight to stderr.
+pub fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(_) => {
+                eprintln!("Ignoring invalid value for {}: {:?}", key, value);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+// Read an optional environment variable; unset or unparsable values give None
+pub fn env_opt<T: FromStr>(key: &str) -> Option<T> {
+    let value = std::env::var(key).ok()?;
+    match value.trim().parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            eprintln!("Ignoring invalid value for {}: {:?}", key, value);
+            None
+        }
+    }
+}
+
+// Read a comma-separated list from an environment variable, falling back to the
+// default when unset or when any item fails to parse
+pub fn env_list<T: FromStr>(key: &str, default: Vec<T>) -> Vec<T> {
+    match std::env::var(key) {
+        Ok(value) => {
+            let parsed: Result<Vec<T>, _> = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::parse)
+                .collect();
+            match parsed {
+                Ok(items) => items,
+                Err(_) => {
+                    eprintln!("Ignoring invalid value for {}: {:?}", key, value);
+                    default
+                }
+            }
+        }
+        Err(_) => default,
+    }
+}
@@ -0,0 +1,192 @@
+use crate::PiiEntity;
+
+// Line separating documents, as in the CoNLL-2003 files most tooling expects
+pub const DOCUMENT_SEPARATOR: &str = "-DOCSTART-\tO";
+
+// A token of the input text and its byte span
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+    // Whether a newline separates this token from the previous one
+    pub after_newline: bool,
+}
+
+// Split text on whitespace, then split leading and trailing punctuation off each
+// word. Punctuation inside a word stays, so emails, phone numbers and URLs
+// remain single tokens.
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut newline = false;
+    let mut word_start = None;
+
+    for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        if c.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                split_word(text, start, index, newline, &mut tokens);
+                newline = false;
+            }
+            newline |= c == '\n';
+        } else if word_start.is_none() {
+            word_start = Some(index);
+        }
+    }
+    tokens
+}
+
+fn split_word<'a>(text: &'a str, start: usize, end: usize, after_newline: bool, tokens: &mut Vec<Token<'a>>) {
+    let word = &text[start..end];
+    let core_start = word.find(|c: char| !is_punctuation(c));
+    let Some(core_start) = core_start else {
+        // Punctuation only, e.g. "--" or "..."
+        for (offset, c) in word.char_indices() {
+            push(text, start + offset, start + offset + c.len_utf8(), after_newline && offset == 0, tokens);
+        }
+        return;
+    };
+    let core_end = word
+        .rfind(|c: char| !is_punctuation(c))
+        .map_or(word.len(), |index| index + word[index..].chars().next().map_or(1, char::len_utf8));
+
+    for (offset, c) in word[..core_start].char_indices() {
+        push(text, start + offset, start + offset + c.len_utf8(), after_newline && offset == 0, tokens);
+    }
+    push(text, start + core_start, start + core_end, after_newline && core_start == 0, tokens);
+    for (offset, c) in word[core_end..].char_indices() {
+        let position = start + core_end + offset;
+        push(text, position, position + c.len_utf8(), false, tokens);
+    }
+}
+
+fn push<'a>(text: &'a str, start: usize, end: usize, after_newline: bool, tokens: &mut Vec<Token<'a>>) {
+    tokens.push(Token {
+        text: &text[start..end],
+        start,
+        end,
+        after_newline,
+    });
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || matches!(c, '“' | '”' | '‘' | '’' | '«' | '»' | '…')
+}
+
+// Tag suffix for a label, e.g. "phone number" becomes PHONE_NUMBER
+pub fn tag_name(label: &str) -> String {
+    label
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+// One document as token-per-line IOB2: `token<TAB>tag`, with a blank line after
+// each sentence (at ., ! or ? and at line breaks). Where entities overlap, the
+// one starting first (or, at the same start, the longer one) is tagged.
+pub fn to_conll(text: &str, entities: &[PiiEntity]) -> String {
+    let mut chosen: Vec<&PiiEntity> = entities.iter().collect();
+    chosen.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    let mut kept: Vec<&PiiEntity> = Vec::with_capacity(chosen.len());
+    for entity in chosen {
+        if kept.last().is_none_or(|last| entity.start >= last.end) {
+            kept.push(entity);
+        }
+    }
+
+    let mut output = String::new();
+    let mut sentence_open = false;
+    let mut current = 0;
+    let mut previous_entity = None;
+    for token in tokenize(text) {
+        // A line break ends the sentence, so an entity spanning it starts over
+        // with a B- tag in the next one
+        if token.after_newline && sentence_open {
+            output.push('\n');
+            previous_entity = None;
+        }
+        while current < kept.len() && kept[current].end <= token.start {
+            current += 1;
+        }
+        let entity = kept.get(current).filter(|entity| entity.start < token.end);
+        let tag = match entity {
+            Some(entity) if previous_entity == Some(current) => format!("I-{}", tag_name(&entity.label)),
+            Some(entity) => format!("B-{}", tag_name(&entity.label)),
+            None => "O".to_string(),
+        };
+        previous_entity = entity.map(|_| current);
+
+        output.push_str(token.text);
+        output.push('\t');
+        output.push_str(&tag);
+        output.push('\n');
+        sentence_open = true;
+        if matches!(token.text, "." | "!" | "?") {
+            output.push('\n');
+            sentence_open = false;
+            previous_entity = None;
+        }
+    }
+    if sentence_open {
+        output.push('\n');
+    }
+    output
+}
+
+// Several documents, each introduced by a -DOCSTART- line
+pub fn documents<'a>(documents: impl IntoIterator<Item = (&'a str, &'a [PiiEntity])>) -> String {
+    let mut output = String::new();
+    for (text, entities) in documents {
+        output.push_str(DOCUMENT_SEPARATOR);
+        output.push_str("\n\n");
+        output.push_str(&to_conll(text, entities));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_in;
+
+    #[test]
+    fn test_tokenize_splits_outer_punctuation() {
+        let tokens: Vec<&str> = tokenize("Hi (Jane Roe), mail jane@example.com!").iter().map(|token| token.text).collect();
+        assert_eq!(tokens, vec!["Hi", "(", "Jane", "Roe", ")", ",", "mail", "jane@example.com", "!"]);
+    }
+
+    #[test]
+    fn test_iob2_tags_and_sentences() {
+        let text = "Call Jane Roe at 555-0100. Thanks\nBye";
+        let entities = vec![entity_in(text, "Jane Roe", "person", 0.9), entity_in(text, "555-0100", "phone number", 0.9)];
+        assert_eq!(
+            to_conll(text, &entities),
+            "Call\tO\nJane\tB-PERSON\nRoe\tI-PERSON\nat\tO\n555-0100\tB-PHONE_NUMBER\n.\tO\n\nThanks\tO\n\nBye\tO\n\n"
+        );
+    }
+
+    #[test]
+    fn test_entity_across_line_break_begins_again() {
+        let text = "Ship to 1 Main St\nSpringfield";
+        let entities = vec![entity_in(text, "1 Main St\nSpringfield", "address", 0.9)];
+        assert_eq!(
+            to_conll(text, &entities),
+            "Ship\tO\nto\tO\n1\tB-ADDRESS\nMain\tI-ADDRESS\nSt\tI-ADDRESS\n\nSpringfield\tB-ADDRESS\n\n"
+        );
+    }
+
+    #[test]
+    fn test_adjacent_entities_of_same_label_both_begin() {
+        let text = "Jane Roe John Doe";
+        let entities = vec![entity_in(text, "Jane Roe", "person", 0.9), entity_in(text, "John Doe", "person", 0.9)];
+        let output = to_conll(text, &entities);
+        let tags: Vec<&str> = output.lines().filter_map(|line| line.split('\t').nth(1)).collect();
+        assert_eq!(tags, vec!["B-PERSON", "I-PERSON", "B-PERSON", "I-PERSON"]);
+    }
+
+    #[test]
+    fn test_documents_are_separated() {
+        let output = documents([("Jane", &[][..]), ("Bob", &[][..])]);
+        assert_eq!(output, "-DOCSTART-\tO\n\nJane\tO\n\n-DOCSTART-\tO\n\nBob\tO\n\n");
+    }
+}
@@ -0,0 +1,73 @@
+// Text around a finding, so a reviewer can judge it without fetching the whole
+// document again: `context_chars` characters on each side of the entity, with
+// where the entity sits inside the snippet.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+// Characters of context on each side at most
+pub const MAX_CONTEXT_CHARS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub text: String,
+    // Byte offsets of the entity within `text`
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn validate(chars: usize) -> Result<(), ApiError> {
+    if chars > MAX_CONTEXT_CHARS {
+        return Err(ApiError::InvalidInput(format!(
+            "context_chars is {}, the limit is {}",
+            chars, MAX_CONTEXT_CHARS
+        )));
+    }
+    Ok(())
+}
+
+// The entity at byte offsets `start..end` of `text` with up to `chars`
+// characters before and after it
+pub fn snippet(text: &str, start: usize, end: usize, chars: usize) -> Snippet {
+    let from = match chars {
+        0 => start,
+        _ => text[..start].char_indices().rev().nth(chars - 1).map_or(0, |(index, _)| index),
+    };
+    let to = text[end..].char_indices().nth(chars).map_or(text.len(), |(index, _)| end + index);
+    Snippet {
+        text: text[from..to].to_string(),
+        start: start - from,
+        end: end - from,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_keeps_the_entity_position() {
+        let text = "Please call Jane Roe tomorrow morning";
+        let snippet = snippet(text, 12, 20, 5);
+        assert_eq!(snippet.text, "call Jane Roe tomo");
+        assert_eq!(&snippet.text[snippet.start..snippet.end], "Jane Roe");
+    }
+
+    #[test]
+    fn test_snippet_stops_at_the_edges() {
+        let text = "Jane Roe";
+        assert_eq!(snippet(text, 0, 8, 50).text, "Jane Roe");
+        assert_eq!(snippet(text, 0, 4, 0).text, "Jane");
+    }
+
+    #[test]
+    fn test_snippet_counts_characters_not_bytes() {
+        let text = "Grüße an Jörg Müller, bis bald";
+        let start = text.find("Jörg").unwrap();
+        let end = start + "Jörg Müller".len();
+        let snippet = snippet(text, start, end, 3);
+        assert_eq!(snippet.text, "an Jörg Müller, b");
+        assert_eq!(&snippet.text[snippet.start..snippet.end], "Jörg Müller");
+    }
+}
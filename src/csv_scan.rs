@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::error::ApiError;
+use crate::PiiEntity;
+
+// A non-empty cell selected for scanning. Rows are 0-based data rows (the
+// header row, if any, is not counted).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvTable {
+    pub headers: Option<Vec<String>>,
+    // Indices of the columns that are scanned
+    pub columns: Vec<usize>,
+    pub rows: usize,
+    pub cells: Vec<Cell>,
+}
+
+impl CsvTable {
+    pub fn column_name(&self, column: usize) -> Option<String> {
+        self.headers.as_ref()?.get(column).cloned()
+    }
+}
+
+// How to read an uploaded CSV file
+#[derive(Debug, Clone, Default)]
+pub struct CsvOptions {
+    pub has_headers: bool,
+    pub delimiter: u8,
+    // Comma-separated header names or 0-based indices; None scans every column
+    pub columns: Option<String>,
+    pub max_cells: usize,
+}
+
+// Parse a CSV file and collect the non-empty cells of the selected columns
+pub fn parse_csv(bytes: &[u8], options: &CsvOptions) -> Result<CsvTable, ApiError> {
+    let invalid = |e: csv::Error| ApiError::InvalidInput(format!("failed to parse CSV: {}", e));
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(options.has_headers)
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_reader(bytes);
+
+    let headers = if options.has_headers {
+        Some(reader.headers().map_err(invalid)?.iter().map(str::to_string).collect::<Vec<_>>())
+    } else {
+        None
+    };
+    let selected = match &options.columns {
+        Some(columns) => Some(select_columns(columns, headers.as_deref())?),
+        None => None,
+    };
+
+    let mut cells = Vec::new();
+    let mut rows = 0;
+    let mut width = headers.as_ref().map_or(0, Vec::len);
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(invalid)?;
+        rows += 1;
+        width = width.max(record.len());
+        for (column, value) in record.iter().enumerate() {
+            let wanted = selected.as_ref().is_none_or(|selected| selected.contains(&column));
+            if !wanted || value.trim().is_empty() {
+                continue;
+            }
+            if cells.len() == options.max_cells {
+                return Err(ApiError::PayloadTooLarge(format!(
+                    "CSV has more than {} non-empty cells to scan",
+                    options.max_cells
+                )));
+            }
+            cells.push(Cell {
+                row,
+                column,
+                text: value.to_string(),
+            });
+        }
+    }
+
+    Ok(CsvTable {
+        headers,
+        columns: selected.unwrap_or_else(|| (0..width).collect()),
+        rows,
+        cells,
+    })
+}
+
+// Resolve a column selection such as "email,notes" or "0,3" to column indices
+fn select_columns(selection: &str, headers: Option<&[String]>) -> Result<Vec<usize>, ApiError> {
+    let mut columns = Vec::new();
+    for name in selection.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let by_name = headers.and_then(|headers| headers.iter().position(|header| header.trim() == name));
+        let column = by_name
+            .or_else(|| name.parse().ok())
+            .ok_or_else(|| ApiError::InvalidInput(format!("unknown column {:?}", name)))?;
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    if columns.is_empty() {
+        return Err(ApiError::InvalidInput("columns must name at least one column".to_string()));
+    }
+    Ok(columns)
+}
+
+// Findings of one scanned column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnSummary {
+    pub index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub cells_scanned: usize,
+    pub cells_with_pii: usize,
+    // Entity count per label
+    pub labels: BTreeMap<String, usize>,
+}
+
+// One entity, located by cell; offsets are relative to the cell value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvHit {
+    pub row: usize,
+    pub column: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column_name: Option<String>,
+    #[serde(flatten)]
+    pub entity: PiiEntity,
+}
+
+// Combine per-cell detection results (in `table.cells` order) into column
+// summaries and a flat list of hits
+pub fn summarize(table: &CsvTable, results: Vec<Vec<PiiEntity>>) -> (Vec<ColumnSummary>, Vec<CsvHit>) {
+    let mut summaries: Vec<ColumnSummary> = table
+        .columns
+        .iter()
+        .map(|&index| ColumnSummary {
+            index,
+            name: table.column_name(index),
+            cells_scanned: 0,
+            cells_with_pii: 0,
+            labels: BTreeMap::new(),
+        })
+        .collect();
+
+    let mut hits = Vec::new();
+    for (cell, entities) in table.cells.iter().zip(results) {
+        if let Some(summary) = summaries.iter_mut().find(|summary| summary.index == cell.column) {
+            summary.cells_scanned += 1;
+            if !entities.is_empty() {
+                summary.cells_with_pii += 1;
+            }
+            for entity in &entities {
+                *summary.labels.entry(entity.label.clone()).or_default() += 1;
+            }
+        }
+        hits.extend(entities.into_iter().map(|entity| CsvHit {
+            row: cell.row,
+            column: cell.column,
+            column_name: table.column_name(cell.column),
+            entity,
+        }));
+    }
+    (summaries, hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_at;
+
+    fn options(columns: Option<&str>) -> CsvOptions {
+        CsvOptions {
+            has_headers: true,
+            delimiter: b',',
+            columns: columns.map(str::to_string),
+            max_cells: 100,
+        }
+    }
+
+    const CSV: &[u8] = b"id,name,notes\n1,Jane Roe,call 555-0100\n2,,\n3,John Doe,fine\n";
+
+    #[test]
+    fn test_parse_skips_empty_cells() {
+        let table = parse_csv(CSV, &options(None)).unwrap();
+        assert_eq!(table.rows, 3);
+        assert_eq!(table.columns, vec![0, 1, 2]);
+        assert_eq!(table.cells.len(), 7);
+        assert_eq!(table.cells[1], Cell { row: 0, column: 1, text: "Jane Roe".to_string() });
+    }
+
+    #[test]
+    fn test_select_columns_by_name_or_index() {
+        let table = parse_csv(CSV, &options(Some("notes, 1"))).unwrap();
+        assert_eq!(table.columns, vec![2, 1]);
+        assert!(table.cells.iter().all(|cell| cell.column != 0));
+
+        assert!(parse_csv(CSV, &options(Some("ssn"))).is_err());
+    }
+
+    #[test]
+    fn test_cell_limit() {
+        let limited = CsvOptions { max_cells: 2, ..options(None) };
+        assert_eq!(parse_csv(CSV, &limited).unwrap_err().error_code(), "payload_too_large");
+    }
+
+    #[test]
+    fn test_summarize_counts_per_column() {
+        let table = parse_csv(CSV, &options(Some("name"))).unwrap();
+        let (summaries, hits) = summarize(&table, vec![vec![entity_at("Jane Roe", "person", 0, 0.9)], vec![]]);
+
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].name.as_deref(), Some("name"));
+        assert_eq!(summaries[0].cells_scanned, 2);
+        assert_eq!(summaries[0].cells_with_pii, 1);
+        assert_eq!(summaries[0].labels["person"], 1);
+        assert_eq!((hits[0].row, hits[0].column), (0, 1));
+    }
+}
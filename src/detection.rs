@@ -0,0 +1,355 @@
+use gliner::model::input::text::TextInput;
+
+use std::collections::BTreeMap;
+
+use crate::adjacent;
+use crate::aliases::LabelMap;
+use crate::chunking::{padding_stats, schedule_bucketed, split_into_chunks, Chunk, PaddingStats};
+use crate::config::Config;
+use crate::inference::InferenceProgress;
+use crate::pipeline::{self, Detector};
+use crate::pool::Model;
+use crate::prepass::Prepass;
+use crate::recognizers::{self, RecognizerOptions};
+use crate::PiiEntity;
+
+pub type DetectionError = Box<dyn std::error::Error + Send + Sync>;
+
+// Entities found per document, plus how the work was scheduled
+pub struct BatchDetection {
+    pub documents: Vec<Vec<PiiEntity>>,
+    pub chunks: usize,
+    // Chunks sent to the main model after the optional pre-pass
+    pub escalated: usize,
+    pub batches: usize,
+    pub padding: PaddingStats,
+}
+
+// How the work of a detection run was scheduled
+#[derive(Debug, Clone, Copy)]
+pub struct DetectionStats {
+    pub chunks: usize,
+    pub escalated: usize,
+    pub batches: usize,
+    pub padding: PaddingStats,
+}
+
+// Run detection over several documents at once and collect the results in
+// document order. See `detect_documents_streaming` for how work is scheduled.
+pub fn detect_documents(
+    model: &Model,
+    prepass: Option<&Prepass>,
+    texts: &[&str],
+    labels: &[&str],
+    recognizer_options: &RecognizerOptions,
+    config: &Config,
+    progress: &InferenceProgress,
+) -> Result<BatchDetection, DetectionError> {
+    let mut documents: Vec<Vec<PiiEntity>> = vec![Vec::new(); texts.len()];
+    let stats = detect_documents_streaming(model, prepass, texts, labels, recognizer_options, config, progress, |document, entities| {
+        documents[document] = entities;
+    })?;
+
+    Ok(BatchDetection {
+        documents,
+        chunks: stats.chunks,
+        escalated: stats.escalated,
+        batches: stats.batches,
+        padding: stats.padding,
+    })
+}
+
+// Run detection over several documents at once. Documents are chunked, the
+// chunks of the whole request are packed into fixed-size inference batches,
+// and the spans are mapped back to document offsets afterwards. Chunks of
+// similar length are batched together to keep padding low. When a pre-pass is
+// configured, only chunks it flags are escalated to the main model. Progress is
+// reported per inference batch, and a cancelled run stops at the next batch.
+// Rule-based recognizer findings are merged in as each document completes, and
+// findings are reported under the requested labels rather than their aliases.
+//
+// `on_document` is called once per document, as soon as its last chunk has been
+// through the model, so documents complete in scheduling order rather than
+// request order.
+#[allow(clippy::too_many_arguments)]
+pub fn detect_documents_streaming(
+    model: &Model,
+    prepass: Option<&Prepass>,
+    texts: &[&str],
+    labels: &[&str],
+    recognizer_options: &RecognizerOptions,
+    config: &Config,
+    progress: &InferenceProgress,
+    mut on_document: impl FnMut(usize, Vec<PiiEntity>),
+) -> Result<DetectionStats, DetectionError> {
+    let label_map = LabelMap::resolve(labels, config, recognizer_options.label_prompts.as_ref());
+    let prompts = label_map.prompts();
+    let chunks: Vec<Chunk> = texts
+        .iter()
+        .enumerate()
+        .flat_map(|(document, text)| {
+            split_into_chunks(document, text, config.max_chunk_chars, config.chunk_overlap)
+        })
+        .collect();
+    let total_chunks = chunks.len();
+
+    let chunks: Vec<Chunk> = match prepass {
+        Some(prepass) => {
+            let chunk_texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+            let flags = prepass.candidates(&chunk_texts, &prompts, config.inference_batch_size)?;
+            chunks
+                .into_iter()
+                .zip(flags)
+                .filter_map(|(chunk, flagged)| flagged.then_some(chunk))
+                .collect()
+        }
+        None => chunks,
+    };
+    let batches = schedule_bucketed(&chunks, config.inference_batch_size, &config.length_buckets);
+    let padding = padding_stats(&chunks, &batches);
+
+    progress.set_total_batches(batches.len());
+
+    // Documents whose chunks were all screened out are complete straight away
+    let mut remaining = vec![0usize; texts.len()];
+    for chunk in &chunks {
+        remaining[chunk.document] += 1;
+    }
+    for (document, &count) in remaining.iter().enumerate() {
+        if count == 0 {
+            let mut entities = Vec::new();
+            recognizers::apply(texts[document], labels, recognizer_options, config, &mut entities);
+            on_document(document, entities);
+        }
+    }
+
+    let mut pending: Vec<Vec<PiiEntity>> = vec![Vec::new(); texts.len()];
+    for batch in &batches {
+        if progress.is_cancelled() {
+            return Err("detection cancelled".into());
+        }
+        let batch_texts: Vec<&str> = batch.iter().map(|&index| chunks[index].text.as_str()).collect();
+        let input = TextInput::from_str(&batch_texts, &prompts)?;
+        let output = model.inference(input)?;
+
+        for (position, spans) in output.spans.iter().enumerate() {
+            let chunk = &chunks[batch[position]];
+            for span in spans {
+                let (start, end) = span.offsets();
+                pending[chunk.document].push(PiiEntity {
+                    text: span.text().to_string(),
+                    label: span.class().to_string(),
+                    confidence: span.probability(),
+                    start: chunk.offset + start,
+                    end: chunk.offset + end,
+                    attributes: BTreeMap::new(),
+                });
+            }
+        }
+        progress.complete_batch();
+
+        for &index in batch {
+            let document = chunks[index].document;
+            remaining[document] -= 1;
+            if remaining[document] == 0 {
+                let mut entities = std::mem::take(&mut pending[document]);
+                dedupe_entities(&mut entities);
+                recognizers::apply(texts[document], labels, recognizer_options, config, &mut entities);
+                on_document(document, entities);
+            }
+        }
+    }
+
+    Ok(DetectionStats {
+        chunks: total_chunks,
+        escalated: chunks.len(),
+        batches: batches.len(),
+        padding,
+    })
+}
+
+// Run detection over one document chunk by chunk, in document order, calling
+// `on_chunk` with each chunk's entities (at document offsets) as soon as its
+// batch has been through the model. Used for progressive output, where reading
+// order matters more than the padding savings of length bucketing. Entities in
+// the overlap between chunks may be reported twice. The model is prompted per
+// `label_map`, and entities come back under the requested labels with adjacent
+// spans joined within the chunk; spans split by a chunk boundary are only
+// joined once the whole document has been through recognizers::apply.
+pub fn detect_chunks_in_order(
+    model: &Model,
+    prepass: Option<&Prepass>,
+    text: &str,
+    label_map: &LabelMap,
+    config: &Config,
+    progress: &InferenceProgress,
+    mut on_chunk: impl FnMut(usize, &Chunk, Vec<PiiEntity>),
+) -> Result<DetectionStats, DetectionError> {
+    let labels = &label_map.prompts();
+    let chunks = split_into_chunks(0, text, config.max_chunk_chars, config.chunk_overlap);
+    let total_chunks = chunks.len();
+    let flags = match prepass {
+        Some(prepass) => {
+            let chunk_texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+            prepass.candidates(&chunk_texts, labels, config.inference_batch_size)?
+        }
+        None => vec![true; chunks.len()],
+    };
+    let escalated: Vec<usize> = (0..chunks.len()).filter(|&index| flags[index]).collect();
+    let batches: Vec<Vec<usize>> = escalated
+        .chunks(config.inference_batch_size.max(1))
+        .map(|batch| batch.to_vec())
+        .collect();
+    let padding = padding_stats(&chunks, &batches);
+    progress.set_total_batches(batches.len());
+
+    for batch in &batches {
+        if progress.is_cancelled() {
+            return Err("detection cancelled".into());
+        }
+        let batch_texts: Vec<&str> = batch.iter().map(|&index| chunks[index].text.as_str()).collect();
+        let output = model.inference(TextInput::from_str(&batch_texts, labels)?)?;
+        progress.complete_batch();
+
+        for (position, spans) in output.spans.iter().enumerate() {
+            let chunk = &chunks[batch[position]];
+            let mut entities: Vec<PiiEntity> = spans
+                .iter()
+                .map(|span| {
+                    let (start, end) = span.offsets();
+                    PiiEntity {
+                        text: span.text().to_string(),
+                        label: span.class().to_string(),
+                        confidence: span.probability(),
+                        start: chunk.offset + start,
+                        end: chunk.offset + end,
+                        attributes: BTreeMap::new(),
+                    }
+                })
+                .collect();
+            chunk_findings(text, label_map, config, &mut entities);
+            on_chunk(batch[position], chunk, entities);
+        }
+    }
+
+    Ok(DetectionStats {
+        chunks: total_chunks,
+        escalated: escalated.len(),
+        batches: batches.len(),
+        padding,
+    })
+}
+
+// One chunk's entities as progressive output reports them: under the requested
+// labels, with adjacent spans of the same label joined per GLINER_JOIN_MAX_GAP
+fn chunk_findings(text: &str, label_map: &LabelMap, config: &Config, entities: &mut Vec<PiiEntity>) {
+    label_map.restore(entities);
+    if let Some(max_gap) = config.join_max_gap {
+        adjacent::join(text, entities, max_gap, config.join_score);
+    }
+}
+
+// The model as a pipeline detector over one text, chunked but without the
+// pre-pass. Requests go through `detect_documents` instead, which batches
+// chunks across documents and merges the other detectors' findings in.
+pub struct ModelDetector<'a> {
+    pub model: &'a Model,
+    pub config: &'a Config,
+}
+
+impl Detector for ModelDetector<'_> {
+    fn name(&self) -> &'static str {
+        pipeline::MODEL
+    }
+
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError> {
+        let mut found = Vec::new();
+        let label_map = LabelMap::resolve(labels, self.config, None);
+        detect_chunks_in_order(self.model, None, text, &label_map, self.config, &InferenceProgress::default(), |_, _, entities| {
+            found.extend(entities)
+        })?;
+        dedupe_entities(&mut found);
+        Ok(found)
+    }
+}
+
+// One model run over a short text, without chunking, pre-pass or recognizers
+pub fn model_entities(model: &Model, text: &str, label_map: &LabelMap) -> Result<Vec<PiiEntity>, DetectionError> {
+    let output = model.inference(TextInput::from_str(&[text], &label_map.prompts())?)?;
+    let mut entities: Vec<PiiEntity> = output
+        .spans
+        .iter()
+        .flatten()
+        .map(|span| {
+            let (start, end) = span.offsets();
+            PiiEntity {
+                text: span.text().to_string(),
+                label: span.class().to_string(),
+                confidence: span.probability(),
+                start,
+                end,
+                attributes: BTreeMap::new(),
+            }
+        })
+        .collect();
+    label_map.restore(&mut entities);
+    Ok(entities)
+}
+
+// Sort entities by position and drop the duplicates produced by chunk overlap,
+// keeping the highest confidence seen for each span
+pub fn dedupe_entities(entities: &mut Vec<PiiEntity>) {
+    entities.sort_by(|a, b| (a.start, a.end, &a.label).cmp(&(b.start, b.end, &b.label)));
+    entities.dedup_by(|later, kept| {
+        let same = later.start == kept.start && later.end == kept.end && later.label == kept.label;
+        if same && later.confidence > kept.confidence {
+            kept.confidence = later.confidence;
+        }
+        same
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_dedupe_keeps_highest_confidence() {
+        let mut entities = vec![
+            entity("person", 10, 18, 0.7),
+            entity("email", 0, 5, 0.9),
+            entity("person", 10, 18, 0.95),
+        ];
+        dedupe_entities(&mut entities);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].label, "email");
+        assert_eq!(entities[1].confidence, 0.95);
+    }
+
+    #[test]
+    fn test_dedupe_keeps_distinct_labels() {
+        let mut entities = vec![entity("person", 0, 4, 0.8), entity("organization", 0, 4, 0.6)];
+        dedupe_entities(&mut entities);
+        assert_eq!(entities.len(), 2);
+    }
+
+    #[test]
+    fn test_chunk_findings_join_adjacent_spans() {
+        let text = "Signed: John Doe";
+        let mut entities = vec![entity("person", 8, 12, 0.9), entity("person", 13, 16, 0.8)];
+        let label_map = LabelMap::resolve(&["person"], &Config::default(), None);
+        chunk_findings(text, &label_map, &Config::default(), &mut entities);
+        assert_eq!(entities.len(), 2);
+
+        let config = Config {
+            join_max_gap: Some(1),
+            ..Config::default()
+        };
+        chunk_findings(text, &label_map, &config, &mut entities);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].text, "John Doe");
+        assert_eq!(entities[0].confidence, 0.8);
+    }
+}
@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+
+use crate::PiiEntity;
+
+// A run of consecutive added lines in one file of a unified diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddedBlock {
+    // Path in the new version of the tree
+    pub path: String,
+    // 1-based line number of the first line in the new file
+    pub first_line: usize,
+    // The added lines, without their `+` markers, joined with newlines
+    pub text: String,
+}
+
+// An entity found in an added line. Offsets are relative to the start of that
+// line; an entity running on into the next line is reported on the line it starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffFinding {
+    pub path: String,
+    pub line: usize,
+    #[serde(flatten)]
+    pub entity: PiiEntity,
+}
+
+// Lines still to come in the current hunk, old and new side
+#[derive(Default)]
+struct Hunk {
+    old_remaining: usize,
+    new_remaining: usize,
+    next_line: usize,
+}
+
+// Collect the added lines of a unified diff (as produced by `git diff` or
+// `diff -u`), grouped into blocks of consecutive lines. Removed and context
+// lines, deleted files and binary files are ignored.
+pub fn added_blocks(diff: &str) -> Vec<AddedBlock> {
+    let mut blocks: Vec<AddedBlock> = Vec::new();
+    let mut path: Option<String> = None;
+    let mut hunk = Hunk::default();
+    // Whether the previous line was an added line extending the last block
+    let mut extending = false;
+
+    for line in diff.lines() {
+        if hunk.old_remaining > 0 || hunk.new_remaining > 0 {
+            match line.as_bytes().first() {
+                Some(b'+') => {
+                    if let Some(path) = &path {
+                        let text = &line[1..];
+                        match blocks.last_mut() {
+                            Some(block) if extending => {
+                                block.text.push('\n');
+                                block.text.push_str(text);
+                            }
+                            _ => blocks.push(AddedBlock {
+                                path: path.clone(),
+                                first_line: hunk.next_line,
+                                text: text.to_string(),
+                            }),
+                        }
+                        extending = true;
+                    }
+                    hunk.new_remaining = hunk.new_remaining.saturating_sub(1);
+                    hunk.next_line += 1;
+                    continue;
+                }
+                Some(b'-') => {
+                    hunk.old_remaining = hunk.old_remaining.saturating_sub(1);
+                    extending = false;
+                    continue;
+                }
+                // "\ No newline at end of file"
+                Some(b'\\') => continue,
+                // Context line; some tools strip the leading space of blank ones
+                Some(b' ') | None => {
+                    hunk.old_remaining = hunk.old_remaining.saturating_sub(1);
+                    hunk.new_remaining = hunk.new_remaining.saturating_sub(1);
+                    hunk.next_line += 1;
+                    extending = false;
+                    continue;
+                }
+                // Anything else ends a truncated hunk
+                Some(_) => hunk = Hunk::default(),
+            }
+        }
+
+        extending = false;
+        if line.starts_with("diff ") {
+            path = None;
+        } else if let Some(target) = line.strip_prefix("+++ ") {
+            path = new_path(target);
+        } else if line.starts_with("@@ ") {
+            if let Some(parsed) = parse_hunk_header(line) {
+                hunk = parsed;
+            }
+        }
+    }
+    blocks
+}
+
+// Path from a `+++` header; None for deleted files
+fn new_path(target: &str) -> Option<String> {
+    // `diff -u` appends a tab and a timestamp
+    let target = target.split('\t').next().unwrap_or(target).trim_end();
+    let target = target.trim_matches('"');
+    if target == "/dev/null" {
+        return None;
+    }
+    Some(target.strip_prefix("b/").unwrap_or(target).to_string())
+}
+
+// Parse "@@ -12,3 +14,5 @@ optional section heading"; counts default to 1
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let mut ranges = line.strip_prefix("@@ ")?.split(' ');
+    let old = ranges.next()?.strip_prefix('-')?;
+    let new = ranges.next()?.strip_prefix('+')?;
+    let (_, old_count) = parse_range(old)?;
+    let (new_start, new_count) = parse_range(new)?;
+    Some(Hunk {
+        old_remaining: old_count,
+        new_remaining: new_count,
+        next_line: new_start,
+    })
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+// Pair per-block detection results (in `blocks` order) with file paths and line
+// numbers, moving offsets from the block to the line each entity starts on
+pub fn findings(blocks: &[AddedBlock], results: Vec<Vec<PiiEntity>>) -> Vec<DiffFinding> {
+    let mut findings = Vec::new();
+    for (block, entities) in blocks.iter().zip(results) {
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(block.text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        for mut entity in entities {
+            let index = line_starts.partition_point(|&start| start <= entity.start) - 1;
+            entity.start -= line_starts[index];
+            entity.end -= line_starts[index];
+            findings.push(DiffFinding {
+                path: block.path.clone(),
+                line: block.first_line + index,
+                entity,
+            });
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const DIFF: &str = "diff --git a/notes.txt b/notes.txt
+index 1111111..2222222 100644
+--- a/notes.txt
++++ b/notes.txt
+@@ -1,3 +1,4 @@
+ Meeting notes
+-Call Bob
++Call Jane Roe
++at jane@example.com
+ Thanks
+@@ -10,0 +12 @@ section
++++ not a header
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-John Doe
+diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ
+";
+
+    #[test]
+    fn test_collects_added_lines_only() {
+        let blocks = added_blocks(DIFF);
+        assert_eq!(
+            blocks,
+            vec![
+                AddedBlock {
+                    path: "notes.txt".to_string(),
+                    first_line: 2,
+                    text: "Call Jane Roe\nat jane@example.com".to_string(),
+                },
+                AddedBlock {
+                    path: "notes.txt".to_string(),
+                    first_line: 12,
+                    text: "++ not a header".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_diff_paths() {
+        let diff = "--- notes.orig\t2024-01-01 10:00:00\n+++ notes\t2024-01-02 10:00:00\n@@ -0,0 +1 @@\n+Jane\n";
+        let blocks = added_blocks(diff);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].path, "notes");
+        assert_eq!(blocks[0].first_line, 1);
+    }
+
+    #[test]
+    fn test_findings_are_line_relative() {
+        let blocks = added_blocks(DIFF);
+        let text = &blocks[0].text;
+        let start = text.find("jane@").unwrap();
+        let entity = PiiEntity {
+            text: "jane@example.com".to_string(),
+            label: "email".to_string(),
+            confidence: 0.9,
+            start,
+            end: start + 16,
+            attributes: BTreeMap::new(),
+        };
+        let findings = findings(&blocks, vec![vec![entity], Vec::new()]);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].path, "notes.txt");
+        assert_eq!(findings[0].line, 3);
+        assert_eq!((findings[0].entity.start, findings[0].entity.end), (3, 19));
+    }
+}
@@ -0,0 +1,164 @@
+// Open-ended entity discovery, served by POST /api/pii/discover: texts are run
+// through the model with a broad, generic label set instead of a PII profile,
+// and every entity-like span comes back under the type the model suggests for
+// it. Meant for exploring an unfamiliar dataset before settling on the labels
+// to detect.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::PiiEntity;
+
+// Generic types asked for when GLINER_DISCOVERY_LABELS is not set
+pub const DEFAULT_LABELS: &[&str] = &[
+    "person",
+    "organization",
+    "location",
+    "address",
+    "date",
+    "time",
+    "money",
+    "quantity",
+    "email address",
+    "phone number",
+    "url",
+    "identifier",
+    "account number",
+    "product",
+    "event",
+    "facility",
+    "job title",
+    "nationality",
+    "language",
+    "medical condition",
+    "medication",
+    "vehicle",
+    "law",
+    "work of art",
+];
+
+// Distinct examples reported per suggested type
+pub const MAX_EXAMPLES: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct DiscoverRequest {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub texts: Vec<String>,
+    // Types to ask for in addition to the configured discovery labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    // Leave span text and examples out of the response; always on when
+    // GLINER_PRIVACY_MODE is set
+    #[serde(default)]
+    pub privacy_mode: Option<bool>,
+}
+
+// A type the model found spans of, across all texts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuggestedType {
+    pub label: String,
+    pub count: usize,
+    pub documents: usize,
+    pub mean_confidence: f32,
+    // The most frequent span texts, most frequent first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discovery {
+    // Labels the texts were run with
+    pub labels: Vec<String>,
+    // Per text, in request order
+    pub documents: Vec<Vec<PiiEntity>>,
+    // Types found, most frequent first; types nothing was found for are left out
+    pub types: Vec<SuggestedType>,
+}
+
+// The configured discovery labels followed by the request's extra ones, each once
+pub fn labels(configured: &[String], extra: &[String]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::with_capacity(configured.len() + extra.len());
+    for label in configured.iter().chain(extra).map(|label| label.trim()) {
+        if !label.is_empty() && !labels.iter().any(|known| known.eq_ignore_ascii_case(label)) {
+            labels.push(label.to_string());
+        }
+    }
+    labels
+}
+
+// Count findings per type, with the documents they appear in and examples
+pub fn summarize(documents: &[Vec<PiiEntity>], with_examples: bool) -> Vec<SuggestedType> {
+    #[derive(Default)]
+    struct Tally {
+        count: usize,
+        documents: usize,
+        confidence: f32,
+        examples: BTreeMap<String, usize>,
+    }
+
+    let mut tallies: BTreeMap<&str, Tally> = BTreeMap::new();
+    for entities in documents {
+        let mut seen: Vec<&str> = Vec::new();
+        for entity in entities {
+            let tally = tallies.entry(entity.label.as_str()).or_default();
+            tally.count += 1;
+            tally.confidence += entity.confidence;
+            if with_examples {
+                *tally.examples.entry(entity.text.clone()).or_default() += 1;
+            }
+            if !seen.contains(&entity.label.as_str()) {
+                seen.push(&entity.label);
+                tally.documents += 1;
+            }
+        }
+    }
+
+    let mut types: Vec<SuggestedType> = tallies
+        .into_iter()
+        .map(|(label, tally)| {
+            let mut examples: Vec<(String, usize)> = tally.examples.into_iter().collect();
+            examples.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            SuggestedType {
+                label: label.to_string(),
+                count: tally.count,
+                documents: tally.documents,
+                mean_confidence: tally.confidence / tally.count as f32,
+                examples: examples.into_iter().take(MAX_EXAMPLES).map(|(text, _)| text).collect(),
+            }
+        })
+        .collect();
+    types.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label.cmp(&b.label)));
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_at;
+
+    #[test]
+    fn test_labels_are_deduplicated() {
+        let configured = vec!["person".to_string(), "product".to_string()];
+        let extra = vec!["Person".to_string(), " ticket id ".to_string(), String::new()];
+        assert_eq!(labels(&configured, &extra), vec!["person", "product", "ticket id"]);
+    }
+
+    #[test]
+    fn test_summary_counts_types_and_examples() {
+        let documents = vec![
+            vec![entity_at("Acme", "organization", 0, 0.8), entity_at("Jane Roe", "person", 0, 0.9), entity_at("Acme", "organization", 0, 0.6)],
+            vec![entity_at("Globex", "organization", 0, 0.7)],
+        ];
+        let types = summarize(&documents, true);
+
+        assert_eq!(types[0].label, "organization");
+        assert_eq!((types[0].count, types[0].documents), (3, 2));
+        assert!((types[0].mean_confidence - 0.7).abs() < 1e-6);
+        assert_eq!(types[0].examples, vec!["Acme", "Globex"]);
+        assert_eq!(types[1].label, "person");
+
+        assert!(summarize(&documents, false)[0].examples.is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::PiiEntity;
+
+// Label of the findings, matching the "date of birth" label of the built-in profiles
+pub const LABEL: &str = "date of birth";
+
+// How far (in bytes) a context cue may precede the date it introduces
+const MAX_CUE_GAP: usize = 40;
+
+// Which of the first two numbers of a numeric date like 03/04/1980 is the month
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    MonthFirst,
+    DayFirst,
+    // No locale given: accept either reading
+    Either,
+}
+
+impl DateOrder {
+    // Order for a BCP 47 locale such as "en-US" or "de-DE". Locales without a
+    // region (and Canada, which uses both) leave the order open.
+    pub fn for_locale(locale: Option<&str>) -> Self {
+        let Some(locale) = locale else {
+            return DateOrder::Either;
+        };
+        let region = locale
+            .split(['-', '_'])
+            .skip(1)
+            .find(|part| part.len() == 2)
+            .map(str::to_ascii_uppercase);
+        match region.as_deref() {
+            Some("US" | "PH" | "FM" | "MH" | "PW" | "GU" | "PR" | "AS" | "VI" | "UM") => DateOrder::MonthFirst,
+            Some("CA") | None => DateOrder::Either,
+            Some(_) => DateOrder::DayFirst,
+        }
+    }
+}
+
+fn cue_pattern() -> &'static Regex {
+    static CUE: OnceLock<Regex> = OnceLock::new();
+    CUE.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:d\.?o\.?b\b\.?|date\s+of\s+birth|birth\s*date|birthday|born(?:\s+on)?|geb\.|geboren(?:\s+am)?|fecha\s+de\s+nacimiento|n[ée]e?\s+le)",
+        )
+        .expect("valid date of birth cue pattern")
+    })
+}
+
+const MONTHS: &str = "jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?";
+
+fn date_patterns() -> &'static [Regex; 4] {
+    static DATES: OnceLock<[Regex; 4]> = OnceLock::new();
+    DATES.get_or_init(|| {
+        let build = |pattern: &str| Regex::new(pattern).expect("valid date pattern");
+        [
+            // 1980-04-03
+            build(r"\b(?P<year>\d{4})-(?P<month>\d{1,2})-(?P<day>\d{1,2})\b"),
+            // 03/04/1980, 3.4.80, 03-04-1980: day and month resolved by locale
+            build(r"\b(?P<first>\d{1,2})(?P<sep>[./-])(?P<second>\d{1,2})(?P<sep2>[./-])(?P<year>\d{4}|\d{2})\b"),
+            // 3 April 1980, 3rd Apr. 1980
+            build(&format!(
+                r"(?i)\b(?P<day>\d{{1,2}})(?:st|nd|rd|th)?\s+(?P<name>{})\.?,?\s+(?P<year>\d{{4}})\b",
+                MONTHS
+            )),
+            // April 3, 1980
+            build(&format!(
+                r"(?i)\b(?P<name>{})\.?\s+(?P<day>\d{{1,2}})(?:st|nd|rd|th)?,?\s+(?P<year>\d{{4}})\b",
+                MONTHS
+            )),
+        ]
+    })
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let prefix: String = name.chars().take(3).collect::<String>().to_ascii_lowercase();
+    let months = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    months.iter().position(|month| *month == prefix).map(|index| index as u32 + 1)
+}
+
+fn is_valid(year: u32, month: u32, day: u32) -> bool {
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+// Two-digit years are read as 19xx or 20xx; either is plausible for a birth date
+fn full_year(year: &str) -> Option<u32> {
+    let value: u32 = year.parse().ok()?;
+    match year.len() {
+        2 => Some(1900 + value),
+        _ if (1850..=2100).contains(&value) => Some(value),
+        _ => None,
+    }
+}
+
+// Whether a date match is a valid calendar date, and whether its day and month
+// could only be read one way
+fn check(captures: &regex::Captures, order: DateOrder) -> Option<bool> {
+    let number = |name: &str| captures.name(name)?.as_str().parse::<u32>().ok();
+    let year = full_year(captures.name("year")?.as_str())?;
+
+    if let (Some(first), Some(second)) = (number("first"), number("second")) {
+        if captures["sep"] != captures["sep2"] {
+            return None;
+        }
+        let month_first = is_valid(year, first, second);
+        let day_first = is_valid(year, second, first);
+        return match order {
+            DateOrder::MonthFirst => month_first.then_some(true),
+            DateOrder::DayFirst => day_first.then_some(true),
+            DateOrder::Either if month_first && day_first => Some(first == second),
+            DateOrder::Either => (month_first || day_first).then_some(true),
+        };
+    }
+
+    let month = match captures.name("name") {
+        Some(name) => month_number(name.as_str())?,
+        None => number("month")?,
+    };
+    is_valid(year, month, number("day")?).then_some(true)
+}
+
+// Dates introduced by a birth cue ("DOB:", "born on", "date of birth") within
+// the same line. Dates without a cue are left to the model's generic labels.
+pub fn find_dates_of_birth(text: &str, order: DateOrder) -> Vec<PiiEntity> {
+    let cues: Vec<usize> = cue_pattern().find_iter(text).map(|cue| cue.end()).collect();
+    if cues.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found: Vec<PiiEntity> = Vec::new();
+    for pattern in date_patterns() {
+        for captures in pattern.captures_iter(text) {
+            let date = captures.get(0).expect("whole match");
+            let cued = cues.iter().any(|&cue_end| {
+                cue_end <= date.start()
+                    && date.start() - cue_end <= MAX_CUE_GAP
+                    && !text[cue_end..date.start()].contains(['\n', ';'])
+            });
+            if !cued || found.iter().any(|entity| entity.start < date.end() && date.start() < entity.end) {
+                continue;
+            }
+            let Some(unambiguous) = check(&captures, order) else {
+                continue;
+            };
+            found.push(PiiEntity {
+                text: date.as_str().to_string(),
+                label: LABEL.to_string(),
+                confidence: if unambiguous { 0.95 } else { 0.85 },
+                start: date.start(),
+                end: date.end(),
+                attributes: BTreeMap::new(),
+            });
+        }
+    }
+    found.sort_by_key(|entity| entity.start);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dates(text: &str, order: DateOrder) -> Vec<String> {
+        find_dates_of_birth(text, order).into_iter().map(|entity| entity.text).collect()
+    }
+
+    #[test]
+    fn test_locale_order() {
+        assert_eq!(DateOrder::for_locale(Some("en-US")), DateOrder::MonthFirst);
+        assert_eq!(DateOrder::for_locale(Some("en_GB")), DateOrder::DayFirst);
+        assert_eq!(DateOrder::for_locale(Some("de")), DateOrder::Either);
+        assert_eq!(DateOrder::for_locale(None), DateOrder::Either);
+    }
+
+    #[test]
+    fn test_cue_is_required() {
+        let text = "Admitted 03/04/2021. Patient DOB: 12/25/1980, born in Ohio.";
+        let found = find_dates_of_birth(text, DateOrder::Either);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].label, LABEL);
+        assert_eq!(&text[found[0].start..found[0].end], "12/25/1980");
+    }
+
+    #[test]
+    fn test_numeric_dates_follow_locale() {
+        let text = "Date of birth: 25/12/1980";
+        assert_eq!(dates(text, DateOrder::DayFirst), vec!["25/12/1980"]);
+        assert!(dates(text, DateOrder::MonthFirst).is_empty());
+        assert_eq!(dates(text, DateOrder::Either), vec!["25/12/1980"]);
+
+        let ambiguous = find_dates_of_birth("DOB 03/04/1980", DateOrder::Either);
+        assert!(ambiguous[0].confidence < 0.9);
+        assert!(dates("DOB 31.02.1980", DateOrder::Either).is_empty());
+    }
+
+    #[test]
+    fn test_written_and_iso_dates() {
+        assert_eq!(dates("She was born on April 3, 1980.", DateOrder::Either), vec!["April 3, 1980"]);
+        assert_eq!(dates("Geb. 3. März 1980 / born 3rd Apr. 1980", DateOrder::Either), vec!["3rd Apr. 1980"]);
+        assert_eq!(dates("birthdate=1980-02-29", DateOrder::Either), vec!["1980-02-29"]);
+        assert!(dates("birthdate=1981-02-29", DateOrder::Either).is_empty());
+    }
+}
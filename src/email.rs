@@ -0,0 +1,159 @@
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
+
+use crate::error::ApiError;
+use crate::extract::{self, DocumentFormat, ExtractedText};
+use crate::html;
+
+// Headers scanned for names and addresses, in the order they are reported
+const SCANNED_HEADERS: &[&str] = &["From", "To", "Cc", "Bcc", "Reply-To", "Subject"];
+
+// Deepest nesting of multiparts and attached messages followed
+const MAX_DEPTH: usize = 8;
+
+// Text taken from one part of a message. `part` names where it came from:
+// "header:<name>", "body:<section>:<media type>" or "attachment:<section>:<filename>",
+// where the section is the IMAP-style part number, e.g. "1.2".
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailSection {
+    pub part: String,
+    pub text: String,
+}
+
+// Split an RFC 822 message into the texts detection runs over: the address and
+// subject headers, every text/plain and text/html body part (HTML is stripped
+// to its visible text), and attachments in a supported document format (their
+// extracted text joined into one section). Other attachments are ignored.
+pub fn email_sections(bytes: &[u8]) -> Result<Vec<EmailSection>, ApiError> {
+    let message = mailparse::parse_mail(bytes)
+        .map_err(|e| ApiError::InvalidInput(format!("failed to parse email message: {}", e)))?;
+
+    let mut sections: Vec<EmailSection> = SCANNED_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = message.headers.get_all_values(name).join(", ");
+            (!value.trim().is_empty()).then(|| EmailSection {
+                part: format!("header:{}", name.to_ascii_lowercase()),
+                text: value,
+            })
+        })
+        .collect();
+    collect_parts(&message, "1", 0, &mut sections)?;
+    Ok(sections)
+}
+
+fn collect_parts(part: &ParsedMail, section: &str, depth: usize, sections: &mut Vec<EmailSection>) -> Result<(), ApiError> {
+    if depth > MAX_DEPTH {
+        return Err(ApiError::InvalidInput(format!("email nests parts more than {} levels deep", MAX_DEPTH)));
+    }
+    if !part.subparts.is_empty() {
+        for (index, subpart) in part.subparts.iter().enumerate() {
+            // A single-part message's body is part 1, a multipart's children are 1, 2, ...
+            let child = if depth == 0 { (index + 1).to_string() } else { format!("{}.{}", section, index + 1) };
+            collect_parts(subpart, &child, depth + 1, sections)?;
+        }
+        return Ok(());
+    }
+
+    let invalid = |e: mailparse::MailParseError| ApiError::InvalidInput(format!("failed to decode email part {}: {}", section, e));
+    let disposition = part.get_content_disposition();
+    let filename = disposition
+        .params
+        .get("filename")
+        .or_else(|| part.ctype.params.get("name"))
+        .cloned();
+    let mimetype = part.ctype.mimetype.to_ascii_lowercase();
+
+    let attached = disposition.disposition == DispositionType::Attachment || filename.is_some();
+    if !attached && (mimetype == "text/plain" || mimetype == "text/html") {
+        let body = part.get_body().map_err(invalid)?;
+        let text = if mimetype == "text/html" { html::strip_html(&body).text } else { body };
+        // Trailing line breaks before the next boundary are not part of the text;
+        // trimming only the end keeps offsets into the section unchanged
+        sections.push(EmailSection {
+            part: format!("body:{}:{}", section, mimetype),
+            text: text.trim_end().to_string(),
+        });
+        return Ok(());
+    }
+
+    let format = filename.as_deref().and_then(DocumentFormat::from_filename).or_else(|| {
+        let (top, sub) = mimetype.split_once('/')?;
+        DocumentFormat::from_media_type(top, sub)
+    });
+    let Some(format) = format else { return Ok(()) };
+    if format == DocumentFormat::Email && depth >= MAX_DEPTH {
+        return Ok(());
+    }
+    // Attachments that can't be read are left out rather than failing the message
+    if let Ok(extracted) = extract::extract_text(format, part.get_body_raw().map_err(invalid)?) {
+        sections.push(EmailSection {
+            part: format!("attachment:{}:{}", section, filename.as_deref().unwrap_or(format.name())),
+            text: ExtractedText::join(&extracted.sections, extracted.unit),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "From: Jane Roe <jane@example.com>\r
+To: john.doe@example.org\r
+Subject: Payroll for Max Mustermann\r
+MIME-Version: 1.0\r
+Content-Type: multipart/mixed; boundary=\"outer\"\r
+\r
+--outer\r
+Content-Type: multipart/alternative; boundary=\"inner\"\r
+\r
+--inner\r
+Content-Type: text/plain; charset=utf-8\r
+\r
+Call me on 555-0100.\r
+--inner\r
+Content-Type: text/html; charset=utf-8\r
+\r
+<p>Call me on <b>555-0100</b>.</p>\r
+--inner--\r
+--outer\r
+Content-Type: text/plain\r
+Content-Disposition: attachment; filename=\"notes.txt\"\r
+Content-Transfer-Encoding: base64\r
+\r
+U1NOIDA3OC0wNS0xMTIw\r
+--outer\r
+Content-Type: image/png\r
+Content-Disposition: attachment; filename=\"logo.png\"\r
+\r
+PNG\r
+--outer--\r
+";
+
+    #[test]
+    fn test_sections_are_tagged_with_their_part() {
+        let sections = email_sections(MESSAGE.as_bytes()).unwrap();
+        let parts: Vec<&str> = sections.iter().map(|section| section.part.as_str()).collect();
+        assert_eq!(
+            parts,
+            vec![
+                "header:from",
+                "header:to",
+                "header:subject",
+                "body:1.1:text/plain",
+                "body:1.2:text/html",
+                "attachment:2:notes.txt",
+            ]
+        );
+        assert_eq!(sections[2].text, "Payroll for Max Mustermann");
+        assert_eq!(sections[4].text, "Call me on 555-0100.");
+        assert_eq!(sections[5].text, "SSN 078-05-1120");
+    }
+
+    #[test]
+    fn test_single_part_message() {
+        let sections = email_sections(b"Subject: Hi\r\n\r\nJane Roe here").unwrap();
+        assert_eq!(sections[1].part, "body:1:text/plain");
+        assert_eq!(sections[1].text, "Jane Roe here");
+    }
+}
@@ -0,0 +1,253 @@
+#[cfg(feature = "server")]
+use rocket::http::{Header, Status};
+#[cfg(feature = "server")]
+use rocket::request::Request;
+#[cfg(feature = "server")]
+use rocket::response::{self, Responder};
+#[cfg(feature = "server")]
+use rocket::serde::json::Json;
+#[cfg(feature = "server")]
+use rocket::catch;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Errors returned by the API handlers. Each variant maps to an HTTP status and a
+// stable `error_code` that clients can branch on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    ModelNotLoaded,
+    InvalidInput(String),
+    InferenceFailed(String),
+    PayloadTooLarge(String),
+    // The request deadline passed; carries how far inference got
+    Timeout {
+        timeout_ms: u64,
+        elapsed_ms: u64,
+        completed_batches: usize,
+        total_batches: usize,
+    },
+    // Too many requests are already queued for inference
+    Overloaded {
+        queue_depth: usize,
+        capacity: usize,
+    },
+    // The instance is draining and no longer takes new detection work
+    Draining,
+    // Missing or wrong admin token
+    Unauthorized,
+    // A stored resource that does not exist, e.g. an unknown result id
+    NotFound(String),
+    // Reading or writing stored results failed
+    StorageFailed(String),
+    // Inference kept failing; the model is being reloaded
+    CircuitOpen,
+}
+
+// Error body, shaped like `ApiResponse` plus the error code
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub data: Option<serde_json::Value>,
+    pub message: Option<String>,
+    pub error_code: String,
+    // Id of the failed request, as in the `X-Request-Id` response header, to
+    // quote when reporting a problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    #[cfg(feature = "server")]
+    pub fn status(&self) -> Status {
+        match self {
+            ApiError::ModelNotLoaded => Status::ServiceUnavailable,
+            ApiError::InvalidInput(_) => Status::UnprocessableEntity,
+            ApiError::InferenceFailed(_) => Status::InternalServerError,
+            ApiError::PayloadTooLarge(_) => Status::PayloadTooLarge,
+            ApiError::Timeout { .. } => Status::GatewayTimeout,
+            ApiError::Overloaded { .. } => Status::TooManyRequests,
+            ApiError::Draining => Status::ServiceUnavailable,
+            ApiError::Unauthorized => Status::Unauthorized,
+            ApiError::NotFound(_) => Status::NotFound,
+            ApiError::StorageFailed(_) => Status::InternalServerError,
+            ApiError::CircuitOpen => Status::ServiceUnavailable,
+        }
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ApiError::ModelNotLoaded => "model_not_loaded",
+            ApiError::InvalidInput(_) => "invalid_input",
+            ApiError::InferenceFailed(_) => "inference_failed",
+            ApiError::PayloadTooLarge(_) => "payload_too_large",
+            ApiError::Timeout { .. } => "timeout",
+            ApiError::Overloaded { .. } => "queue_full",
+            ApiError::Draining => "draining",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::StorageFailed(_) => "storage_failed",
+            ApiError::CircuitOpen => "circuit_open",
+        }
+    }
+
+    // Machine-readable diagnostics returned in `data`
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            ApiError::Timeout { timeout_ms, elapsed_ms, completed_batches, total_batches } => {
+                Some(serde_json::json!({
+                    "timeout_ms": timeout_ms,
+                    "elapsed_ms": elapsed_ms,
+                    "completed_batches": completed_batches,
+                    "total_batches": total_batches,
+                }))
+            }
+            ApiError::Overloaded { queue_depth, capacity } => Some(serde_json::json!({
+                "queue_depth": queue_depth,
+                "capacity": capacity,
+            })),
+            _ => None,
+        }
+    }
+
+    pub fn body(&self) -> ErrorResponse {
+        ErrorResponse {
+            success: false,
+            data: self.details(),
+            message: Some(self.to_string()),
+            error_code: self.error_code().to_string(),
+            request_id: None,
+        }
+    }
+
+    // Body of the response to `request`, carrying its request id. The failure is
+    // logged by code only, since messages can quote input.
+    #[cfg(feature = "server")]
+    pub fn body_for(&self, request: &Request<'_>) -> ErrorResponse {
+        let request_id = crate::logging::RequestId::of(request);
+        tracing::warn!(
+            request_id = %request_id,
+            status = self.status().code,
+            error_code = self.error_code(),
+            "Request failed"
+        );
+        ErrorResponse {
+            request_id: Some(request_id.0),
+            ..self.body()
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::ModelNotLoaded => {
+                write!(f, "PII detection model not loaded. Please ensure model files are available.")
+            }
+            ApiError::InvalidInput(message) => write!(f, "Invalid input: {}", message),
+            ApiError::InferenceFailed(message) => write!(f, "Inference failed: {}", message),
+            ApiError::PayloadTooLarge(message) => write!(f, "Payload too large: {}", message),
+            ApiError::Timeout { timeout_ms, .. } => {
+                write!(f, "Request did not complete within {} ms", timeout_ms)
+            }
+            ApiError::Overloaded { queue_depth, capacity } => {
+                write!(f, "Inference queue is full ({} of {} slots in use), retry later", queue_depth, capacity)
+            }
+            ApiError::Draining => write!(f, "Instance is draining for maintenance, retry on another instance"),
+            ApiError::Unauthorized => write!(f, "Admin token missing or invalid"),
+            ApiError::NotFound(message) => write!(f, "Not found: {}", message),
+            ApiError::StorageFailed(message) => write!(f, "Storage failed: {}", message),
+            ApiError::CircuitOpen => write!(f, "Inference is failing repeatedly and the model is being reloaded, retry later"),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[cfg(feature = "server")]
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let private = request.rocket().state::<crate::config::Config>().is_some_and(|config| config.privacy_mode);
+        let error = crate::privacy::scrub_error(private, self);
+        let mut response = (error.status(), Json(error.body_for(request))).respond_to(request)?;
+        match error {
+            ApiError::Overloaded { .. } => {
+                response.set_header(Header::new("Retry-After", "1"));
+            }
+            ApiError::CircuitOpen => {
+                response.set_header(Header::new("Retry-After", "10"));
+            }
+            _ => {}
+        }
+        Ok(response)
+    }
+}
+
+// Catchers so that errors raised by Rocket itself (malformed JSON, body limits)
+// share the same shape and error codes as handler errors
+
+#[cfg(feature = "server")]
+#[catch(400)]
+pub fn bad_request(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ApiError::InvalidInput("request body could not be parsed".to_string()).body_for(request))
+}
+
+#[cfg(feature = "server")]
+#[catch(401)]
+pub fn unauthorized(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ApiError::Unauthorized.body_for(request))
+}
+
+#[cfg(feature = "server")]
+#[catch(413)]
+pub fn payload_too_large(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ApiError::PayloadTooLarge("request body exceeds the configured limit".to_string()).body_for(request))
+}
+
+#[cfg(feature = "server")]
+#[catch(415)]
+pub fn unsupported_media_type(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ApiError::InvalidInput("request body encoding is not supported by this endpoint".to_string()).body_for(request))
+}
+
+#[cfg(feature = "server")]
+#[catch(422)]
+pub fn unprocessable_entity(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ApiError::InvalidInput("request body does not match the expected schema".to_string()).body_for(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn test_error_status_mapping() {
+        assert_eq!(ApiError::ModelNotLoaded.status(), Status::ServiceUnavailable);
+        assert_eq!(ApiError::InvalidInput("x".into()).status(), Status::UnprocessableEntity);
+        assert_eq!(ApiError::InferenceFailed("x".into()).status(), Status::InternalServerError);
+        assert_eq!(ApiError::PayloadTooLarge("x".into()).status(), Status::PayloadTooLarge);
+        let timeout = ApiError::Timeout { timeout_ms: 10, elapsed_ms: 11, completed_batches: 1, total_batches: 4 };
+        assert_eq!(timeout.status(), Status::GatewayTimeout);
+        assert_eq!(ApiError::Overloaded { queue_depth: 4, capacity: 4 }.status(), Status::TooManyRequests);
+        assert_eq!(ApiError::Draining.status(), Status::ServiceUnavailable);
+        assert_eq!(ApiError::Unauthorized.status(), Status::Unauthorized);
+        assert_eq!(ApiError::NotFound("x".into()).status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_timeout_body_has_diagnostics() {
+        let body = ApiError::Timeout { timeout_ms: 10, elapsed_ms: 11, completed_batches: 1, total_batches: 4 }.body();
+        assert_eq!(body.error_code, "timeout");
+        let details = body.data.expect("timeout diagnostics");
+        assert_eq!(details["completed_batches"], 1);
+        assert_eq!(details["total_batches"], 4);
+    }
+
+    #[test]
+    fn test_error_body_has_stable_code() {
+        let body = ApiError::InvalidInput("text is empty".into()).body();
+        assert!(!body.success);
+        assert_eq!(body.error_code, "invalid_input");
+        assert_eq!(body.message.unwrap(), "Invalid input: text is empty");
+    }
+}
@@ -0,0 +1,311 @@
+// Scoring detections against gold annotations, for POST /api/eval: precision,
+// recall and F1 overall and per label. Spans match exactly (same offsets) or
+// partially (overlapping), always with the same label, and each gold entity
+// can be matched by one finding at most. POST /api/eval/sweep scores the same
+// findings at a range of thresholds to pick one from.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::PiiEntity;
+
+// An annotated entity, with offsets as the detect endpoints report them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldEntity {
+    pub label: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalDocument {
+    pub text: String,
+    #[serde(default)]
+    pub entities: Vec<GoldEntity>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanMatch {
+    #[default]
+    Exact,
+    Partial,
+}
+
+impl SpanMatch {
+    fn matches(self, found: &PiiEntity, gold: &GoldEntity) -> bool {
+        found.label == gold.label
+            && match self {
+                SpanMatch::Exact => found.start == gold.start && found.end == gold.end,
+                SpanMatch::Partial => found.start < gold.end && gold.start < found.end,
+            }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalRequest {
+    pub documents: Vec<EvalDocument>,
+    #[serde(default, rename = "match")]
+    pub span_match: SpanMatch,
+    // Labels to detect; defaults to every label in the annotations
+    #[serde(default)]
+    pub labels: Option<Vec<String>>,
+    // Ignore findings scored below this
+    #[serde(default)]
+    pub threshold: Option<f32>,
+}
+
+impl EvalRequest {
+    pub fn labels(&self) -> Vec<String> {
+        match &self.labels {
+            Some(labels) if !labels.is_empty() => labels.clone(),
+            _ => gold_labels(&self.documents),
+        }
+    }
+}
+
+pub fn gold_labels(documents: &[EvalDocument]) -> Vec<String> {
+    let labels: BTreeSet<&String> = documents.iter().flat_map(|document| &document.entities).map(|entity| &entity.label).collect();
+    labels.into_iter().cloned().collect()
+}
+
+// The sweep's thresholds, from the model's own 0.5 (below which it reports
+// nothing) up in steps of 0.05
+pub const DEFAULT_SWEEP: [f32; 10] = [0.5, 0.55, 0.6, 0.65, 0.7, 0.75, 0.8, 0.85, 0.9, 0.95];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepRequest {
+    // `threshold` is not used; every threshold in `thresholds` is scored
+    #[serde(flatten)]
+    pub eval: EvalRequest,
+    #[serde(default)]
+    pub thresholds: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scores {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Scores {
+    pub fn of(true_positives: usize, false_positives: usize, false_negatives: usize) -> Self {
+        let ratio = |part: usize, whole: usize| if whole == 0 { 0.0 } else { part as f64 / whole as f64 };
+        let precision = ratio(true_positives, true_positives + false_positives);
+        let recall = ratio(true_positives, true_positives + false_negatives);
+        let f1 = if precision + recall == 0.0 { 0.0 } else { 2.0 * precision * recall / (precision + recall) };
+        Scores {
+            true_positives,
+            false_positives,
+            false_negatives,
+            precision,
+            recall,
+            f1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    #[serde(rename = "match")]
+    pub span_match: SpanMatch,
+    pub documents: usize,
+    pub overall: Scores,
+    pub per_label: BTreeMap<String, Scores>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurvePoint {
+    pub threshold: f32,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Curve {
+    // In threshold order
+    pub points: Vec<CurvePoint>,
+    // The point with the best F1; of equal ones, the highest threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommended: Option<CurvePoint>,
+}
+
+impl Curve {
+    fn push(&mut self, threshold: f32, scores: &Scores) {
+        self.points.push(CurvePoint {
+            threshold,
+            precision: scores.precision,
+            recall: scores.recall,
+            f1: scores.f1,
+        });
+    }
+
+    fn recommend(mut self) -> Self {
+        self.recommended = self
+            .points
+            .iter()
+            .copied()
+            .reduce(|best, point| if point.f1 >= best.f1 { point } else { best });
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepReport {
+    #[serde(rename = "match")]
+    pub span_match: SpanMatch,
+    pub documents: usize,
+    pub overall: Curve,
+    pub per_label: BTreeMap<String, Curve>,
+}
+
+// Score the findings at each threshold. They come from one detection run, so
+// this is only meaningful for thresholds at or above the model's own.
+pub fn sweep(gold: &[Vec<GoldEntity>], found: &[Vec<PiiEntity>], span_match: SpanMatch, thresholds: &[f32]) -> SweepReport {
+    let mut thresholds = thresholds.to_vec();
+    thresholds.sort_by(f32::total_cmp);
+    thresholds.dedup();
+
+    let mut overall = Curve::default();
+    let mut per_label: BTreeMap<String, Curve> = BTreeMap::new();
+    for threshold in thresholds {
+        let report = evaluate(gold, found, span_match, Some(threshold));
+        overall.push(threshold, &report.overall);
+        // Labels nothing was found or annotated for at this threshold score zero
+        for label in report.per_label.keys() {
+            per_label.entry(label.clone()).or_default();
+        }
+        for (label, curve) in per_label.iter_mut() {
+            curve.push(threshold, &report.per_label.get(label).copied().unwrap_or_default());
+        }
+    }
+    SweepReport {
+        span_match,
+        documents: gold.len(),
+        overall: overall.recommend(),
+        per_label: per_label.into_iter().map(|(label, curve)| (label, curve.recommend())).collect(),
+    }
+}
+
+// Score each document's findings against its gold entities. Findings are
+// matched in order of confidence, so a gold entity goes to the best-scored
+// finding that matches it.
+pub fn evaluate(gold: &[Vec<GoldEntity>], found: &[Vec<PiiEntity>], span_match: SpanMatch, threshold: Option<f32>) -> EvalReport {
+    // (true positives, false positives, false negatives) per label
+    let mut counts: BTreeMap<String, (usize, usize, usize)> = BTreeMap::new();
+    for (gold, found) in gold.iter().zip(found) {
+        let mut found: Vec<&PiiEntity> = found
+            .iter()
+            .filter(|entity| entity.confidence >= threshold.unwrap_or(f32::MIN))
+            .collect();
+        found.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let mut matched = vec![false; gold.len()];
+        for entity in found {
+            let hit = (0..gold.len()).find(|&index| !matched[index] && span_match.matches(entity, &gold[index]));
+            let label = counts.entry(entity.label.clone()).or_default();
+            match hit {
+                Some(index) => {
+                    matched[index] = true;
+                    label.0 += 1;
+                }
+                None => label.1 += 1,
+            }
+        }
+        for (entity, _) in gold.iter().zip(&matched).filter(|(_, &matched)| !matched) {
+            counts.entry(entity.label.clone()).or_default().2 += 1;
+        }
+    }
+
+    let per_label: BTreeMap<String, Scores> = counts
+        .into_iter()
+        .map(|(label, (tp, fp, fn_))| (label, Scores::of(tp, fp, fn_)))
+        .collect();
+    let total = |field: fn(&Scores) -> usize| per_label.values().map(field).sum::<usize>();
+    EvalReport {
+        span_match,
+        documents: gold.len(),
+        overall: Scores::of(total(|s| s.true_positives), total(|s| s.false_positives), total(|s| s.false_negatives)),
+        per_label,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    fn gold(label: &str, start: usize, end: usize) -> GoldEntity {
+        GoldEntity {
+            label: label.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_exact_and_partial_matching() {
+        let golds = vec![vec![gold("person", 0, 8), gold("email", 20, 36), gold("phone", 40, 52)]];
+        let finds = vec![vec![entity("person", 0, 8, 0.9), entity("email", 20, 33, 0.8), entity("address", 60, 70, 0.7)]];
+
+        let exact = evaluate(&golds, &finds, SpanMatch::Exact, None);
+        assert_eq!((exact.overall.true_positives, exact.overall.false_positives, exact.overall.false_negatives), (1, 2, 2));
+        assert_eq!(exact.per_label["person"].f1, 1.0);
+        assert_eq!(exact.per_label["email"].recall, 0.0);
+
+        let partial = evaluate(&golds, &finds, SpanMatch::Partial, None);
+        assert_eq!(partial.overall.true_positives, 2);
+        assert_eq!(partial.per_label["email"].precision, 1.0);
+        assert_eq!(partial.per_label["phone"].false_negatives, 1);
+        assert_eq!(partial.per_label["address"].false_positives, 1);
+    }
+
+    #[test]
+    fn test_gold_entity_matches_once() {
+        let golds = vec![vec![gold("person", 0, 8)]];
+        let finds = vec![vec![entity("person", 0, 4, 0.6), entity("person", 0, 8, 0.9)]];
+        let report = evaluate(&golds, &finds, SpanMatch::Partial, None);
+        assert_eq!((report.overall.true_positives, report.overall.false_positives), (1, 1));
+    }
+
+    #[test]
+    fn test_threshold_drops_low_scores() {
+        let golds = vec![vec![gold("person", 0, 8)]];
+        let finds = vec![vec![entity("person", 0, 8, 0.4)]];
+        let report = evaluate(&golds, &finds, SpanMatch::Exact, Some(0.5));
+        assert_eq!(report.overall.recall, 0.0);
+        assert_eq!(report.overall.precision, 0.0);
+    }
+
+    #[test]
+    fn test_sweep_recommends_best_f1() {
+        let golds = vec![vec![gold("person", 0, 8), gold("person", 20, 28)]];
+        let finds = vec![vec![entity("person", 0, 8, 0.9), entity("person", 20, 28, 0.7), entity("person", 40, 48, 0.55)]];
+        let report = sweep(&golds, &finds, SpanMatch::Exact, &[0.8, 0.5, 0.6]);
+
+        let thresholds: Vec<f32> = report.overall.points.iter().map(|point| point.threshold).collect();
+        assert_eq!(thresholds, vec![0.5, 0.6, 0.8]);
+        let recommended = report.per_label["person"].recommended.unwrap();
+        assert_eq!(recommended.threshold, 0.6);
+        assert_eq!((recommended.precision, recommended.recall), (1.0, 1.0));
+        assert_eq!(report.per_label["person"].points[2].recall, 0.5);
+    }
+
+    #[test]
+    fn test_labels_default_to_annotations() {
+        let request: EvalRequest = serde_json::from_value(serde_json::json!({
+            "documents": [{"text": "Jane Roe, jane@example.com", "entities": [
+                {"label": "person", "start": 0, "end": 8},
+                {"label": "email", "start": 10, "end": 26}
+            ]}],
+            "match": "partial"
+        }))
+        .unwrap();
+        assert_eq!(request.span_match, SpanMatch::Partial);
+        assert_eq!(request.labels(), vec!["email".to_string(), "person".to_string()]);
+    }
+}
@@ -0,0 +1,353 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use crate::charset::{self, TextEncoding};
+use crate::email;
+use crate::error::ApiError;
+
+// Uploaded file formats text can be extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    PlainText,
+    Markdown,
+    Pdf,
+    Docx,
+    Odt,
+    // RFC 822 message (.eml)
+    Email,
+    // Raster image, read with OCR
+    Image,
+}
+
+impl DocumentFormat {
+    // Pick the format from the file extension
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let extension = Path::new(filename).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "txt" | "text" | "log" => Some(DocumentFormat::PlainText),
+            "md" | "markdown" => Some(DocumentFormat::Markdown),
+            "pdf" => Some(DocumentFormat::Pdf),
+            "docx" => Some(DocumentFormat::Docx),
+            "odt" => Some(DocumentFormat::Odt),
+            "eml" => Some(DocumentFormat::Email),
+            "png" | "jpg" | "jpeg" | "tif" | "tiff" | "bmp" | "gif" | "webp" => Some(DocumentFormat::Image),
+            _ => None,
+        }
+    }
+
+    // Fallback for uploads without a usable filename, e.g. "text/markdown"
+    pub fn from_media_type(top: &str, sub: &str) -> Option<Self> {
+        match (top.to_ascii_lowercase().as_str(), sub.to_ascii_lowercase().as_str()) {
+            ("text", "plain") => Some(DocumentFormat::PlainText),
+            ("text", "markdown") | ("text", "x-markdown") => Some(DocumentFormat::Markdown),
+            ("application", "pdf") => Some(DocumentFormat::Pdf),
+            ("application", "vnd.openxmlformats-officedocument.wordprocessingml.document") => Some(DocumentFormat::Docx),
+            ("application", "vnd.oasis.opendocument.text") => Some(DocumentFormat::Odt),
+            ("message", "rfc822") => Some(DocumentFormat::Email),
+            ("image", _) => Some(DocumentFormat::Image),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DocumentFormat::PlainText => "plain_text",
+            DocumentFormat::Markdown => "markdown",
+            DocumentFormat::Pdf => "pdf",
+            DocumentFormat::Docx => "docx",
+            DocumentFormat::Odt => "odt",
+            DocumentFormat::Email => "email",
+            DocumentFormat::Image => "image",
+        }
+    }
+}
+
+// What each extracted section of a document corresponds to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionUnit {
+    // The whole file; offsets are file-relative
+    File,
+    // One page per section, numbered from 1; offsets are page-relative
+    Page,
+    // One paragraph (or heading) per section, indexed from 0; offsets are
+    // paragraph-relative
+    Paragraph,
+    // One header, body part or attachment of an email, named in `parts`;
+    // offsets are relative to that part's text
+    MimePart,
+}
+
+// Text of an uploaded file, split into the sections detection runs over
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedText {
+    pub unit: SectionUnit,
+    pub sections: Vec<String>,
+    // Name of each section for MIME parts, empty for other units
+    pub parts: Vec<String>,
+    // Character encoding the file was decoded from, for text formats
+    pub encoding: Option<TextEncoding>,
+}
+
+impl ExtractedText {
+    // Sections joined back into one text, e.g. for redacted output. Pages are
+    // separated by a form feed, as text extraction tools conventionally do.
+    pub fn join(sections: &[String], unit: SectionUnit) -> String {
+        match unit {
+            SectionUnit::File => sections.concat(),
+            SectionUnit::Page => sections.join("\u{c}"),
+            SectionUnit::Paragraph => sections.join("\n"),
+            SectionUnit::MimePart => sections.join("\n\n"),
+        }
+    }
+}
+
+// Extract the text of an uploaded file. Formats that are already text are passed
+// through unchanged so entity offsets stay file-relative (Markdown syntax is
+// scanned as-is), unless they have to be transcoded from another encoding. PDFs are read from their text layer, page by page, and word
+// processor documents paragraph by paragraph. Emails are split into their
+// headers, body parts and readable attachments.
+pub fn extract_text(format: DocumentFormat, bytes: Vec<u8>) -> Result<ExtractedText, ApiError> {
+    let extracted = extract_sections(format, bytes)?;
+    if extracted.sections.iter().all(|section| section.trim().is_empty()) {
+        let message = match format {
+            DocumentFormat::Pdf => "PDF has no text layer (scanned documents need OCR)",
+            _ => "file contains no text",
+        };
+        return Err(ApiError::InvalidInput(message.to_string()));
+    }
+    Ok(extracted)
+}
+
+// Extract the sections of a file without checking that any of them has text,
+// e.g. to find the blank pages of a PDF that need OCR
+pub fn extract_sections(format: DocumentFormat, bytes: Vec<u8>) -> Result<ExtractedText, ApiError> {
+    let extracted = match format {
+        DocumentFormat::PlainText | DocumentFormat::Markdown => {
+            let (text, encoding) = charset::decode(bytes)?;
+            ExtractedText {
+                unit: SectionUnit::File,
+                sections: vec![text],
+                parts: Vec::new(),
+                encoding: Some(encoding),
+            }
+        }
+        DocumentFormat::Pdf => ExtractedText {
+            unit: SectionUnit::Page,
+            sections: pdf_extract::extract_text_from_mem_by_pages(&bytes)
+                .map_err(|e| ApiError::InvalidInput(format!("failed to read PDF: {}", e)))?,
+            parts: Vec::new(),
+            encoding: None,
+        },
+        DocumentFormat::Docx => ExtractedText {
+            unit: SectionUnit::Paragraph,
+            sections: xml_paragraphs(&zip_entry(&bytes, "word/document.xml")?, &DOCX)?,
+            parts: Vec::new(),
+            encoding: None,
+        },
+        DocumentFormat::Odt => ExtractedText {
+            unit: SectionUnit::Paragraph,
+            sections: xml_paragraphs(&zip_entry(&bytes, "content.xml")?, &ODT)?,
+            parts: Vec::new(),
+            encoding: None,
+        },
+        DocumentFormat::Email => {
+            let (parts, sections) = email::email_sections(&bytes)?
+                .into_iter()
+                .map(|section| (section.part, section.text))
+                .unzip();
+            ExtractedText {
+                unit: SectionUnit::MimePart,
+                sections,
+                parts,
+                encoding: None,
+            }
+        }
+        // Only direct uploads are sent through OCR, see `ocr::extract_with_ocr`
+        DocumentFormat::Image => {
+            return Err(ApiError::InvalidInput("images are only scanned as direct uploads, with OCR".to_string()))
+        }
+    };
+    Ok(extracted)
+}
+
+// Largest XML part read out of an Office archive, to bound zip bombs
+const MAX_XML_BYTES: u64 = 64 * 1024 * 1024;
+
+fn zip_entry(bytes: &[u8], name: &str) -> Result<String, ApiError> {
+    let invalid = |e: &dyn std::fmt::Display| ApiError::InvalidInput(format!("failed to read document archive: {}", e));
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| invalid(&e))?;
+    let entry = archive.by_name(name).map_err(|e| invalid(&e))?;
+
+    let mut xml = String::new();
+    entry.take(MAX_XML_BYTES + 1).read_to_string(&mut xml).map_err(|e| invalid(&e))?;
+    if xml.len() as u64 > MAX_XML_BYTES {
+        return Err(ApiError::PayloadTooLarge(format!("{} expands to more than {} bytes", name, MAX_XML_BYTES)));
+    }
+    Ok(xml)
+}
+
+// Element names of a word processing format's body markup
+struct Markup {
+    paragraphs: &'static [&'static [u8]],
+    // Text is only taken from inside this element when set, otherwise from
+    // anywhere within a paragraph
+    run_text: Option<&'static [u8]>,
+    tab: &'static [u8],
+    line_break: &'static [u8],
+    // Element standing for a run of spaces, with the count in its `<name>:c` attribute
+    spaces: Option<&'static [u8]>,
+}
+
+const DOCX: Markup = Markup {
+    paragraphs: &[b"w:p"],
+    run_text: Some(b"w:t"),
+    tab: b"w:tab",
+    line_break: b"w:br",
+    spaces: None,
+};
+
+const ODT: Markup = Markup {
+    paragraphs: &[b"text:p", b"text:h"],
+    run_text: None,
+    tab: b"text:tab",
+    line_break: b"text:line-break",
+    spaces: Some(b"text:s"),
+};
+
+// Text of every top-level paragraph in document order, empty ones included so
+// paragraph indices match the document
+fn xml_paragraphs(xml: &str, markup: &Markup) -> Result<Vec<String>, ApiError> {
+    let invalid = |e: quick_xml::Error| ApiError::InvalidInput(format!("malformed document XML: {}", e));
+    let mut reader = Reader::from_str(xml);
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut in_run = 0usize;
+
+    loop {
+        match reader.read_event().map_err(invalid)? {
+            Event::Start(element) => {
+                let name = element.name();
+                if markup.paragraphs.contains(&name.as_ref()) {
+                    depth += 1;
+                } else if Some(name.as_ref()) == markup.run_text {
+                    in_run += 1;
+                }
+            }
+            Event::End(element) => {
+                let name = element.name();
+                if markup.paragraphs.contains(&name.as_ref()) {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        paragraphs.push(std::mem::take(&mut current));
+                    }
+                } else if Some(name.as_ref()) == markup.run_text {
+                    in_run = in_run.saturating_sub(1);
+                }
+            }
+            Event::Empty(element) => {
+                let name = element.name();
+                let name = name.as_ref();
+                if markup.paragraphs.contains(&name) {
+                    if depth == 0 {
+                        paragraphs.push(String::new());
+                    }
+                } else if depth > 0 {
+                    if name == markup.tab {
+                        current.push('\t');
+                    } else if name == markup.line_break {
+                        current.push('\n');
+                    } else if Some(name) == markup.spaces {
+                        let count = element
+                            .attributes()
+                            .flatten()
+                            .find(|attribute| attribute.key.local_name().as_ref() == b"c")
+                            .and_then(|attribute| attribute.unescape_value().ok()?.parse().ok())
+                            .unwrap_or(1usize);
+                        current.extend(std::iter::repeat_n(' ', count));
+                    }
+                }
+            }
+            Event::Text(text) if depth > 0 && (markup.run_text.is_none() || in_run > 0) => {
+                current.push_str(&text.unescape().map_err(invalid)?);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(paragraphs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_filename() {
+        assert_eq!(DocumentFormat::from_filename("notes.TXT"), Some(DocumentFormat::PlainText));
+        assert_eq!(DocumentFormat::from_filename("docs/README.md"), Some(DocumentFormat::Markdown));
+        assert_eq!(DocumentFormat::from_filename("contract.pdf"), Some(DocumentFormat::Pdf));
+        assert_eq!(DocumentFormat::from_filename("inbox/0001.eml"), Some(DocumentFormat::Email));
+        assert_eq!(DocumentFormat::from_filename("archive.zip"), None);
+        assert_eq!(DocumentFormat::from_filename("no-extension"), None);
+    }
+
+    #[test]
+    fn test_format_from_media_type() {
+        assert_eq!(DocumentFormat::from_media_type("text", "markdown"), Some(DocumentFormat::Markdown));
+        assert_eq!(DocumentFormat::from_media_type("application", "pdf"), Some(DocumentFormat::Pdf));
+        assert_eq!(DocumentFormat::from_media_type("image", "png"), Some(DocumentFormat::Image));
+        assert_eq!(DocumentFormat::from_media_type("video", "mp4"), None);
+    }
+
+    #[test]
+    fn test_text_passes_through_unchanged() {
+        let text = "# Contact\n\nEmail **jane@example.com**";
+        let extracted = extract_text(DocumentFormat::Markdown, text.as_bytes().to_vec()).unwrap();
+        assert_eq!(extracted.unit, SectionUnit::File);
+        assert_eq!(extracted.sections, vec![text.to_string()]);
+    }
+
+    #[test]
+    fn test_invalid_or_empty_files_are_rejected() {
+        assert!(extract_text(DocumentFormat::PlainText, vec![0xff, 0xfe, 0x00]).is_err());
+        assert!(extract_text(DocumentFormat::PlainText, b" \n\t".to_vec()).is_err());
+        assert!(extract_text(DocumentFormat::Pdf, b"not a pdf".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_docx_paragraphs() {
+        let xml = r#"<w:document><w:body>
+            <w:p><w:r><w:t>Employee: </w:t></w:r><w:r><w:t>Jane &amp; John</w:t></w:r></w:p>
+            <w:p/>
+            <w:p><w:r><w:instrText>PAGE</w:instrText><w:t>SSN</w:t><w:tab/><w:t>078-05-1120</w:t></w:r></w:p>
+        </w:body></w:document>"#;
+        let paragraphs = xml_paragraphs(xml, &DOCX).unwrap();
+        assert_eq!(paragraphs, vec!["Employee: Jane & John", "", "SSN\t078-05-1120"]);
+    }
+
+    #[test]
+    fn test_odt_paragraphs() {
+        let xml = r#"<office:text>
+            <text:h>Contract</text:h>
+            <text:p>Signed by <text:span>Max Mustermann</text:span><text:s text:c="3"/>Berlin<text:line-break/>2024</text:p>
+        </office:text>"#;
+        let paragraphs = xml_paragraphs(xml, &ODT).unwrap();
+        assert_eq!(paragraphs, vec!["Contract", "Signed by Max Mustermann   Berlin\n2024"]);
+    }
+
+    #[test]
+    fn test_office_files_must_be_archives() {
+        assert!(extract_text(DocumentFormat::Docx, b"plain text".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_pages_join_with_form_feed() {
+        let pages = vec!["one".to_string(), "two".to_string()];
+        assert_eq!(ExtractedText::join(&pages, SectionUnit::Page), "one\u{c}two");
+    }
+}
@@ -0,0 +1,144 @@
+// Examples collected from production for fine-tuning: a sample of detections
+// the model was unsure about (a finding scored below
+// GLINER_FEEDBACK_CONFIDENCE_BELOW) and the decisions of human reviewers (see
+// `review`), kept in memory with their text until exported in GLiNER's
+// training format from GET /api/admin/training/export.
+// Stored texts are as sensitive as the traffic they came from, so collection
+// is off unless GLINER_FEEDBACK_CAPACITY is set, and privacy mode requests are
+// never kept.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+
+use crate::canary::take_share;
+use crate::config::Config;
+use crate::model_info::unix_now;
+use crate::training::{self, TrainingExample};
+use crate::PiiEntity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackSource {
+    // Model output as is, sampled because a finding scored low
+    LowConfidence,
+    // A detection corrected by a reviewer's decision on one of its findings
+    Review,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackExample {
+    pub text: String,
+    // Byte offsets into `text`
+    pub entities: Vec<PiiEntity>,
+    pub source: FeedbackSource,
+    // Unix seconds
+    pub recorded_at: u64,
+}
+
+pub struct FeedbackStore {
+    capacity: usize,
+    confidence_below: f32,
+    percent: f64,
+    requests: AtomicU64,
+    examples: Mutex<VecDeque<FeedbackExample>>,
+}
+
+pub type FeedbackState = Arc<Option<FeedbackStore>>;
+
+impl FeedbackStore {
+    pub fn new(capacity: usize, confidence_below: f32, percent: f64) -> Self {
+        FeedbackStore {
+            capacity,
+            confidence_below,
+            percent: percent.clamp(0.0, 100.0),
+            requests: AtomicU64::new(0),
+            examples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (config.feedback_capacity > 0).then(|| {
+            FeedbackStore::new(config.feedback_capacity, config.feedback_confidence_below, config.feedback_sample_percent)
+        })
+    }
+
+    // Keep a detection for training when one of its findings scored low, for
+    // the sampled share of such detections
+    pub fn sample(&self, text: &str, entities: &[PiiEntity]) -> bool {
+        if !entities.iter().any(|entity| entity.confidence < self.confidence_below) {
+            return false;
+        }
+        if !take_share(&self.requests, self.percent) {
+            return false;
+        }
+        self.record(FeedbackExample {
+            text: text.to_string(),
+            entities: entities.to_vec(),
+            source: FeedbackSource::LowConfidence,
+            recorded_at: unix_now(),
+        });
+        true
+    }
+
+    // Add an example, dropping the oldest once the store is full
+    pub fn record(&self, example: FeedbackExample) {
+        let mut examples = self.lock();
+        examples.push_back(example);
+        while examples.len() > self.capacity {
+            examples.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The collected examples in training format, oldest first; `drain` also
+    // removes them so the next export only holds newer ones
+    pub fn export(&self, drain: bool) -> Vec<TrainingExample> {
+        let mut examples = self.lock();
+        let exported = examples.iter().map(|example| training::example(&example.text, &example.entities)).collect();
+        if drain {
+            examples.clear();
+        }
+        exported
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<FeedbackExample>> {
+        self.examples.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_samples_only_low_confidence_detections() {
+        let store = FeedbackStore::new(10, 0.7, 100.0);
+        assert!(!store.sample("Call Jane Roe", &[entity("person", 5, 13, 0.95)]));
+        assert!(!store.sample("Nothing here", &[]));
+        assert!(store.sample("Call Jane Roe", &[entity("person", 5, 13, 0.55)]));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_oldest_examples_are_dropped() {
+        let store = FeedbackStore::new(2, 0.7, 100.0);
+        for text in ["Call Ann", "Call Bob", "Call Cid"] {
+            store.sample(text, &[entity("person", 5, 8, 0.5)]);
+        }
+        let exported = store.export(true);
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[0].tokenized_text, vec!["Call".to_string(), "Bob".to_string()]);
+        assert_eq!(exported[0].ner, vec![(1, 1, "person".to_string())]);
+        assert!(store.is_empty());
+    }
+}
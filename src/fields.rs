@@ -0,0 +1,146 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::ApiError;
+
+// Entity fields a caller may select. Single-text detection reports `probability`
+// and `sequence`, the other endpoints `confidence`; file scans add `page` or
+// `paragraph`, recognizers may add `attributes`, and single-text detection
+// adds `context` and `offsets` on request.
+pub const ENTITY_FIELDS: &[&str] = &[
+    "text",
+    "label",
+    "confidence",
+    "probability",
+    "sequence",
+    "start",
+    "end",
+    "page",
+    "paragraph",
+    "attributes",
+    "context",
+    "offsets",
+];
+
+// Sparse fieldset for entities in a response, e.g. `fields=label,start,end`.
+// Lets high-volume callers drop span text (and so avoid echoing PII back) or
+// scores they don't use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSelection {
+    fields: Vec<String>,
+}
+
+impl FieldSelection {
+    pub fn from_list<S: AsRef<str>>(fields: &[S]) -> Result<Self, ApiError> {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| field.as_ref().trim())
+            .filter(|field| !field.is_empty())
+            .map(str::to_string)
+            .collect();
+        if fields.is_empty() {
+            return Err(ApiError::InvalidInput("fields must name at least one entity field".to_string()));
+        }
+        if let Some(unknown) = fields.iter().find(|field| !ENTITY_FIELDS.contains(&field.as_str())) {
+            return Err(ApiError::InvalidInput(format!(
+                "unknown entity field {:?}, expected any of {}",
+                unknown,
+                ENTITY_FIELDS.join(", ")
+            )));
+        }
+        Ok(FieldSelection { fields })
+    }
+
+    // Every entity field except `field`, or the selected ones except it
+    pub fn without(selection: Option<Self>, field: &str) -> Self {
+        let fields = match selection {
+            Some(selection) => selection.fields,
+            None => ENTITY_FIELDS.iter().map(|field| field.to_string()).collect(),
+        };
+        FieldSelection {
+            fields: fields.into_iter().filter(|selected| selected != field).collect(),
+        }
+    }
+
+    // Comma-separated form used in query strings and form fields
+    pub fn parse(value: &str) -> Result<Self, ApiError> {
+        Self::from_list(&value.split(',').collect::<Vec<_>>())
+    }
+
+    // The `fields` query parameter wins over one given in the request body
+    pub fn resolve(query: Option<&str>, body: Option<&[String]>) -> Result<Option<Self>, ApiError> {
+        match (query, body) {
+            (Some(query), _) => Self::parse(query).map(Some),
+            (None, Some(body)) => Self::from_list(body).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    // Drop unselected keys from every entity object nested anywhere in `value`
+    pub fn apply(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, child) in object.iter_mut() {
+                    match (key.as_str(), child) {
+                        ("entities", Value::Array(entities)) => {
+                            for entity in entities {
+                                if let Value::Object(entity) = entity {
+                                    entity.retain(|field, _| self.fields.iter().any(|selected| selected == field));
+                                }
+                            }
+                        }
+                        (_, child) => self.apply(child),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.apply(item)),
+            _ => {}
+        }
+    }
+}
+
+// Serialize response data, trimming entity fields when a selection was given
+pub fn sparse<T: Serialize>(data: &T, selection: Option<&FieldSelection>) -> Result<Value, ApiError> {
+    let mut value = serde_json::to_value(data).map_err(|e| ApiError::InferenceFailed(format!("failed to serialize response: {}", e)))?;
+    if let Some(selection) = selection {
+        selection.apply(&mut value);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rejects_unknown_fields() {
+        assert!(FieldSelection::parse("label, start,end").is_ok());
+        assert!(FieldSelection::parse("label,ssn").is_err());
+        assert!(FieldSelection::parse(" , ").is_err());
+    }
+
+    #[test]
+    fn test_query_wins_over_body() {
+        let body = vec!["text".to_string()];
+        let selection = FieldSelection::resolve(Some("label"), Some(&body)).unwrap().unwrap();
+        assert_eq!(selection, FieldSelection::parse("label").unwrap());
+        assert!(FieldSelection::resolve(None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_apply_trims_nested_entities() {
+        let mut value = json!({
+            "text": "John Doe",
+            "results": [{
+                "index": 0,
+                "entities": [{"text": "John Doe", "label": "person", "confidence": 0.9, "start": 0, "end": 8}]
+            }]
+        });
+        FieldSelection::parse("label,start,end").unwrap().apply(&mut value);
+
+        assert_eq!(value["results"][0]["entities"][0], json!({"label": "person", "start": 0, "end": 8}));
+        assert_eq!(value["text"], "John Doe");
+        assert_eq!(value["results"][0]["index"], 0);
+    }
+}
@@ -0,0 +1,88 @@
+// Filters on what a detect response reports, from the query string, e.g.
+// `labels=email,phone&min_confidence=0.8&counts_only=true`. Unlike the labels
+// and threshold used for inference they don't change what the model looks for:
+// detection, auditing and redaction still see every finding. `counts_only`
+// reports per-label counts without any text, for dashboards that must not
+// receive raw PII.
+
+use std::collections::BTreeMap;
+
+use crate::error::ApiError;
+use crate::PiiEntity;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResponseFilter {
+    labels: Option<Vec<String>>,
+    min_confidence: Option<f32>,
+    pub counts_only: bool,
+}
+
+impl ResponseFilter {
+    pub fn parse(labels: Option<&str>, min_confidence: Option<f32>, counts_only: Option<bool>) -> Result<Self, ApiError> {
+        let labels = match labels {
+            Some(list) => {
+                let labels: Vec<String> = list
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if labels.is_empty() {
+                    return Err(ApiError::InvalidInput("labels must name at least one label".to_string()));
+                }
+                Some(labels)
+            }
+            None => None,
+        };
+        if let Some(min) = min_confidence.filter(|min| !(0.0..=1.0).contains(min)) {
+            return Err(ApiError::InvalidInput(format!("min_confidence is {}, expected 0 to 1", min)));
+        }
+        Ok(ResponseFilter {
+            labels,
+            min_confidence,
+            counts_only: counts_only.unwrap_or(false),
+        })
+    }
+
+    pub fn keeps(&self, entity: &PiiEntity) -> bool {
+        let label = match &self.labels {
+            Some(labels) => labels.contains(&entity.label),
+            None => true,
+        };
+        label && entity.confidence >= self.min_confidence.unwrap_or(f32::MIN)
+    }
+
+    pub fn apply(&self, entities: &mut Vec<PiiEntity>) {
+        entities.retain(|entity| self.keeps(entity));
+    }
+}
+
+// Findings per label, for `counts_only` responses
+pub fn counts(entities: &[PiiEntity]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for entity in entities {
+        *counts.entry(entity.label.clone()).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_filters_by_label_and_confidence() {
+        let filter = ResponseFilter::parse(Some("email, phone"), Some(0.8), None).unwrap();
+        let mut entities = vec![entity("email", 0, 1, 0.9), entity("email", 0, 1, 0.6), entity("phone", 0, 1, 0.85), entity("person", 0, 1, 0.99)];
+        filter.apply(&mut entities);
+        assert_eq!(counts(&entities), BTreeMap::from([("email".to_string(), 1), ("phone".to_string(), 1)]));
+    }
+
+    #[test]
+    fn test_rejects_bad_parameters() {
+        assert!(ResponseFilter::parse(Some(" , "), None, None).is_err());
+        assert!(ResponseFilter::parse(None, Some(1.5), None).is_err());
+        assert_eq!(ResponseFilter::parse(None, None, Some(false)).unwrap(), ResponseFilter::default());
+    }
+}
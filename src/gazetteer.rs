@@ -0,0 +1,99 @@
+// Known terms per label, for names the model can't be expected to know: project
+// codenames, internal system names, customer lists. Loaded once from the JSON
+// object in GLINER_GAZETTEER_FILE, e.g. {"project": ["Bluebird", "Red Kite"]},
+// and matched case-insensitively on word boundaries.
+
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::detection::DetectionError;
+use crate::pipeline::Detector;
+use crate::PiiEntity;
+
+#[derive(Debug, Default)]
+pub struct Gazetteer {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl Gazetteer {
+    pub fn new(terms: HashMap<String, Vec<String>>) -> Result<Self, regex::Error> {
+        let mut patterns = Vec::new();
+        for (label, mut terms) in terms {
+            terms.retain(|term| !term.trim().is_empty());
+            if terms.is_empty() {
+                continue;
+            }
+            // Longest first, so "Red Kite Two" wins over "Red Kite"
+            terms.sort_by_key(|term| std::cmp::Reverse(term.len()));
+            let alternatives: Vec<String> = terms.iter().map(|term| regex::escape(term.trim())).collect();
+            patterns.push((label, Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|")))?));
+        }
+        patterns.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(Gazetteer { patterns })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, DetectionError> {
+        let terms: HashMap<String, Vec<String>> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Gazetteer::new(terms)?)
+    }
+}
+
+impl Detector for Gazetteer {
+    fn name(&self) -> &'static str {
+        "gazetteer"
+    }
+
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError> {
+        let mut found = Vec::new();
+        for (label, pattern) in self.patterns.iter().filter(|(label, _)| labels.contains(&label.as_str())) {
+            found.extend(pattern.find_iter(text).map(|term| PiiEntity {
+                text: term.as_str().to_string(),
+                label: label.clone(),
+                confidence: 0.9,
+                start: term.start(),
+                end: term.end(),
+                attributes: BTreeMap::new(),
+            }));
+        }
+        Ok(found)
+    }
+}
+
+// The configured gazetteer, loaded on first use. A file that can't be read is
+// logged once and leaves the gazetteer empty.
+pub fn shared(config: &Config) -> Option<&'static Gazetteer> {
+    static LOADED: OnceLock<Option<Gazetteer>> = OnceLock::new();
+    LOADED
+        .get_or_init(|| {
+            let path = config.gazetteer_file.as_deref()?;
+            match Gazetteer::from_file(path) {
+                Ok(gazetteer) => Some(gazetteer),
+                Err(e) => {
+                    tracing::warn!(error = %e, path, "Failed to load the gazetteer; continuing without it");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms_match_whole_words_of_requested_labels() {
+        let gazetteer = Gazetteer::new(HashMap::from([
+            ("project".to_string(), vec!["Red Kite".to_string(), "Red Kite Two".to_string()]),
+            ("system".to_string(), vec!["Ledger".to_string()]),
+        ]))
+        .unwrap();
+        let text = "Moving red kite two and Red Kites to the ledger";
+        let found = gazetteer.detect(text, &["project"]).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "red kite two");
+        assert_eq!(gazetteer.detect(text, &["project", "system"]).unwrap().len(), 2);
+    }
+}
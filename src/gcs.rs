@@ -0,0 +1,115 @@
+// Google Cloud Storage as a source of objects to scan, built with the `gcs`
+// feature. Objects are named by bucket and object name, fetched with an OAuth
+// access token from GOOGLE_OAUTH_ACCESS_TOKEN or, on Google Cloud, from the
+// metadata server; or by a signed URL. STORAGE_EMULATOR_HOST points requests at
+// an emulator, which needs no token.
+
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::remote::{self, RemoteObject};
+
+const API_HOST: &str = "https://storage.googleapis.com";
+const METADATA_TOKEN_URL: &str = "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+// Object to scan: `bucket` and `object`, or a signed `url`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GcsObject {
+    #[serde(default)]
+    pub bucket: Option<String>,
+    #[serde(default)]
+    pub object: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+}
+
+// Download the object, within the upload size limit
+pub async fn fetch(object: &GcsObject, config: &Config) -> Result<RemoteObject, ApiError> {
+    let client = reqwest::Client::new();
+    // Emulators are commonly given as host:port
+    let emulator = remote::env_var("STORAGE_EMULATOR_HOST")
+        .map(|host| if host.contains("://") { host } else { format!("http://{}", host) });
+    match (object.url.as_deref(), object.bucket.as_deref(), object.object.as_deref()) {
+        (Some(url), None, None) => {
+            let url = remote::signed_url(url, &["storage.googleapis.com"], emulator.as_deref(), "X-Goog-Signature")?;
+            let name = remote::url_name(&url);
+            remote::download(client.get(url), name, config.max_upload_bytes).await
+        }
+        (None, Some(bucket), Some(name)) if !bucket.is_empty() && !name.is_empty() => {
+            let base = emulator.as_deref().unwrap_or(API_HOST);
+            let mut request = client.get(media_url(base, bucket, name));
+            if emulator.is_none() {
+                request = request.bearer_auth(access_token(&client).await?);
+            }
+            remote::download(request, remote::object_name(name), config.max_upload_bytes).await
+        }
+        _ => Err(ApiError::InvalidInput("give either bucket and object, or a signed url".to_string())),
+    }
+}
+
+// JSON API download url of an object's contents
+pub fn media_url(base: &str, bucket: &str, object: &str) -> String {
+    format!(
+        "{}/storage/v1/b/{}/o/{}?alt=media",
+        base.trim_end_matches('/'),
+        remote::encode_component(bucket),
+        remote::encode_component(object)
+    )
+}
+
+// GOOGLE_OAUTH_ACCESS_TOKEN (e.g. from `gcloud auth print-access-token`), or
+// the token of the instance's service account
+async fn access_token(client: &reqwest::Client) -> Result<String, ApiError> {
+    if let Some(token) = remote::env_var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+        return Ok(token);
+    }
+    let missing = |e: reqwest::Error| {
+        ApiError::StorageFailed(format!(
+            "no GCS credentials: set GOOGLE_OAUTH_ACCESS_TOKEN or run on Google Cloud ({})",
+            e
+        ))
+    };
+    let token: MetadataToken = client
+        .get(METADATA_TOKEN_URL)
+        .header("Metadata-Flavor", "Google")
+        .timeout(std::time::Duration::from_secs(2))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(missing)?
+        .json()
+        .await
+        .map_err(missing)?;
+    Ok(token.access_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_url_encodes_object_names() {
+        assert_eq!(
+            media_url(API_HOST, "lake", "raw/q3 report.pdf"),
+            "https://storage.googleapis.com/storage/v1/b/lake/o/raw%2Fq3%20report.pdf?alt=media"
+        );
+        assert_eq!(media_url("http://localhost:4443/", "lake", "a.txt"), "http://localhost:4443/storage/v1/b/lake/o/a.txt?alt=media");
+    }
+
+    #[test]
+    fn test_signed_url_hosts() {
+        let domains = ["storage.googleapis.com"];
+        let signed = "https://storage.googleapis.com/lake/a.pdf?X-Goog-Algorithm=GOOG4-RSA-SHA256&X-Goog-Signature=abc";
+        assert!(remote::signed_url(signed, &domains, None, "X-Goog-Signature").is_ok());
+        let unsigned = "https://storage.googleapis.com/lake/a.pdf";
+        assert!(remote::signed_url(unsigned, &domains, None, "X-Goog-Signature").is_err());
+        let foreign = "https://storage.googleapis.com.example.net/a.pdf?X-Goog-Signature=abc";
+        assert!(remote::signed_url(foreign, &domains, None, "X-Goog-Signature").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Mentions of the same entity across a text: findings with the same label whose
+// text matches once case and whitespace are normalized ("Jane  Roe" and
+// "JANE ROE" are one entity; "J. Roe" is not). Consistent redaction needs to
+// know which spans are the same entity.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::PiiEntity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Mention {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityGroup {
+    pub id: usize,
+    pub label: String,
+    // Text of the first mention; left out in privacy mode
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    // In order of appearance
+    pub mentions: Vec<Mention>,
+}
+
+// Lowercase, with runs of whitespace as single spaces
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// Group findings by label and normalized text, numbering groups in order of
+// their first mention
+pub fn group(entities: &[PiiEntity]) -> Vec<EntityGroup> {
+    let mut ordered: Vec<&PiiEntity> = entities.iter().collect();
+    ordered.sort_by_key(|entity| (entity.start, entity.end));
+
+    let mut groups: Vec<EntityGroup> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+    for entity in ordered {
+        let key = (entity.label.clone(), normalize(&entity.text));
+        let id = *index.entry(key).or_insert_with(|| {
+            groups.push(EntityGroup {
+                id: groups.len(),
+                label: entity.label.clone(),
+                text: entity.text.clone(),
+                mentions: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[id].mentions.push(Mention {
+            start: entity.start,
+            end: entity.end,
+        });
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_at;
+
+    #[test]
+    fn test_case_and_whitespace_variants_group_together() {
+        let groups = group(&[
+            entity_at("JANE ROE", "person", 40, 0.9),
+            entity_at("Jane Roe", "person", 0, 0.9),
+            entity_at("Jane  Roe", "person", 20, 0.9),
+            entity_at("J. Roe", "person", 60, 0.9),
+            entity_at("Jane Roe", "organization", 80, 0.9),
+        ]);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].text, "Jane Roe");
+        let starts: Vec<usize> = groups[0].mentions.iter().map(|mention| mention.start).collect();
+        assert_eq!(starts, vec![0, 20, 40]);
+        assert_eq!((groups[1].id, groups[1].text.as_str()), (1, "J. Roe"));
+        assert_eq!(groups[2].label, "organization");
+    }
+}
@@ -0,0 +1,158 @@
+// Inference self-test for readiness probes: a tiny detection on the warmup text,
+// timed from when it holds a model session, so a load balancer can eject an
+// instance whose ONNX runtime has become slow or stuck. The outcome is cached
+// for a few seconds so frequent probes don't compete with real traffic. The
+// same self-test decides when a model reloaded after the circuit breaker opened
+// may take traffic again.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use gliner::model::input::text::TextInput;
+
+use crate::breaker::CircuitBreaker;
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::inference::{run_with_deadline, InferenceQueue, RequestDeadline};
+use crate::pool::{Model, ModelPool, Priority};
+use crate::service::{init_model, ModelState, DEFAULT_LABELS};
+use crate::timing;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestResult {
+    // Inference time, without waiting for a session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    // Slower than GLINER_SELF_TEST_MAX_MS, or failed
+    pub degraded: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SelfTestResult {
+    // Judge a run against the latency threshold
+    pub fn of(outcome: Result<f64, ApiError>, max_ms: u64) -> Self {
+        match outcome {
+            Ok(latency_ms) => SelfTestResult {
+                latency_ms: Some(latency_ms),
+                degraded: latency_ms > max_ms as f64,
+                error: None,
+            },
+            Err(e) => SelfTestResult {
+                latency_ms: None,
+                degraded: true,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+// The last self-test and when it ran
+#[derive(Default)]
+pub struct SelfTest {
+    last: Mutex<Option<(Instant, SelfTestResult)>>,
+}
+
+impl SelfTest {
+    // The cached result while it is fresh, or a new run. Concurrent probes wait
+    // for the same run instead of starting their own.
+    pub async fn check(&self, model_state: &ModelState, queue: &InferenceQueue, config: &Config) -> SelfTestResult {
+        let mut last = self.last.lock().await;
+        if let Some((at, result)) = last.as_ref() {
+            if at.elapsed() < Duration::from_secs(config.self_test_cache_secs) {
+                return result.clone();
+            }
+        }
+        let result = SelfTestResult::of(probe(model_state, queue, config).await, config.self_test_max_ms);
+        if result.degraded {
+            tracing::warn!(latency_ms = ?result.latency_ms, error = ?result.error, "Inference self-test degraded");
+        }
+        *last = Some((Instant::now(), result.clone()));
+        result
+    }
+}
+
+// Run one inference on the warmup text, returning how long the model took
+pub async fn probe(model_state: &ModelState, queue: &InferenceQueue, config: &Config) -> Result<f64, ApiError> {
+    let text = config.warmup_text.clone();
+    let deadline = RequestDeadline(Duration::from_millis(config.request_timeout_ms), Some(Priority::Interactive));
+    run_with_deadline(model_state, queue, deadline, move |model, _| timed_inference(model, &text)).await
+}
+
+fn timed_inference(model: &Model, text: &str) -> Result<f64, ApiError> {
+    let started = Instant::now();
+    let input = TextInput::from_str(&[text], DEFAULT_LABELS).map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+    model.inference(input).map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+    Ok(timing::millis(started.elapsed()))
+}
+
+// Whenever the breaker opens, load fresh sessions from the model files and, once
+// they pass the self-test, swap them in and close the breaker. Work still
+// running on the old sessions finishes on them.
+pub fn spawn_recovery(breaker: Arc<CircuitBreaker>, model_state: ModelState, config: Config) {
+    tokio::spawn(async move {
+        loop {
+            breaker.tripped().await;
+            while breaker.is_open() {
+                match reload(&config).await {
+                    Ok((pool, latency_ms)) => {
+                        *model_state.lock().await = Some(Arc::new(pool));
+                        breaker.close();
+                        tracing::info!(latency_ms, "Model reloaded and self-test passed; circuit breaker closed");
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "Model reload after repeated failures did not pass; retrying");
+                        tokio::time::sleep(Duration::from_secs(config.breaker_retry_secs.max(1))).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+// New sessions, with the self-test latency of the first
+async fn reload(config: &Config) -> Result<(ModelPool, f64), Box<dyn std::error::Error + Send + Sync>> {
+    let (pool, _) = init_model(config).await?;
+    let (text, max_ms) = (config.warmup_text.clone(), config.self_test_max_ms);
+    let (pool, outcome) = tokio::task::spawn_blocking(move || {
+        let outcome = timed_inference(&pool.checkout(), &text);
+        (pool, outcome)
+    })
+    .await?;
+    let result = SelfTestResult::of(outcome, max_ms);
+    match result.latency_ms {
+        Some(latency_ms) if !result.degraded => Ok((pool, latency_ms)),
+        _ => Err(result.error.unwrap_or_else(|| format!("self-test took longer than {} ms", max_ms)).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_slow_or_failed_runs_are_degraded() {
+        assert!(!SelfTestResult::of(Ok(120.0), 500).degraded);
+        assert!(SelfTestResult::of(Ok(730.5), 500).degraded);
+        let failed = SelfTestResult::of(Err(ApiError::ModelNotLoaded), 500);
+        assert!(failed.degraded);
+        assert!(failed.latency_ms.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_is_cached() {
+        let state: ModelState = Arc::new(Mutex::new(None));
+        let queue = InferenceQueue::new(1);
+        let config = Config::default();
+        let self_test = SelfTest::default();
+        let first = self_test.check(&state, &queue, &config).await;
+        assert!(first.degraded);
+
+        // Answered from the cache rather than by another run
+        let second = self_test.check(&state, &queue, &config).await;
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+
+use crate::markdown;
+use crate::normalization::Normalization;
+use crate::PiiEntity;
+
+// How the text of a request is to be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputFormat {
+    #[default]
+    Text,
+    // Markup is stripped before detection and offsets are mapped back to the HTML
+    Html,
+    // Formatting syntax is stripped likewise, see `markdown::strip_markdown`
+    Markdown,
+}
+
+// Elements whose content is never visible
+const HIDDEN_ELEMENTS: &[&str] = &["script", "style", "template", "noscript"];
+
+// Elements that separate words when stripped, e.g. `<p>Jane</p><p>Doe</p>`
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "footer", "form", "h1", "h2",
+    "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p", "pre", "section", "table", "tbody",
+    "td", "tfoot", "th", "thead", "title", "tr", "ul",
+];
+
+// A stretch of visible text and the markup it came from. Verbatim runs were
+// copied byte for byte; other runs are one decoded character reference or an
+// inserted separator, and map to the whole source span.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Run {
+    pub(crate) text: usize,
+    pub(crate) source: usize,
+    pub(crate) source_len: usize,
+    pub(crate) verbatim: bool,
+}
+
+// Text a request's model input is built from, with a way back to the offsets of
+// the original input
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisibleText {
+    pub text: String,
+    // None when the input was plain text and offsets need no mapping
+    runs: Option<Vec<Run>>,
+}
+
+impl VisibleText {
+    pub fn new(input: &str, format: InputFormat) -> Self {
+        match format {
+            InputFormat::Text => VisibleText {
+                text: input.to_string(),
+                runs: None,
+            },
+            InputFormat::Html => strip_html(input),
+            InputFormat::Markdown => markdown::strip_markdown(input, false),
+        }
+    }
+
+    // Normalize the text for detection, keeping offsets mapped to the original
+    // input through both steps
+    pub fn normalized(self, normalization: &Normalization) -> Self {
+        let Some((text, runs)) = normalization.apply(&self.text) else { return self };
+        let runs = match &self.runs {
+            None => runs,
+            Some(_) => runs.iter().flat_map(|run| self.compose(run)).collect(),
+        };
+        VisibleText { text, runs: Some(runs) }
+    }
+
+    // Runs over the original input for a run over this text. Verbatim runs are
+    // split wherever the runs of this text change; other runs map as a whole.
+    fn compose(&self, outer: &Run) -> Vec<Run> {
+        let Some(runs) = &self.runs else { return vec![outer.clone()] };
+        let (start, end) = (outer.source, outer.source + outer.source_len);
+        if !outer.verbatim || start == end {
+            let source = self.source_start(start);
+            return vec![Run {
+                text: outer.text,
+                source,
+                source_len: self.source_end(end).saturating_sub(source),
+                verbatim: false,
+            }];
+        }
+        let first = runs.partition_point(|run| run.text <= start).saturating_sub(1);
+        runs[first..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, run)| run.text < end)
+            .map(|(index, run)| {
+                let run_end = runs.get(first + index + 1).map(|next| next.text).unwrap_or(self.text.len());
+                let (from, to) = (run.text.max(start), run_end.min(end));
+                let text = outer.text + (from - start);
+                if run.verbatim {
+                    Run { text, source: run.source + (from - run.text), source_len: to - from, verbatim: true }
+                } else {
+                    Run { text, source: run.source, source_len: run.source_len, verbatim: false }
+                }
+            })
+            .collect()
+    }
+
+    // Byte offset in the original input of a visible-text start offset
+    pub fn source_start(&self, offset: usize) -> usize {
+        let Some(runs) = &self.runs else { return offset };
+        match find_run(runs, offset) {
+            Some(run) if run.verbatim => run.source + (offset - run.text),
+            Some(run) => run.source,
+            None => 0,
+        }
+    }
+
+    // Byte offset in the original input of a visible-text (exclusive) end offset
+    pub fn source_end(&self, offset: usize) -> usize {
+        let Some(runs) = &self.runs else { return offset };
+        if offset == 0 {
+            return self.source_start(0);
+        }
+        match find_run(runs, offset - 1) {
+            Some(run) if run.verbatim => run.source + (offset - run.text),
+            Some(run) => run.source + run.source_len,
+            None => 0,
+        }
+    }
+
+    // Move entity offsets from the visible text to the original input. Entity
+    // text stays as detected (tags stripped, character references decoded).
+    pub fn map_entities(&self, entities: &mut [PiiEntity]) {
+        if self.runs.is_none() {
+            return;
+        }
+        for entity in entities {
+            let (start, end) = (self.source_start(entity.start), self.source_end(entity.end));
+            entity.start = start;
+            entity.end = end;
+        }
+    }
+}
+
+fn find_run(runs: &[Run], offset: usize) -> Option<&Run> {
+    let index = runs.partition_point(|run| run.text <= offset);
+    runs.get(index.checked_sub(1)?)
+}
+
+#[derive(Default)]
+pub(crate) struct Builder {
+    text: String,
+    runs: Vec<Run>,
+    // Source span of a block element seen since the last visible text
+    pending_break: Option<(usize, usize)>,
+}
+
+impl Builder {
+    pub(crate) fn push(&mut self, text: &str, source: usize, source_len: usize, verbatim: bool) {
+        if let Some((break_source, break_len)) = self.pending_break.take() {
+            if !self.text.is_empty() && !self.text.ends_with(char::is_whitespace) {
+                self.append("\n", break_source, break_len, false);
+            }
+        }
+        self.append(text, source, source_len, verbatim);
+    }
+
+    fn append(&mut self, text: &str, source: usize, source_len: usize, verbatim: bool) {
+        self.runs.push(Run {
+            text: self.text.len(),
+            source,
+            source_len,
+            verbatim,
+        });
+        self.text.push_str(text);
+    }
+
+    // Word break for a block element, added only if more text follows
+    fn separate(&mut self, source: usize, source_len: usize) {
+        self.pending_break.get_or_insert((source, source_len));
+    }
+
+    pub(crate) fn finish(self) -> VisibleText {
+        VisibleText {
+            text: self.text,
+            runs: Some(self.runs),
+        }
+    }
+}
+
+// Strip tags, comments and invisible elements, decode character references, and
+// record where every piece of the remaining text came from
+pub fn strip_html(html: &str) -> VisibleText {
+    let mut builder = Builder::default();
+    let mut position = 0;
+
+    while position < html.len() {
+        let rest = &html[position..];
+        if rest.starts_with("<!--") {
+            position += rest.find("-->").map(|end| end + 3).unwrap_or(rest.len());
+        } else if let Some((name, closing, len)) = parse_tag(rest) {
+            if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                builder.separate(position, len);
+            }
+            position += len;
+            if !closing && HIDDEN_ELEMENTS.contains(&name.as_str()) {
+                position += skip_hidden_content(&html[position..], &name);
+            }
+        } else if let Some((decoded, len)) = decode_reference(rest) {
+            let mut buffer = [0u8; 4];
+            builder.push(decoded.encode_utf8(&mut buffer), position, len, false);
+            position += len;
+        } else {
+            // Copy up to the next possible tag or reference, at least one character
+            let first = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            let len = rest[first..].find(['<', '&']).map(|next| next + first).unwrap_or(rest.len());
+            builder.push(&rest[..len], position, len, true);
+            position += len;
+        }
+    }
+
+    builder.finish()
+}
+
+// Recognise a tag at the start of `input`: returns its lowercase name, whether
+// it is a closing tag, and its length including the closing `>`
+fn parse_tag(input: &str) -> Option<(String, bool, usize)> {
+    let body = input.strip_prefix('<')?;
+    let (closing, body) = match body.strip_prefix('/') {
+        Some(body) => (true, body),
+        None => (false, body),
+    };
+    let first = body.chars().next()?;
+    if !(first.is_ascii_alphabetic() || (!closing && (first == '!' || first == '?'))) {
+        return None;
+    }
+
+    let name: String = body
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '!' || *c == '?')
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    // Find the closing `>`, skipping over quoted attribute values
+    let mut quote = None;
+    for (index, c) in input.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => return Some((name, closing, index + 1)),
+            _ => {}
+        }
+    }
+    // Unterminated tag: treat the rest of the input as markup
+    Some((name, closing, input.len()))
+}
+
+// Length of the content of a hidden element up to and including its end tag
+fn skip_hidden_content(input: &str, name: &str) -> usize {
+    let lower = input.to_ascii_lowercase();
+    let end_tag = format!("</{}", name);
+    match lower.find(&end_tag) {
+        Some(start) => match input[start..].find('>') {
+            Some(end) => start + end + 1,
+            None => input.len(),
+        },
+        None => input.len(),
+    }
+}
+
+// Decode a character reference such as `&amp;`, `&#64;` or `&#x40;`
+fn decode_reference(input: &str) -> Option<(char, usize)> {
+    let body = input.strip_prefix('&')?;
+    let end = body.char_indices().take(32).find(|&(_, c)| c == ';')?.0;
+    let name = &body[..end];
+    let decoded = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        char::from_u32(code)?
+    } else {
+        match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            // Non-breaking spaces read as ordinary word breaks for the model
+            "nbsp" => ' ',
+            _ => return None,
+        }
+    };
+    Some((decoded, end + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_in;
+
+    #[test]
+    fn test_strips_tags_and_hidden_content() {
+        let html = "<html><head><style>p { color: red }</style></head><body><p>Hi <b>Jane</b></p><script>var x = '<p>';</script><p>Bye</p></body></html>";
+        assert_eq!(strip_html(html).text, "Hi Jane\nBye");
+    }
+
+    #[test]
+    fn test_offsets_map_back_to_markup() {
+        let html = r#"<p class="x">Mail <a href="mailto:j@example.com">j@example.com</a> now</p>"#;
+        let visible = strip_html(html);
+        let mut entities = vec![entity_in(&visible.text, "j@example.com", "test", 1.0)];
+        visible.map_entities(&mut entities);
+
+        assert_eq!(&html[entities[0].start..entities[0].end], "j@example.com");
+        assert_eq!(entities[0].start, html.find(">j@").unwrap() + 1);
+    }
+
+    #[test]
+    fn test_entity_spanning_tags_and_references() {
+        let html = "<p>Jo<em>hn</em> D&#111;e &amp; co</p>";
+        let visible = strip_html(html);
+        assert_eq!(visible.text, "John Doe & co");
+
+        let mut entities = vec![entity_in(&visible.text, "John Doe", "test", 1.0)];
+        visible.map_entities(&mut entities);
+        assert_eq!(&html[entities[0].start..entities[0].end], "Jo<em>hn</em> D&#111;e");
+    }
+
+    #[test]
+    fn test_plain_text_is_untouched() {
+        let visible = VisibleText::new("a < b & c", InputFormat::Text);
+        assert_eq!(visible.text, "a < b & c");
+        assert_eq!(visible.source_start(4), 4);
+    }
+
+    #[test]
+    fn test_stray_angle_brackets_are_text() {
+        assert_eq!(strip_html("1 < 2 and 3 > 2 & so on").text, "1 < 2 and 3 > 2 & so on");
+    }
+}
@@ -0,0 +1,353 @@
+#[cfg(feature = "server")]
+use rocket::http::Status;
+#[cfg(feature = "server")]
+use rocket::request::{self, FromRequest, Request};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::breaker::CircuitBreaker;
+use crate::clock::{self, SharedClock};
+#[cfg(feature = "server")]
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::pool::{Model, Priority};
+use crate::service::ModelState;
+
+// Header clients can use to ask for a shorter (or, up to the configured
+// maximum, longer) deadline than the default
+pub const TIMEOUT_HEADER: &str = "X-Request-Timeout-Ms";
+
+// Header clients can use to run a request as `interactive` or `batch` work
+// instead of the endpoint's default
+pub const PRIORITY_HEADER: &str = "X-Priority";
+
+// Shared between a running inference task and the request waiting on it: the
+// task reports how far it got, the request flips `cancelled` when it gives up
+#[derive(Default)]
+pub struct InferenceProgress {
+    cancelled: AtomicBool,
+    total_batches: AtomicUsize,
+    completed_batches: AtomicUsize,
+}
+
+impl InferenceProgress {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn set_total_batches(&self, total: usize) {
+        self.total_batches.store(total, Ordering::SeqCst);
+    }
+
+    pub fn complete_batch(&self) {
+        self.completed_batches.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn total_batches(&self) -> usize {
+        self.total_batches.load(Ordering::SeqCst)
+    }
+
+    pub fn completed_batches(&self) -> usize {
+        self.completed_batches.load(Ordering::SeqCst)
+    }
+}
+
+// Bounded admission in front of the model: at most `capacity` requests may be
+// waiting for or running inference, anything beyond that is turned away with 429.
+// While draining, new work is still admitted until the grace period ends. While
+// the circuit breaker is open nothing is admitted.
+pub struct InferenceQueue {
+    semaphore: Arc<Semaphore>,
+    capacity: usize,
+    // When a drain's grace period ends; None while not draining
+    drain_deadline: Mutex<Option<Instant>>,
+    clock: SharedClock,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl InferenceQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_clock(capacity, clock::system())
+    }
+
+    pub fn with_clock(capacity: usize, clock: SharedClock) -> Self {
+        let capacity = capacity.max(1);
+        InferenceQueue {
+            semaphore: Arc::new(Semaphore::new(capacity)),
+            capacity,
+            drain_deadline: Mutex::new(None),
+            clock,
+            breaker: Arc::new(CircuitBreaker::disabled()),
+        }
+    }
+
+    pub fn with_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Arc::new(breaker);
+        self
+    }
+
+    pub fn breaker(&self) -> &Arc<CircuitBreaker> {
+        &self.breaker
+    }
+
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    pub fn try_admit(&self) -> Result<OwnedSemaphorePermit, ApiError> {
+        if !self.accepting() {
+            return Err(ApiError::Draining);
+        }
+        if self.breaker.is_open() {
+            return Err(ApiError::CircuitOpen);
+        }
+        self.semaphore.clone().try_acquire_owned().map_err(|_| ApiError::Overloaded {
+            queue_depth: self.depth(),
+            capacity: self.capacity,
+        })
+    }
+
+    // Requests currently waiting for or running inference
+    pub fn depth(&self) -> usize {
+        self.capacity - self.semaphore.available_permits()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Start draining: keep admitting work for `grace`, then refuse it. Draining
+    // again keeps the earlier deadline.
+    pub fn drain(&self, grace: Duration) -> Instant {
+        let mut deadline = self.drain_deadline.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *deadline.get_or_insert_with(|| self.clock.now() + grace)
+    }
+
+    pub fn cancel_drain(&self) {
+        *self.drain_deadline.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    pub fn drain_deadline(&self) -> Option<Instant> {
+        *self.drain_deadline.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.drain_deadline().is_some()
+    }
+
+    // Whether new detection work is admitted
+    pub fn accepting(&self) -> bool {
+        self.drain_deadline().is_none_or(|deadline| self.clock.now() < deadline)
+    }
+}
+
+// How long a request may spend waiting for and running inference, and the
+// priority the client asked for, if any
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestDeadline(pub Duration, pub Option<Priority>);
+
+impl RequestDeadline {
+    // Use `priority` unless the client chose one
+    pub fn or_priority(self, priority: Priority) -> Self {
+        RequestDeadline(self.0, Some(self.1.unwrap_or(priority)))
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.1.unwrap_or(Priority::Interactive)
+    }
+}
+
+#[cfg(feature = "server")]
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestDeadline {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let defaults = Config::default();
+        let config = request.rocket().state::<Config>().unwrap_or(&defaults);
+
+        let priority = match request.headers().get_one(PRIORITY_HEADER) {
+            None => None,
+            Some(value) => match Priority::parse(value) {
+                Some(priority) => Some(priority),
+                None => {
+                    return request::Outcome::Error((
+                        Status::UnprocessableEntity,
+                        ApiError::InvalidInput(format!("{} must be interactive or batch", PRIORITY_HEADER)),
+                    ));
+                }
+            },
+        };
+        match request.headers().get_one(TIMEOUT_HEADER) {
+            None => request::Outcome::Success(RequestDeadline(Duration::from_millis(config.request_timeout_ms), priority)),
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(ms) if ms > 0 => {
+                    let ms = ms.min(config.max_request_timeout_ms);
+                    request::Outcome::Success(RequestDeadline(Duration::from_millis(ms), priority))
+                }
+                _ => request::Outcome::Error((
+                    Status::UnprocessableEntity,
+                    ApiError::InvalidInput(format!("{} must be a positive integer", TIMEOUT_HEADER)),
+                )),
+            },
+        }
+    }
+}
+
+// Run model work on the blocking pool so it can't stall the async runtime, and
+// give up once the deadline passes. Time spent waiting for a free session counts
+// towards the deadline. On timeout the work is told to stop at its next batch
+// boundary; an ONNX call already in flight still runs to completion. The queue
+// slot is held until the work actually finishes, even if the request gave up.
+// The session is checked out at the deadline's priority.
+pub async fn run_with_deadline<T, F>(
+    model_state: &ModelState,
+    queue: &InferenceQueue,
+    deadline: RequestDeadline,
+    work: F,
+) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Model, &InferenceProgress) -> Result<T, ApiError> + Send + 'static,
+{
+    let permit = queue.try_admit()?;
+    let started = Instant::now();
+    let progress = Arc::new(InferenceProgress::default());
+    let task_progress = progress.clone();
+    let state = model_state.clone();
+    let priority = deadline.priority();
+
+    let task = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let pool = state.blocking_lock().clone().ok_or(ApiError::ModelNotLoaded)?;
+        let session = pool.checkout_with(priority);
+        if task_progress.is_cancelled() {
+            return Err(ApiError::InferenceFailed("request was cancelled before inference started".to_string()));
+        }
+        work(&session, &task_progress)
+    });
+
+    match tokio::time::timeout(deadline.0, task).await {
+        Ok(joined) => {
+            let result = joined.unwrap_or_else(|e| Err(ApiError::InferenceFailed(format!("inference task failed: {}", e))));
+            queue.breaker().observe(&result);
+            result
+        }
+        Err(_) => {
+            progress.cancel();
+            Err(ApiError::Timeout {
+                timeout_ms: deadline.0.as_millis() as u64,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                completed_batches: progress.completed_batches(),
+                total_batches: progress.total_batches(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use tokio::sync::Mutex;
+
+    #[test]
+    fn test_progress_tracking() {
+        let progress = InferenceProgress::default();
+        progress.set_total_batches(3);
+        progress.complete_batch();
+
+        assert_eq!(progress.completed_batches(), 1);
+        assert_eq!(progress.total_batches(), 3);
+        assert!(!progress.is_cancelled());
+
+        progress.cancel();
+        assert!(progress.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_without_model() {
+        let state: ModelState = Arc::new(Mutex::new(None));
+        let queue = InferenceQueue::new(1);
+        let result = run_with_deadline(&state, &queue, RequestDeadline(Duration::from_secs(1), None), |_, _| Ok(())).await;
+        assert_eq!(result.unwrap_err(), ApiError::ModelNotLoaded);
+        assert_eq!(queue.depth(), 0);
+    }
+
+    #[test]
+    fn test_endpoint_priority_yields_to_the_client() {
+        let deadline = RequestDeadline(Duration::from_secs(1), None);
+        assert_eq!(deadline.priority(), Priority::Interactive);
+        assert_eq!(deadline.or_priority(Priority::Batch).priority(), Priority::Batch);
+        let chosen = RequestDeadline(Duration::from_secs(1), Some(Priority::Interactive));
+        assert_eq!(chosen.or_priority(Priority::Batch).priority(), Priority::Interactive);
+    }
+
+    #[test]
+    fn test_queue_rejects_when_full() {
+        let queue = InferenceQueue::new(2);
+        let _first = queue.try_admit().expect("slot available");
+        let _second = queue.try_admit().expect("slot available");
+        assert_eq!(queue.depth(), 2);
+
+        let error = queue.try_admit().unwrap_err();
+        assert_eq!(error, ApiError::Overloaded { queue_depth: 2, capacity: 2 });
+        assert_eq!(error.error_code(), "queue_full");
+    }
+
+    #[test]
+    fn test_open_breaker_refuses_work() {
+        let queue = InferenceQueue::new(2).with_breaker(CircuitBreaker::new(crate::breaker::BreakerSettings {
+            window: 2,
+            min_requests: 1,
+            failure_rate: 1.0,
+        }));
+        queue.breaker().observe::<()>(&Err(ApiError::InferenceFailed("session error".to_string())));
+        assert_eq!(queue.try_admit().unwrap_err(), ApiError::CircuitOpen);
+        queue.breaker().close();
+        assert!(queue.try_admit().is_ok());
+    }
+
+    #[test]
+    fn test_drain_grace_follows_clock() {
+        let clock = TestClock::new();
+        let queue = InferenceQueue::with_clock(2, clock.clone());
+        queue.drain(Duration::from_secs(30));
+
+        clock.advance(Duration::from_secs(29));
+        assert!(queue.accepting());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(queue.try_admit().unwrap_err(), ApiError::Draining);
+    }
+
+    #[test]
+    fn test_drain_refuses_work_after_grace() {
+        let queue = InferenceQueue::new(2);
+        queue.drain(Duration::from_secs(60));
+        assert!(queue.is_draining());
+        assert!(queue.try_admit().is_ok());
+
+        queue.cancel_drain();
+        queue.drain(Duration::ZERO);
+        assert_eq!(queue.try_admit().unwrap_err(), ApiError::Draining);
+
+        queue.cancel_drain();
+        assert!(queue.try_admit().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_expires_while_waiting_for_model_state() {
+        let state: ModelState = Arc::new(Mutex::new(None));
+        let _held = state.lock().await;
+
+        let queue = InferenceQueue::new(1);
+        let result = run_with_deadline(&state, &queue, RequestDeadline(Duration::from_millis(20), None), |_, _| Ok(())).await;
+        assert_eq!(result.unwrap_err().error_code(), "timeout");
+    }
+}
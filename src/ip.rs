@@ -0,0 +1,228 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+
+use crate::PiiEntity;
+
+// Label of the findings, matching the "ip address" label of the built-in profiles
+pub const LABEL: &str = "ip address";
+
+// Where an address routes. Only public addresses identify someone; the others
+// show up by the thousand in infrastructure logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpScope {
+    Public,
+    Private,
+    Loopback,
+    LinkLocal,
+    // Unspecified, broadcast, multicast, documentation, benchmarking, shared
+    // address space and other special-purpose ranges
+    Reserved,
+}
+
+impl IpScope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IpScope::Public => "public",
+            IpScope::Private => "private",
+            IpScope::Loopback => "loopback",
+            IpScope::LinkLocal => "link_local",
+            IpScope::Reserved => "reserved",
+        }
+    }
+}
+
+pub fn classify(address: IpAddr) -> IpScope {
+    match address {
+        IpAddr::V4(address) => classify_v4(address),
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(mapped) => classify_v4(mapped),
+            None => classify_v6(address),
+        },
+    }
+}
+
+fn classify_v4(address: Ipv4Addr) -> IpScope {
+    let [a, b, _, _] = address.octets();
+    if address.is_private() {
+        IpScope::Private
+    } else if address.is_loopback() {
+        IpScope::Loopback
+    } else if address.is_link_local() {
+        IpScope::LinkLocal
+    } else if address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_multicast()
+        || address.is_documentation()
+        || a == 0
+        || a >= 240
+        // Shared address space (carrier-grade NAT) and benchmarking
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (b == 18 || b == 19))
+    {
+        IpScope::Reserved
+    } else {
+        IpScope::Public
+    }
+}
+
+fn classify_v6(address: Ipv6Addr) -> IpScope {
+    let first = address.segments()[0];
+    if address.is_loopback() {
+        IpScope::Loopback
+    } else if first & 0xfe00 == 0xfc00 {
+        // Unique local addresses, the IPv6 counterpart of RFC 1918
+        IpScope::Private
+    } else if first & 0xffc0 == 0xfe80 {
+        IpScope::LinkLocal
+    } else if address.is_unspecified() || address.is_multicast() || (first == 0x2001 && address.segments()[1] == 0x0db8) {
+        IpScope::Reserved
+    } else {
+        IpScope::Public
+    }
+}
+
+fn candidate_patterns() -> &'static [Regex; 2] {
+    static PATTERNS: OnceLock<[Regex; 2]> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        // IPv6 first, so the IPv4 tail of ::ffff:10.0.0.1 is not reported on its own
+        [
+            Regex::new(r"(?i)[0-9a-f]{0,4}(?::[0-9a-f]{0,4}){1,6}:(?:\d{1,3}\.){3}\d{1,3}|[0-9a-f]{0,4}(?::[0-9a-f]{0,4}){2,7}")
+                .expect("valid IPv6 pattern"),
+            Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").expect("valid IPv4 pattern"),
+        ]
+    })
+}
+
+// Whether a candidate is really part of something longer: an identifier, a
+// version string such as 1.2.3.4.5, a MAC address or a path like std::net
+fn embedded(text: &str, start: usize, end: usize) -> bool {
+    let joins = |c: char| c.is_alphanumeric() || c == '_' || c == ':';
+    let mut before = text[..start].chars().rev();
+    let mut after = text[end..].chars();
+    let dotted = |next: Option<char>, beyond: Option<char>| next == Some('.') && beyond.is_some_and(|c| c.is_ascii_digit());
+    let (previous, after_next) = (before.next(), after.next());
+    previous.is_some_and(joins)
+        || after_next.is_some_and(joins)
+        || dotted(previous, before.next())
+        || dotted(after_next, after.next())
+}
+
+// Tag an address with its version and scope
+pub fn attributes(address: IpAddr) -> BTreeMap<String, String> {
+    let version = if address.is_ipv4() { "4" } else { "6" };
+    BTreeMap::from([
+        ("ip_version".to_string(), version.to_string()),
+        ("ip_scope".to_string(), classify(address).name().to_string()),
+    ])
+}
+
+// IPv4 and IPv6 addresses in the text. Non-public addresses are only reported
+// when `include_private` is set.
+pub fn find_ip_addresses(text: &str, include_private: bool) -> Vec<PiiEntity> {
+    let mut found: Vec<PiiEntity> = Vec::new();
+    for pattern in candidate_patterns() {
+        for candidate in pattern.find_iter(text) {
+            let (start, end) = (candidate.start(), candidate.end());
+            if embedded(text, start, end) {
+                continue;
+            }
+            // A lone group such as "d::" is more likely code than an address
+            let is_v6 = candidate.as_str().contains(':');
+            if is_v6 && candidate.as_str().split(':').filter(|group| !group.is_empty()).count() < 2 {
+                continue;
+            }
+            if found.iter().any(|entity| entity.start < end && start < entity.end) {
+                continue;
+            }
+            let Ok(address) = candidate.as_str().parse::<IpAddr>() else {
+                continue;
+            };
+            if !include_private && classify(address) != IpScope::Public {
+                continue;
+            }
+            found.push(PiiEntity {
+                text: candidate.as_str().to_string(),
+                label: LABEL.to_string(),
+                confidence: 0.99,
+                start,
+                end,
+                attributes: attributes(address),
+            });
+        }
+    }
+    found.sort_by_key(|entity| entity.start);
+    found
+}
+
+// Classify addresses the model labelled as IP addresses, dropping non-public
+// ones unless `include_private` is set. Spans that don't parse are left alone.
+pub fn classify_entities(entities: &mut Vec<PiiEntity>, include_private: bool) {
+    entities.retain_mut(|entity| {
+        if entity.label != LABEL {
+            return true;
+        }
+        let Ok(address) = entity.text.trim().parse::<IpAddr>() else {
+            return true;
+        };
+        entity.attributes.extend(attributes(address));
+        include_private || classify(address) == IpScope::Public
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_at;
+
+    fn scope(address: &str) -> &'static str {
+        classify(address.parse().unwrap()).name()
+    }
+
+    #[test]
+    fn test_classify_ranges() {
+        assert_eq!(scope("8.8.8.8"), "public");
+        assert_eq!(scope("10.1.2.3"), "private");
+        assert_eq!(scope("192.168.0.1"), "private");
+        assert_eq!(scope("127.0.0.1"), "loopback");
+        assert_eq!(scope("169.254.10.1"), "link_local");
+        assert_eq!(scope("100.64.0.1"), "reserved");
+        assert_eq!(scope("203.0.113.7"), "reserved");
+        assert_eq!(scope("2606:4700::1111"), "public");
+        assert_eq!(scope("fd12:3456::1"), "private");
+        assert_eq!(scope("fe80::1"), "link_local");
+        assert_eq!(scope("::1"), "loopback");
+        assert_eq!(scope("::ffff:10.0.0.1"), "private");
+    }
+
+    #[test]
+    fn test_only_public_addresses_by_default() {
+        let text = "GET from 10.0.0.12 via 172.16.4.1, client 81.2.69.160 and 2001:4860:4860::8888";
+        let found = find_ip_addresses(text, false);
+        let texts: Vec<&str> = found.iter().map(|entity| entity.text.as_str()).collect();
+        assert_eq!(texts, vec!["81.2.69.160", "2001:4860:4860::8888"]);
+        assert_eq!(found[1].attributes["ip_version"], "6");
+
+        assert_eq!(find_ip_addresses(text, true).len(), 4);
+    }
+
+    #[test]
+    fn test_rejects_lookalikes() {
+        assert!(find_ip_addresses("version 1.2.3.4.5, 999.1.1.1, at 10:30:00, mac 00:1a:2b:3c:4d:5e", true).is_empty());
+    }
+
+    #[test]
+    fn test_classify_model_entities() {
+        let mut entities = vec![
+            entity_at("192.168.1.1", LABEL, 0, 0.7),
+            entity_at("81.2.69.160", LABEL, 0, 0.7),
+            entity_at("the gateway", LABEL, 0, 0.7),
+        ];
+        classify_entities(&mut entities, false);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].attributes["ip_scope"], "public");
+        assert!(entities[1].attributes.is_empty());
+    }
+}
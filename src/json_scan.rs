@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::ApiError;
+use crate::PiiEntity;
+
+// A string value of the scanned document and where it lives
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonString {
+    // RFC 6901 JSON Pointer, e.g. /customers/3/notes
+    pub pointer: String,
+    pub text: String,
+}
+
+// Entities found in one string value; offsets are relative to that string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonFinding {
+    pub pointer: String,
+    pub entities: Vec<PiiEntity>,
+}
+
+// Collect the non-blank string values of a document in document order. Object
+// keys are not scanned; numbers, booleans and nulls carry no text.
+pub fn collect_strings(document: &Value, max_strings: usize, max_chars: usize) -> Result<Vec<JsonString>, ApiError> {
+    let mut strings = Vec::new();
+    walk(document, &mut String::new(), &mut strings, max_strings, max_chars)?;
+    Ok(strings)
+}
+
+fn walk(
+    value: &Value,
+    pointer: &mut String,
+    strings: &mut Vec<JsonString>,
+    max_strings: usize,
+    max_chars: usize,
+) -> Result<(), ApiError> {
+    match value {
+        Value::String(text) if !text.trim().is_empty() => {
+            if strings.len() == max_strings {
+                return Err(ApiError::PayloadTooLarge(format!(
+                    "document has more than {} string values to scan",
+                    max_strings
+                )));
+            }
+            let length = text.chars().count();
+            if length > max_chars {
+                return Err(ApiError::PayloadTooLarge(format!(
+                    "string at {} is {} characters long, the limit is {}",
+                    display(pointer),
+                    length,
+                    max_chars
+                )));
+            }
+            strings.push(JsonString {
+                pointer: pointer.clone(),
+                text: text.clone(),
+            });
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let parent = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                walk(item, pointer, strings, max_strings, max_chars)?;
+                pointer.truncate(parent);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, item) in fields {
+                let parent = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&escape(key));
+                walk(item, pointer, strings, max_strings, max_chars)?;
+                pointer.truncate(parent);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Escape a key as a JSON Pointer reference token
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+// The root pointer is the empty string, which reads badly in messages
+fn display(pointer: &str) -> &str {
+    if pointer.is_empty() {
+        "the document root"
+    } else {
+        pointer
+    }
+}
+
+// Pair per-string detection results (in `strings` order) with their pointers,
+// keeping only strings that contain entities
+pub fn findings(strings: &[JsonString], results: Vec<Vec<PiiEntity>>) -> Vec<JsonFinding> {
+    strings
+        .iter()
+        .zip(results)
+        .filter(|(_, entities)| !entities.is_empty())
+        .map(|(string, entities)| JsonFinding {
+            pointer: string.pointer.clone(),
+            entities,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collect_strings_with_pointers() {
+        let document = json!({
+            "customers": [
+                {"name": "Jane Roe", "age": 41, "notes": " "},
+                {"name": "John Doe", "tags": ["vip", null]}
+            ],
+            "a/b": {"m~n": "escaped"}
+        });
+        let strings = collect_strings(&document, 100, 1000).unwrap();
+        let pointers: Vec<&str> = strings.iter().map(|string| string.pointer.as_str()).collect();
+
+        assert_eq!(pointers, vec!["/a~1b/m~0n", "/customers/0/name", "/customers/1/name", "/customers/1/tags/0"]);
+        assert_eq!(document.pointer(&strings[0].pointer), Some(&json!("escaped")));
+        assert_eq!(strings[2].text, "John Doe");
+    }
+
+    #[test]
+    fn test_collect_root_string() {
+        let strings = collect_strings(&json!("call 555-0100"), 100, 1000).unwrap();
+        assert_eq!(strings, vec![JsonString { pointer: String::new(), text: "call 555-0100".to_string() }]);
+    }
+
+    #[test]
+    fn test_collect_strings_limits() {
+        let document = json!(["a", "b", "c"]);
+        assert!(matches!(collect_strings(&document, 2, 1000), Err(ApiError::PayloadTooLarge(_))));
+
+        let error = collect_strings(&json!({"notes": "too long"}), 10, 3).unwrap_err();
+        assert!(matches!(error, ApiError::PayloadTooLarge(message) if message.contains("/notes")));
+    }
+}
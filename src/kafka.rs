@@ -0,0 +1,273 @@
+// Kafka consumer mode, built with the `kafka` feature and run with
+// `gliner-rs-api kafka`: messages are read from GLINER_KAFKA_INPUT_TOPIC, the
+// text in GLINER_KAFKA_FIELD (or the whole payload) is scanned, and the message
+// is produced to GLINER_KAFKA_OUTPUT_TOPIC annotated with its findings or with
+// them redacted. Offsets are committed once the output is acknowledged, so
+// every message is delivered at least once.
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{Header, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::detection::DetectionError;
+use crate::error::ApiError;
+use crate::masking::{apply_masking, MaskingStrategy};
+use crate::recognizers::RecognizerOptions;
+use crate::service::{Detection, PiiService};
+use crate::{privacy, validation};
+
+// Header carrying the number of entities found in a message
+pub const ENTITIES_HEADER: &str = "x-pii-entities";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaAction {
+    // Add the findings to the message
+    Annotate,
+    // Replace the scanned text with its redacted form
+    Redact,
+}
+
+impl KafkaAction {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "annotate" => Ok(KafkaAction::Annotate),
+            "redact" => Ok(KafkaAction::Redact),
+            other => Err(format!("unknown GLINER_KAFKA_ACTION {}, expected annotate or redact", other)),
+        }
+    }
+}
+
+// Where to consume from and produce to, and what to do with each message
+#[derive(Debug, Clone)]
+pub struct KafkaSettings {
+    pub brokers: String,
+    pub group_id: String,
+    pub input_topic: String,
+    pub output_topic: String,
+    // JSON pointer of the text to scan, e.g. "/message"; None scans the whole
+    // payload as UTF-8 text
+    pub field: Option<String>,
+    pub action: KafkaAction,
+}
+
+impl KafkaSettings {
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let required = |value: &Option<String>, name: &str| value.clone().ok_or_else(|| format!("kafka mode needs {}", name));
+        let field = config.kafka_field.clone().filter(|field| !field.is_empty());
+        if let Some(field) = &field {
+            if !field.starts_with('/') {
+                return Err(format!("GLINER_KAFKA_FIELD {} must be a JSON pointer such as /message", field));
+            }
+        }
+        Ok(KafkaSettings {
+            brokers: required(&config.kafka_brokers, "GLINER_KAFKA_BROKERS")?,
+            group_id: config.kafka_group_id.clone(),
+            input_topic: required(&config.kafka_input_topic, "GLINER_KAFKA_INPUT_TOPIC")?,
+            output_topic: required(&config.kafka_output_topic, "GLINER_KAFKA_OUTPUT_TOPIC")?,
+            field,
+            action: KafkaAction::parse(&config.kafka_action)?,
+        })
+    }
+}
+
+// A consumed message, parsed far enough to find its text
+pub struct ParsedMessage {
+    // The payload as JSON when a field is scanned
+    document: Option<Value>,
+    pub text: String,
+}
+
+// Find the text to scan in a payload
+pub fn parse_message(payload: &[u8], field: Option<&str>) -> Result<ParsedMessage, ApiError> {
+    let Some(field) = field else {
+        let text = std::str::from_utf8(payload).map_err(|_| ApiError::InvalidInput("payload is not UTF-8 text".to_string()))?;
+        return Ok(ParsedMessage { document: None, text: text.to_string() });
+    };
+    let document: Value =
+        serde_json::from_slice(payload).map_err(|e| ApiError::InvalidInput(format!("payload is not JSON: {}", e)))?;
+    let text = document
+        .pointer(field)
+        .and_then(Value::as_str)
+        .ok_or_else(|| ApiError::InvalidInput(format!("payload has no string at {}", field)))?
+        .to_string();
+    Ok(ParsedMessage { document: Some(document), text })
+}
+
+// Output payload of a scanned message. Annotated JSON payloads gain a top-level
+// `pii` object; annotated text payloads become `{"text", "pii"}`. Redacted
+// payloads keep their shape with the text rewritten, using the profile's
+// masking or `[LABEL]` placeholders.
+pub fn render_message(message: ParsedMessage, field: Option<&str>, detection: Detection, action: KafkaAction, private: bool) -> Vec<u8> {
+    let Detection { mut entities, redacted_text } = detection;
+    match action {
+        KafkaAction::Redact => {
+            let redacted = redacted_text.unwrap_or_else(|| apply_masking(&message.text, &entities, |_| MaskingStrategy::Redact));
+            match (message.document, field) {
+                (Some(mut document), Some(field)) => {
+                    if let Some(value) = document.pointer_mut(field) {
+                        *value = Value::String(redacted);
+                    }
+                    document.to_string().into_bytes()
+                }
+                _ => redacted.into_bytes(),
+            }
+        }
+        KafkaAction::Annotate => {
+            if private {
+                privacy::clear_text(&mut entities);
+            }
+            let pii = serde_json::json!({ "total_entities": entities.len(), "entities": entities });
+            let document = match message.document {
+                Some(Value::Object(mut object)) => {
+                    object.insert("pii".to_string(), pii);
+                    Value::Object(object)
+                }
+                Some(other) => serde_json::json!({ "payload": other, "pii": pii }),
+                None => serde_json::json!({ "text": message.text, "pii": pii }),
+            };
+            document.to_string().into_bytes()
+        }
+    }
+}
+
+// Consume, scan and produce until interrupted
+pub async fn run(config: Config, profile: Option<String>) -> Result<(), DetectionError> {
+    let settings = KafkaSettings::from_config(&config)?;
+    let service = Arc::new(PiiService::load(config.clone()).await?);
+    // Fail on an unknown profile before anything is consumed
+    service.profile(profile.as_deref())?;
+
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &settings.brokers)
+        .set("group.id", &settings.group_id)
+        .set("enable.auto.commit", "false")
+        .set("auto.offset.reset", "earliest")
+        .create()?;
+    consumer.subscribe(&[settings.input_topic.as_str()])?;
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &settings.brokers)
+        .set("message.timeout.ms", "30000")
+        .create()?;
+    tracing::info!(
+        input = %settings.input_topic,
+        output = %settings.output_topic,
+        action = ?settings.action,
+        "Consuming from Kafka"
+    );
+
+    loop {
+        let message = tokio::select! {
+            message = consumer.recv() => message?,
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("Stopping the Kafka consumer");
+                return Ok(());
+            }
+        };
+        let payload = message.payload().unwrap_or_default().to_vec();
+        let scanned = {
+            let (service, settings, profile) = (service.clone(), settings.clone(), profile.clone());
+            tokio::task::spawn_blocking(move || scan_payload(&service, &settings, &payload, profile.as_deref())).await?
+        };
+        match scanned {
+            Ok((entities, output)) => {
+                let count = entities.to_string();
+                let headers = OwnedHeaders::new().insert(Header { key: ENTITIES_HEADER, value: Some(&count) });
+                let mut record: FutureRecord<[u8], [u8]> = FutureRecord::to(&settings.output_topic).payload(&output[..]).headers(headers);
+                if let Some(key) = message.key() {
+                    record = record.key(key);
+                }
+                producer.send(record, Duration::from_secs(0)).await.map_err(|(e, _)| e)?;
+            }
+            // Messages that cannot be scanned are logged and skipped
+            Err(e) => tracing::warn!(
+                topic = message.topic(),
+                partition = message.partition(),
+                offset = message.offset(),
+                error = %e,
+                "Skipping Kafka message"
+            ),
+        }
+        consumer.commit_message(&message, CommitMode::Async)?;
+    }
+}
+
+// Scan one payload, returning the number of entities found and the output payload
+fn scan_payload(
+    service: &PiiService,
+    settings: &KafkaSettings,
+    payload: &[u8],
+    profile: Option<&str>,
+) -> Result<(usize, Vec<u8>), ApiError> {
+    let config = service.config();
+    let field = settings.field.as_deref();
+    let message = parse_message(payload, field)?;
+    validation::validate_text(&message.text, config)?;
+    let detection = service.detect(&message.text, profile, &RecognizerOptions::default())?;
+    let count = detection.entities.len();
+    Ok((count, render_message(message, field, detection, settings.action, config.privacy_mode)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PiiEntity;
+    use std::collections::BTreeMap;
+
+    fn email_detection(start: usize) -> Detection {
+        let entity = PiiEntity {
+            text: "jane@example.org".to_string(),
+            label: "email".to_string(),
+            confidence: 0.9,
+            start,
+            end: start + 16,
+            attributes: BTreeMap::new(),
+        };
+        Detection { entities: vec![entity], redacted_text: None }
+    }
+
+    #[test]
+    fn test_redacts_a_json_field() {
+        let payload = br#"{"user": 7, "event": {"message": "mail jane@example.org"}}"#;
+        let message = parse_message(payload, Some("/event/message")).unwrap();
+        assert_eq!(message.text, "mail jane@example.org");
+        let output = render_message(message, Some("/event/message"), email_detection(5), KafkaAction::Redact, false);
+        let output: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(output["event"]["message"], "mail [EMAIL]");
+        assert_eq!(output["user"], 7);
+    }
+
+    #[test]
+    fn test_annotates_text_payloads() {
+        let message = parse_message(b"jane@example.org", None).unwrap();
+        let output = render_message(message, None, email_detection(0), KafkaAction::Annotate, true);
+        let output: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(output["text"], "jane@example.org");
+        assert_eq!(output["pii"]["total_entities"], 1);
+        assert!(output["pii"]["entities"][0].get("text").is_none());
+    }
+
+    #[test]
+    fn test_rejects_payloads_without_the_field() {
+        assert!(parse_message(b"not json", Some("/message")).is_err());
+        assert!(parse_message(br#"{"message": 42}"#, Some("/message")).is_err());
+    }
+
+    #[test]
+    fn test_settings_need_brokers_and_topics() {
+        let config = Config::default();
+        assert!(KafkaSettings::from_config(&config).is_err());
+        let config = Config {
+            kafka_brokers: Some("localhost:9092".to_string()),
+            kafka_input_topic: Some("logs".to_string()),
+            kafka_output_topic: Some("logs-scrubbed".to_string()),
+            kafka_field: Some("message".to_string()),
+            ..Config::default()
+        };
+        assert!(KafkaSettings::from_config(&config).is_err());
+    }
+}
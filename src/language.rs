@@ -0,0 +1,171 @@
+// Language of the submitted text, reported with single-text detections, and
+// routing to a model per language: GLINER_LANGUAGE_MODELS maps ISO 639-3 codes
+// to models, e.g. "deu=urchade/gliner_multi-v2.1,*=urchade/gliner_multi-v2.1"
+// sends German, and every language without an entry of its own, to a
+// multilingual model. Routed models load next to the primary one with its
+// settings; until one is ready its languages stay on the primary.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+#[cfg(feature = "server")]
+use crate::loading;
+use crate::service::ModelState;
+
+// Matches languages without an entry of their own
+pub const ANY_LANGUAGE: &str = "*";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedLanguage {
+    // ISO 639-3, e.g. "eng"
+    pub code: String,
+    pub confidence: f64,
+    // Whether the text was long and distinctive enough to be sure
+    pub reliable: bool,
+}
+
+pub fn detect(text: &str) -> Option<DetectedLanguage> {
+    let info = whatlang::detect(text)?;
+    Some(DetectedLanguage {
+        code: info.lang().code().to_string(),
+        confidence: info.confidence(),
+        reliable: info.is_reliable(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageModel {
+    pub language: String,
+    pub model: String,
+}
+
+impl FromStr for LanguageModel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once('=') {
+            Some((language, model)) if !language.trim().is_empty() && !model.trim().is_empty() => Ok(LanguageModel {
+                language: language.trim().to_ascii_lowercase(),
+                model: model.trim().to_string(),
+            }),
+            _ => Err(format!("invalid language model {:?}, expected language=model", value)),
+        }
+    }
+}
+
+// A model other than the primary, with the languages routed to it
+struct Route {
+    languages: Vec<String>,
+    model: String,
+    model_state: ModelState,
+    ready: AtomicBool,
+}
+
+pub struct LanguageRouter {
+    routes: Vec<Route>,
+}
+
+pub type LanguageRouterState = Arc<LanguageRouter>;
+
+impl LanguageRouter {
+    // Languages mapped to the primary model need no route of their own, and
+    // languages sharing a model share one loaded copy
+    pub fn from_config(config: &Config) -> Self {
+        let mut routes: Vec<Route> = Vec::new();
+        for entry in &config.language_models {
+            if entry.model == config.model_name {
+                continue;
+            }
+            match routes.iter_mut().find(|route| route.model == entry.model) {
+                Some(route) => route.languages.push(entry.language.clone()),
+                None => routes.push(Route {
+                    languages: vec![entry.language.clone()],
+                    model: entry.model.clone(),
+                    model_state: Arc::new(Mutex::new(None)),
+                    ready: AtomicBool::new(false),
+                }),
+            }
+        }
+        LanguageRouter { routes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    // The loaded model serving `language`, if it isn't the primary. A language's
+    // own entry wins over the `*` fallback, which never takes languages mapped
+    // to the primary.
+    pub fn pick(&self, language: &str, config: &Config) -> Option<(&ModelState, &str)> {
+        let entry = config
+            .language_models
+            .iter()
+            .find(|entry| entry.language == language)
+            .or_else(|| config.language_models.iter().find(|entry| entry.language == ANY_LANGUAGE))?;
+        self.routes
+            .iter()
+            .find(|route| route.model == entry.model && route.ready.load(Ordering::Relaxed))
+            .map(|route| (&route.model_state, route.model.as_str()))
+    }
+
+    // Load and warm up the routed models one after the other
+    #[cfg(feature = "server")]
+    pub async fn load(&self, config: &Config) {
+        for route in &self.routes {
+            match loading::load_secondary(config, &route.model).await {
+                Ok(pool) => {
+                    *route.model_state.lock().await = Some(pool);
+                    route.ready.store(true, Ordering::Relaxed);
+                    tracing::info!(model = %route.model, languages = ?route.languages, "Language model ready");
+                }
+                Err(e) => tracing::error!(model = %route.model, error = %e, "Failed to load a language model; its languages stay on the primary"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(entries: &[&str]) -> Config {
+        Config {
+            model_name: "primary".to_string(),
+            language_models: entries.iter().map(|entry| entry.parse().unwrap()).collect(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_language() {
+        let german = detect("Sehr geehrte Damen und Herren, bitte senden Sie die Unterlagen an meine neue Adresse.").unwrap();
+        assert_eq!(german.code, "deu");
+        let english = detect("Please send the documents to my new address before the end of the week.").unwrap();
+        assert_eq!(english.code, "eng");
+    }
+
+    #[test]
+    fn test_routes_only_ready_models() {
+        let config = config(&["eng=primary", "deu=multi", "*=multi"]);
+        let router = LanguageRouter::from_config(&config);
+        assert_eq!(router.routes.len(), 1);
+        assert_eq!(router.routes[0].languages, vec!["deu", "*"]);
+        assert!(router.pick("deu", &config).is_none());
+
+        router.routes[0].ready.store(true, Ordering::Relaxed);
+        assert_eq!(router.pick("deu", &config).map(|(_, model)| model), Some("multi"));
+        assert_eq!(router.pick("fra", &config).map(|(_, model)| model), Some("multi"));
+        assert!(router.pick("eng", &config).is_none());
+    }
+
+    #[test]
+    fn test_parse_language_model() {
+        let entry: LanguageModel = " DEU = urchade/gliner_multi-v2.1".parse().unwrap();
+        assert_eq!((entry.language.as_str(), entry.model.as_str()), ("deu", "urchade/gliner_multi-v2.1"));
+        assert!("deu".parse::<LanguageModel>().is_err());
+    }
+}
@@ -0,0 +1,283 @@
+// Loading the model at startup: downloading missing model files when a hub is
+// configured, creating the sessions and warming them up. With GLINER_LAZY_LOAD
+// the server starts listening first and this runs in the background; either way
+// the current phase is served by GET /v1/model/status. Admins can unload the
+// model to free its memory and load it again later.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::Config;
+use crate::pool::ModelPool;
+use crate::service::{init_model, warmup_model, ModelState};
+use crate::{ModelInfoState, Readiness};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum LoadPhase {
+    Pending,
+    Downloading {
+        file: String,
+        downloaded_bytes: u64,
+        // Unknown when the server sends no Content-Length
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        total_bytes: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        percent: Option<f64>,
+    },
+    Loading,
+    Warming,
+    Ready,
+    Failed {
+        error: String,
+    },
+    // Dropped through POST /admin/model/unload
+    Unloaded,
+}
+
+impl LoadPhase {
+    pub fn downloading(file: &str, downloaded_bytes: u64, total_bytes: Option<u64>) -> Self {
+        let percent = total_bytes
+            .filter(|&total| total > 0)
+            .map(|total| (downloaded_bytes as f64 * 1000.0 / total as f64).round() / 10.0);
+        LoadPhase::Downloading {
+            file: file.to_string(),
+            downloaded_bytes,
+            total_bytes,
+            percent,
+        }
+    }
+}
+
+// Served by GET /v1/model/status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelStatus {
+    pub model: String,
+    #[serde(flatten)]
+    pub phase: LoadPhase,
+    // Since the server started
+    pub elapsed_secs: u64,
+}
+
+pub struct ModelLoad {
+    model: String,
+    phase: Mutex<LoadPhase>,
+    started: Instant,
+}
+
+pub type ModelLoadState = Arc<ModelLoad>;
+
+impl ModelLoad {
+    pub fn new(model: &str) -> Self {
+        ModelLoad {
+            model: model.to_string(),
+            phase: Mutex::new(LoadPhase::Pending),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn set(&self, phase: LoadPhase) {
+        *self.phase.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = phase;
+    }
+
+    pub fn status(&self) -> ModelStatus {
+        ModelStatus {
+            model: self.model.clone(),
+            phase: self.phase.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone(),
+            elapsed_secs: self.started.elapsed().as_secs(),
+        }
+    }
+
+    // Mark a new load as started, unless one is already under way
+    pub fn begin(&self) -> bool {
+        let mut phase = self.phase.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if matches!(*phase, LoadPhase::Pending | LoadPhase::Downloading { .. } | LoadPhase::Loading | LoadPhase::Warming) {
+            return false;
+        }
+        *phase = LoadPhase::Pending;
+        true
+    }
+
+    fn fail(&self, error: String) {
+        self.set(LoadPhase::Failed { error });
+    }
+}
+
+// Download, load and warm up the model, publishing it to `model_state` and
+// `info_state` and marking the instance ready once warmup went through. A model
+// already loaded keeps serving until the new one replaces it.
+pub async fn load(config: Config, load: ModelLoadState, model_state: ModelState, info_state: ModelInfoState, readiness: Readiness) {
+    if let Err(e) = download_missing(&config, &load).await {
+        tracing::error!(error = %e, "Failed to download model files; continuing without the model, PII detection will not work");
+        load.fail(format!("download failed: {}", e));
+        return;
+    }
+
+    load.set(LoadPhase::Loading);
+    // Creating ONNX sessions blocks for a while, so keep it off the async workers
+    let loading = {
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || tokio::runtime::Handle::current().block_on(init_model(&config))).await
+    };
+    let (pool, info) = match loading {
+        Ok(Ok(loaded)) => loaded,
+        Ok(Err(e)) => {
+            tracing::error!(error = %e, "Failed to initialize model; continuing without it, PII detection will not work");
+            load.fail(e.to_string());
+            return;
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Model loading task failed");
+            load.fail(e.to_string());
+            return;
+        }
+    };
+
+    // Only report ready once warmup has gone through the whole inference path
+    load.set(LoadPhase::Warming);
+    let pool = Arc::new(pool);
+    let warmup = {
+        let (pool, text, runs) = (pool.clone(), config.warmup_text.clone(), config.warmup_runs);
+        tokio::task::spawn_blocking(move || pool.models().try_for_each(|model| warmup_model(model, &text, runs))).await
+    };
+    *model_state.lock().await = Some(pool);
+    *info_state.lock().await = Some(info);
+    match warmup {
+        Ok(Ok(())) => {
+            readiness.set_ready(true);
+            load.set(LoadPhase::Ready);
+        }
+        Ok(Err(e)) => {
+            tracing::error!(error = %e, "Warmup inference failed");
+            load.fail(format!("warmup failed: {}", e));
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Warmup task failed");
+            load.fail(format!("warmup failed: {}", e));
+        }
+    }
+}
+
+// Load and warm up the sessions of another model with the same settings, for
+// models served next to the primary one (canary, shadow)
+pub async fn load_secondary(config: &Config, model: &str) -> Result<Arc<ModelPool>, String> {
+    let config = Config {
+        model_name: model.to_string(),
+        ..config.clone()
+    };
+    let loading = {
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || tokio::runtime::Handle::current().block_on(init_model(&config))).await
+    };
+    let pool = match loading {
+        Ok(Ok((pool, _))) => Arc::new(pool),
+        Ok(Err(e)) => return Err(e.to_string()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let warmup = {
+        let (pool, text, runs) = (pool.clone(), config.warmup_text.clone(), config.warmup_runs);
+        tokio::task::spawn_blocking(move || pool.models().try_for_each(|model| warmup_model(model, &text, runs))).await
+    };
+    match warmup {
+        Ok(Ok(())) => Ok(pool),
+        Ok(Err(e)) => Err(format!("warmup failed: {}", e)),
+        Err(e) => Err(format!("warmup failed: {}", e)),
+    }
+}
+
+// Drop the model; its memory is freed once work still running on it finishes
+pub async fn unload(load: &ModelLoad, model_state: &ModelState, info_state: &ModelInfoState, readiness: &Readiness) {
+    readiness.set_ready(false);
+    *model_state.lock().await = None;
+    *info_state.lock().await = None;
+    load.set(LoadPhase::Unloaded);
+    tracing::info!("Model unloaded; detection returns 503 until it is loaded again");
+}
+
+// With GLINER_MODEL_HUB_URL set, fetch the tokenizer and ONNX files that are not
+// on disk yet from `{hub}/{model}/resolve/main/{file}`
+async fn download_missing(config: &Config, load: &ModelLoad) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(hub) = config.model_hub_url.as_deref() else {
+        return Ok(());
+    };
+    let client = reqwest::Client::new();
+    for (file, path) in [("tokenizer.json", config.tokenizer_path()), ("model.onnx", config.onnx_path())] {
+        if Path::new(&path).exists() {
+            continue;
+        }
+        let url = format!("{}/{}/resolve/main/{}", hub.trim_end_matches('/'), config.model_name, file);
+        tracing::info!(url = %url, path = %path, "Downloading model file");
+        download(&client, &url, file, &path, load).await?;
+    }
+    Ok(())
+}
+
+// Stream `url` to `path`, through a temporary file so an interrupted download
+// is not mistaken for a complete one on the next start
+async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    file: &str,
+    path: &str,
+    load: &ModelLoad,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let total = response.content_length();
+    if let Some(parent) = Path::new(path).parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = format!("{}.part", path);
+    let mut out = tokio::fs::File::create(&partial).await?;
+    let mut downloaded = 0;
+    load.set(LoadPhase::downloading(file, downloaded, total));
+    while let Some(chunk) = response.chunk().await? {
+        out.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        load.set(LoadPhase::downloading(file, downloaded, total));
+    }
+    out.flush().await?;
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_percent() {
+        assert_eq!(
+            LoadPhase::downloading("model.onnx", 512, Some(2048)),
+            LoadPhase::Downloading {
+                file: "model.onnx".to_string(),
+                downloaded_bytes: 512,
+                total_bytes: Some(2048),
+                percent: Some(25.0),
+            }
+        );
+        let unknown = serde_json::to_value(LoadPhase::downloading("model.onnx", 512, None)).unwrap();
+        assert!(unknown.get("percent").is_none());
+    }
+
+    #[test]
+    fn test_one_load_at_a_time() {
+        let load = ModelLoad::new("gliner");
+        assert!(!load.begin());
+        load.set(LoadPhase::Unloaded);
+        assert!(load.begin());
+        assert_eq!(load.status().phase, LoadPhase::Pending);
+        assert!(!load.begin());
+    }
+
+    #[test]
+    fn test_status_json_shape() {
+        let load = ModelLoad::new("onnx-community/gliner-multitask-large-v0.5");
+        load.set(LoadPhase::Warming);
+        let status = serde_json::to_value(load.status()).unwrap();
+        assert_eq!(status["phase"], "warming");
+        assert_eq!(status["model"], "onnx-community/gliner-multitask-large-v0.5");
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::PiiEntity;
+
+// A non-blank log line and its 1-based line number
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub line: usize,
+    pub text: String,
+}
+
+// Entities found in one log line; offsets are relative to that line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFinding {
+    pub line: usize,
+    pub entities: Vec<PiiEntity>,
+}
+
+// Totals over a whole log, with how often each label was found
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LogSummary {
+    pub lines_read: usize,
+    pub lines_scanned: usize,
+    // Lines that could not be scanned, e.g. because they were too long
+    pub lines_skipped: usize,
+    pub lines_with_findings: usize,
+    pub total_hits: usize,
+    pub labels: BTreeMap<String, usize>,
+}
+
+impl LogSummary {
+    // Count a scanned line's entities
+    pub fn record(&mut self, entities: &[PiiEntity]) {
+        self.lines_scanned += 1;
+        if !entities.is_empty() {
+            self.lines_with_findings += 1;
+        }
+        self.total_hits += entities.len();
+        for entity in entities {
+            *self.labels.entry(entity.label.clone()).or_default() += 1;
+        }
+    }
+}
+
+// Final line of a log scan's output
+#[derive(Serialize, Deserialize)]
+pub struct SummaryLine {
+    pub summary: LogSummary,
+}
+
+// Read lines into batches of up to `batch_size` non-blank lines, numbering them
+// as they go
+pub struct LineBatcher {
+    batch_size: usize,
+    lines_read: usize,
+    pending: Vec<LogLine>,
+}
+
+impl LineBatcher {
+    pub fn new(batch_size: usize) -> Self {
+        LineBatcher {
+            batch_size: batch_size.max(1),
+            lines_read: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    // Add the next line of the log, returning a batch once one is full
+    pub fn push(&mut self, text: String) -> Option<Vec<LogLine>> {
+        self.lines_read += 1;
+        // Lines keep their terminator when read from CRLF files
+        let text = match text.strip_suffix('\r') {
+            Some(stripped) => stripped.to_string(),
+            None => text,
+        };
+        if !text.trim().is_empty() {
+            self.pending.push(LogLine {
+                line: self.lines_read,
+                text,
+            });
+        }
+        (self.pending.len() >= self.batch_size).then(|| self.finish())
+    }
+
+    // Take whatever is left, e.g. at the end of the log
+    pub fn finish(&mut self) -> Vec<LogLine> {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub fn lines_read(&self) -> usize {
+        self.lines_read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_batches_skip_blank_lines_but_keep_numbers() {
+        let mut batcher = LineBatcher::new(2);
+        assert_eq!(batcher.push("GET /users/42\r".to_string()), None);
+        assert_eq!(batcher.push("   ".to_string()), None);
+        let batch = batcher.push("login jane@example.com".to_string()).unwrap();
+        assert_eq!(
+            batch,
+            vec![
+                LogLine { line: 1, text: "GET /users/42".to_string() },
+                LogLine { line: 3, text: "login jane@example.com".to_string() },
+            ]
+        );
+        assert_eq!(batcher.push("done".to_string()), None);
+        assert_eq!(batcher.finish().len(), 1);
+        assert_eq!(batcher.lines_read(), 4);
+    }
+
+    #[test]
+    fn test_summary_counts_labels() {
+        let mut summary = LogSummary::default();
+        summary.record(&[entity("email", 0, 1, 0.9), entity("person", 0, 1, 0.9)]);
+        summary.record(&[]);
+        summary.record(&[entity("email", 0, 1, 0.9)]);
+
+        assert_eq!(summary.lines_scanned, 3);
+        assert_eq!(summary.lines_with_findings, 2);
+        assert_eq!(summary.total_hits, 3);
+        assert_eq!(summary.labels["email"], 2);
+        assert_eq!(summary.labels["person"], 1);
+    }
+}
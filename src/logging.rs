@@ -0,0 +1,201 @@
+// Structured logging through `tracing`. Log lines carry request ids, latencies,
+// label counts and hashes of submitted text, never the text itself. Level and
+// format come from GLINER_LOG_LEVEL and GLINER_LOG_FORMAT.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+use crate::PiiEntity;
+
+#[cfg(feature = "server")]
+use rocket::fairing::{Fairing, Info, Kind};
+#[cfg(feature = "server")]
+use rocket::http::Header;
+#[cfg(feature = "server")]
+use rocket::request::{self, FromRequest, Request};
+#[cfg(feature = "server")]
+use rocket::{Data, Response};
+#[cfg(feature = "server")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "server")]
+use std::time::Instant;
+
+// Install the global subscriber, writing to stderr so CLI output on stdout stays
+// machine-readable. Records from the `log` crate (Rocket's) are captured too.
+// Later calls are no-ops.
+pub fn init(config: &Config) {
+    let filter = EnvFilter::try_new(&config.log_level).unwrap_or_else(|e| {
+        eprintln!("Ignoring invalid value for GLINER_LOG_LEVEL: {:?} ({})", config.log_level, e);
+        EnvFilter::new("info")
+    });
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+    let result = match config.log_format.as_str() {
+        "json" => builder.json().flatten_event(true).try_init(),
+        "pretty" => builder.try_init(),
+        other => {
+            eprintln!("Ignoring invalid value for GLINER_LOG_FORMAT: {:?}, expected pretty or json", other);
+            builder.try_init()
+        }
+    };
+    // Already installed, e.g. by an embedding program
+    drop(result);
+}
+
+// Short SHA-256 of a text, for correlating log lines about the same input
+// without logging it
+pub fn text_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Findings per label, the only view of results that gets logged
+pub fn label_counts<'a>(entities: impl IntoIterator<Item = &'a PiiEntity>) -> BTreeMap<&'a str, usize> {
+    let mut counts = BTreeMap::new();
+    for entity in entities {
+        *counts.entry(entity.label.as_str()).or_default() += 1;
+    }
+    counts
+}
+
+// Header a caller can set to choose the request id, and that every response
+// carries it back in
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// Longest caller-supplied request id that is honored
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Whether a caller-supplied id can be used as is: short, and made only of
+// characters that cannot break a log line or a header
+pub fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|byte| byte.is_ascii_alphanumeric() || b"-_.:/".contains(&byte))
+}
+
+// Identifier of one HTTP request, shared by every log line and error body about it
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+#[cfg(feature = "server")]
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "server")]
+static NEXT_REQUEST: AtomicU64 = AtomicU64::new(1);
+
+#[cfg(feature = "server")]
+impl RequestId {
+    // Process start time and a counter, unique per instance
+    fn generate() -> Self {
+        static STARTED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+        let started = *STARTED.get_or_init(crate::model_info::unix_now);
+        RequestId(format!("{:x}-{:x}", started, NEXT_REQUEST.fetch_add(1, Ordering::Relaxed)))
+    }
+
+    // The caller's `X-Request-Id` when it is valid, otherwise a generated one.
+    // Invalid ids are replaced rather than rejected, so tracing never fails a call.
+    pub fn of(request: &Request<'_>) -> RequestId {
+        request
+            .local_cache(|| match request.headers().get_one(REQUEST_ID_HEADER) {
+                Some(id) if valid_request_id(id) => RequestId(id.to_string()),
+                _ => RequestId::generate(),
+            })
+            .clone()
+    }
+}
+
+#[cfg(feature = "server")]
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(RequestId::of(request))
+    }
+}
+
+// When a request arrived, kept in its local cache
+#[cfg(feature = "server")]
+struct Started(Instant);
+
+// Milliseconds since the request arrived
+#[cfg(feature = "server")]
+pub fn elapsed_ms(request: &Request<'_>) -> f64 {
+    request.local_cache(|| Started(Instant::now())).0.elapsed().as_secs_f64() * 1000.0
+}
+
+// Logs one line per request: id, method, route, status and latency, and returns
+// the id in `X-Request-Id`. The query string is left out, as it may carry caller data.
+#[cfg(feature = "server")]
+pub struct RequestLog;
+
+#[cfg(feature = "server")]
+#[rocket::async_trait]
+impl Fairing for RequestLog {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request log",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| Started(Instant::now()));
+        RequestId::of(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let route = request.route().map(|route| route.uri.to_string());
+        let request_id = RequestId::of(request);
+        response.set_header(Header::new(REQUEST_ID_HEADER, request_id.0.clone()));
+        tracing::info!(
+            request_id = %request_id,
+            method = %request.method(),
+            path = %request.uri().path(),
+            route = route.as_deref().unwrap_or("-"),
+            status = response.status().code,
+            latency_ms = elapsed_ms(request),
+            "request completed"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_text_hash_is_short_and_stable() {
+        assert_eq!(text_hash("Jane Roe"), text_hash("Jane Roe"));
+        assert_ne!(text_hash("Jane Roe"), text_hash("Jane Doe"));
+        assert_eq!(text_hash("Jane Roe").len(), 16);
+    }
+
+    #[test]
+    fn test_valid_request_id() {
+        assert!(valid_request_id("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert!(valid_request_id("web-7f3a:checkout/42"));
+        assert!(!valid_request_id(""));
+        assert!(!valid_request_id("id with spaces"));
+        assert!(!valid_request_id("line\nbreak"));
+        assert!(!valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+
+    #[test]
+    fn test_label_counts() {
+        let entities = vec![entity("email", 0, 1, 0.9), entity("person", 0, 1, 0.9), entity("email", 0, 1, 0.9)];
+        let counts = label_counts(&entities);
+        assert_eq!(counts["email"], 2);
+        assert_eq!(counts["person"], 1);
+    }
+}
@@ -1,7 +1,36 @@
-use gliner_rs_api::rocket;
+use gliner_rs_api::cli::{self, Command};
+use gliner_rs_api::config::Config;
+use gliner_rs_api::{logging, rocket, supervisor};
 
 #[rocket::main]
-async fn main() -> Result<(), rocket::Error> {
-    let _ = rocket().await.launch().await?;
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    logging::init(&Config::from_env());
+    if command != Command::Serve {
+        let status = cli::run(command, Config::from_env()).await.map_err(|e| e as Box<dyn std::error::Error>)?;
+        std::process::exit(status);
+    }
+
+    if Config::from_env().supervisor && !supervisor::is_worker() {
+        supervisor::run().await?;
+        return Ok(());
+    }
+
+    let rocket = rocket().await;
+
+    // A supervised worker loads and warms the model first, then serves on a private
+    // port the supervisor forwards to
+    if supervisor::is_worker() {
+        return supervisor::run_worker(rocket).await;
+    }
+
+    let _ = rocket.launch().await?;
     Ok(())
 }
@@ -0,0 +1,364 @@
+// Markdown input (`"input_format": "markdown"`): formatting syntax is stripped
+// before detection so documentation and ticket bodies read as prose, and
+// offsets are mapped back to the Markdown source. Heading, quote and list
+// markers, emphasis, code span backticks, fences and link destinations are
+// removed; text is otherwise copied as written, so entities map back exactly.
+
+use crate::html::{Builder, VisibleText};
+
+// Strip Markdown syntax, leaving out the contents of code blocks and code
+// spans when `skip_code` is set. Line breaks are kept so lines stay apart.
+pub fn strip_markdown(markdown: &str, skip_code: bool) -> VisibleText {
+    let mut builder = Builder::default();
+    // Character and length of the fence of the open code block
+    let mut fence: Option<(char, usize)> = None;
+    let mut start = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let newline = &line[body.len()..];
+        let line_start = start;
+        start += line.len();
+
+        if let Some((marker, len)) = fence {
+            if fence_marker(body).is_some_and(|(c, n)| c == marker && n >= len && body.trim_start().trim_start_matches(c).trim().is_empty()) {
+                fence = None;
+            } else if !skip_code {
+                push(&mut builder, body, line_start);
+            }
+        } else if let Some(open) = fence_marker(body) {
+            fence = Some(open);
+        } else if !is_break_line(body) {
+            let prefix = block_prefix_len(body);
+            let end = heading_end(body, prefix);
+            let table_row = body.trim_start().starts_with('|');
+            inline(&mut builder, body, prefix, end, line_start, skip_code, table_row);
+        }
+        push(&mut builder, newline, line_start + body.len());
+    }
+
+    builder.finish()
+}
+
+// Copy source text verbatim
+fn push(builder: &mut Builder, text: &str, source: usize) {
+    if !text.is_empty() {
+        builder.push(text, source, text.len(), true);
+    }
+}
+
+// A code fence: at most three spaces, then three or more backticks or tildes
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+// Lines with no text: thematic breaks, setext heading underlines and table
+// delimiter rows
+fn is_break_line(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.is_empty() {
+        return false;
+    }
+    let thematic = ['-', '*', '_'].iter().any(|&c| compact.len() >= 3 && compact.chars().all(|x| x == c));
+    let setext = compact.chars().all(|c| c == '=');
+    let delimiter = compact.contains('|') && compact.contains('-') && compact.chars().all(|c| matches!(c, '|' | '-' | ':'));
+    thematic || setext || delimiter
+}
+
+// Length of the block markers at the start of a line: indentation, quote
+// markers, a list marker with an optional task box, or a heading marker
+fn block_prefix_len(line: &str) -> usize {
+    let bytes = line.as_bytes();
+    let skip_spaces = |mut i: usize| {
+        while bytes.get(i).is_some_and(|b| *b == b' ' || *b == b'\t') {
+            i += 1;
+        }
+        i
+    };
+    let mut i = skip_spaces(0);
+    while bytes.get(i) == Some(&b'>') {
+        i = skip_spaces(i + 1);
+    }
+
+    let rest = &line[i..];
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let marker = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else if (1..=9).contains(&digits) && rest[digits..].starts_with(['.', ')']) {
+        digits + 1
+    } else {
+        0
+    };
+    if marker > 0 && (rest.len() == marker || rest[marker..].starts_with([' ', '\t'])) {
+        i = skip_spaces(i + marker);
+        let rest = &line[i..];
+        if ["[ ] ", "[x] ", "[X] "].iter().any(|task| rest.starts_with(task)) {
+            i += 4;
+        }
+        return i;
+    }
+
+    let hashes = rest.bytes().take_while(|b| *b == b'#').count();
+    if (1..=6).contains(&hashes) && (rest.len() == hashes || rest[hashes..].starts_with([' ', '\t'])) {
+        return skip_spaces(i + hashes);
+    }
+    i
+}
+
+// End of a heading's text, before its optional closing `#`s
+fn heading_end(line: &str, prefix: usize) -> usize {
+    if !line.trim_start().starts_with('#') {
+        return line.len();
+    }
+    let trimmed = line.trim_end();
+    let without = trimmed.trim_end_matches('#');
+    if without.len() == trimmed.len() {
+        return trimmed.len();
+    }
+    if without.len() <= prefix {
+        return prefix;
+    }
+    if without.ends_with([' ', '\t']) {
+        without.trim_end().len()
+    } else {
+        trimmed.len()
+    }
+}
+
+// Copy the text of one line from `from` to `to`, dropping inline syntax
+fn inline(builder: &mut Builder, line: &str, from: usize, to: usize, source: usize, skip_code: bool, table_row: bool) {
+    let bytes = line.as_bytes();
+    // Link text ends: where the `]` is and where the destination after it ends
+    let mut links: Vec<(usize, usize)> = Vec::new();
+    let mut i = from;
+
+    while i < to {
+        if let Some(&(close, after)) = links.last() {
+            if i == close {
+                links.pop();
+                i = after;
+                continue;
+            }
+        }
+        let c = bytes[i];
+        match c {
+            b'\\' if bytes.get(i + 1).is_some_and(u8::is_ascii_punctuation) && i + 1 < to => {
+                push(builder, &line[i + 1..i + 2], source + i + 1);
+                i += 2;
+            }
+            b'`' => {
+                let run = run_len(bytes, i, b'`');
+                match find_run(bytes, i + run, to, b'`', run) {
+                    Some(close) => {
+                        if !skip_code {
+                            push(builder, &line[i + run..close], source + i + run);
+                        }
+                        i = close + run;
+                    }
+                    None => {
+                        push(builder, &line[i..i + run], source + i);
+                        i += run;
+                    }
+                }
+            }
+            b'!' | b'[' => {
+                let open = if c == b'!' { i + 1 } else { i };
+                match (bytes.get(open) == Some(&b'[')).then(|| link(line, open, to)).flatten() {
+                    Some(ends) => {
+                        links.push(ends);
+                        i = open + 1;
+                    }
+                    None => {
+                        push(builder, &line[i..i + 1], source + i);
+                        i += 1;
+                    }
+                }
+            }
+            b'<' => match autolink(line, i, to) {
+                Some(end) => {
+                    push(builder, &line[i + 1..end], source + i + 1);
+                    i = end + 1;
+                }
+                None => {
+                    push(builder, "<", source + i);
+                    i += 1;
+                }
+            },
+            b'*' | b'_' | b'~' => {
+                let run = run_len(bytes, i, c).min(to - i);
+                let before = line[..i].chars().next_back();
+                let after = line[i + run..to].chars().next();
+                let space = |c: Option<char>| c.is_none_or(char::is_whitespace);
+                let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+                let markup = match c {
+                    // A lone `*` between spaces is a literal, e.g. "2 * 3"
+                    b'*' => !(space(before) && space(after)),
+                    // Underscores inside words are not emphasis, e.g. jane_roe@example.com
+                    b'_' => !(space(before) && space(after) || word(before) && word(after)),
+                    _ => run == 2,
+                };
+                if !markup {
+                    push(builder, &line[i..i + run], source + i);
+                }
+                i += run;
+            }
+            b'|' if table_row => {
+                builder.push(" ", source + i, 1, false);
+                i += 1;
+            }
+            _ => {
+                let len = line[i..to]
+                    .find(['\\', '`', '!', '[', '<', '*', '_', '~', '|'])
+                    .unwrap_or(to - i)
+                    .max(line[i..].chars().next().map_or(1, char::len_utf8));
+                // Stop at the end of the innermost link text
+                let len = match links.last() {
+                    Some(&(close, _)) if close > i => len.min(close - i),
+                    _ => len,
+                };
+                push(builder, &line[i..i + len], source + i);
+                i += len;
+            }
+        }
+    }
+}
+
+fn run_len(bytes: &[u8], start: usize, c: u8) -> usize {
+    bytes[start..].iter().take_while(|b| **b == c).count()
+}
+
+// Start of the next run of exactly `len` `c`s in `bytes[from..to]`
+fn find_run(bytes: &[u8], from: usize, to: usize, c: u8, len: usize) -> Option<usize> {
+    let mut i = from;
+    while i < to {
+        if bytes[i] == c {
+            let run = run_len(bytes, i, c);
+            if run == len {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+// A link or image at `open` (its `[`): where its text ends and where the
+// destination (`(url "title")`) or reference (`[ref]`) after it ends
+fn link(line: &str, open: usize, to: usize) -> Option<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    let mut close = None;
+    let mut i = open + 1;
+    while i < to {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' => depth += 1,
+            b']' if depth == 0 => {
+                close = Some(i);
+                break;
+            }
+            b']' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    let close = close?;
+    let (opening, closing) = match bytes.get(close + 1) {
+        Some(b'(') => (b'(', b')'),
+        Some(b'[') => (b'[', b']'),
+        _ => return None,
+    };
+    let mut depth = 0;
+    for (offset, &b) in bytes[close + 1..to].iter().enumerate() {
+        if b == opening {
+            depth += 1;
+        } else if b == closing {
+            depth -= 1;
+            if depth == 0 {
+                return Some((close, close + 1 + offset + 1));
+            }
+        }
+    }
+    None
+}
+
+// An autolink such as `<https://example.com>` or `<jane@example.com>`: the
+// offset of its closing `>`
+fn autolink(line: &str, open: usize, to: usize) -> Option<usize> {
+    let end = open + 1 + line[open + 1..to].find('>')?;
+    let inner = &line[open + 1..end];
+    let address = !inner.is_empty() && !inner.contains(char::is_whitespace) && (inner.contains('@') || inner.contains("://"));
+    address.then_some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PiiEntity;
+    use std::collections::BTreeMap;
+
+    // Source text an entity found in the stripped text maps back to
+    fn source_of<'a>(markdown: &'a str, visible: &VisibleText, needle: &str) -> &'a str {
+        let start = visible.text.find(needle).expect("needle in visible text");
+        let mut entities = vec![PiiEntity {
+            text: needle.to_string(),
+            label: "test".to_string(),
+            confidence: 1.0,
+            start,
+            end: start + needle.len(),
+            attributes: BTreeMap::new(),
+        }];
+        visible.map_entities(&mut entities);
+        &markdown[entities[0].start..entities[0].end]
+    }
+
+    #[test]
+    fn test_strips_block_and_inline_syntax() {
+        let markdown = "# Contact *Jane Roe* #\n\n> - [x] Mail **jane_roe@example.com**\n1. See [the wiki](https://wiki.example.com/jane \"Jane\")\n---\n";
+        let visible = strip_markdown(markdown, false);
+        assert_eq!(visible.text, "Contact Jane Roe\n\nMail jane_roe@example.com\nSee the wiki\n\n");
+        assert_eq!(source_of(markdown, &visible, "jane_roe@example.com"), "jane_roe@example.com");
+        assert_eq!(source_of(markdown, &visible, "Jane Roe"), "Jane Roe");
+    }
+
+    #[test]
+    fn test_entity_spanning_syntax_maps_over_it() {
+        let markdown = "Ask **Jane** _Roe_ today";
+        let visible = strip_markdown(markdown, false);
+        assert_eq!(visible.text, "Ask Jane Roe today");
+        assert_eq!(source_of(markdown, &visible, "Jane Roe"), "Jane** _Roe");
+    }
+
+    #[test]
+    fn test_code_is_scanned_unless_skipped() {
+        let markdown = "Run `login jane`:\n\n```sh\nexport EMAIL=jane@example.com\n```\nDone";
+        let scanned = strip_markdown(markdown, false);
+        assert_eq!(scanned.text, "Run login jane:\n\n\nexport EMAIL=jane@example.com\n\nDone");
+        assert_eq!(source_of(markdown, &scanned, "jane@example.com"), "jane@example.com");
+
+        let skipped = strip_markdown(markdown, true);
+        assert_eq!(skipped.text, "Run :\n\n\n\n\nDone");
+    }
+
+    #[test]
+    fn test_tables_autolinks_and_escapes() {
+        let markdown = "| Name | Email |\n|---|:---:|\n| Jane Roe | <jane@example.com> |\n2 \\* 3 * 4";
+        let visible = strip_markdown(markdown, false);
+        assert_eq!(visible.text, "  Name   Email  \n\n  Jane Roe   jane@example.com  \n2 * 3 * 4");
+        assert_eq!(source_of(markdown, &visible, "jane@example.com"), "jane@example.com");
+    }
+
+    #[test]
+    fn test_plain_prose_is_unchanged() {
+        let text = "Call Jane Roe at 555-0100, or mail jane.roe@example.com.";
+        assert_eq!(strip_markdown(text, false).text, text);
+    }
+}
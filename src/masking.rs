@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::PiiEntity;
+
+// How a detected span is rewritten in redacted output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskingStrategy {
+    // Replace with the label in brackets, e.g. "[PERSON]"
+    #[default]
+    Redact,
+    // Replace every character with '*'
+    Mask,
+    // Keep only the last four characters, e.g. "************1111"
+    Partial,
+    // Replace with a short stable hash, so equal values stay linkable
+    Hash,
+}
+
+impl MaskingStrategy {
+    pub fn apply(&self, value: &str, label: &str) -> String {
+        match self {
+            MaskingStrategy::Redact => format!("[{}]", label.to_uppercase().replace(' ', "_")),
+            MaskingStrategy::Mask => "*".repeat(value.chars().count()),
+            MaskingStrategy::Partial => {
+                let count = value.chars().count();
+                let keep = count.saturating_sub(4);
+                value
+                    .chars()
+                    .enumerate()
+                    .map(|(index, c)| if index < keep && !c.is_whitespace() { '*' } else { c })
+                    .collect()
+            }
+            MaskingStrategy::Hash => {
+                let digest = Sha256::digest(value.as_bytes());
+                let hex: String = digest.iter().take(6).map(|byte| format!("{:02x}", byte)).collect();
+                format!("<{}:{}>", label.replace(' ', "_"), hex)
+            }
+        }
+    }
+}
+
+// Rewrite `text` with every entity replaced according to its label's strategy.
+// Entities overlapping one already rewritten are skipped.
+pub fn apply_masking(text: &str, entities: &[PiiEntity], strategy_for: impl Fn(&str) -> MaskingStrategy) -> String {
+    let mut ordered: Vec<&PiiEntity> = entities.iter().collect();
+    ordered.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+    let mut output = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for entity in ordered {
+        if entity.start < cursor || entity.end > text.len() || entity.start >= entity.end {
+            continue;
+        }
+        let (Some(before), Some(value)) = (text.get(cursor..entity.start), text.get(entity.start..entity.end)) else {
+            continue;
+        };
+        output.push_str(before);
+        output.push_str(&strategy_for(&entity.label).apply(value, &entity.label));
+        cursor = entity.end;
+    }
+    output.push_str(&text[cursor..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_strategies() {
+        assert_eq!(MaskingStrategy::Redact.apply("John Doe", "person"), "[PERSON]");
+        assert_eq!(MaskingStrategy::Redact.apply("4111", "credit card number"), "[CREDIT_CARD_NUMBER]");
+        assert_eq!(MaskingStrategy::Mask.apply("Jöhn", "person"), "****");
+        assert_eq!(MaskingStrategy::Partial.apply("4111 1111 1111 1234", "credit card number"), "**** **** **** 1234");
+        assert_eq!(
+            MaskingStrategy::Hash.apply("john@example.com", "email"),
+            MaskingStrategy::Hash.apply("john@example.com", "email")
+        );
+        assert!(MaskingStrategy::Hash.apply("john@example.com", "email").starts_with("<email:"));
+    }
+
+    #[test]
+    fn test_apply_masking_rewrites_spans() {
+        let text = "John Doe wrote to jane@example.com";
+        let entities = vec![entity("email", 18, 34, 0.9), entity("person", 0, 8, 0.9)];
+        let masked = apply_masking(text, &entities, |label| {
+            if label == "email" { MaskingStrategy::Mask } else { MaskingStrategy::Redact }
+        });
+        assert_eq!(masked, "[PERSON] wrote to ****************");
+    }
+
+    #[test]
+    fn test_apply_masking_skips_overlaps() {
+        let text = "John Doe";
+        let entities = vec![entity("person", 0, 8, 0.9), entity("person", 5, 8, 0.9)];
+        assert_eq!(apply_masking(text, &entities, |_| MaskingStrategy::Redact), "[PERSON]");
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::chunking::PaddingStats;
+use crate::pool::PoolSnapshot;
+#[cfg(feature = "server")]
+use crate::shadow::ShadowDiff;
+
+// Process-wide counters, exposed through GET /api/metrics
+#[derive(Default)]
+pub struct Metrics {
+    inference_batches: AtomicU64,
+    real_tokens: AtomicU64,
+    padded_tokens: AtomicU64,
+    prepass_skipped_chunks: AtomicU64,
+    served_by_model: Mutex<BTreeMap<String, u64>>,
+    shadow_comparisons: AtomicU64,
+    shadow_disagreements: AtomicU64,
+    shadow_only_primary: AtomicU64,
+    shadow_only_shadow: AtomicU64,
+    shadow_busy: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub inference_batches: u64,
+    pub real_tokens: u64,
+    pub padded_tokens: u64,
+    pub padding_efficiency: f64,
+    pub prepass_skipped_chunks: u64,
+    // Detections per model that served them, while a canary is configured
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub served_by_model: BTreeMap<String, u64>,
+    // Shadow model comparisons: how many ran, how many disagreed, the findings
+    // only one side had, and comparisons dropped because the shadow was busy
+    #[serde(default)]
+    pub shadow_comparisons: u64,
+    #[serde(default)]
+    pub shadow_disagreements: u64,
+    #[serde(default)]
+    pub shadow_only_primary: u64,
+    #[serde(default)]
+    pub shadow_only_shadow: u64,
+    #[serde(default)]
+    pub shadow_busy: u64,
+    // Filled in from the inference queue when the snapshot is served
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    // Filled in from the session pool, None while no model is loaded
+    pub session_pool: Option<PoolSnapshot>,
+}
+
+impl Metrics {
+    pub fn record_batches(&self, batches: usize, padding: PaddingStats) {
+        self.inference_batches.fetch_add(batches as u64, Ordering::Relaxed);
+        self.real_tokens.fetch_add(padding.real_tokens as u64, Ordering::Relaxed);
+        self.padded_tokens.fetch_add(padding.padded_tokens as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_prepass_skipped(&self, chunks: usize) {
+        self.prepass_skipped_chunks.fetch_add(chunks as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_served_by(&self, model: &str) {
+        let mut served = self.served_by_model.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *served.entry(model.to_string()).or_default() += 1;
+    }
+
+    #[cfg(feature = "server")]
+    pub fn record_shadow(&self, diff: &ShadowDiff) {
+        self.shadow_comparisons.fetch_add(1, Ordering::Relaxed);
+        if diff.disagrees() {
+            self.shadow_disagreements.fetch_add(1, Ordering::Relaxed);
+        }
+        self.shadow_only_primary.fetch_add(diff.only_primary.values().sum::<usize>() as u64, Ordering::Relaxed);
+        self.shadow_only_shadow.fetch_add(diff.only_shadow.values().sum::<usize>() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_shadow_busy(&self) {
+        self.shadow_busy.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let padding = PaddingStats {
+            real_tokens: self.real_tokens.load(Ordering::Relaxed) as usize,
+            padded_tokens: self.padded_tokens.load(Ordering::Relaxed) as usize,
+        };
+        MetricsSnapshot {
+            inference_batches: self.inference_batches.load(Ordering::Relaxed),
+            real_tokens: padding.real_tokens as u64,
+            padded_tokens: padding.padded_tokens as u64,
+            padding_efficiency: padding.efficiency(),
+            prepass_skipped_chunks: self.prepass_skipped_chunks.load(Ordering::Relaxed),
+            served_by_model: self.served_by_model.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone(),
+            shadow_comparisons: self.shadow_comparisons.load(Ordering::Relaxed),
+            shadow_disagreements: self.shadow_disagreements.load(Ordering::Relaxed),
+            shadow_only_primary: self.shadow_only_primary.load(Ordering::Relaxed),
+            shadow_only_shadow: self.shadow_only_shadow.load(Ordering::Relaxed),
+            shadow_busy: self.shadow_busy.load(Ordering::Relaxed),
+            queue_depth: 0,
+            queue_capacity: 0,
+            session_pool: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_accumulate_padding() {
+        let metrics = Metrics::default();
+        metrics.record_batches(2, PaddingStats { real_tokens: 30, padded_tokens: 40 });
+        metrics.record_batches(1, PaddingStats { real_tokens: 10, padded_tokens: 10 });
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.inference_batches, 3);
+        assert_eq!(snapshot.real_tokens, 40);
+        assert_eq!(snapshot.padded_tokens, 50);
+        assert!((snapshot.padding_efficiency - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_served_by_model_counts() {
+        let metrics = Metrics::default();
+        assert!(serde_json::to_value(metrics.snapshot()).unwrap().get("served_by_model").is_none());
+        metrics.record_served_by("gliner-v1");
+        metrics.record_served_by("gliner-v2");
+        metrics.record_served_by("gliner-v1");
+        assert_eq!(metrics.snapshot().served_by_model["gliner-v1"], 2);
+    }
+}
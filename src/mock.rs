@@ -0,0 +1,188 @@
+// Scripted stand-in for the GLiNER model, built with the `mock-model` feature so
+// tests can go through detection, redaction, chunking and batching without the
+// model files. It reads no files and only answers to the model name "mock"
+// (GLINER_MODEL=mock, or as pre-pass, canary or shadow model): every occurrence
+// of a scripted phrase is reported with the phrase's label and score, when the
+// label was asked for and the score reaches the threshold.
+
+use gliner::model::input::text::TextInput;
+use gliner::model::params::Parameters;
+use orp::params::RuntimeParameters;
+
+use crate::config::{onnx_path_for, tokenizer_path_for, Config};
+use crate::detection::DetectionError;
+use crate::model_info::{unix_now, FileInfo, ModelInfo};
+
+// The model name the mock answers to
+pub const MOCK_MODEL: &str = "mock";
+
+// (phrase, label, score) found by the default mock
+pub const DEFAULT_SCRIPT: &[(&str, &str, f32)] = &[
+    ("Jane Roe", "person", 0.95),
+    ("John Smith", "person", 0.9),
+    ("jane@example.com", "email", 0.92),
+    ("john.smith@example.org", "email", 0.88),
+    ("555-0100", "phone", 0.8),
+    ("742 Evergreen Terrace", "address", 0.75),
+    ("Acme Logistics", "organization", 0.6),
+];
+
+#[derive(Debug, Clone)]
+pub struct ScriptedEntity {
+    pub text: String,
+    pub label: String,
+    pub score: f32,
+}
+
+// One finding, with the accessors of a GLiNER span
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockSpan {
+    start: usize,
+    end: usize,
+    text: String,
+    class: String,
+    probability: f32,
+}
+
+impl MockSpan {
+    // Byte offsets into the input text
+    pub fn offsets(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn class(&self) -> &str {
+        &self.class
+    }
+
+    pub fn probability(&self) -> f32 {
+        self.probability
+    }
+}
+
+// Spans per input text, in input order
+pub struct MockOutput {
+    pub spans: Vec<Vec<MockSpan>>,
+}
+
+pub struct MockModel {
+    script: Vec<ScriptedEntity>,
+    threshold: f32,
+}
+
+impl MockModel {
+    // Same arguments as `GLiNER::new`, so model loading works unchanged
+    pub fn new(params: Parameters, _runtime: RuntimeParameters, tokenizer_path: &str, onnx_path: &str) -> Result<Self, DetectionError> {
+        if tokenizer_path != tokenizer_path_for(MOCK_MODEL) || onnx_path != onnx_path_for(MOCK_MODEL) {
+            return Err(format!(
+                "built with the mock-model feature, which can only load the model {:?}, not {}",
+                MOCK_MODEL, onnx_path
+            )
+            .into());
+        }
+        let script = DEFAULT_SCRIPT
+            .iter()
+            .map(|&(text, label, score)| ScriptedEntity {
+                text: text.to_string(),
+                label: label.to_string(),
+                score,
+            })
+            .collect();
+        Ok(MockModel::scripted(script, params.threshold))
+    }
+
+    pub fn scripted(script: Vec<ScriptedEntity>, threshold: f32) -> Self {
+        MockModel { script, threshold }
+    }
+
+    pub fn inference(&self, input: TextInput) -> Result<MockOutput, DetectionError> {
+        let spans = input.texts.iter().map(|text| self.find(text, &input.entities)).collect();
+        Ok(MockOutput { spans })
+    }
+
+    fn find(&self, text: &str, labels: &[String]) -> Vec<MockSpan> {
+        let mut spans: Vec<MockSpan> = self
+            .script
+            .iter()
+            .filter(|entity| entity.score >= self.threshold && labels.contains(&entity.label))
+            .flat_map(|entity| {
+                text.match_indices(entity.text.as_str()).map(|(start, found)| MockSpan {
+                    start,
+                    end: start + found.len(),
+                    text: found.to_string(),
+                    class: entity.label.clone(),
+                    probability: entity.score,
+                })
+            })
+            .collect();
+        spans.sort_by_key(|span| (span.start, span.end));
+        spans
+    }
+}
+
+// What GET /v1/model/info reports for the mock: there are no files to hash
+pub fn model_info(config: &Config, params: &Parameters) -> ModelInfo {
+    let file = |path: String| FileInfo {
+        path,
+        size_bytes: 0,
+        sha256: String::new(),
+    };
+    ModelInfo {
+        name: config.model_name.clone(),
+        pipeline_mode: MOCK_MODEL.to_string(),
+        tokenizer: file(config.tokenizer_path()),
+        onnx: file(config.onnx_path()),
+        max_sequence_length: params.max_length,
+        max_span_width: params.max_width,
+        deterministic: config.deterministic,
+        execution_providers: Vec::new(),
+        threads: if config.deterministic { Some(1) } else { config.threads },
+        sessions: config.sessions,
+        loaded_at: unix_now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> MockModel {
+        MockModel::scripted(
+            vec![
+                ScriptedEntity { text: "Jane Roe".to_string(), label: "person".to_string(), score: 0.9 },
+                ScriptedEntity { text: "555-0100".to_string(), label: "phone".to_string(), score: 0.4 },
+            ],
+            0.5,
+        )
+    }
+
+    #[test]
+    fn test_finds_every_occurrence_of_requested_labels() {
+        let input = TextInput::from_str(&["Jane Roe and Jane Roe", "nobody"], &["person"]).unwrap();
+        let output = model().inference(input).unwrap();
+        let offsets: Vec<(usize, usize)> = output.spans[0].iter().map(MockSpan::offsets).collect();
+        assert_eq!(offsets, vec![(0, 8), (13, 21)]);
+        assert!(output.spans[1].is_empty());
+
+        let input = TextInput::from_str(&["Jane Roe"], &["email"]).unwrap();
+        assert!(model().inference(input).unwrap().spans[0].is_empty());
+    }
+
+    #[test]
+    fn test_threshold_applies_to_scores() {
+        let input = TextInput::from_str(&["Call 555-0100"], &["phone"]).unwrap();
+        assert!(model().inference(input).unwrap().spans[0].is_empty());
+    }
+
+    #[test]
+    fn test_only_loads_the_mock_model() {
+        let load = |name: &str| {
+            MockModel::new(Parameters::default(), RuntimeParameters::default(), &tokenizer_path_for(name), &onnx_path_for(name))
+        };
+        assert!(load(MOCK_MODEL).is_ok());
+        assert!(load("gliner_multi_pii-v1").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gliner::model::params::Parameters;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+// Describes the model a running instance is actually serving
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub name: String,
+    pub pipeline_mode: String,
+    pub tokenizer: FileInfo,
+    pub onnx: FileInfo,
+    pub max_sequence_length: Option<usize>,
+    pub max_span_width: usize,
+    pub deterministic: bool,
+    // Requested execution providers in order of preference
+    pub execution_providers: Vec<String>,
+    pub threads: Option<usize>,
+    pub sessions: usize,
+    // Unix timestamp (seconds) of when the model finished loading
+    pub loaded_at: u64,
+}
+
+impl ModelInfo {
+    pub fn collect(config: &Config, params: &Parameters) -> io::Result<Self> {
+        Ok(ModelInfo {
+            name: config.model_name.clone(),
+            pipeline_mode: "token".to_string(),
+            tokenizer: file_info(&config.tokenizer_path())?,
+            onnx: file_info(&config.onnx_path())?,
+            max_sequence_length: params.max_length,
+            max_span_width: params.max_width,
+            deterministic: config.deterministic,
+            execution_providers: crate::runtime::provider_kinds(config)
+                .map(|kinds| kinds.iter().map(|kind| kind.name().to_string()).collect())
+                .unwrap_or_default(),
+            threads: if config.deterministic { Some(1) } else { config.threads },
+            sessions: config.sessions,
+            loaded_at: unix_now(),
+        })
+    }
+}
+
+// Size and SHA-256 of a file, hashed in blocks so large ONNX files aren't read into memory
+pub fn file_info(path: &str) -> io::Result<FileInfo> {
+    let mut file = File::open(Path::new(path))?;
+    let size_bytes = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Ok(FileInfo {
+        path: path.to_string(),
+        size_bytes,
+        sha256,
+    })
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_info_hashes_contents() {
+        let path = std::env::temp_dir().join("gliner-rs-api-model-info-test.txt");
+        std::fs::write(&path, b"abc").expect("write temp file");
+
+        let info = file_info(path.to_str().unwrap()).expect("file info");
+        assert_eq!(info.size_bytes, 3);
+        assert_eq!(info.sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_file_info_missing_file() {
+        assert!(file_info("models/does-not-exist/model.onnx").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// Input normalization applied before detection, so that text obfuscated with
+// invisible characters, compatibility forms or look-alike letters reads the way
+// the model was trained on ("j\u{200b}ohn@example.com", "ｊｏｈｎ", "jоhn" with a
+// Cyrillic "о"). Every change is recorded as a run so offsets can be mapped
+// back to the original text.
+
+use std::str::FromStr;
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::Config;
+use crate::html::Run;
+
+// Unicode normalization form applied to the input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalizationForm {
+    None,
+    #[default]
+    Nfc,
+    // Also folds compatibility forms: full-width letters, ligatures, circled digits
+    Nfkc,
+}
+
+impl FromStr for NormalizationForm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(NormalizationForm::None),
+            "nfc" => Ok(NormalizationForm::Nfc),
+            "nfkc" => Ok(NormalizationForm::Nfkc),
+            other => Err(format!("unknown normalization form {:?}, expected none, nfc or nfkc", other)),
+        }
+    }
+}
+
+// Characters with no visible width, used to split PII so patterns and the model
+// miss it
+const ZERO_WIDTH: &[char] = &[
+    '\u{00ad}', // soft hyphen
+    '\u{180e}', // Mongolian vowel separator
+    '\u{200b}', // zero-width space
+    '\u{200c}', // zero-width non-joiner
+    '\u{200d}', // zero-width joiner
+    '\u{2060}', // word joiner
+    '\u{2061}', '\u{2062}', '\u{2063}', '\u{2064}', // invisible operators
+    '\u{feff}', // zero-width no-break space (byte order mark)
+];
+
+// Letters and punctuation commonly substituted for their Latin look-alikes.
+// Folding rewrites genuine Cyrillic and Greek text too, so it is opt-in.
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'), ('е', 'e'), ('о', 'o'), ('р', 'p'), ('с', 'c'), ('у', 'y'), ('х', 'x'), ('і', 'i'),
+    ('ј', 'j'), ('ѕ', 's'), ('ԁ', 'd'), ('ԛ', 'q'), ('ԝ', 'w'), ('һ', 'h'), ('ӏ', 'l'),
+    ('А', 'A'), ('В', 'B'), ('Е', 'E'), ('К', 'K'), ('М', 'M'), ('Н', 'H'), ('О', 'O'), ('Р', 'P'),
+    ('С', 'C'), ('Т', 'T'), ('Х', 'X'), ('І', 'I'), ('Ј', 'J'), ('Ѕ', 'S'),
+    // Greek
+    ('ο', 'o'), ('ν', 'v'), ('α', 'a'), ('ι', 'i'), ('κ', 'k'),
+    ('Α', 'A'), ('Β', 'B'), ('Ε', 'E'), ('Ζ', 'Z'), ('Η', 'H'), ('Ι', 'I'), ('Κ', 'K'), ('Μ', 'M'),
+    ('Ν', 'N'), ('Ο', 'O'), ('Ρ', 'P'), ('Τ', 'T'), ('Υ', 'Y'), ('Χ', 'X'),
+    // Punctuation
+    ('\u{2010}', '-'), ('\u{2011}', '-'), ('\u{2012}', '-'), ('\u{2013}', '-'), ('\u{2212}', '-'),
+    ('\u{2024}', '.'), ('\u{ff0e}', '.'), ('\u{2018}', '\''), ('\u{2019}', '\''),
+];
+
+// How request text is normalized before detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Normalization {
+    pub form: NormalizationForm,
+    pub strip_zero_width: bool,
+    pub fold_confusables: bool,
+}
+
+impl Normalization {
+    pub fn from_config(config: &Config) -> Self {
+        Normalization {
+            form: config.normalization_form,
+            strip_zero_width: config.strip_zero_width,
+            fold_confusables: config.fold_confusables,
+        }
+    }
+
+    // Normalized text and the runs mapping it to `text`, or None when
+    // normalization leaves the text unchanged
+    pub(crate) fn apply(&self, text: &str) -> Option<(String, Vec<Run>)> {
+        let mut output = String::with_capacity(text.len());
+        let mut runs: Vec<Run> = Vec::new();
+        let mut changed = false;
+
+        for (source, cluster) in clusters(text) {
+            let normalized = self.normalize_cluster(cluster);
+            if normalized == cluster {
+                // Extend the previous verbatim run when the two are contiguous
+                match runs.last_mut() {
+                    Some(run) if run.verbatim && run.source + run.source_len == source => run.source_len += cluster.len(),
+                    _ => runs.push(Run {
+                        text: output.len(),
+                        source,
+                        source_len: cluster.len(),
+                        verbatim: true,
+                    }),
+                }
+                output.push_str(cluster);
+                continue;
+            }
+            changed = true;
+            if !normalized.is_empty() {
+                runs.push(Run {
+                    text: output.len(),
+                    source,
+                    source_len: cluster.len(),
+                    verbatim: false,
+                });
+                output.push_str(&normalized);
+            }
+        }
+
+        changed.then_some((output, runs))
+    }
+
+    fn normalize_cluster(&self, cluster: &str) -> String {
+        let visible = cluster.chars().filter(|c| !(self.strip_zero_width && ZERO_WIDTH.contains(c)));
+        let normalized: String = match self.form {
+            NormalizationForm::None => visible.collect(),
+            NormalizationForm::Nfc => visible.nfc().collect(),
+            NormalizationForm::Nfkc => visible.nfkc().collect(),
+        };
+        if !self.fold_confusables {
+            return normalized;
+        }
+        normalized
+            .chars()
+            .map(|c| CONFUSABLES.iter().find(|(from, _)| *from == c).map(|(_, to)| *to).unwrap_or(c))
+            .collect()
+    }
+}
+
+// Split text into a starter character and the combining marks that follow it,
+// with their byte offsets. Normalization forms compose within these clusters,
+// so each one can be normalized on its own and mapped back as a unit.
+fn clusters(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut rest = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = rest.next()?;
+        while let Some(&(_, c)) = rest.peek() {
+            if canonical_combining_class(c) == 0 {
+                break;
+            }
+            rest.next();
+        }
+        let end = rest.peek().map(|&(index, _)| index).unwrap_or(text.len());
+        Some((start, &text[start..end]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::{InputFormat, VisibleText};
+
+    fn normalization(form: NormalizationForm, fold_confusables: bool) -> Normalization {
+        Normalization {
+            form,
+            strip_zero_width: true,
+            fold_confusables,
+        }
+    }
+
+    #[test]
+    fn test_strips_zero_width_characters() {
+        let input = "mail j\u{200b}ohn@exam\u{feff}ple.com now";
+        let visible = VisibleText::new(input, InputFormat::Text).normalized(&normalization(NormalizationForm::Nfc, false));
+        assert_eq!(visible.text, "mail john@example.com now");
+
+        let start = visible.text.find("john").unwrap();
+        let end = start + "john@example.com".len();
+        assert_eq!(&input[visible.source_start(start)..visible.source_end(end)], "j\u{200b}ohn@exam\u{feff}ple.com");
+    }
+
+    #[test]
+    fn test_compatibility_forms_and_composition() {
+        let input = "Ｊｏｈｎ Zoe\u{0308}";
+        let visible = VisibleText::new(input, InputFormat::Text).normalized(&normalization(NormalizationForm::Nfkc, false));
+        assert_eq!(visible.text, "John Zoë");
+        assert_eq!(&input[visible.source_start(0)..visible.source_end(4)], "Ｊｏｈｎ");
+        assert_eq!(&input[visible.source_start(5)..visible.source_end(visible.text.len())], "Zoe\u{0308}");
+
+        // NFC leaves full-width letters alone
+        let visible = VisibleText::new(input, InputFormat::Text).normalized(&normalization(NormalizationForm::Nfc, false));
+        assert_eq!(visible.text, "Ｊｏｈｎ Zoë");
+    }
+
+    #[test]
+    fn test_confusable_folding_is_opt_in() {
+        let input = "j\u{043e}hn@example.com";
+        let plain = VisibleText::new(input, InputFormat::Text).normalized(&normalization(NormalizationForm::Nfc, false));
+        assert_eq!(plain.text, input);
+
+        let folded = VisibleText::new(input, InputFormat::Text).normalized(&normalization(NormalizationForm::Nfc, true));
+        assert_eq!(folded.text, "john@example.com");
+        assert_eq!(folded.source_end(folded.text.len()), input.len());
+    }
+
+    #[test]
+    fn test_normalizes_html_visible_text() {
+        let html = "<p>j&#8203;ohn@example.com</p>";
+        let visible = VisibleText::new(html, InputFormat::Html).normalized(&normalization(NormalizationForm::Nfc, false));
+        assert_eq!(visible.text, "john@example.com");
+        assert_eq!(&html[visible.source_start(0)..visible.source_end(visible.text.len())], "j&#8203;ohn@example.com");
+    }
+
+    #[test]
+    fn test_unchanged_text_needs_no_mapping() {
+        assert_eq!(normalization(NormalizationForm::Nfkc, true).apply("John Doe"), None);
+    }
+}
@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::extract::{self, DocumentFormat, ExtractedText, SectionUnit};
+
+// Pixel rectangle of recognised text, in the coordinates of the scanned image
+// (for PDFs, the page rendered at the configured DPI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    fn union(self, other: BoundingBox) -> BoundingBox {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        let right = (self.left + self.width).max(other.left + other.width);
+        let bottom = (self.top + self.height).max(other.top + other.height);
+        BoundingBox {
+            left,
+            top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    start: usize,
+    end: usize,
+    bbox: BoundingBox,
+}
+
+// Text recognised on one image or page, with where each word was found
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OcrPage {
+    pub text: String,
+    words: Vec<Word>,
+}
+
+impl OcrPage {
+    // Box around the words a span of `text` covers
+    pub fn bounding_box(&self, start: usize, end: usize) -> Option<BoundingBox> {
+        self.words
+            .iter()
+            .filter(|word| word.start < end && word.end > start)
+            .map(|word| word.bbox)
+            .reduce(BoundingBox::union)
+    }
+}
+
+// How to reach the OCR tools
+#[derive(Debug, Clone)]
+pub struct OcrSettings {
+    pub tesseract: String,
+    pub pdftoppm: String,
+    pub languages: String,
+    pub dpi: u32,
+}
+
+impl OcrSettings {
+    pub fn from_config(config: &Config) -> Self {
+        OcrSettings {
+            tesseract: config.tesseract_path.clone(),
+            pdftoppm: config.pdftoppm_path.clone(),
+            languages: config.ocr_languages.clone(),
+            dpi: config.ocr_dpi,
+        }
+    }
+}
+
+// Text of an uploaded file, with the OCR layout of each section that was read
+// from an image (None for sections taken from a text layer)
+pub struct OcrExtraction {
+    pub text: ExtractedText,
+    pub layouts: Vec<Option<OcrPage>>,
+}
+
+// Extract the text of an upload, reading images, and the pages of PDFs that have
+// no text layer, with OCR. Other formats are extracted as usual.
+pub fn extract_with_ocr(format: DocumentFormat, bytes: Vec<u8>, settings: &OcrSettings) -> Result<OcrExtraction, ApiError> {
+    match format {
+        DocumentFormat::Image => {
+            let page = recognize_image(&bytes, settings)?;
+            into_extraction(vec![String::new()], vec![Some(page)])
+        }
+        DocumentFormat::Pdf => {
+            let text = extract::extract_sections(format, bytes.clone())?;
+            if text.sections.iter().all(|section| !section.trim().is_empty()) {
+                let layouts = vec![None; text.sections.len()];
+                return Ok(OcrExtraction { text, layouts });
+            }
+            // OCR every page once, but only use it where the text layer is blank
+            let pages = recognize_pdf(&bytes, settings)?;
+            let mut sections = text.sections;
+            sections.resize(sections.len().max(pages.len()), String::new());
+            let layouts = pages.into_iter().map(Some).chain(std::iter::repeat(None));
+            let layouts = sections
+                .iter()
+                .zip(layouts)
+                .map(|(section, page)| if section.trim().is_empty() { page } else { None })
+                .collect();
+            into_extraction(sections, layouts)
+        }
+        _ => {
+            let text = extract::extract_text(format, bytes)?;
+            let layouts = vec![None; text.sections.len()];
+            Ok(OcrExtraction { text, layouts })
+        }
+    }
+}
+
+fn into_extraction(mut sections: Vec<String>, layouts: Vec<Option<OcrPage>>) -> Result<OcrExtraction, ApiError> {
+    for (section, layout) in sections.iter_mut().zip(&layouts) {
+        if let Some(page) = layout {
+            section.clone_from(&page.text);
+        }
+    }
+    if sections.iter().all(|section| section.trim().is_empty()) {
+        return Err(ApiError::InvalidInput("no text was recognised in the file".to_string()));
+    }
+    Ok(OcrExtraction {
+        text: ExtractedText {
+            unit: SectionUnit::Page,
+            sections,
+            parts: Vec::new(),
+            encoding: None,
+        },
+        layouts,
+    })
+}
+
+// Build the page text from tesseract's TSV output: words of a line are joined
+// by spaces, lines by newlines, and paragraphs and blocks by a blank line
+pub fn parse_tsv(tsv: &str) -> OcrPage {
+    let mut page = OcrPage::default();
+    let mut previous: Option<(&str, &str, &str)> = None;
+
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.split('\t').collect();
+        // level, page, block, paragraph, line, word, left, top, width, height, confidence, text
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let word = columns[11].trim();
+        let bbox = match (columns[6].parse(), columns[7].parse(), columns[8].parse(), columns[9].parse()) {
+            (Ok(left), Ok(top), Ok(width), Ok(height)) => BoundingBox { left, top, width, height },
+            _ => continue,
+        };
+        if word.is_empty() {
+            continue;
+        }
+
+        let line = (columns[2], columns[3], columns[4]);
+        if let Some(last) = previous {
+            let separator = if (last.0, last.1) != (line.0, line.1) {
+                "\n\n"
+            } else if last.2 != line.2 {
+                "\n"
+            } else {
+                " "
+            };
+            page.text.push_str(separator);
+        }
+        previous = Some(line);
+
+        let start = page.text.len();
+        page.text.push_str(word);
+        page.words.push(Word {
+            start,
+            end: page.text.len(),
+            bbox,
+        });
+    }
+    page
+}
+
+#[cfg(feature = "ocr")]
+fn recognize_image(bytes: &[u8], settings: &OcrSettings) -> Result<OcrPage, ApiError> {
+    let tsv = run(&settings.tesseract, &["stdin", "stdout", "-l", &settings.languages, "tsv"], Some(bytes))?;
+    Ok(parse_tsv(&String::from_utf8_lossy(&tsv)))
+}
+
+// Render every page with pdftoppm, then OCR the images in page order
+#[cfg(feature = "ocr")]
+fn recognize_pdf(bytes: &[u8], settings: &OcrSettings) -> Result<Vec<OcrPage>, ApiError> {
+    let workspace = Workspace::create()?;
+    let input = workspace.0.join("input.pdf");
+    std::fs::write(&input, bytes).map_err(|e| ApiError::InferenceFailed(format!("failed to stage PDF for OCR: {}", e)))?;
+
+    let prefix = workspace.0.join("page");
+    let dpi = settings.dpi.to_string();
+    run(
+        &settings.pdftoppm,
+        &["-r", &dpi, "-png", &input.to_string_lossy(), &prefix.to_string_lossy()],
+        None,
+    )?;
+
+    // pdftoppm zero-pads page numbers to the same width, so names sort in page order
+    let mut images: Vec<std::path::PathBuf> = std::fs::read_dir(&workspace.0)
+        .map_err(|e| ApiError::InferenceFailed(format!("failed to list rendered pages: {}", e)))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+        .collect();
+    images.sort();
+
+    images
+        .iter()
+        .map(|image| {
+            let tsv = run(&settings.tesseract, &[&image.to_string_lossy(), "stdout", "-l", &settings.languages, "tsv"], None)?;
+            Ok(parse_tsv(&String::from_utf8_lossy(&tsv)))
+        })
+        .collect()
+}
+
+// Run an OCR tool to completion, feeding it `input` on stdin
+#[cfg(feature = "ocr")]
+fn run(program: &str, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>, ApiError> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ApiError::InferenceFailed(format!("failed to start {}: {}", program, e)))?;
+
+    // Write stdin from another thread so a full stdout pipe can't deadlock us
+    let output = std::thread::scope(|scope| {
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            scope.spawn(move || stdin.write_all(input));
+        }
+        child.wait_with_output()
+    })
+    .map_err(|e| ApiError::InferenceFailed(format!("{} failed: {}", program, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ApiError::InvalidInput(format!("{} could not read the file: {}", program, stderr.trim())));
+    }
+    Ok(output.stdout)
+}
+
+// Scratch directory for rendered pages, removed when dropped
+#[cfg(feature = "ocr")]
+struct Workspace(std::path::PathBuf);
+
+#[cfg(feature = "ocr")]
+impl Workspace {
+    fn create() -> Result<Self, ApiError> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!("gliner-ocr-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).map_err(|e| ApiError::InferenceFailed(format!("failed to create OCR workspace: {}", e)))?;
+        Ok(Workspace(path))
+    }
+}
+
+#[cfg(feature = "ocr")]
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(not(feature = "ocr"))]
+fn recognize_image(_bytes: &[u8], _settings: &OcrSettings) -> Result<OcrPage, ApiError> {
+    Err(ApiError::InvalidInput(OCR_DISABLED.to_string()))
+}
+
+#[cfg(not(feature = "ocr"))]
+fn recognize_pdf(_bytes: &[u8], _settings: &OcrSettings) -> Result<Vec<OcrPage>, ApiError> {
+    Err(ApiError::InvalidInput("PDF has no text layer (scanned documents need OCR, which this build does not include)".to_string()))
+}
+
+#[cfg(not(feature = "ocr"))]
+const OCR_DISABLED: &str = "reading images needs OCR, which this build does not include";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t
+5\t1\t1\t1\t1\t1\t10\t20\t60\t15\t96.1\tName:
+5\t1\t1\t1\t1\t2\t80\t20\t40\t15\t95.0\tJane
+5\t1\t1\t1\t1\t3\t125\t22\t45\t15\t94.2\tRoe
+5\t1\t1\t1\t2\t1\t10\t40\t90\t15\t91.7\t078-05-1120
+5\t1\t2\t1\t1\t1\t10\t90\t50\t15\t90.0\tSigned
+";
+
+    #[test]
+    fn test_parse_tsv_builds_lines_and_blocks() {
+        let page = parse_tsv(TSV);
+        assert_eq!(page.text, "Name: Jane Roe\n078-05-1120\n\nSigned");
+    }
+
+    #[test]
+    fn test_bounding_box_covers_the_span() {
+        let page = parse_tsv(TSV);
+        let start = page.text.find("Jane").unwrap();
+        let end = start + "Jane Roe".len();
+        assert_eq!(
+            page.bounding_box(start, end),
+            Some(BoundingBox { left: 80, top: 20, width: 90, height: 17 })
+        );
+        assert_eq!(page.bounding_box(page.text.len(), page.text.len() + 1), None);
+    }
+}
@@ -0,0 +1,124 @@
+// Entity offsets in the units different clients index strings by. The API
+// reports UTF-8 byte offsets, which Rust and Go slice with directly; JavaScript,
+// Java and C# strings are indexed by UTF-16 code units, and Python by Unicode
+// scalar values (chars). Outside ASCII the three differ, e.g. "😀" is 4 bytes,
+// 2 UTF-16 units and 1 char.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+// One entity's offsets in every unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Offsets {
+    pub utf8: Span,
+    pub utf16: Span,
+    pub chars: Span,
+}
+
+// Char and UTF-16 positions of every char boundary of a text, so each entity
+// converts with a binary search instead of a scan from the start
+pub struct OffsetIndex {
+    // Byte offset of every char, then the text length
+    bytes: Vec<usize>,
+    // UTF-16 offset of every char, then the text's UTF-16 length
+    utf16: Vec<usize>,
+}
+
+impl OffsetIndex {
+    pub fn new(text: &str) -> Self {
+        let mut bytes = Vec::with_capacity(text.len() + 1);
+        let mut utf16 = Vec::with_capacity(text.len() + 1);
+        let mut units = 0;
+        for (index, c) in text.char_indices() {
+            bytes.push(index);
+            utf16.push(units);
+            units += c.len_utf16();
+        }
+        bytes.push(text.len());
+        utf16.push(units);
+        OffsetIndex { bytes, utf16 }
+    }
+
+    // Char index of a byte offset; offsets inside a character count as the
+    // character they fall in
+    fn char_index(&self, byte: usize) -> usize {
+        match self.bytes.binary_search(&byte) {
+            Ok(index) => index,
+            Err(index) => index.saturating_sub(1),
+        }
+    }
+
+    pub fn offsets(&self, start: usize, end: usize) -> Offsets {
+        let (char_start, char_end) = (self.char_index(start), self.char_index(end));
+        Offsets {
+            utf8: Span { start, end },
+            utf16: Span {
+                start: self.utf16[char_start],
+                end: self.utf16[char_end],
+            },
+            chars: Span {
+                start: char_start,
+                end: char_end,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Offsets agree with how each unit slices the text
+    fn check(text: &str, needle: &str) {
+        let start = text.find(needle).expect("needle in text");
+        let offsets = OffsetIndex::new(text).offsets(start, start + needle.len());
+
+        assert_eq!(&text[offsets.utf8.start..offsets.utf8.end], needle);
+        let units: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&units[offsets.utf16.start..offsets.utf16.end]).unwrap(), needle);
+        let chars: Vec<char> = text.chars().collect();
+        assert_eq!(chars[offsets.chars.start..offsets.chars.end].iter().collect::<String>(), needle);
+    }
+
+    #[test]
+    fn test_ascii_offsets_agree() {
+        let offsets = OffsetIndex::new("Call Jane Roe").offsets(5, 13);
+        assert_eq!(offsets.utf8, offsets.utf16);
+        assert_eq!(offsets.utf8, offsets.chars);
+    }
+
+    #[test]
+    fn test_emoji_and_astral_characters() {
+        let text = "😀👍🏽 mail jane@example.com 𝔘𝔫𝔦 Jane";
+        check(text, "jane@example.com");
+        check(text, "Jane");
+
+        let start = text.find("jane").unwrap();
+        let offsets = OffsetIndex::new(text).offsets(start, start + 4);
+        assert_eq!(offsets.utf8.start, 18);
+        assert_eq!(offsets.utf16.start, 12);
+        assert_eq!(offsets.chars.start, 9);
+    }
+
+    #[test]
+    fn test_combining_characters_and_zwj_sequences() {
+        let text = "Ze\u{0301}lie 👩\u{200d}👩\u{200d}👧 Zoe\u{0308} Ba\u{0300}\u{0323}r";
+        check(text, "Ze\u{0301}lie");
+        check(text, "Zoe\u{0308}");
+        check(text, "Ba\u{0300}\u{0323}r");
+    }
+
+    #[test]
+    fn test_end_of_text() {
+        let text = "née 漢字";
+        let offsets = OffsetIndex::new(text).offsets(0, text.len());
+        assert_eq!(offsets.chars, Span { start: 0, end: 6 });
+        assert_eq!(offsets.utf16, Span { start: 0, end: 6 });
+        check(text, "漢字");
+    }
+}
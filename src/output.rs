@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+// Shape of a scan's results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    // SARIF 2.1.0 log, for code scanning dashboards
+    Sarif,
+    // Text with findings highlighted, for human review
+    Html,
+    // Token-per-line IOB2 tags, for NER training and evaluation tooling
+    Conll,
+    // Microsoft Presidio analyzer results, for clients migrating from Presidio
+    Presidio,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<Self, ApiError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "html" => Ok(OutputFormat::Html),
+            "conll" => Ok(OutputFormat::Conll),
+            "presidio" => Ok(OutputFormat::Presidio),
+            other => Err(ApiError::InvalidInput(format!(
+                "unknown output format {:?}, expected json, sarif, html, conll or presidio",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_format_parse() {
+        assert_eq!(OutputFormat::parse("SARIF").unwrap(), OutputFormat::Sarif);
+        assert_eq!(OutputFormat::parse(" html ").unwrap(), OutputFormat::Html);
+        assert_eq!(OutputFormat::parse("conll").unwrap(), OutputFormat::Conll);
+        assert!(OutputFormat::parse("xml").is_err());
+    }
+}
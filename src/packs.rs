@@ -0,0 +1,163 @@
+// Country identifier packs: recognizers for national identifiers whose format
+// and check digits are public, so a match that validates is near certain. A
+// pack runs when it is enabled (GLINER_RECOGNIZER_PACKS, the request's
+// `recognizer_packs` or a profile's `packs`), and each of its identifiers also
+// runs whenever its label is requested.
+
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::checksum;
+use crate::error::ApiError;
+use crate::PiiEntity;
+
+pub const PACKS: &[&str] = &["us", "uk", "de", "in"];
+
+pub struct Identifier {
+    pub pack: &'static str,
+    pub label: &'static str,
+    pattern: &'static str,
+    valid: fn(&str) -> bool,
+}
+
+pub const IDENTIFIERS: &[Identifier] = &[
+    Identifier {
+        pack: "us",
+        label: "social security number",
+        pattern: r"\b\d{3}-\d{2}-\d{4}\b",
+        valid: checksum::ssn_valid,
+    },
+    Identifier {
+        pack: "us",
+        label: "employer identification number",
+        pattern: r"\b\d{2}-\d{7}\b",
+        valid: checksum::ein_valid,
+    },
+    Identifier {
+        pack: "uk",
+        label: "national insurance number",
+        pattern: r"(?i)\b[a-z]{2} ?\d{2} ?\d{2} ?\d{2} ?[a-d]\b",
+        valid: checksum::nino_valid,
+    },
+    Identifier {
+        pack: "uk",
+        label: "nhs number",
+        pattern: r"\b\d{3}[ -]?\d{3}[ -]?\d{4}\b",
+        valid: checksum::nhs_valid,
+    },
+    Identifier {
+        pack: "de",
+        label: "german tax id",
+        pattern: r"\b\d{2} ?\d{3} ?\d{3} ?\d{3}\b",
+        valid: checksum::steuer_id_valid,
+    },
+    Identifier {
+        pack: "in",
+        label: "aadhaar number",
+        pattern: r"\b\d{4}[ -]?\d{4}[ -]?\d{4}\b",
+        valid: checksum::aadhaar_valid,
+    },
+    Identifier {
+        pack: "in",
+        label: "pan number",
+        pattern: r"\b[A-Z]{5}\d{4}[A-Z]\b",
+        valid: checksum::pan_valid,
+    },
+];
+
+fn patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        IDENTIFIERS
+            .iter()
+            .map(|identifier| Regex::new(identifier.pattern).expect("valid identifier pattern"))
+            .collect()
+    })
+}
+
+pub fn validate(packs: &[String]) -> Result<(), ApiError> {
+    match packs.iter().find(|pack| !PACKS.contains(&pack.as_str())) {
+        Some(pack) => Err(ApiError::InvalidInput(format!(
+            "Unknown recognizer pack '{}', expected one of: {}",
+            pack,
+            PACKS.join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+// Labels of the identifiers in the given packs
+pub fn labels(packs: &[String]) -> Vec<String> {
+    IDENTIFIERS
+        .iter()
+        .filter(|identifier| packs.iter().any(|pack| pack == identifier.pack))
+        .map(|identifier| identifier.label.to_string())
+        .collect()
+}
+
+// Identifiers of the enabled packs, or with a requested label, that pass their
+// check. A span matched by an earlier identifier is not matched again.
+pub fn find(text: &str, packs: &[String], labels: &[&str]) -> Vec<PiiEntity> {
+    let mut found: Vec<PiiEntity> = Vec::new();
+    for (identifier, pattern) in IDENTIFIERS.iter().zip(patterns()) {
+        if !packs.iter().any(|pack| pack == identifier.pack) && !labels.contains(&identifier.label) {
+            continue;
+        }
+        for candidate in pattern.find_iter(text) {
+            let (start, end) = (candidate.start(), candidate.end());
+            if found.iter().any(|entity| entity.start < end && start < entity.end) || !(identifier.valid)(candidate.as_str()) {
+                continue;
+            }
+            found.push(PiiEntity {
+                text: candidate.as_str().to_string(),
+                label: identifier.label.to_string(),
+                confidence: 0.95,
+                start,
+                end,
+                attributes: BTreeMap::from([
+                    ("checksum".to_string(), "valid".to_string()),
+                    ("pack".to_string(), identifier.pack.to_string()),
+                ]),
+            });
+        }
+    }
+    found.sort_by_key(|entity| entity.start);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_enabled_packs_find_valid_identifiers() {
+        let text = "NINO AB 12 34 56 C, NHS 943 476 5919 (not 943 476 5918), Steuer-ID 86095742719";
+        let found = find(text, &packs(&["uk", "de"]), &[]);
+        let labels: Vec<&str> = found.iter().map(|entity| entity.label.as_str()).collect();
+        assert_eq!(labels, vec!["national insurance number", "nhs number", "german tax id"]);
+        assert_eq!(found[1].text, "943 476 5919");
+        assert_eq!(found[2].attributes["pack"], "de");
+
+        assert!(find(text, &packs(&["us"]), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_requested_label_runs_without_its_pack() {
+        let text = "PAN ABCPE1234F, EIN 12-3456789";
+        let found = find(text, &[], &["pan number"]);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].start, found[0].end), (4, 14));
+    }
+
+    #[test]
+    fn test_unknown_pack_is_rejected() {
+        assert!(validate(&packs(&["us", "in"])).is_ok());
+        assert!(matches!(validate(&packs(&["fr"])), Err(ApiError::InvalidInput(_))));
+        assert_eq!(labels(&packs(&["in"])), vec!["aadhaar number", "pan number"]);
+    }
+}
@@ -0,0 +1,289 @@
+// PostgreSQL persistence of detection results and audit events, built with the
+// `persistence` feature and enabled by GLINER_DATABASE_URL. Results are kept per
+// request id so they can be fetched after the instance restarts or is
+// redeployed. Stored findings are as sensitive as the scanned text: in privacy
+// mode their text is not stored.
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Request, Response};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
+use sqlx::types::Json;
+use sqlx::Row;
+use std::io;
+use std::sync::Arc;
+
+use crate::audit::{self, AuditEvent, AuditFindings, AuditLog, AuditSink, AuditTrail};
+use crate::config::Config;
+use crate::logging::RequestId;
+use crate::model_info::unix_now;
+use crate::{privacy, PiiEntity};
+
+// Tables are created on startup when missing; columns are only ever added
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS scan_results (
+        id BIGSERIAL PRIMARY KEY,
+        request_id TEXT NOT NULL,
+        created_at BIGINT NOT NULL,
+        route TEXT NOT NULL,
+        caller TEXT,
+        findings JSONB NOT NULL,
+        documents JSONB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS scan_results_request_id ON scan_results (request_id)",
+    "CREATE INDEX IF NOT EXISTS scan_results_created_at ON scan_results (created_at)",
+    "CREATE TABLE IF NOT EXISTS audit_events (
+        id BIGSERIAL PRIMARY KEY,
+        timestamp BIGINT NOT NULL,
+        request_id TEXT NOT NULL,
+        event JSONB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS audit_events_timestamp ON audit_events (timestamp)",
+];
+
+// A stored result without its findings, as listed in the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultSummary {
+    pub request_id: String,
+    // Unix seconds
+    pub created_at: u64,
+    pub route: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<String>,
+    pub findings: AuditFindings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredResult {
+    #[serde(flatten)]
+    pub summary: ResultSummary,
+    // Findings per document, in the order the documents were submitted
+    pub documents: Vec<Vec<PiiEntity>>,
+}
+
+#[derive(Clone)]
+pub struct ResultStore {
+    pool: PgPool,
+}
+
+pub type ResultStoreState = Arc<ResultStore>;
+
+impl ResultStore {
+    // Connect and create the tables if they are missing
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new().max_connections(5).connect(url).await?;
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+        Ok(ResultStore { pool })
+    }
+
+    pub async fn save(&self, result: &StoredResult) -> Result<(), sqlx::Error> {
+        let summary = &result.summary;
+        sqlx::query(
+            "INSERT INTO scan_results (request_id, created_at, route, caller, findings, documents)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(&summary.request_id)
+        .bind(summary.created_at as i64)
+        .bind(&summary.route)
+        .bind(&summary.caller)
+        .bind(Json(&summary.findings))
+        .bind(Json(&result.documents))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // The newest result stored under a request id
+    pub async fn get(&self, request_id: &str) -> Result<Option<StoredResult>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT request_id, created_at, route, caller, findings, documents FROM scan_results
+             WHERE request_id = $1 ORDER BY id DESC LIMIT 1",
+        )
+        .bind(request_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| {
+            let Json(documents) = row.try_get("documents")?;
+            Ok(StoredResult { summary: summary(&row)?, documents })
+        })
+        .transpose()
+    }
+
+    // Newest first, optionally only those created before `before` (Unix seconds)
+    pub async fn list(&self, limit: usize, before: Option<u64>) -> Result<Vec<ResultSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT request_id, created_at, route, caller, findings FROM scan_results
+             WHERE $1::BIGINT IS NULL OR created_at < $1
+             ORDER BY created_at DESC, id DESC LIMIT $2",
+        )
+        .bind(before.map(|before| before as i64))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(summary).collect()
+    }
+
+    // Delete results stored before `cutoff` (Unix seconds)
+    pub async fn purge_results(&self, cutoff: u64) -> Result<u64, sqlx::Error> {
+        let done = sqlx::query("DELETE FROM scan_results WHERE created_at < $1")
+            .bind(cutoff as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(done.rows_affected())
+    }
+
+    // Delete audit events recorded before `cutoff` (Unix seconds)
+    pub async fn purge_audit(&self, cutoff: u64) -> Result<u64, sqlx::Error> {
+        let done = sqlx::query("DELETE FROM audit_events WHERE timestamp < $1")
+            .bind(cutoff as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(done.rows_affected())
+    }
+
+    pub async fn record_audit(&self, event: &AuditEvent) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO audit_events (timestamp, request_id, event) VALUES ($1, $2, $3)")
+            .bind(event.timestamp as i64)
+            .bind(&event.request_id)
+            .bind(Json(event))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+fn summary(row: &PgRow) -> Result<ResultSummary, sqlx::Error> {
+    let Json(findings) = row.try_get("findings")?;
+    Ok(ResultSummary {
+        request_id: row.try_get("request_id")?,
+        created_at: row.try_get::<i64, _>("created_at")? as u64,
+        route: row.try_get("route")?,
+        caller: row.try_get("caller")?,
+        findings,
+    })
+}
+
+// Audit sink writing to the `audit_events` table. Inserts run in the
+// background so a slow database does not hold up responses.
+pub struct DatabaseSink {
+    store: ResultStore,
+}
+
+impl AuditSink for DatabaseSink {
+    fn write(&self, event: &AuditEvent) -> io::Result<()> {
+        let handle = tokio::runtime::Handle::try_current().map_err(io::Error::other)?;
+        let store = self.store.clone();
+        let event = event.clone();
+        handle.spawn(async move {
+            if let Err(e) = store.record_audit(&event).await {
+                tracing::error!(request_id = %event.request_id, error = %e, "Failed to store audit event");
+            }
+        });
+        Ok(())
+    }
+}
+
+// Audit log for GLINER_AUDIT_SINK, which may also be "database" when a store is connected
+pub fn audit_log(config: &Config, store: Option<&ResultStore>) -> Result<Option<AuditLog>, String> {
+    match (config.audit_sink.as_str(), store) {
+        ("database", Some(store)) => {
+            let sink = DatabaseSink { store: store.clone() };
+            Ok(Some(AuditLog::new(vec![Box::new(sink)], config.audit_recent)))
+        }
+        ("database", None) => Err("GLINER_AUDIT_SINK=database needs GLINER_DATABASE_URL".to_string()),
+        _ => AuditLog::from_config(config),
+    }
+}
+
+// Stores the findings of every successful audited call under its request id
+pub struct ResultRecorder;
+
+#[rocket::async_trait]
+impl Fairing for ResultRecorder {
+    fn info(&self) -> Info {
+        Info {
+            name: "Result recorder",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(store) = request.rocket().state::<Option<ResultStoreState>>().and_then(Option::as_ref) else {
+            return;
+        };
+        let trail = request.local_cache(AuditTrail::default);
+        if !trail.requested() || !response.status().class().is_success() {
+            return;
+        }
+        let (Some(findings), Some(mut documents)) = (trail.summary(), trail.take_entities()) else {
+            return;
+        };
+        let private = request.rocket().state::<Config>().is_some_and(|config| config.privacy_mode);
+        if private || trail.private() {
+            documents.iter_mut().for_each(|entities| privacy::clear_text(entities));
+        }
+
+        let result = StoredResult {
+            summary: ResultSummary {
+                request_id: RequestId::of(request).0,
+                created_at: unix_now(),
+                route: request.route().map(|route| route.uri.to_string()).unwrap_or_default(),
+                caller: audit::caller_key(request),
+                findings,
+            },
+            documents,
+        };
+        let store = store.clone();
+        tokio::spawn(async move {
+            if let Err(e) = store.save(&result).await {
+                tracing::error!(request_id = %result.summary.request_id, error = %e, "Failed to store detection result");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_stored_result_shape() {
+        let entity = PiiEntity {
+            text: "jane@example.org".to_string(),
+            label: "email".to_string(),
+            confidence: 0.9,
+            start: 0,
+            end: 16,
+            attributes: BTreeMap::new(),
+        };
+        let documents = vec![vec![entity]];
+        let result = StoredResult {
+            summary: ResultSummary {
+                request_id: "checkout-42".to_string(),
+                created_at: 1_792_141_923,
+                route: "/v1/pii/detect?<fields>".to_string(),
+                caller: None,
+                findings: AuditFindings::of(&documents),
+            },
+            documents,
+        };
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["request_id"], "checkout-42");
+        assert_eq!(value["findings"]["total_entities"], 1);
+        assert_eq!(value["documents"][0][0]["label"], "email");
+        let parsed: StoredResult = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.summary.findings, result.summary.findings);
+    }
+
+    #[test]
+    fn test_database_audit_sink_needs_a_store() {
+        let config = Config {
+            audit_sink: "database".to_string(),
+            ..Config::default()
+        };
+        assert!(audit_log(&config, None).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+// Detection as a pipeline of detectors whose findings are merged. The GLiNER
+// model is one detector; the rule-based ones (dates of birth, IP addresses,
+// country packs, the gazetteer) run next to it, and checksum validation
+// re-scores model findings before the merge. Which detectors run and how their
+// findings are merged is configurable per deployment and per request.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::detection::DetectionError;
+use crate::PiiEntity;
+
+pub const MODEL: &str = "model";
+
+// Names of the detectors that can be selected, in the order they run
+pub const DETECTORS: &[&str] = &[MODEL, "date_of_birth", "ip_address", "packs", "gazetteer"];
+
+pub trait Detector {
+    fn name(&self) -> &'static str;
+    // Findings in `text` for the requested labels
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError>;
+}
+
+impl<D: Detector + ?Sized> Detector for &D {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError> {
+        (**self).detect(text, labels)
+    }
+}
+
+// How findings of different detectors that overlap are reconciled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    // Rule-based findings replace the model findings they overlap
+    #[default]
+    RulePriority,
+    // Model findings replace the rule-based findings they overlap
+    ModelPriority,
+    // Every finding is kept; the same span and label is reported once
+    Union,
+    // Findings are kept when enough detectors report an overlapping span of
+    // the same label
+    Vote,
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "rule-priority" => Ok(MergeStrategy::RulePriority),
+            "model-priority" => Ok(MergeStrategy::ModelPriority),
+            "union" => Ok(MergeStrategy::Union),
+            "vote" => Ok(MergeStrategy::Vote),
+            other => Err(format!(
+                "unknown merge strategy {:?}, expected rule-priority, model-priority, union or vote",
+                other
+            )),
+        }
+    }
+}
+
+// What one detector found
+pub struct Findings {
+    pub detector: &'static str,
+    pub entities: Vec<PiiEntity>,
+}
+
+fn overlaps(a: &PiiEntity, b: &PiiEntity) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+// Merge the findings of several detectors into one list in text order. Under
+// `Vote`, kept findings get a `votes` attribute with how many detectors agreed.
+pub fn merge(strategy: MergeStrategy, min_votes: usize, sources: Vec<Findings>) -> Vec<PiiEntity> {
+    let mut merged = match strategy {
+        MergeStrategy::RulePriority | MergeStrategy::ModelPriority => {
+            let (model, rules): (Vec<Findings>, Vec<Findings>) = sources.into_iter().partition(|source| source.detector == MODEL);
+            let model: Vec<PiiEntity> = model.into_iter().flat_map(|source| source.entities).collect();
+            let rules: Vec<PiiEntity> = rules.into_iter().flat_map(|source| source.entities).collect();
+            let (mut kept, winners) = match strategy {
+                MergeStrategy::RulePriority => (model, rules),
+                _ => (rules, model),
+            };
+            kept.retain(|entity| !winners.iter().any(|winner| overlaps(entity, winner)));
+            kept.extend(winners);
+            kept
+        }
+        MergeStrategy::Union => {
+            let mut all: Vec<PiiEntity> = sources.into_iter().flat_map(|source| source.entities).collect();
+            crate::detection::dedupe_entities(&mut all);
+            all
+        }
+        MergeStrategy::Vote => vote(min_votes, sources),
+    };
+    merged.sort_by_key(|entity| (entity.start, entity.end));
+    merged
+}
+
+// Keep the best finding of each group of overlapping same-label findings that
+// at least `min_votes` detectors took part in
+fn vote(min_votes: usize, sources: Vec<Findings>) -> Vec<PiiEntity> {
+    let tagged: Vec<(&str, PiiEntity)> = sources
+        .into_iter()
+        .flat_map(|source| source.entities.into_iter().map(move |entity| (source.detector, entity)))
+        .collect();
+    let mut kept = Vec::new();
+    for (index, (detector, entity)) in tagged.iter().enumerate() {
+        let mut voters = BTreeSet::from([*detector]);
+        let mut outranked = false;
+        for (other, (rival_detector, rival)) in tagged.iter().enumerate() {
+            if other == index || rival.label != entity.label || !overlaps(rival, entity) {
+                continue;
+            }
+            voters.insert(*rival_detector);
+            outranked |= rival.confidence > entity.confidence || (rival.confidence == entity.confidence && other < index);
+        }
+        if voters.len() < min_votes || outranked {
+            continue;
+        }
+        let mut entity = entity.clone();
+        entity.attributes.insert("votes".to_string(), voters.len().to_string());
+        kept.push(entity);
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    fn sources() -> Vec<Findings> {
+        vec![
+            Findings {
+                detector: MODEL,
+                entities: vec![entity("person", 0, 8, 0.9), entity("date of birth", 14, 22, 0.6)],
+            },
+            Findings {
+                detector: "date_of_birth",
+                entities: vec![entity("date of birth", 14, 24, 0.95)],
+            },
+            Findings {
+                detector: "ip_address",
+                entities: vec![entity("ip address", 30, 41, 0.99)],
+            },
+        ]
+    }
+
+    fn spans(entities: &[PiiEntity]) -> Vec<(usize, usize)> {
+        entities.iter().map(|entity| (entity.start, entity.end)).collect()
+    }
+
+    #[test]
+    fn test_priority_strategies() {
+        assert_eq!(spans(&merge(MergeStrategy::RulePriority, 2, sources())), vec![(0, 8), (14, 24), (30, 41)]);
+        assert_eq!(spans(&merge(MergeStrategy::ModelPriority, 2, sources())), vec![(0, 8), (14, 22), (30, 41)]);
+        assert_eq!(spans(&merge(MergeStrategy::Union, 2, sources())), vec![(0, 8), (14, 22), (14, 24), (30, 41)]);
+    }
+
+    #[test]
+    fn test_vote_keeps_agreed_findings() {
+        let merged = merge(MergeStrategy::Vote, 2, sources());
+        assert_eq!(spans(&merged), vec![(14, 24)]);
+        assert_eq!(merged[0].attributes["votes"], "2");
+        assert_eq!(merge(MergeStrategy::Vote, 1, sources()).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!("Model-Priority".parse::<MergeStrategy>(), Ok(MergeStrategy::ModelPriority));
+        assert!("majority".parse::<MergeStrategy>().is_err());
+    }
+}
@@ -0,0 +1,197 @@
+// Post-processing plugins: WASM modules listed in GLINER_PLUGINS that see each
+// detection result before the response is built, for rules that belong to one
+// deployment (suppressing known false positives, renaming labels, client
+// specific scoring) rather than to the crate. Needs the `wasm-plugins` feature.
+//
+// A plugin exports its `memory`, `alloc(len: i32) -> i32` returning where the
+// host may write `len` bytes, and `process(ptr: i32, len: i32) -> i64`. The
+// host writes `{"text": ..., "entities": [...]}` as JSON and calls `process`,
+// which returns the offset of its JSON output in the high 32 bits and the
+// length in the low 32 bits. The output is `{"entities": [...]}`, the findings
+// to keep. Plugins import nothing, and each run gets a fresh instance with a
+// budget of GLINER_PLUGIN_FUEL instructions.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::detection::DetectionError;
+use crate::PiiEntity;
+
+#[derive(Serialize)]
+struct PluginInput<'a> {
+    text: &'a str,
+    entities: &'a [PiiEntity],
+}
+
+#[derive(Deserialize)]
+struct PluginOutput {
+    entities: Vec<PiiEntity>,
+}
+
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+}
+
+pub type PluginsState = Arc<Option<Plugins>>;
+
+impl Plugins {
+    pub fn from_config(config: &Config) -> Result<Option<Self>, DetectionError> {
+        if config.plugins.is_empty() {
+            return Ok(None);
+        }
+        let plugins = Plugin::load_all(&config.plugins, config.plugin_fuel)?;
+        Ok(Some(Plugins { plugins }))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(Plugin::name).collect()
+    }
+
+    // Run the plugins in order, each on the previous one's findings
+    pub fn run(&self, text: &str, entities: &mut Vec<PiiEntity>) -> Result<(), DetectionError> {
+        for plugin in &self.plugins {
+            let input = serde_json::to_vec(&PluginInput { text, entities })?;
+            let output = plugin.call(&input).map_err(|e| format!("plugin {} failed: {}", plugin.name(), e))?;
+            *entities = parse_output(text, &output).map_err(|e| format!("plugin {} returned {}", plugin.name(), e))?;
+        }
+        Ok(())
+    }
+}
+
+// Findings from a plugin's output, checked against the text. The span text is
+// taken from the offsets, so a plugin can't report text that isn't there.
+fn parse_output(text: &str, output: &[u8]) -> Result<Vec<PiiEntity>, DetectionError> {
+    let mut entities = serde_json::from_slice::<PluginOutput>(output)?.entities;
+    for entity in &mut entities {
+        let span = text.get(entity.start..entity.end).filter(|_| entity.start <= entity.end);
+        let Some(span) = span else {
+            return Err(format!("offsets {}..{} outside the text", entity.start, entity.end).into());
+        };
+        if entity.label.trim().is_empty() || !(0.0..=1.0).contains(&entity.confidence) {
+            return Err(format!("an invalid finding at {}..{}", entity.start, entity.end).into());
+        }
+        entity.text = span.to_string();
+    }
+    entities.sort_by_key(|entity| (entity.start, entity.end));
+    Ok(entities)
+}
+
+#[cfg(feature = "wasm-plugins")]
+struct Plugin {
+    name: String,
+    engine: wasmtime::Engine,
+    module: wasmtime::Module,
+    fuel: u64,
+}
+
+#[cfg(feature = "wasm-plugins")]
+impl Plugin {
+    fn load_all(paths: &[String], fuel: u64) -> Result<Vec<Plugin>, DetectionError> {
+        let mut settings = wasmtime::Config::new();
+        settings.consume_fuel(true);
+        let engine = wasmtime::Engine::new(&settings)?;
+        paths
+            .iter()
+            .map(|path| {
+                let module = wasmtime::Module::from_file(&engine, path).map_err(|e| format!("loading plugin {}: {}", path, e))?;
+                let name = std::path::Path::new(path).file_stem().map_or(path.clone(), |stem| stem.to_string_lossy().into_owned());
+                Ok(Plugin::new(name, engine.clone(), module, fuel))
+            })
+            .collect()
+    }
+
+    fn new(name: String, engine: wasmtime::Engine, module: wasmtime::Module, fuel: u64) -> Self {
+        Plugin { name, engine, module, fuel }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn call(&self, input: &[u8]) -> Result<Vec<u8>, DetectionError> {
+        let mut store = wasmtime::Store::new(&self.engine, ());
+        store.set_fuel(self.fuel)?;
+        let instance = wasmtime::Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance.get_memory(&mut store, "memory").ok_or("no exported memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let process = instance.get_typed_func::<(i32, i32), i64>(&mut store, "process")?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+        let packed = process.call(&mut store, (ptr, len))? as u64;
+        let mut output = vec![0; (packed & 0xffff_ffff) as usize];
+        memory.read(&store, (packed >> 32) as usize, &mut output)?;
+        Ok(output)
+    }
+}
+
+// Without the runtime no plugin can be loaded
+#[cfg(not(feature = "wasm-plugins"))]
+enum Plugin {}
+
+#[cfg(not(feature = "wasm-plugins"))]
+impl Plugin {
+    fn load_all(_paths: &[String], _fuel: u64) -> Result<Vec<Plugin>, DetectionError> {
+        Err("GLINER_PLUGINS is set, but the server was built without the wasm-plugins feature".into())
+    }
+
+    fn name(&self) -> &str {
+        match *self {}
+    }
+
+    fn call(&self, _input: &[u8]) -> Result<Vec<u8>, DetectionError> {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_is_checked_against_the_text() {
+        let text = "Call Jane Roe";
+        let output = br#"{"entities": [{"text": "anything", "label": "customer", "confidence": 0.7, "start": 5, "end": 13}]}"#;
+        let entities = parse_output(text, output).unwrap();
+        assert_eq!((entities[0].text.as_str(), entities[0].label.as_str()), ("Jane Roe", "customer"));
+
+        let outside = br#"{"entities": [{"text": "", "label": "person", "confidence": 0.7, "start": 5, "end": 40}]}"#;
+        assert!(parse_output(text, outside).is_err());
+        let bad_score = br#"{"entities": [{"text": "", "label": "person", "confidence": 7.0, "start": 5, "end": 13}]}"#;
+        assert!(parse_output(text, bad_score).is_err());
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    fn plugin(process: &str) -> Plugins {
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "process") (param i32 i32) (result i64) {}))"#,
+            process
+        );
+        let mut settings = wasmtime::Config::new();
+        settings.consume_fuel(true);
+        let engine = wasmtime::Engine::new(&settings).unwrap();
+        let module = wasmtime::Module::new(&engine, wat).unwrap();
+        Plugins {
+            plugins: vec![Plugin::new("test".to_string(), engine, module, 100_000)],
+        }
+    }
+
+    #[cfg(feature = "wasm-plugins")]
+    #[test]
+    fn test_plugin_runs_and_is_bounded() {
+        // Echoes its input, which already has the shape of an output
+        let echo = plugin("local.get 0 i64.extend_i32_u i64.const 32 i64.shl local.get 1 i64.extend_i32_u i64.or");
+        let text = "Call Jane Roe";
+        let mut entities = parse_output(text, br#"{"entities": [{"text": "", "label": "person", "confidence": 0.9, "start": 5, "end": 13}]}"#).unwrap();
+        echo.run(text, &mut entities).unwrap();
+        assert_eq!(entities[0].text, "Jane Roe");
+
+        let spinning = plugin("(loop br 0) i64.const 0");
+        assert!(spinning.run(text, &mut entities).is_err());
+    }
+}
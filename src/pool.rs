@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+#[cfg(not(feature = "mock-model"))]
+use gliner::{model::GLiNER, model::pipeline::token::TokenMode};
+
+// The model detection runs on: GLiNER in token mode, or the scripted stand-in
+// from `mock` in builds with the `mock-model` feature
+#[cfg(not(feature = "mock-model"))]
+pub type Model = GLiNER<TokenMode>;
+#[cfg(feature = "mock-model")]
+pub type Model = crate::mock::MockModel;
+
+// Independent ONNX sessions for the served model
+pub type ModelPool = SessionPool<Model>;
+
+#[derive(Default)]
+struct SessionStats {
+    leases: AtomicU64,
+    busy_micros: AtomicU64,
+    in_use: AtomicBool,
+}
+
+struct Session<M> {
+    model: M,
+    stats: SessionStats,
+}
+
+// Which work a checkout is for. Batch work (bulk endpoints, file and archive
+// scans, scheduled scans) never takes the sessions reserved for interactive
+// requests, and yields to interactive requests waiting for a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Interactive,
+    Batch,
+}
+
+impl Priority {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "interactive" => Some(Priority::Interactive),
+            "batch" => Some(Priority::Batch),
+            _ => None,
+        }
+    }
+}
+
+// Idle sessions and what is waiting for them, under one lock
+struct Checkouts {
+    idle: Vec<usize>,
+    interactive_waiting: usize,
+    batch_leases: usize,
+}
+
+// Per-session usage, exposed through GET /api/metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub id: usize,
+    pub in_use: bool,
+    pub leases: u64,
+    pub busy_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    pub size: usize,
+    pub idle: usize,
+    // Sessions batch work may not hold
+    #[serde(default)]
+    pub interactive_reserved: usize,
+    #[serde(default)]
+    pub batch_in_use: usize,
+    // Checkouts that found every session busy, and the total time they waited;
+    // steady growth here means the pool is too small for the load
+    pub waits: u64,
+    pub wait_ms: u64,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+// A fixed set of sessions for the same model. Each inference checks out one
+// session for its whole run, so up to `size` requests execute in parallel
+// instead of queueing behind a single session.
+pub struct SessionPool<M> {
+    sessions: Vec<Session<M>>,
+    checkouts: Mutex<Checkouts>,
+    returned: Condvar,
+    interactive_reserved: usize,
+    waits: AtomicU64,
+    wait_micros: AtomicU64,
+}
+
+impl<M> SessionPool<M> {
+    pub fn new(models: Vec<M>) -> Self {
+        let idle = (0..models.len()).rev().collect();
+        SessionPool {
+            sessions: models
+                .into_iter()
+                .map(|model| Session { model, stats: SessionStats::default() })
+                .collect(),
+            checkouts: Mutex::new(Checkouts {
+                idle,
+                interactive_waiting: 0,
+                batch_leases: 0,
+            }),
+            returned: Condvar::new(),
+            interactive_reserved: 0,
+            waits: AtomicU64::default(),
+            wait_micros: AtomicU64::default(),
+        }
+    }
+
+    // Keep `sessions` for interactive work; batch work always gets at least one
+    pub fn reserve_interactive(mut self, sessions: usize) -> Self {
+        self.interactive_reserved = sessions.min(self.size().saturating_sub(1));
+        self
+    }
+
+    pub fn size(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn models(&self) -> impl Iterator<Item = &M> {
+        self.sessions.iter().map(|session| &session.model)
+    }
+
+    // Take an idle session for interactive work, blocking until one is returned
+    // if all are busy. Only call this from a blocking context.
+    pub fn checkout(&self) -> SessionLease<'_, M> {
+        self.checkout_with(Priority::Interactive)
+    }
+
+    // Take an idle session for work of the given priority, blocking until it
+    // may have one. Only call this from a blocking context.
+    pub fn checkout_with(&self, priority: Priority) -> SessionLease<'_, M> {
+        let mut checkouts = self.checkouts.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !self.available(&checkouts, priority) {
+            let started = Instant::now();
+            if priority == Priority::Interactive {
+                checkouts.interactive_waiting += 1;
+            }
+            while !self.available(&checkouts, priority) {
+                checkouts = self.returned.wait(checkouts).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            if priority == Priority::Interactive {
+                checkouts.interactive_waiting -= 1;
+            }
+            self.waits.fetch_add(1, Ordering::Relaxed);
+            self.wait_micros.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        }
+        let index = checkouts.idle.pop().expect("idle session available");
+        if priority == Priority::Batch {
+            checkouts.batch_leases += 1;
+        }
+        drop(checkouts);
+
+        let stats = &self.sessions[index].stats;
+        stats.in_use.store(true, Ordering::Relaxed);
+        stats.leases.fetch_add(1, Ordering::Relaxed);
+        SessionLease {
+            pool: self,
+            index,
+            priority,
+            started: Instant::now(),
+        }
+    }
+
+    fn available(&self, checkouts: &Checkouts, priority: Priority) -> bool {
+        match priority {
+            Priority::Interactive => !checkouts.idle.is_empty(),
+            Priority::Batch => {
+                !checkouts.idle.is_empty()
+                    && checkouts.interactive_waiting == 0
+                    && checkouts.batch_leases < self.size() - self.interactive_reserved
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> PoolSnapshot {
+        let (idle, batch_in_use) = self
+            .checkouts
+            .lock()
+            .map(|checkouts| (checkouts.idle.len(), checkouts.batch_leases))
+            .unwrap_or((0, 0));
+        PoolSnapshot {
+            size: self.size(),
+            idle,
+            interactive_reserved: self.interactive_reserved,
+            batch_in_use,
+            waits: self.waits.load(Ordering::Relaxed),
+            wait_ms: self.wait_micros.load(Ordering::Relaxed) / 1000,
+            sessions: self
+                .sessions
+                .iter()
+                .enumerate()
+                .map(|(id, session)| SessionSnapshot {
+                    id,
+                    in_use: session.stats.in_use.load(Ordering::Relaxed),
+                    leases: session.stats.leases.load(Ordering::Relaxed),
+                    busy_ms: session.stats.busy_micros.load(Ordering::Relaxed) / 1000,
+                })
+                .collect(),
+        }
+    }
+}
+
+// Exclusive use of one session; returned to the pool on drop
+pub struct SessionLease<'a, M> {
+    pool: &'a SessionPool<M>,
+    index: usize,
+    priority: Priority,
+    started: Instant,
+}
+
+impl<M> SessionLease<'_, M> {
+    pub fn id(&self) -> usize {
+        self.index
+    }
+}
+
+impl<M> Deref for SessionLease<'_, M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.pool.sessions[self.index].model
+    }
+}
+
+impl<M> Drop for SessionLease<'_, M> {
+    fn drop(&mut self) {
+        let stats = &self.pool.sessions[self.index].stats;
+        stats.busy_micros.fetch_add(self.started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        stats.in_use.store(false, Ordering::Relaxed);
+
+        let mut checkouts = self.pool.checkouts.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        checkouts.idle.push(self.index);
+        if self.priority == Priority::Batch {
+            checkouts.batch_leases -= 1;
+        }
+        // Waiters of both priorities share the condvar, so wake them all and let
+        // each recheck whether it may take the session
+        self.pool.returned.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_leases_use_distinct_sessions() {
+        let pool = SessionPool::new(vec!["a", "b"]);
+        let first = pool.checkout();
+        let second = pool.checkout();
+        assert_ne!(first.id(), second.id());
+        assert_eq!(pool.snapshot().idle, 0);
+
+        drop(first);
+        drop(second);
+        let snapshot = pool.snapshot();
+        assert_eq!(snapshot.idle, 2);
+        assert_eq!(snapshot.sessions.iter().map(|session| session.leases).sum::<u64>(), 2);
+        assert!(snapshot.sessions.iter().all(|session| !session.in_use));
+    }
+
+    #[test]
+    fn test_checkout_waits_for_a_returned_session() {
+        let pool = Arc::new(SessionPool::new(vec![1]));
+        let held = pool.checkout();
+
+        let waiter = {
+            let pool = pool.clone();
+            std::thread::spawn(move || *pool.checkout())
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        drop(held);
+
+        assert_eq!(waiter.join().unwrap(), 1);
+        assert_eq!(pool.snapshot().waits, 1);
+    }
+
+    #[test]
+    fn test_batch_work_leaves_reserved_sessions_idle() {
+        let pool = Arc::new(SessionPool::new(vec!["a", "b", "c"]).reserve_interactive(1));
+        let first = pool.checkout_with(Priority::Batch);
+        let second = pool.checkout_with(Priority::Batch);
+        assert_eq!(pool.snapshot().batch_in_use, 2);
+
+        let third = {
+            let pool = pool.clone();
+            std::thread::spawn(move || pool.checkout_with(Priority::Batch).id())
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        // The reserved session still serves interactive work right away
+        let interactive = pool.checkout();
+        assert_ne!(interactive.id(), first.id());
+        drop(interactive);
+        assert!(!third.is_finished());
+
+        drop(first);
+        third.join().unwrap();
+        drop(second);
+        assert_eq!(pool.snapshot().batch_in_use, 0);
+    }
+
+    #[test]
+    fn test_reservation_leaves_batch_one_session() {
+        let pool = SessionPool::new(vec!["a"]).reserve_interactive(2);
+        assert_eq!(pool.snapshot().interactive_reserved, 0);
+        drop(pool.checkout_with(Priority::Batch));
+    }
+}
@@ -0,0 +1,133 @@
+use gliner::{
+    model::params::Parameters,
+    model::input::text::TextInput,
+};
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::config::{onnx_path_for, tokenizer_path_for, Config};
+use crate::detection::DetectionError;
+use crate::pool::Model;
+use crate::runtime::runtime_parameters;
+
+// Cheap first pass deciding which chunks are worth sending to the main model.
+// It only has to be good at recall: anything it flags is confirmed (or not) by
+// the large model, anything it misses is never looked at again.
+pub enum Prepass {
+    // Label-agnostic patterns for things that usually surround PII
+    Rules,
+    // A smaller GLiNER model run with a low threshold
+    Model(Box<Model>),
+}
+
+impl Prepass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Prepass::Rules => "rules",
+            Prepass::Model(_) => "model",
+        }
+    }
+
+    // Flag, per text, whether the pre-pass found a candidate hit
+    pub fn candidates(&self, texts: &[&str], labels: &[&str], batch_size: usize) -> Result<Vec<bool>, DetectionError> {
+        match self {
+            Prepass::Rules => Ok(texts.iter().map(|text| rules_match(text)).collect()),
+            Prepass::Model(model) => {
+                let mut flags = Vec::with_capacity(texts.len());
+                for batch in texts.chunks(batch_size.max(1)) {
+                    let output = model.inference(TextInput::from_str(batch, labels)?)?;
+                    flags.extend(output.spans.iter().map(|spans| !spans.is_empty()));
+                }
+                Ok(flags)
+            }
+        }
+    }
+}
+
+// Build the configured pre-pass, if any
+pub fn load_prepass(config: &Config) -> Result<Option<Prepass>, DetectionError> {
+    match config.prepass.as_str() {
+        "off" | "" => Ok(None),
+        "rules" => Ok(Some(Prepass::Rules)),
+        "model" => {
+            let model_name = config
+                .prepass_model
+                .as_deref()
+                .ok_or("GLINER_PREPASS=model requires GLINER_PREPASS_MODEL")?;
+            tracing::info!(model = %model_name, "Loading pre-pass model");
+            let model = Model::new(
+                Parameters::default().with_threshold(config.prepass_threshold),
+                runtime_parameters(config)?,
+                &tokenizer_path_for(model_name),
+                &onnx_path_for(model_name),
+            )?;
+            Ok(Some(Prepass::Model(Box::new(model))))
+        }
+        other => Err(format!("unknown pre-pass mode {:?}, expected off, rules or model", other).into()),
+    }
+}
+
+fn rule_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // Email addresses
+            r"[\w.+-]+@[\w-]+\.[\w.-]+",
+            // Phone numbers, card numbers and other long digit runs
+            r"\+?\d[\d\s().-]{5,}\d",
+            // Capitalized word sequences: names, organizations, street names
+            r"\b\p{Lu}\p{Ll}+(?:\s+\p{Lu}\p{Ll}+)+\b",
+            // House numbers followed by a capitalized word
+            r"\b\d{1,5}\s+\p{Lu}\p{Ll}+",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("valid pre-pass pattern"))
+        .collect()
+    })
+}
+
+pub fn rules_match(text: &str) -> bool {
+    rule_patterns().iter().any(|pattern| pattern.is_match(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_flag_likely_pii() {
+        assert!(rules_match("write to jane@example.com"));
+        assert!(rules_match("call (555) 123-4567 tomorrow"));
+        assert!(rules_match("meeting with John Doe"));
+        assert!(rules_match("ship to 42 Baker street"));
+    }
+
+    #[test]
+    fn test_rules_skip_clean_text() {
+        assert!(!rules_match("the build finished in 12 seconds without errors."));
+        assert!(!rules_match("Nothing to see here"));
+    }
+
+    #[test]
+    fn test_rules_prepass_candidates() {
+        let flags = Prepass::Rules
+            .candidates(&["no hits here", "mail bob@example.org"], &["email"], 8)
+            .expect("rules never fail");
+        assert_eq!(flags, vec![false, true]);
+    }
+
+    #[test]
+    fn test_load_prepass_modes() {
+        let config = Config::default();
+        assert!(load_prepass(&config).unwrap().is_none());
+
+        let rules = Config { prepass: "rules".to_string(), ..Config::default() };
+        assert_eq!(load_prepass(&rules).unwrap().unwrap().name(), "rules");
+
+        let missing_model = Config { prepass: "model".to_string(), ..Config::default() };
+        assert!(load_prepass(&missing_model).is_err());
+
+        let unknown = Config { prepass: "magic".to_string(), ..Config::default() };
+        assert!(load_prepass(&unknown).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::conll::tag_name;
+use crate::PiiEntity;
+
+// Presidio entity types of the labels the service and its profiles use. Labels
+// not listed map to their upper-cased form, e.g. "passport number" becomes
+// PASSPORT_NUMBER.
+const ENTITY_TYPES: &[(&str, &str)] = &[
+    ("person", "PERSON"),
+    ("email", "EMAIL_ADDRESS"),
+    ("phone", "PHONE_NUMBER"),
+    ("phone number", "PHONE_NUMBER"),
+    ("address", "LOCATION"),
+    ("location", "LOCATION"),
+    ("organization", "ORGANIZATION"),
+    ("date of birth", "DATE_TIME"),
+    ("credit card number", "CREDIT_CARD"),
+    ("social security number", "US_SSN"),
+    ("ip address", "IP_ADDRESS"),
+    ("iban", "IBAN_CODE"),
+    ("url", "URL"),
+];
+
+// Name reported as the recognizer of every result
+pub const RECOGNIZER_NAME: &str = "GlinerRecognizer";
+
+// Body of Presidio's POST /analyze. Only the fields that affect detection here
+// are read; others, such as `correlation_id`, are accepted and ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzeRequest {
+    pub text: String,
+    pub language: String,
+    // Presidio entity types to look for; all supported types when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_threshold: Option<f32>,
+    // Exact texts never to report, e.g. a company's own support address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_list: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecognitionMetadata {
+    pub recognizer_name: String,
+    pub recognizer_identifier: String,
+}
+
+// One finding in Presidio's RecognizerResult shape. Offsets are in characters
+// (Unicode code points), as Python string indices are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecognizerResult {
+    pub entity_type: String,
+    pub start: usize,
+    pub end: usize,
+    pub score: f32,
+    pub analysis_explanation: Option<serde_json::Value>,
+    pub recognition_metadata: RecognitionMetadata,
+}
+
+// Presidio entity type of a label
+pub fn entity_type(label: &str) -> String {
+    ENTITY_TYPES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(label.trim()))
+        .map(|(_, entity_type)| entity_type.to_string())
+        .unwrap_or_else(|| tag_name(label))
+}
+
+// Labels to detect for the requested Presidio entity types: every label that
+// maps to a known type, or the type read back as a label otherwise
+pub fn labels_for(entity_types: &[String]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for requested in entity_types {
+        let requested = requested.trim().to_ascii_uppercase();
+        let mut matching: Vec<String> = ENTITY_TYPES
+            .iter()
+            .filter(|(_, entity_type)| *entity_type == requested)
+            .map(|(label, _)| label.to_string())
+            .collect();
+        if matching.is_empty() {
+            matching.push(requested.to_lowercase().replace('_', " "));
+        }
+        for label in matching {
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+    labels
+}
+
+// Supported entity types for a set of labels, without duplicates
+pub fn entity_types(labels: &[String]) -> Vec<String> {
+    let mut types: Vec<String> = labels.iter().map(|label| entity_type(label)).collect();
+    types.sort();
+    types.dedup();
+    types
+}
+
+// Convert findings (with byte offsets into `text`) to Presidio results, sorted
+// by position
+pub fn results(text: &str, entities: &[PiiEntity]) -> Vec<RecognizerResult> {
+    let mut results: Vec<RecognizerResult> = entities
+        .iter()
+        .map(|entity| RecognizerResult {
+            entity_type: entity_type(&entity.label),
+            start: char_offset(text, entity.start),
+            end: char_offset(text, entity.end),
+            score: entity.confidence,
+            analysis_explanation: None,
+            recognition_metadata: RecognitionMetadata {
+                recognizer_name: RECOGNIZER_NAME.to_string(),
+                recognizer_identifier: format!("{}_{}", RECOGNIZER_NAME, entity.label.replace(' ', "_")),
+            },
+        })
+        .collect();
+    results.sort_by_key(|result| (result.start, result.end));
+    results
+}
+
+fn char_offset(text: &str, byte_offset: usize) -> usize {
+    let byte_offset = byte_offset.min(text.len());
+    match text.get(..byte_offset) {
+        Some(prefix) => prefix.chars().count(),
+        None => text.char_indices().take_while(|(index, _)| *index < byte_offset).count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_in;
+
+    #[test]
+    fn test_entity_types() {
+        assert_eq!(entity_type("email"), "EMAIL_ADDRESS");
+        assert_eq!(entity_type("Phone Number"), "PHONE_NUMBER");
+        assert_eq!(entity_type("passport number"), "PASSPORT_NUMBER");
+        assert_eq!(labels_for(&["phone_number".to_string()]), vec!["phone", "phone number"]);
+        assert_eq!(labels_for(&["PASSPORT_NUMBER".to_string()]), vec!["passport number"]);
+    }
+
+    #[test]
+    fn test_results_use_character_offsets() {
+        let text = "Zoë Roe: zoe@example.com";
+        let entities = vec![entity_in(text, "zoe@example.com", "email", 0.85), entity_in(text, "Zoë Roe", "person", 0.85)];
+        let results = results(text, &entities);
+
+        assert_eq!(results[0].entity_type, "PERSON");
+        assert_eq!((results[0].start, results[0].end), (0, 7));
+        assert_eq!((results[1].start, results[1].end), (9, 24));
+        let json = serde_json::to_value(&results[1]).unwrap();
+        assert_eq!(json["analysis_explanation"], serde_json::Value::Null);
+        assert_eq!(json["recognition_metadata"]["recognizer_name"], RECOGNIZER_NAME);
+    }
+}
@@ -0,0 +1,132 @@
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::fields::FieldSelection;
+use crate::output::OutputFormat;
+use crate::PiiEntity;
+
+// Start of the error raised when the model cannot tokenize a text; what follows
+// comes from the tokenizer and may quote the input
+pub const MODEL_INPUT_ERROR: &str = "failed to process input text";
+
+// Whether a request runs in privacy mode: always when the instance is
+// configured for it, otherwise when the request asks. A request cannot opt out
+// of an instance-wide privacy mode.
+pub fn enabled(config: &Config, requested: Option<bool>) -> bool {
+    config.privacy_mode || requested.unwrap_or(false)
+}
+
+// Entity fields to return: in privacy mode, the selected ones (or all) except
+// the matched `text`
+pub fn selection(selection: Option<FieldSelection>, private: bool) -> Option<FieldSelection> {
+    if private {
+        Some(FieldSelection::without(selection, "text"))
+    } else {
+        selection
+    }
+}
+
+// HTML and CoNLL output reproduce the whole text, so privacy mode rules them out
+pub fn check_output_format(private: bool, format: OutputFormat) -> Result<(), ApiError> {
+    if private && matches!(format, OutputFormat::Html | OutputFormat::Conll) {
+        return Err(ApiError::InvalidInput(
+            "html and conll output reproduce the input text and are not available in privacy mode".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Context snippets quote the text around each finding, so privacy mode rules
+// them out too
+pub fn check_context(private: bool) -> Result<(), ApiError> {
+    if private {
+        return Err(ApiError::InvalidInput("context snippets reproduce the input text and are not available in privacy mode".to_string()));
+    }
+    Ok(())
+}
+
+// Drop the top-level `text` echoed by single-text detection
+pub fn omit_text(result: &mut Value) {
+    if let Some(object) = result.as_object_mut() {
+        object.remove("text");
+    }
+}
+
+// Clear the matched text of entities; empty texts are left out when serialized
+pub fn clear_text(entities: &mut [PiiEntity]) {
+    for entity in entities {
+        entity.text.clear();
+    }
+}
+
+// Errors whose message may carry model or tokenizer output, and so possibly
+// parts of the input, lose their details in privacy mode
+pub fn scrub_error(private: bool, error: ApiError) -> ApiError {
+    if !private {
+        return error;
+    }
+    match error {
+        ApiError::InferenceFailed(_) => ApiError::InferenceFailed("details withheld in privacy mode".to_string()),
+        ApiError::InvalidInput(message) if message.starts_with(MODEL_INPUT_ERROR) => {
+            ApiError::InvalidInput(format!("{}: details withheld in privacy mode", MODEL_INPUT_ERROR))
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_selection_drops_entity_text() {
+        let mut value = json!({"entities": [{"text": "Jane Roe", "label": "person", "start": 0, "end": 8}]});
+        selection(None, true).unwrap().apply(&mut value);
+        assert_eq!(value["entities"][0], json!({"label": "person", "start": 0, "end": 8}));
+
+        let chosen = FieldSelection::parse("text,label").ok();
+        assert_eq!(selection(chosen.clone(), false), chosen);
+        let mut value = json!({"entities": [{"text": "Jane Roe", "label": "person"}]});
+        selection(chosen, true).unwrap().apply(&mut value);
+        assert_eq!(value["entities"][0], json!({"label": "person"}));
+    }
+
+    #[test]
+    fn test_cleared_text_is_not_serialized() {
+        let mut entities = vec![PiiEntity {
+            text: "jane@example.com".to_string(),
+            label: "email".to_string(),
+            confidence: 0.9,
+            start: 5,
+            end: 21,
+            attributes: BTreeMap::new(),
+        }];
+        clear_text(&mut entities);
+        let value = serde_json::to_value(&entities[0]).unwrap();
+        assert!(value.get("text").is_none());
+        assert_eq!(value["start"], 5);
+    }
+
+    #[test]
+    fn test_config_cannot_be_overridden() {
+        let config = Config {
+            privacy_mode: true,
+            ..Config::default()
+        };
+        assert!(enabled(&config, Some(false)));
+        assert!(enabled(&Config::default(), Some(true)));
+        assert!(!enabled(&Config::default(), None));
+    }
+
+    #[test]
+    fn test_scrub_error_withholds_model_messages() {
+        let error = ApiError::InvalidInput(format!("{}: bad token in 'Jane Roe'", MODEL_INPUT_ERROR));
+        assert!(!scrub_error(true, error.clone()).to_string().contains("Jane"));
+        assert_eq!(scrub_error(false, error.clone()), error);
+        let error = ApiError::InvalidInput("text must not be empty or whitespace-only".to_string());
+        assert_eq!(scrub_error(true, error.clone()), error);
+    }
+}
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::masking::MaskingStrategy;
+use crate::packs;
+use crate::PiiEntity;
+
+// A named bundle of labels, thresholds and masking strategies for one
+// regulation, selectable per request with `"profile": "<name>"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelProfile {
+    pub name: String,
+    pub labels: Vec<String>,
+    // Minimum confidence for a finding to be reported
+    pub threshold: f32,
+    // Per-label overrides of `threshold`
+    #[serde(default)]
+    pub label_thresholds: HashMap<String, f32>,
+    #[serde(default)]
+    pub masking: MaskingStrategy,
+    // Per-label overrides of `masking`
+    #[serde(default)]
+    pub label_masking: HashMap<String, MaskingStrategy>,
+    // Country identifier packs whose labels are added to `labels`, e.g. ["uk"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packs: Vec<String>,
+}
+
+impl LabelProfile {
+    pub fn label_refs(&self) -> Vec<&str> {
+        self.labels.iter().map(String::as_str).collect()
+    }
+
+    pub fn threshold_for(&self, label: &str) -> f32 {
+        self.label_thresholds.get(label).copied().unwrap_or(self.threshold)
+    }
+
+    pub fn masking_for(&self, label: &str) -> MaskingStrategy {
+        self.label_masking.get(label).copied().unwrap_or(self.masking)
+    }
+
+    // Drop findings below the profile's thresholds
+    pub fn filter(&self, entities: &mut Vec<PiiEntity>) {
+        entities.retain(|entity| entity.confidence >= self.threshold_for(&entity.label));
+    }
+}
+
+fn profile(
+    name: &str,
+    labels: &[&str],
+    threshold: f32,
+    label_thresholds: &[(&str, f32)],
+    label_masking: &[(&str, MaskingStrategy)],
+) -> LabelProfile {
+    LabelProfile {
+        name: name.to_string(),
+        labels: labels.iter().map(|label| label.to_string()).collect(),
+        threshold,
+        label_thresholds: label_thresholds.iter().map(|(label, value)| (label.to_string(), *value)).collect(),
+        masking: MaskingStrategy::Redact,
+        label_masking: label_masking.iter().map(|(label, strategy)| (label.to_string(), *strategy)).collect(),
+        packs: Vec::new(),
+    }
+}
+
+// Presets shipped with the service
+pub fn builtin_profiles() -> Vec<LabelProfile> {
+    vec![
+        // The HIPAA Safe Harbor identifiers that are detectable in free text
+        profile(
+            "hipaa",
+            &[
+                "person",
+                "date of birth",
+                "address",
+                "phone number",
+                "fax number",
+                "email",
+                "social security number",
+                "medical record number",
+                "health insurance id number",
+                "ip address",
+            ],
+            0.4,
+            &[("date of birth", 0.3), ("medical record number", 0.3)],
+            &[],
+        ),
+        profile(
+            "pci-dss",
+            &[
+                "credit card number",
+                "credit card expiration date",
+                "cvv",
+                "person",
+            ],
+            0.5,
+            &[("credit card number", 0.3)],
+            &[("credit card number", MaskingStrategy::Partial)],
+        ),
+        profile(
+            "gdpr",
+            &[
+                "person",
+                "email",
+                "phone number",
+                "address",
+                "date of birth",
+                "ip address",
+                "national id number",
+                "passport number",
+                "bank account number",
+            ],
+            0.5,
+            &[],
+            &[("email", MaskingStrategy::Hash)],
+        ),
+    ]
+}
+
+pub type ProfileRegistry = HashMap<String, LabelProfile>;
+
+// Built-in presets, overridden or extended by the JSON array in
+// GLINER_PROFILES_FILE when set
+pub fn load_profiles(config: &Config) -> Result<ProfileRegistry, Box<dyn std::error::Error + Send + Sync>> {
+    let mut profiles: ProfileRegistry = builtin_profiles()
+        .into_iter()
+        .map(|profile| (profile.name.clone(), profile))
+        .collect();
+
+    if let Some(path) = &config.profiles_file {
+        let contents = std::fs::read_to_string(path)?;
+        let custom: Vec<LabelProfile> = serde_json::from_str(&contents)?;
+        for profile in custom {
+            packs::validate(&profile.packs)?;
+            profiles.insert(profile.name.clone(), profile);
+        }
+    }
+
+    Ok(profiles)
+}
+
+pub fn resolve<'a>(profiles: &'a ProfileRegistry, name: Option<&str>) -> Result<Option<&'a LabelProfile>, ApiError> {
+    match name {
+        None => Ok(None),
+        Some(name) => profiles.get(name).map(Some).ok_or_else(|| {
+            let mut known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            known.sort_unstable();
+            ApiError::InvalidInput(format!("unknown profile {:?}, available: {}", name, known.join(", ")))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_builtin_profiles_are_registered() {
+        let profiles = load_profiles(&Config::default()).expect("builtin profiles");
+        for name in ["hipaa", "pci-dss", "gdpr"] {
+            assert!(profiles.contains_key(name), "missing {}", name);
+        }
+    }
+
+    #[test]
+    fn test_profile_thresholds_and_masking() {
+        let profiles = load_profiles(&Config::default()).unwrap();
+        let pci = &profiles["pci-dss"];
+
+        assert_eq!(pci.threshold_for("credit card number"), 0.3);
+        assert_eq!(pci.threshold_for("person"), 0.5);
+        assert_eq!(pci.masking_for("credit card number"), MaskingStrategy::Partial);
+        assert_eq!(pci.masking_for("person"), MaskingStrategy::Redact);
+
+        let mut entities = vec![entity("credit card number", 0, 1, 0.35), entity("person", 0, 1, 0.35)];
+        pci.filter(&mut entities);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].label, "credit card number");
+    }
+
+    #[test]
+    fn test_resolve_unknown_profile() {
+        let profiles = load_profiles(&Config::default()).unwrap();
+        assert!(resolve(&profiles, None).unwrap().is_none());
+        assert_eq!(resolve(&profiles, Some("gdpr")).unwrap().unwrap().name, "gdpr");
+
+        let error = resolve(&profiles, Some("sox")).unwrap_err();
+        assert_eq!(error.error_code(), "invalid_input");
+        assert!(error.to_string().contains("hipaa"));
+    }
+
+    #[test]
+    fn test_profile_deserializes_with_defaults() {
+        let profile: LabelProfile = serde_json::from_value(serde_json::json!({
+            "name": "hr",
+            "labels": ["person", "salary"],
+            "threshold": 0.6
+        }))
+        .expect("valid profile");
+        assert_eq!(profile.masking, MaskingStrategy::Redact);
+        assert_eq!(profile.threshold_for("salary"), 0.6);
+    }
+}
@@ -0,0 +1,212 @@
+// Protobuf messages of proto/gliner.proto, in the form prost generates them,
+// plus conversions to and from the JSON request and response types. Only built
+// with the `protobuf` feature.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::error::ApiError;
+use crate::recognizers::RecognizerOptions;
+use crate::routes::{self, BatchRequest, DocumentRef, PiiRequest};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DetectRequest {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(string, optional, tag = "2")]
+    pub id: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub profile: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub locale: Option<String>,
+    #[prost(bool, optional, tag = "5")]
+    pub include_private_ips: Option<bool>,
+    #[prost(bool, optional, tag = "6")]
+    pub enrich_cards: Option<bool>,
+}
+
+// The JSON responses are read back into these, so they also derive Deserialize;
+// the single-document response calls the confidence "probability"
+#[derive(Clone, PartialEq, prost::Message, Deserialize)]
+#[serde(default)]
+pub struct Entity {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(string, tag = "2")]
+    pub label: String,
+    #[prost(float, tag = "3")]
+    #[serde(alias = "probability")]
+    pub confidence: f32,
+    #[prost(uint64, tag = "4")]
+    pub start: u64,
+    #[prost(uint64, tag = "5")]
+    pub end: u64,
+    #[prost(btree_map = "string, string", tag = "6")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Deserialize)]
+#[serde(default)]
+pub struct DetectResponse {
+    #[prost(string, optional, tag = "1")]
+    pub id: Option<String>,
+    #[prost(string, tag = "2")]
+    pub text: String,
+    #[prost(message, repeated, tag = "3")]
+    pub entities: Vec<Entity>,
+    #[prost(uint64, tag = "4")]
+    pub total_entities: u64,
+    #[prost(string, optional, tag = "5")]
+    pub profile: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub redacted_text: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BatchDocument {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(string, optional, tag = "2")]
+    pub id: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BatchDetectRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub documents: Vec<BatchDocument>,
+    #[prost(string, optional, tag = "2")]
+    pub profile: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub locale: Option<String>,
+    #[prost(bool, optional, tag = "4")]
+    pub include_private_ips: Option<bool>,
+    #[prost(bool, optional, tag = "5")]
+    pub enrich_cards: Option<bool>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Deserialize)]
+#[serde(default)]
+pub struct DocumentResult {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(string, optional, tag = "2")]
+    pub id: Option<String>,
+    #[prost(message, repeated, tag = "3")]
+    pub entities: Vec<Entity>,
+    #[prost(uint64, tag = "4")]
+    pub total_entities: u64,
+    #[prost(string, optional, tag = "5")]
+    pub redacted_text: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Deserialize)]
+#[serde(default)]
+pub struct BatchDetectResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: Vec<DocumentResult>,
+    #[prost(uint64, tag = "2")]
+    pub total_documents: u64,
+    #[prost(string, optional, tag = "3")]
+    pub profile: Option<String>,
+}
+
+impl From<DetectRequest> for PiiRequest {
+    fn from(request: DetectRequest) -> Self {
+        PiiRequest {
+            text: request.text,
+            reference: DocumentRef {
+                id: request.id,
+                metadata: None,
+            },
+            profile: request.profile,
+            recognizers: RecognizerOptions {
+                locale: request.locale,
+                include_private_ips: request.include_private_ips,
+                enrich_cards: request.enrich_cards,
+                recognizer_packs: None,
+                detectors: None,
+                merge_strategy: None,
+                label_prompts: None,
+            },
+            ..PiiRequest::default()
+        }
+    }
+}
+
+impl From<BatchDetectRequest> for BatchRequest {
+    fn from(request: BatchDetectRequest) -> Self {
+        BatchRequest {
+            documents: request
+                .documents
+                .into_iter()
+                .map(|document| routes::BatchDocument {
+                    text: document.text,
+                    reference: DocumentRef {
+                        id: document.id,
+                        metadata: None,
+                    },
+                })
+                .collect(),
+            profile: request.profile,
+            fields: None,
+            input_format: Default::default(),
+            skip_code_blocks: false,
+            output_format: Default::default(),
+            recognizers: RecognizerOptions {
+                locale: request.locale,
+                include_private_ips: request.include_private_ips,
+                enrich_cards: request.enrich_cards,
+                recognizer_packs: None,
+                detectors: None,
+                merge_strategy: None,
+                label_prompts: None,
+            },
+            privacy_mode: None,
+        }
+    }
+}
+
+// Read a JSON endpoint's `data` back into its protobuf message
+pub fn from_json<T: for<'de> Deserialize<'de>>(data: Option<serde_json::Value>) -> Result<T, ApiError> {
+    serde_json::from_value(data.unwrap_or_default())
+        .map_err(|e| ApiError::InferenceFailed(format!("failed to build protobuf response: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_request_round_trip_and_conversion() {
+        let request = DetectRequest {
+            text: "Call Jane Roe".to_string(),
+            id: Some("ticket-7".to_string()),
+            locale: Some("en-GB".to_string()),
+            ..DetectRequest::default()
+        };
+        let decoded = DetectRequest::decode(request.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, request);
+
+        let converted = PiiRequest::from(decoded);
+        assert_eq!(converted.reference.id.as_deref(), Some("ticket-7"));
+        assert_eq!(converted.recognizers.locale.as_deref(), Some("en-GB"));
+        assert_eq!(converted.profile, None);
+    }
+
+    #[test]
+    fn test_response_from_json_result() {
+        let data = serde_json::json!({
+            "text": "Call Jane Roe",
+            "entities": [{"text": "Jane Roe", "label": "person", "sequence": 0, "probability": 0.9, "start": 5, "end": 13}],
+            "total_entities": 1,
+            "message": "PII detection completed successfully",
+            "id": "ticket-7"
+        });
+        let response: DetectResponse = from_json(Some(data)).unwrap();
+        assert_eq!(response.id.as_deref(), Some("ticket-7"));
+        assert_eq!(response.entities[0].confidence, 0.9);
+        assert_eq!((response.entities[0].start, response.entities[0].end), (5, 13));
+        assert!(!response.encode_to_vec().is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::adjacent;
+use crate::aliases::LabelMap;
+use crate::card;
+use crate::checksum::{self, ChecksumPolicy};
+use crate::config::Config;
+use crate::dob::{self, DateOrder};
+use crate::detection::DetectionError;
+use crate::error::ApiError;
+use crate::gazetteer;
+use crate::ip;
+use crate::packs;
+use crate::pipeline::{self, Detector, Findings, MergeStrategy};
+use crate::PiiEntity;
+
+// Per-request settings for the rule-based recognizers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecognizerOptions {
+    // BCP 47 locale of the text, e.g. "en-US", used to read numeric dates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    // Also report private, loopback and reserved IP addresses; defaults to
+    // GLINER_IP_INCLUDE_PRIVATE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_private_ips: Option<bool>,
+    // Add the card network and a masked number to valid card findings; defaults
+    // to GLINER_CARD_ENRICHMENT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrich_cards: Option<bool>,
+    // Country identifier packs to run, e.g. ["us"]; defaults to
+    // GLINER_RECOGNIZER_PACKS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recognizer_packs: Option<Vec<String>>,
+    // Detectors to run, e.g. ["model", "packs"]; defaults to GLINER_DETECTORS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detectors: Option<Vec<String>>,
+    // How overlapping findings are merged; defaults to GLINER_MERGE_STRATEGY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_strategy: Option<MergeStrategy>,
+    // Phrases to prompt the model with per label, on top of GLINER_LABEL_PROMPTS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_prompts: Option<HashMap<String, String>>,
+}
+
+impl RecognizerOptions {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if let Some(names) = &self.recognizer_packs {
+            packs::validate(names)?;
+        }
+        if let Some(unknown) = self.detectors.iter().flatten().find(|name| !pipeline::DETECTORS.contains(&name.as_str())) {
+            return Err(ApiError::InvalidInput(format!(
+                "Unknown detector '{}', expected one of: {}",
+                unknown,
+                pipeline::DETECTORS.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    fn selected(&self, config: &Config, detector: &str) -> bool {
+        self.detectors.as_ref().unwrap_or(&config.detectors).iter().any(|name| name == detector)
+    }
+}
+
+struct DatesOfBirth(DateOrder);
+
+impl Detector for DatesOfBirth {
+    fn name(&self) -> &'static str {
+        "date_of_birth"
+    }
+
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError> {
+        Ok(match labels.contains(&dob::LABEL) {
+            true => dob::find_dates_of_birth(text, self.0),
+            false => Vec::new(),
+        })
+    }
+}
+
+struct IpAddresses {
+    include_private: bool,
+}
+
+impl Detector for IpAddresses {
+    fn name(&self) -> &'static str {
+        "ip_address"
+    }
+
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError> {
+        Ok(match labels.contains(&ip::LABEL) {
+            true => ip::find_ip_addresses(text, self.include_private),
+            false => Vec::new(),
+        })
+    }
+}
+
+struct CountryPacks(Vec<String>);
+
+impl Detector for CountryPacks {
+    fn name(&self) -> &'static str {
+        "packs"
+    }
+
+    fn detect(&self, text: &str, labels: &[&str]) -> Result<Vec<PiiEntity>, DetectionError> {
+        Ok(packs::find(text, &self.0, labels))
+    }
+}
+
+// The rule-based detectors selected for a request, in the order they run
+pub fn detectors(options: &RecognizerOptions, config: &Config) -> Vec<Box<dyn Detector>> {
+    let mut detectors: Vec<Box<dyn Detector>> = Vec::new();
+    if options.selected(config, "date_of_birth") {
+        detectors.push(Box::new(DatesOfBirth(DateOrder::for_locale(options.locale.as_deref()))));
+    }
+    if options.selected(config, "ip_address") {
+        detectors.push(Box::new(IpAddresses {
+            include_private: include_private_ips(options, config),
+        }));
+    }
+    if options.selected(config, "packs") {
+        let enabled = options.recognizer_packs.as_ref().unwrap_or(&config.recognizer_packs);
+        detectors.push(Box::new(CountryPacks(enabled.clone())));
+    }
+    if options.selected(config, "gazetteer") {
+        if let Some(gazetteer) = gazetteer::shared(config) {
+            detectors.push(Box::new(gazetteer));
+        }
+    }
+    detectors
+}
+
+// Rule-based recognizers run next to the model for identifiers whose shape and
+// context are known. Each one only runs when its label was requested, except
+// that an enabled country pack runs all of its identifiers.
+pub fn recognize(text: &str, labels: &[&str], options: &RecognizerOptions, config: &Config) -> Vec<PiiEntity> {
+    sources(text, labels, options, config).into_iter().flat_map(|source| source.entities).collect()
+}
+
+// Rule-based detectors never fail, so their findings are collected directly
+fn sources(text: &str, labels: &[&str], options: &RecognizerOptions, config: &Config) -> Vec<Findings> {
+    detectors(options, config)
+        .iter()
+        .map(|detector| Findings {
+            detector: detector.name(),
+            entities: detector.detect(text, labels).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn include_private_ips(options: &RecognizerOptions, config: &Config) -> bool {
+    options.include_private_ips.unwrap_or(config.ip_include_private)
+}
+
+// Merge recognizer findings with the model's, by default letting a recognizer
+// finding replace model findings it overlaps, so a date of birth is not also
+// reported as a generic date (see `pipeline::MergeStrategy` for the others).
+// IP addresses the model found are classified the same way as recognized ones,
+// and card numbers are enriched from the BIN table when asked to. Model findings
+// with a checksum (cards, IBANs, SSNs) are re-scored first, per GLINER_CHECKSUM_*.
+// Label aliases and prompts are resolved for the merge and findings come back
+// under the requested labels.
+pub fn apply(text: &str, labels: &[&str], options: &RecognizerOptions, config: &Config, entities: &mut Vec<PiiEntity>) {
+    let label_map = LabelMap::resolve(labels, config, options.label_prompts.as_ref());
+    let labels = &label_map.labels();
+    label_map.canonicalize(entities);
+    if let Some(max_gap) = config.join_max_gap {
+        adjacent::join(text, entities, max_gap, config.join_score);
+    }
+    checksum::rescore(entities, &ChecksumPolicy::from_config(config));
+    if labels.contains(&ip::LABEL) {
+        ip::classify_entities(entities, include_private_ips(options, config));
+    }
+    if labels.contains(&card::LABEL) && options.enrich_cards.unwrap_or(config.card_enrichment) {
+        card::enrich_entities(entities);
+    }
+    let model = match options.selected(config, pipeline::MODEL) {
+        true => std::mem::take(entities),
+        false => Vec::new(),
+    };
+    let mut findings = vec![Findings {
+        detector: pipeline::MODEL,
+        entities: model,
+    }];
+    findings.extend(sources(text, labels, options, config));
+    let strategy = options.merge_strategy.unwrap_or(config.merge_strategy);
+    *entities = pipeline::merge(strategy, config.merge_min_votes, findings);
+    label_map.restore(entities);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_recognizer_replaces_overlapping_model_date() {
+        let text = "Jane Roe, DOB 04/03/1980";
+        let mut entities = vec![entity("person", 0, 8, 0.6), entity("date", 14, 24, 0.6)];
+        apply(text, &["person", "date of birth"], &RecognizerOptions::default(), &Config::default(), &mut entities);
+
+        let labels: Vec<&str> = entities.iter().map(|entity| entity.label.as_str()).collect();
+        assert_eq!(labels, vec!["person", "date of birth"]);
+    }
+
+    #[test]
+    fn test_split_model_spans_are_joined_when_configured() {
+        let text = "Ask John Doe";
+        let split = || vec![entity("person", 4, 8, 0.6), entity("person", 9, 12, 0.6)];
+
+        let mut entities = split();
+        apply(text, &["person"], &RecognizerOptions::default(), &Config::default(), &mut entities);
+        assert_eq!(entities.len(), 2);
+
+        let config = Config {
+            join_max_gap: Some(1),
+            ..Config::default()
+        };
+        let mut entities = split();
+        apply(text, &["person"], &RecognizerOptions::default(), &config, &mut entities);
+        assert_eq!((entities.len(), entities[0].text.as_str()), (1, "John Doe"));
+    }
+
+    #[test]
+    fn test_recognizer_needs_its_label() {
+        let mut entities = Vec::new();
+        apply("DOB 04/03/1980", &["person"], &RecognizerOptions::default(), &Config::default(), &mut entities);
+        assert!(entities.is_empty());
+    }
+
+    #[test]
+    fn test_private_ips_follow_request_then_config() {
+        let text = "login from 192.168.1.20";
+        let mut config = Config::default();
+        assert!(recognize(text, &["ip address"], &RecognizerOptions::default(), &config).is_empty());
+
+        config.ip_include_private = true;
+        let found = recognize(text, &["ip address"], &RecognizerOptions::default(), &config);
+        assert_eq!(found[0].attributes["ip_scope"], "private");
+
+        let options = RecognizerOptions {
+            include_private_ips: Some(false),
+            ..Default::default()
+        };
+        assert!(recognize(text, &["ip address"], &options, &config).is_empty());
+    }
+
+    #[test]
+    fn test_packs_follow_request_then_config() {
+        let text = "SSN 219-09-9999, NINO AB123456C";
+        let config = Config {
+            recognizer_packs: vec!["uk".to_string()],
+            ..Default::default()
+        };
+        let found = recognize(text, &["person"], &RecognizerOptions::default(), &config);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].label, "national insurance number");
+
+        let options = RecognizerOptions {
+            recognizer_packs: Some(vec!["us".to_string()]),
+            ..Default::default()
+        };
+        let found = recognize(text, &["person"], &options, &config);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "219-09-9999");
+    }
+
+    #[test]
+    fn test_recognizers_run_for_aliased_labels() {
+        let config = Config {
+            label_aliases: vec!["dob=date of birth".parse().unwrap()],
+            ..Default::default()
+        };
+        let mut entities = vec![entity("dob", 14, 24, 0.6)];
+        apply("Jane Roe, DOB 04/03/1980", &["dob"], &RecognizerOptions::default(), &config, &mut entities);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].label, "dob");
+        assert!(entities[0].confidence > 0.6);
+    }
+
+    #[test]
+    fn test_detector_selection_and_merge_strategy() {
+        let text = "Jane Roe, DOB 04/03/1980";
+        let labels = ["person", "date of birth"];
+        let model = || vec![entity("person", 0, 8, 0.6), entity("date of birth", 14, 24, 0.6)];
+
+        // Without the model, only recognizer findings remain
+        let options = RecognizerOptions {
+            detectors: Some(vec!["date_of_birth".to_string()]),
+            ..Default::default()
+        };
+        let mut entities = model();
+        apply(text, &labels, &options, &Config::default(), &mut entities);
+        assert_eq!(entities.len(), 1);
+        assert!(entities[0].confidence > 0.6);
+
+        // Voting keeps the date both the model and the recognizer found
+        let options = RecognizerOptions {
+            merge_strategy: Some(MergeStrategy::Vote),
+            ..Default::default()
+        };
+        let mut entities = model();
+        apply(text, &labels, &options, &Config::default(), &mut entities);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].attributes["votes"], "2");
+
+        let options = RecognizerOptions {
+            detectors: Some(vec!["regex".to_string()]),
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
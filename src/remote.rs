@@ -0,0 +1,208 @@
+// Objects fetched from cloud storage for scanning, so large files in a data
+// lake do not have to be downloaded and re-uploaded by the client. Each backend
+// signs its own requests; the download itself is shared and bounded by the
+// upload limit.
+
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+// A downloaded object, with what is needed to pick its extractor
+pub struct RemoteObject {
+    // Last segment of the object's key, e.g. "report.pdf"
+    pub name: Option<String>,
+    // Type and subtype of the stored object's Content-Type
+    pub media_type: Option<(String, String)>,
+    pub bytes: Vec<u8>,
+}
+
+// Send a prepared GET request and read the object it returns in chunks, giving
+// up as soon as more than `max_bytes` arrive
+pub async fn download(request: reqwest::RequestBuilder, name: Option<String>, max_bytes: u64) -> Result<RemoteObject, ApiError> {
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| ApiError::StorageFailed(format!("could not reach storage: {}", e)))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(ApiError::NotFound("object does not exist".to_string()));
+    }
+    if !status.is_success() {
+        return Err(ApiError::StorageFailed(format!("storage answered {}", status)));
+    }
+    let too_large = || ApiError::PayloadTooLarge(format!("object exceeds the {} byte upload limit", max_bytes));
+    if response.content_length().is_some_and(|length| length > max_bytes) {
+        return Err(too_large());
+    }
+
+    let media_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_media_type);
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| ApiError::StorageFailed(format!("object download failed: {}", e)))?
+    {
+        if (bytes.len() + chunk.len()) as u64 > max_bytes {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(RemoteObject { name, media_type, bytes })
+}
+
+// "application/pdf; charset=binary" as ("application", "pdf")
+pub fn parse_media_type(value: &str) -> Option<(String, String)> {
+    let essence = value.split(';').next()?.trim().to_ascii_lowercase();
+    let (top, sub) = essence.split_once('/')?;
+    (!top.is_empty() && !sub.is_empty()).then(|| (top.to_string(), sub.to_string()))
+}
+
+// File name of an object key, for picking an extractor by extension
+pub fn object_name(key: &str) -> Option<String> {
+    key.rsplit('/').next().filter(|name| !name.is_empty()).map(str::to_string)
+}
+
+// File name of the object a url points at
+pub fn url_name(url: &reqwest::Url) -> Option<String> {
+    url.path_segments().and_then(|mut segments| segments.next_back()).and_then(object_name)
+}
+
+// Percent-encode an object key for a request path, leaving its `/` separators
+pub fn encode_key(key: &str) -> String {
+    encode(key, b"-_.~/")
+}
+
+// Percent-encode a value used as a single path segment or query value
+pub fn encode_component(value: &str) -> String {
+    encode(value, b"-_.~")
+}
+
+fn encode(value: &str, unreserved: &[u8]) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || unreserved.contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// Read a variable from the environment, as storage credentials conventionally are
+pub fn env_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.trim().is_empty())
+}
+
+// Whether a url may be followed as a presigned object url: https to a host under
+// one of the provider's domains, or the configured emulator/endpoint host, and
+// carrying the provider's signature parameter. Keeps the service from being
+// pointed at arbitrary hosts on its network.
+pub fn signed_url(url: &str, domains: &[&str], endpoint: Option<&str>, signature: &str) -> Result<reqwest::Url, ApiError> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| ApiError::InvalidInput(format!("invalid presigned url: {}", e)))?;
+    let host = parsed.host_str().unwrap_or_default().to_ascii_lowercase();
+    let endpoint_host = endpoint
+        .and_then(|endpoint| reqwest::Url::parse(endpoint).ok())
+        .and_then(|endpoint| endpoint.host_str().map(str::to_ascii_lowercase));
+    let provider_host = parsed.scheme() == "https"
+        && domains.iter().any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)));
+    if !provider_host && endpoint_host.as_deref() != Some(host.as_str()) {
+        return Err(ApiError::InvalidInput(format!("presigned url host {} is not the storage service or its configured endpoint", host)));
+    }
+    if !parsed.query_pairs().any(|(name, _)| name == signature) {
+        return Err(ApiError::InvalidInput(format!("url is not presigned (no {})", signature)));
+    }
+    Ok(parsed)
+}
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|byte| byte ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(block.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// (year, month, day, hour, minute, second) in UTC of a Unix time, for the
+// request dates storage services sign
+pub fn civil_time(unix_seconds: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let days = (unix_seconds / 86_400) as i64;
+    let seconds = unix_seconds % 86_400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day, seconds / 3_600, seconds % 3_600 / 60, seconds % 60)
+}
+
+// "Fri, 16 Oct 2026 09:12:03 GMT" for a Unix time
+pub fn http_date(unix_seconds: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let (year, month, day, hour, minute, second) = civil_time(unix_seconds);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(unix_seconds / 86_400 % 7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        hour,
+        minute,
+        second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_media_type() {
+        assert_eq!(parse_media_type("application/PDF; charset=binary"), Some(("application".to_string(), "pdf".to_string())));
+        assert_eq!(parse_media_type("binary"), None);
+    }
+
+    #[test]
+    fn test_object_name_and_key_encoding() {
+        assert_eq!(object_name("exports/2026/report.pdf").as_deref(), Some("report.pdf"));
+        assert_eq!(object_name("exports/"), None);
+        assert_eq!(encode_key("exports/q3 report+final.pdf"), "exports/q3%20report%2Bfinal.pdf");
+        assert_eq!(encode_component("exports/q3 report.pdf"), "exports%2Fq3%20report.pdf");
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(hex(&mac), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn test_http_date() {
+        assert_eq!(http_date(1_792_141_923), "Fri, 16 Oct 2026 09:12:03 GMT");
+        assert_eq!(http_date(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::ApiError;
+use crate::model_info::unix_now;
+use crate::packs;
+use crate::profiles::{builtin_profiles, LabelProfile, ProfileRegistry};
+use crate::ProfilesState;
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Replicable configuration of an instance, exchanged between regional
+// deployments. Today that is the label profile registry; tenants, allow/deny
+// lists and watchlists do not exist in this service yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub exported_at: u64,
+    // SHA-256 of the canonical profile list, equal on every instance holding the
+    // same configuration; compare it to spot drift across the fleet
+    pub checksum: String,
+    pub profiles: Vec<LabelProfile>,
+}
+
+// Profiles sorted by name, serialized with sorted keys so the checksum does not
+// depend on map iteration order
+pub fn checksum(profiles: &[LabelProfile]) -> String {
+    let mut sorted: Vec<&LabelProfile> = profiles.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    let canonical = serde_json::to_value(&sorted).map(|value| value.to_string()).unwrap_or_default();
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn export(registry: &ProfileRegistry) -> ConfigBundle {
+    let mut profiles: Vec<LabelProfile> = registry.values().cloned().collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    ConfigBundle {
+        version: BUNDLE_VERSION,
+        exported_at: unix_now(),
+        checksum: checksum(&profiles),
+        profiles,
+    }
+}
+
+// Build the registry a bundle describes: the built-in presets, overridden or
+// extended by the bundle's profiles
+pub fn import(bundle: ConfigBundle) -> Result<ProfileRegistry, ApiError> {
+    if bundle.version != BUNDLE_VERSION {
+        return Err(ApiError::InvalidInput(format!(
+            "unsupported config bundle version {}, expected {}",
+            bundle.version, BUNDLE_VERSION
+        )));
+    }
+    if !bundle.checksum.is_empty() && bundle.checksum != checksum(&bundle.profiles) {
+        return Err(ApiError::InvalidInput("config bundle checksum does not match its profiles".to_string()));
+    }
+    if let Some(profile) = bundle.profiles.iter().find(|profile| profile.name.trim().is_empty() || profile.labels.is_empty()) {
+        return Err(ApiError::InvalidInput(format!("profile {:?} needs a name and at least one label", profile.name)));
+    }
+    for profile in &bundle.profiles {
+        packs::validate(&profile.packs)?;
+    }
+
+    let mut registry: ProfileRegistry = builtin_profiles()
+        .into_iter()
+        .map(|profile| (profile.name.clone(), profile))
+        .collect();
+    for profile in bundle.profiles {
+        registry.insert(profile.name.clone(), profile);
+    }
+    Ok(registry)
+}
+
+// Periodically pull the configuration of a peer instance and adopt it when it
+// differs from ours. Failures are logged and retried at the next interval.
+pub fn spawn_pull_sync(peer_url: String, token: Option<String>, interval: Duration, profiles: ProfilesState) {
+    // The legacy path, so peers from before /v1 existed can still be followed
+    let url = format!("{}/api/admin/config", peer_url.trim_end_matches('/'));
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match pull(&client, &url, token.as_deref()).await {
+                Ok(bundle) => apply_if_changed(&profiles, bundle).await,
+                Err(e) => tracing::warn!(url = %url, error = %e, "Config sync failed"),
+            }
+        }
+    });
+}
+
+async fn pull(client: &reqwest::Client, url: &str, token: Option<&str>) -> Result<ConfigBundle, String> {
+    let mut request = client.get(url).timeout(Duration::from_secs(10));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("peer answered {}", response.status()));
+    }
+    let body: crate::ApiResponse<ConfigBundle> = response.json().await.map_err(|e| e.to_string())?;
+    body.data.ok_or_else(|| "peer sent no configuration".to_string())
+}
+
+async fn apply_if_changed(profiles: &Arc<tokio::sync::RwLock<ProfileRegistry>>, bundle: ConfigBundle) {
+    let current = export(&*profiles.read().await).checksum;
+    if current == bundle.checksum {
+        return;
+    }
+    let checksum = bundle.checksum.clone();
+    match import(bundle) {
+        Ok(registry) => {
+            *profiles.write().await = registry;
+            tracing::info!(checksum = %checksum, "Config sync: adopted peer configuration");
+        }
+        Err(e) => tracing::warn!(error = %e, "Config sync: rejected peer configuration"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> ProfileRegistry {
+        builtin_profiles()
+            .into_iter()
+            .map(|profile| (profile.name.clone(), profile))
+            .collect()
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mut custom = builtin_profiles().remove(0);
+        custom.name = "hr".to_string();
+        custom.labels = vec!["person".to_string(), "salary".to_string()];
+        let mut source = registry();
+        source.insert(custom.name.clone(), custom);
+
+        let bundle = export(&source);
+        let json = serde_json::to_string(&bundle).unwrap();
+        let imported = import(serde_json::from_str(&json).unwrap()).unwrap();
+
+        assert_eq!(imported.len(), source.len());
+        assert_eq!(imported["hr"].labels, vec!["person", "salary"]);
+        assert_eq!(export(&imported).checksum, bundle.checksum);
+    }
+
+    #[test]
+    fn test_checksum_is_order_independent() {
+        let mut profiles = builtin_profiles();
+        let forward = checksum(&profiles);
+        profiles.reverse();
+        assert_eq!(checksum(&profiles), forward);
+    }
+
+    #[test]
+    fn test_import_rejects_tampered_bundle() {
+        let mut bundle = export(&registry());
+        bundle.profiles[0].threshold = 0.01;
+        assert!(import(bundle).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use crate::extract::{ExtractedText, SectionUnit};
+use crate::service::{FileEntity, PiiEntity};
+
+// Styling for standalone reports; labels are shown after each highlighted span
+const STYLE: &str = "body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 60rem; color: #1f2328; }
+table { border-collapse: collapse; margin-bottom: 2rem; }
+th, td { border: 1px solid #d0d7de; padding: 0.25rem 0.75rem; text-align: left; }
+pre { white-space: pre-wrap; word-wrap: break-word; background: #f6f8fa; padding: 1rem; border-radius: 6px; }
+mark { background: #fff1a8; border-radius: 3px; padding: 0 2px; }
+mark::after { content: attr(data-label); font-size: 0.7em; color: #6e4c00; margin-left: 0.3em; vertical-align: super; }";
+
+// A block of a report: its text with the entities found in it, under an
+// optional heading such as a page number or archive member path
+pub struct ReportSection<'a> {
+    pub heading: Option<String>,
+    pub text: &'a str,
+    pub entities: Vec<&'a PiiEntity>,
+}
+
+// Escape text for use in HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// The text as HTML with every entity wrapped in
+// `<mark data-label="..." data-score="...">`. Where entities overlap, the one
+// starting first (or, at the same start, the longer one) is marked.
+pub fn highlight(text: &str, entities: &[&PiiEntity]) -> String {
+    let mut sorted: Vec<&PiiEntity> = entities.to_vec();
+    sorted.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+    let mut html = String::with_capacity(text.len() + entities.len() * 64);
+    let mut position = 0;
+    for entity in sorted {
+        if entity.start < position || text.get(entity.start..entity.end).is_none() {
+            continue;
+        }
+        html.push_str(&escape(&text[position..entity.start]));
+        html.push_str(&format!(
+            "<mark data-label=\"{}\" data-score=\"{:.2}\">{}</mark>",
+            escape(&entity.label),
+            entity.confidence,
+            escape(&text[entity.start..entity.end])
+        ));
+        position = entity.end;
+    }
+    html.push_str(&escape(&text[position..]));
+    html
+}
+
+// Sections of an extracted file, each with the entities found in it. Pages and
+// email parts get a heading, prefixed with `prefix` (e.g. an archive member's
+// path) when given; blank sections are left out.
+pub fn file_sections<'a>(prefix: Option<&str>, text: &'a ExtractedText, entities: &'a [FileEntity]) -> Vec<ReportSection<'a>> {
+    let mut found: Vec<Vec<&PiiEntity>> = vec![Vec::new(); text.sections.len()];
+    for entity in entities {
+        let section = match (entity.page, entity.paragraph, &entity.part) {
+            (Some(page), _, _) => page - 1,
+            (_, Some(paragraph), _) => paragraph,
+            (_, _, Some(part)) => text.parts.iter().position(|name| name == part).unwrap_or(0),
+            _ => 0,
+        };
+        if let Some(section) = found.get_mut(section) {
+            section.push(&entity.entity);
+        }
+    }
+
+    text.sections
+        .iter()
+        .zip(found)
+        .enumerate()
+        .filter(|(_, (section, _))| !section.trim().is_empty())
+        .map(|(index, (section, entities))| {
+            let own = match text.unit {
+                SectionUnit::Page => Some(format!("page {}", index + 1)),
+                SectionUnit::MimePart => text.parts.get(index).cloned(),
+                SectionUnit::File | SectionUnit::Paragraph => None,
+            };
+            let heading = match (prefix, own) {
+                (Some(prefix), Some(own)) => Some(format!("{}, {}", prefix, own)),
+                (Some(prefix), None) => Some(prefix.to_string()),
+                (None, own) => own,
+            };
+            ReportSection {
+                heading,
+                text: section,
+                entities,
+            }
+        })
+        .collect()
+}
+
+// A standalone HTML page: a count of findings per label, then every section
+// with its findings highlighted. Paragraph sections run on under the previous
+// heading.
+pub fn report(title: &str, sections: &[ReportSection<'_>]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for entity in sections.iter().flat_map(|section| &section.entities) {
+        *counts.entry(entity.label.as_str()).or_default() += 1;
+    }
+    let total: usize = counts.values().sum();
+    let title = escape(title);
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>PII report: {}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>PII report: {}</h1>\n<p>{} finding{}</p>\n",
+        title,
+        STYLE,
+        title,
+        total,
+        if total == 1 { "" } else { "s" }
+    );
+    if !counts.is_empty() {
+        html.push_str("<table>\n<thead><tr><th>Label</th><th>Findings</th></tr></thead>\n<tbody>\n");
+        for (label, count) in &counts {
+            html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", escape(label), count));
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+    for section in sections {
+        html.push_str("<section>\n");
+        if let Some(heading) = &section.heading {
+            html.push_str(&format!("<h2>{}</h2>\n", escape(heading)));
+        }
+        html.push_str(&format!("<pre>{}</pre>\n</section>\n", highlight(section.text, &section.entities)));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_in;
+
+    #[test]
+    fn test_highlight_escapes_and_marks() {
+        let text = "<b>Jane Roe</b> & jane@example.com";
+        let person = entity_in(text, "Jane Roe", "person", 0.912);
+        let email = entity_in(text, "jane@example.com", "email", 0.99);
+        assert_eq!(
+            highlight(text, &[&email, &person]),
+            "&lt;b&gt;<mark data-label=\"person\" data-score=\"0.91\">Jane Roe</mark>&lt;/b&gt; &amp; \
+             <mark data-label=\"email\" data-score=\"0.99\">jane@example.com</mark>"
+        );
+    }
+
+    #[test]
+    fn test_highlight_skips_overlaps() {
+        let text = "Jane Roe";
+        let full = entity_in(text, "Jane Roe", "person", 0.9);
+        let first = entity_in(text, "Jane", "first name", 0.8);
+        let last = entity_in(text, "Roe", "last name", 0.8);
+        let html = highlight(text, &[&first, &last, &full]);
+        assert_eq!(html, "<mark data-label=\"person\" data-score=\"0.90\">Jane Roe</mark>");
+    }
+
+    #[test]
+    fn test_report_sections_and_summary() {
+        let text = ExtractedText {
+            unit: SectionUnit::Page,
+            sections: vec!["Call Jane".to_string(), " ".to_string(), "Mail jane@example.com".to_string()],
+            parts: Vec::new(),
+            encoding: None,
+        };
+        let entities = vec![FileEntity {
+            entity: entity_in(&text.sections[2], "jane@example.com", "email", 0.95),
+            page: Some(3),
+            paragraph: None,
+            part: None,
+            bbox: None,
+        }];
+        let sections = file_sections(Some("notes.pdf"), &text, &entities);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].heading.as_deref(), Some("notes.pdf, page 3"));
+        assert_eq!(sections[1].entities.len(), 1);
+
+        let html = report("notes.zip", &sections);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<p>1 finding</p>"));
+        assert!(html.contains("<tr><td>email</td><td>1</td></tr>"));
+        assert!(html.contains("<h2>notes.pdf, page 1</h2>"));
+    }
+}
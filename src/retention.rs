@@ -0,0 +1,216 @@
+// Retention of what the service keeps about past calls: stored results and
+// audit events. Both hold evidence of sensitive data, so each can be given a
+// time to live, enforced by a background sweeper, and purged on demand.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::audit::AuditState;
+use crate::clock::{self, SharedClock};
+use crate::config::Config;
+use crate::error::ApiError;
+#[cfg(feature = "persistence")]
+use crate::persistence::ResultStoreState;
+
+// Age in seconds from a number of seconds or a number with a unit: "90", "30m",
+// "12h", "7d"
+pub fn parse_age(value: &str) -> Result<u64, ApiError> {
+    let value = value.trim();
+    let invalid = || ApiError::InvalidInput(format!("invalid age {:?}, expected seconds or a number with s, m, h or d", value));
+    let (number, unit) = match value.char_indices().last() {
+        Some((index, unit)) if unit.is_ascii_alphabetic() => (&value[..index], unit),
+        _ => (value, 's'),
+    };
+    let scale = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3_600,
+        'd' => 86_400,
+        _ => return Err(invalid()),
+    };
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    number.checked_mul(scale).ok_or_else(invalid)
+}
+
+// What one purge removed; counts are None for stores that are not configured
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PurgeReport {
+    // Unix seconds; everything older was removed
+    pub cutoff: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_events: Option<u64>,
+}
+
+// The stores retention applies to, whichever are configured, and the clock
+// ages are measured against
+#[derive(Clone)]
+pub struct Retention {
+    pub audit: Option<AuditState>,
+    #[cfg(feature = "persistence")]
+    pub results: Option<ResultStoreState>,
+    pub clock: SharedClock,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            audit: None,
+            #[cfg(feature = "persistence")]
+            results: None,
+            clock: clock::system(),
+        }
+    }
+}
+
+impl Retention {
+    // Unix seconds before which records are older than `age`
+    pub fn cutoff(&self, age: u64) -> u64 {
+        self.clock.unix_now().saturating_sub(age)
+    }
+
+    pub async fn purge_results(&self, cutoff: u64) -> Result<Option<u64>, ApiError> {
+        #[cfg(feature = "persistence")]
+        if let Some(store) = &self.results {
+            let removed = store
+                .purge_results(cutoff)
+                .await
+                .map_err(|e| ApiError::StorageFailed(format!("could not purge stored results: {}", e)))?;
+            return Ok(Some(removed));
+        }
+        let _ = cutoff;
+        Ok(None)
+    }
+
+    pub async fn purge_audit(&self, cutoff: u64) -> Result<Option<u64>, ApiError> {
+        let mut removed = None;
+        if let Some(audit) = self.audit.clone() {
+            let count = tokio::task::spawn_blocking(move || audit.purge(cutoff))
+                .await
+                .map_err(|e| ApiError::StorageFailed(e.to_string()))?
+                .map_err(|e| ApiError::StorageFailed(format!("could not purge audit events: {}", e)))?;
+            removed = Some(count);
+        }
+        #[cfg(feature = "persistence")]
+        if let Some(store) = &self.results {
+            let count = store
+                .purge_audit(cutoff)
+                .await
+                .map_err(|e| ApiError::StorageFailed(format!("could not purge audit events: {}", e)))?;
+            removed = Some(removed.unwrap_or(0) + count);
+        }
+        Ok(removed)
+    }
+
+    // Apply the configured time to live of results and audit events once; one
+    // report per store with a time to live, each with its own cutoff
+    pub async fn sweep(&self, config: &Config) -> Vec<PurgeReport> {
+        let mut reports = Vec::new();
+        if let Some(ttl) = config.results_ttl_secs {
+            let cutoff = self.cutoff(ttl);
+            match self.purge_results(cutoff).await {
+                Ok(removed) => reports.push(PurgeReport { cutoff, results: removed, audit_events: None }),
+                Err(e) => tracing::error!(error = %e, "Retention sweep of stored results failed"),
+            }
+        }
+        if let Some(ttl) = config.audit_ttl_secs {
+            let cutoff = self.cutoff(ttl);
+            match self.purge_audit(cutoff).await {
+                Ok(removed) => reports.push(PurgeReport { cutoff, results: None, audit_events: removed }),
+                Err(e) => tracing::error!(error = %e, "Retention sweep of audit events failed"),
+            }
+        }
+        reports
+    }
+}
+
+// Sweep expired results and audit events every GLINER_RETENTION_SWEEP_SECS,
+// when either has a time to live
+pub fn spawn_sweeper(retention: Retention, config: Config) {
+    if config.results_ttl_secs.is_none() && config.audit_ttl_secs.is_none() {
+        return;
+    }
+    let interval = Duration::from_secs(config.retention_sweep_secs.max(1));
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for report in retention.sweep(&config).await {
+                if report.results.unwrap_or(0) + report.audit_events.unwrap_or(0) > 0 {
+                    tracing::info!(
+                        cutoff = report.cutoff,
+                        results = report.results.unwrap_or(0),
+                        audit_events = report.audit_events.unwrap_or(0),
+                        "Retention sweep removed expired records"
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+    use crate::clock::{Clock, TestClock};
+    use crate::test_support::audit_event;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90").unwrap(), 90);
+        assert_eq!(parse_age("30m").unwrap(), 1_800);
+        assert_eq!(parse_age("12h").unwrap(), 43_200);
+        assert_eq!(parse_age("7d").unwrap(), 604_800);
+        assert!(parse_age("").is_err());
+        assert!(parse_age("7w").is_err());
+        assert!(parse_age("-1d").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_without_stores() {
+        let retention = Retention::default();
+        assert_eq!(retention.purge_results(100).await.unwrap(), None);
+        assert_eq!(retention.purge_audit(100).await.unwrap(), None);
+
+        let retention = Retention {
+            audit: Some(Arc::new(AuditLog::new(Vec::new(), 10))),
+            ..Retention::default()
+        };
+        assert_eq!(retention.purge_audit(100).await.unwrap(), Some(0));
+    }
+
+    // The update fills `results` when the persistence feature is on
+    #[allow(clippy::needless_update)]
+    #[tokio::test]
+    async fn test_sweep_expires_by_clock_and_reports_cutoffs() {
+        let clock = TestClock::new();
+        let audit = Arc::new(AuditLog::new(Vec::new(), 10));
+        let start = clock.unix_now();
+        audit.record(audit_event(start));
+        audit.record(audit_event(start + 60));
+        let retention = Retention {
+            audit: Some(audit.clone()),
+            clock: clock.clone(),
+            ..Retention::default()
+        };
+        let config = Config {
+            audit_ttl_secs: Some(100),
+            ..Config::default()
+        };
+
+        // Neither event is past its time to live yet
+        let reports = retention.sweep(&config).await;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].cutoff, start - 100);
+        assert_eq!(reports[0].audit_events, Some(0));
+
+        clock.advance(Duration::from_secs(130));
+        let reports = retention.sweep(&config).await;
+        assert_eq!(reports[0].cutoff, start + 30);
+        assert_eq!(reports[0].audit_events, Some(1));
+        assert_eq!(audit.recent(10, None).len(), 1);
+    }
+}
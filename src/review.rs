@@ -0,0 +1,191 @@
+// Human review of uncertain findings: findings of single-text detections scored
+// inside the review band (GLINER_REVIEW_CONFIDENCE_MIN up to
+// GLINER_REVIEW_CONFIDENCE_MAX) wait in a pending queue until a reviewer
+// approves or rejects them through /api/review. Decisions become corrected
+// examples in the feedback store, so they reach the training export.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::feedback::{FeedbackExample, FeedbackSource, FeedbackStore};
+use crate::model_info::unix_now;
+use crate::PiiEntity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub id: u64,
+    pub request_id: String,
+    // Unix seconds
+    pub created_at: u64,
+    // The finding to decide on
+    pub entity: PiiEntity,
+    pub text: String,
+    // Every finding of the detection, for context; offsets are into `text`
+    pub entities: Vec<PiiEntity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewOutcome {
+    pub id: u64,
+    pub decision: Decision,
+    // Whether the decision was added to the feedback store
+    pub recorded: bool,
+}
+
+pub struct ReviewQueue {
+    capacity: usize,
+    confidence_min: f32,
+    confidence_max: f32,
+    next_id: AtomicU64,
+    pending: Mutex<VecDeque<ReviewItem>>,
+}
+
+pub type ReviewState = Arc<Option<ReviewQueue>>;
+
+impl ReviewQueue {
+    pub fn new(capacity: usize, confidence_min: f32, confidence_max: f32) -> Self {
+        ReviewQueue {
+            capacity,
+            confidence_min,
+            confidence_max,
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (config.review_capacity > 0).then(|| {
+            ReviewQueue::new(config.review_capacity, config.review_confidence_min, config.review_confidence_max)
+        })
+    }
+
+    // Queue the findings of a detection that fall in the review band, returning
+    // how many were queued. When the queue is full the oldest items are dropped.
+    pub fn submit(&self, request_id: &str, text: &str, entities: &[PiiEntity]) -> usize {
+        let uncertain: Vec<&PiiEntity> = entities
+            .iter()
+            .filter(|entity| entity.confidence >= self.confidence_min && entity.confidence < self.confidence_max)
+            .collect();
+        if uncertain.is_empty() {
+            return 0;
+        }
+        let mut pending = self.lock();
+        for entity in &uncertain {
+            pending.push_back(ReviewItem {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                request_id: request_id.to_string(),
+                created_at: unix_now(),
+                entity: (*entity).clone(),
+                text: text.to_string(),
+                entities: entities.to_vec(),
+            });
+        }
+        while pending.len() > self.capacity {
+            if let Some(dropped) = pending.pop_front() {
+                tracing::warn!(id = dropped.id, "Review queue full; dropped the oldest pending item");
+            }
+        }
+        uncertain.len()
+    }
+
+    // Pending items, oldest first
+    pub fn pending(&self, limit: usize) -> Vec<ReviewItem> {
+        self.lock().iter().take(limit).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Take an item off the queue with the reviewer's decision. The detection's
+    // findings, with the reviewed one kept or removed, go to the feedback store.
+    pub fn decide(&self, id: u64, decision: Decision, feedback: Option<&FeedbackStore>) -> Result<ReviewOutcome, ApiError> {
+        let item = {
+            let mut pending = self.lock();
+            let index = pending
+                .iter()
+                .position(|item| item.id == id)
+                .ok_or_else(|| ApiError::NotFound(format!("no pending review item {}", id)))?;
+            pending.remove(index).expect("index found above")
+        };
+        let reviewed = |entity: &PiiEntity| entity.start == item.entity.start && entity.end == item.entity.end && entity.label == item.entity.label;
+        let mut entities: Vec<PiiEntity> = item.entities.iter().filter(|entity| !reviewed(entity)).cloned().collect();
+        if decision == Decision::Approve {
+            entities.push(PiiEntity {
+                confidence: 1.0,
+                ..item.entity.clone()
+            });
+        }
+        if let Some(feedback) = feedback {
+            feedback.record(FeedbackExample {
+                text: item.text,
+                entities,
+                source: FeedbackSource::Review,
+                recorded_at: unix_now(),
+            });
+        }
+        tracing::info!(id, request_id = %item.request_id, label = %item.entity.label, ?decision, "Review decision recorded");
+        Ok(ReviewOutcome {
+            id,
+            decision,
+            recorded: feedback.is_some(),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<ReviewItem>> {
+        self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    fn detection() -> Vec<PiiEntity> {
+        vec![entity("person", 5, 13, 0.95), entity("phone", 17, 25, 0.45)]
+    }
+
+    #[test]
+    fn test_only_the_band_is_queued() {
+        let queue = ReviewQueue::new(10, 0.3, 0.6);
+        assert_eq!(queue.submit("req-1", "Call Jane Roe at 555-0100", &detection()), 1);
+        let pending = queue.pending(10);
+        assert_eq!(pending[0].entity.label, "phone");
+        assert_eq!(pending[0].entities.len(), 2);
+    }
+
+    #[test]
+    fn test_decisions_feed_the_feedback_store() {
+        let queue = ReviewQueue::new(10, 0.3, 0.6);
+        let feedback = FeedbackStore::new(10, 0.7, 100.0);
+        queue.submit("req-1", "Call Jane Roe at 555-0100", &detection());
+        queue.submit("req-2", "Call Jane Roe at 555-0100", &detection());
+        let ids: Vec<u64> = queue.pending(10).iter().map(|item| item.id).collect();
+
+        let approved = queue.decide(ids[0], Decision::Approve, Some(&feedback)).unwrap();
+        assert!(approved.recorded);
+        queue.decide(ids[1], Decision::Reject, Some(&feedback)).unwrap();
+        assert!(queue.is_empty());
+
+        let exported = feedback.export(false);
+        assert_eq!(exported[0].ner.len(), 2);
+        assert_eq!(exported[1].ner.len(), 1);
+        assert!(matches!(queue.decide(ids[0], Decision::Approve, None), Err(ApiError::NotFound(_))));
+    }
+}
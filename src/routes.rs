@@ -0,0 +1,4711 @@
+// HTTP layer: Rocket routes, request guards and managed state, on top of the
+// Rocket-free detection core in `service`. Only built with the `server` feature.
+
+use rocket::{delete, get, post, put, launch, routes, catchers, serde::json::Json, http::Status, Build, Rocket, State};
+use rocket::data::{ByteUnit, Data, Limits};
+use rocket::form::{Form, FromForm};
+use rocket::fs::TempFile;
+use rocket::futures::Stream;
+use rocket::http::ContentType;
+use rocket::response::content::RawHtml;
+use rocket::response::stream::{Event, EventStream, TextStream};
+use rocket::Responder;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use gliner::model::input::text::TextInput;
+
+#[cfg(feature = "dev")]
+use crate::synthetic;
+use crate::{archive, benchmark, chunking, clock, compare, conll, context, eval, csv_scan, detection, diff, error, fields, filters, grouping, json_scan, logging, ocr, pipeline, prepass, presidio, privacy, profiles, recognizers, replication, report, retention, s3, sarif, sentences, timing, validation};
+use crate::admin::{AdminAuth, AdminStats, DrainStatus};
+use crate::benchmark::{BenchmarkReport, BenchmarkRequest};
+use crate::audit::{AuditEvent, AuditFindings, AuditRecorder, AuditState, AuditTrail};
+use crate::archive::{ArchiveContents, ArchiveFormat, ArchiveLimits, SkippedMember};
+use crate::config::Config;
+use crate::csv_scan::{ColumnSummary, CsvHit, CsvOptions};
+use crate::diff::DiffFinding;
+use crate::filters::ResponseFilter;
+use crate::json_scan::JsonFinding;
+use crate::loading::{self, ModelLoad, ModelLoadState, ModelStatus};
+use crate::log_scan::{LineBatcher, LogFinding, LogLine, LogSummary, SummaryLine};
+use crate::logging::{RequestId, RequestLog};
+use crate::clock::SharedClock;
+use crate::codec::{Body, Encoded};
+#[cfg(feature = "protobuf")]
+use crate::codec::Protobuf;
+use crate::error::ApiError;
+use crate::eval::{EvalReport, EvalRequest, SweepReport, SweepRequest};
+use crate::extract::{DocumentFormat, ExtractedText, SectionUnit};
+use crate::feedback::{FeedbackState, FeedbackStore};
+use crate::review::{Decision, ReviewItem, ReviewOutcome, ReviewQueue, ReviewState};
+use crate::fields::FieldSelection;
+use crate::training::TrainingExample;
+use crate::breaker::{BreakerSettings, CircuitBreaker};
+use crate::charset::TextEncoding;
+use crate::canary::{Canary, CanaryState};
+use crate::compare::{CompareRequest, ModelComparison};
+use crate::discovery::{self, DiscoverRequest, Discovery};
+use crate::shadow::{self, Shadow, ShadowDiff, ShadowRun, ShadowState};
+use crate::health::{self, SelfTest, SelfTestResult};
+use crate::html::{InputFormat, VisibleText};
+use crate::markdown;
+use crate::normalization::Normalization;
+use crate::offsets::OffsetIndex;
+use crate::inference::{run_with_deadline, InferenceProgress, InferenceQueue, RequestDeadline};
+use crate::metrics::{Metrics, MetricsSnapshot};
+use crate::model_info::{unix_now, ModelInfo};
+use crate::ocr::{BoundingBox, OcrExtraction, OcrSettings};
+use crate::output::OutputFormat;
+use crate::masking::apply_masking;
+use crate::pool::{Model, ModelPool, Priority};
+use crate::aliases::LabelMap;
+use crate::language::{self, LanguageRouter, LanguageRouterState};
+use crate::plugins::{Plugins, PluginsState};
+use crate::prepass::Prepass;
+#[cfg(feature = "persistence")]
+use crate::persistence::{ResultRecorder, ResultStore, ResultStoreState, ResultSummary, StoredResult};
+#[cfg(feature = "protobuf")]
+use crate::proto;
+use crate::presidio::{AnalyzeRequest, RecognizerResult};
+use crate::profiles::{LabelProfile, ProfileRegistry};
+use crate::recognizers::RecognizerOptions;
+use crate::replication::ConfigBundle;
+use crate::retention::{parse_age, PurgeReport, Retention};
+use crate::remote::RemoteObject;
+use crate::s3::S3Object;
+use crate::schedule::{RunDeadLetters, ScanEngine, ScheduleSpec, ScheduleStatus, Scheduler, SchedulerState};
+#[cfg(feature = "gcs")]
+use crate::gcs::{self, GcsObject};
+#[cfg(feature = "azure")]
+use crate::azure::{self, AzureBlob};
+use crate::service::{apply_profile, profile_labels, FileEntity, ModelState, PiiEntity};
+use crate::streaming::{events_with_deadline, stream_with_deadline};
+use crate::timing::{StageTimings, Stopwatch};
+use crate::versioning::{VersionHeaders, API_VERSION, LEGACY_BASE, V1_BASE};
+
+#[derive(Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub status: String,
+    pub message: String,
+    // Outcome of the inference self-test, when one was asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_test: Option<SelfTestResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+    // Version of the route group that answered, see `versioning`
+    #[serde(default = "default_api_version")]
+    pub api_version: u32,
+}
+
+fn default_api_version() -> u32 {
+    API_VERSION
+}
+
+// Result type for handlers that can fail
+pub type ApiResult<T> = Result<Json<ApiResponse<T>>, ApiError>;
+
+// Result type for handlers that answer in JSON, MessagePack or CBOR, following
+// the Accept header
+pub type EncodedResult<T> = Result<Encoded<ApiResponse<T>>, ApiError>;
+
+// Results of a file scan: JSON (including SARIF) or a standalone HTML report
+#[derive(Responder)]
+pub enum ScanOutput {
+    Json(Json<ApiResponse<serde_json::Value>>),
+    Html(RawHtml<String>),
+}
+
+// Caller-supplied correlation data, echoed untouched wherever the document's
+// results show up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentRef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl DocumentRef {
+    // Copy the id and metadata into a JSON object result
+    pub fn echo_into(&self, result: &mut serde_json::Value) {
+        if let Some(object) = result.as_object_mut() {
+            if let Some(id) = &self.id {
+                object.insert("id".to_string(), serde_json::Value::String(id.clone()));
+            }
+            if let Some(metadata) = &self.metadata {
+                object.insert("metadata".to_string(), serde_json::Value::Object(metadata.clone()));
+            }
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct PiiRequest {
+    pub text: String,
+    #[serde(flatten)]
+    pub reference: DocumentRef,
+    // Named label profile, e.g. "hipaa"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    // Entity fields to return; the `fields` query parameter takes precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+    // "html" or "markdown" to detect on the visible text and report offsets
+    // into the markup
+    #[serde(default)]
+    pub input_format: InputFormat,
+    // With markdown input, leave code blocks and code spans out of detection
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_code_blocks: bool,
+    // "sarif" to get the findings as a SARIF 2.1.0 log instead, "html" for the
+    // text with findings highlighted, "conll" for IOB2 tags, or "presidio" for
+    // Presidio analyzer results
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    #[serde(flatten)]
+    pub recognizers: RecognizerOptions,
+    // Leave the text and matched spans out of the response; always on when
+    // GLINER_PRIVACY_MODE is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy_mode: Option<bool>,
+    // Add per-stage timings and token counts to the response as `debug`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub debug: bool,
+    // Characters of surrounding text to report with each entity as `context`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_chars: Option<usize>,
+    // Detect sentence by sentence, batched, and add the findings grouped by
+    // sentence as `sentences`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sentences: bool,
+    // Add `groups`: the findings that are mentions of the same entity
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub groups: bool,
+    // Add `offsets` to each entity: its span in UTF-8 bytes, UTF-16 code units
+    // and chars
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub offset_units: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BatchDocument {
+    pub text: String,
+    #[serde(flatten)]
+    pub reference: DocumentRef,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub documents: Vec<BatchDocument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+    // Applies to every document of the batch
+    #[serde(default)]
+    pub input_format: InputFormat,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_code_blocks: bool,
+    // "conll" for IOB2 tags of every document instead, or "presidio" for an
+    // array of Presidio analyzer results per document; SARIF and HTML are
+    // single-document formats
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    #[serde(flatten)]
+    pub recognizers: RecognizerOptions,
+    // Leave matched spans out of the results; always on when
+    // GLINER_PRIVACY_MODE is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy_mode: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DocumentResult {
+    pub index: usize,
+    #[serde(flatten)]
+    pub reference: DocumentRef,
+    pub entities: Vec<PiiEntity>,
+    pub total_entities: usize,
+    // Text with findings masked per the selected profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted_text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BatchResponse {
+    pub results: Vec<DocumentResult>,
+    pub total_documents: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub total_chunks: usize,
+    pub escalated_chunks: usize,
+    pub inference_batches: usize,
+    pub padding_efficiency: f64,
+}
+
+// Multipart body of POST /api/pii/scan/file
+#[derive(FromForm)]
+pub struct FileScanForm<'r> {
+    pub file: TempFile<'r>,
+    pub profile: Option<String>,
+    // Comma-separated entity fields to return
+    pub fields: Option<String>,
+    // "json" (default), "sarif", or "html" for a standalone report page
+    pub output_format: Option<String>,
+}
+
+// Settings of a scan of a stored object, the same as those of a file upload
+#[derive(Deserialize, Default)]
+pub struct ObjectScanSettings {
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub output_format: Option<String>,
+}
+
+// Body of POST /api/pii/scan/s3: the object, by bucket and key or presigned url
+#[derive(Deserialize)]
+pub struct S3ScanRequest {
+    #[serde(flatten)]
+    pub object: S3Object,
+    #[serde(flatten)]
+    pub settings: ObjectScanSettings,
+}
+
+// Body of POST /api/pii/scan/gcs: the object, by bucket and object name or signed url
+#[cfg(feature = "gcs")]
+#[derive(Deserialize)]
+pub struct GcsScanRequest {
+    #[serde(flatten)]
+    pub object: GcsObject,
+    #[serde(flatten)]
+    pub settings: ObjectScanSettings,
+}
+
+// Body of POST /api/pii/scan/azure: the blob, by container and blob name or SAS url
+#[cfg(feature = "azure")]
+#[derive(Deserialize)]
+pub struct AzureScanRequest {
+    #[serde(flatten)]
+    pub blob: AzureBlob,
+    #[serde(flatten)]
+    pub settings: ObjectScanSettings,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileScanResponse {
+    pub filename: Option<String>,
+    pub format: DocumentFormat,
+    // Encoding a text file was decoded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<TextEncoding>,
+    pub size_bytes: usize,
+    // Offsets are byte offsets into the uploaded file (into its UTF-8
+    // transcoding when it was in another encoding), or into the extracted text
+    // of the entity's page, paragraph or email part
+    pub entities: Vec<FileEntity>,
+    pub total_entities: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted_text: Option<String>,
+    pub total_chunks: usize,
+    pub inference_batches: usize,
+}
+
+// Findings for one member of an uploaded archive
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveMemberResult {
+    pub path: String,
+    pub format: DocumentFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<TextEncoding>,
+    pub entities: Vec<FileEntity>,
+    pub total_entities: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted_text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveScanResponse {
+    pub filename: Option<String>,
+    pub archive: ArchiveFormat,
+    pub size_bytes: usize,
+    // Scanned members in archive order, with offsets relative to each member
+    pub members: Vec<ArchiveMemberResult>,
+    // Members that were not scanned (unsupported type, too large, unreadable)
+    pub skipped: Vec<SkippedMember>,
+    pub total_entities: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub total_chunks: usize,
+    pub inference_batches: usize,
+}
+
+// Multipart body of POST /api/pii/scan/csv
+#[derive(FromForm)]
+pub struct CsvScanForm<'r> {
+    pub file: TempFile<'r>,
+    // Comma-separated header names or 0-based indices; all columns when absent
+    pub columns: Option<String>,
+    pub has_headers: Option<bool>,
+    // Single-character field delimiter, "," by default
+    pub delimiter: Option<String>,
+    pub profile: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CsvScanResponse {
+    pub filename: Option<String>,
+    pub rows: usize,
+    pub cells_scanned: usize,
+    pub columns: Vec<ColumnSummary>,
+    pub hits: Vec<CsvHit>,
+    pub total_hits: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub inference_batches: usize,
+}
+
+// Body of POST /api/pii/scan/json: any JSON document, scanned string by string
+#[derive(Serialize, Deserialize)]
+pub struct JsonScanRequest {
+    pub document: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(flatten)]
+    pub recognizers: RecognizerOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsonScanResponse {
+    pub strings_scanned: usize,
+    // Strings containing entities, keyed by JSON Pointer, in document order
+    pub findings: Vec<JsonFinding>,
+    pub total_hits: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub inference_batches: usize,
+}
+
+// Body of POST /api/pii/scan/diff: a unified diff, of which only added lines are scanned
+#[derive(Serialize, Deserialize)]
+pub struct DiffScanRequest {
+    pub diff: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(flatten)]
+    pub recognizers: RecognizerOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DiffScanResponse {
+    pub files_scanned: usize,
+    pub lines_scanned: usize,
+    // Findings by file path and line number in the new version, in diff order
+    pub findings: Vec<DiffFinding>,
+    pub total_hits: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub inference_batches: usize,
+}
+
+// Details of the loaded model, None until loading succeeds
+pub type ModelInfoState = Arc<Mutex<Option<ModelInfo>>>;
+
+// Optional first-pass screen in front of the main model
+pub type PrepassState = Arc<Option<Prepass>>;
+
+// Admission control in front of the model
+pub type QueueState = Arc<InferenceQueue>;
+
+// Named label profiles (built-in presets plus configured ones), replaceable at
+// runtime through config import and peer sync
+pub type ProfilesState = Arc<RwLock<ProfileRegistry>>;
+
+// Shared counters
+pub type MetricsState = Arc<Metrics>;
+
+// Readiness flag, only set once the model is loaded and warmed up; clones share it
+#[derive(Default, Clone)]
+pub struct Readiness {
+    ready: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::SeqCst);
+    }
+}
+
+#[get("/health")]
+pub fn health_check() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+        message: "API is running".to_string(),
+        self_test: None,
+    })
+}
+
+// With `?self_test=true` a ready instance also runs (or reuses) a tiny inference
+// and reports 503 "degraded" when it was too slow or failed
+#[get("/health/ready?<self_test>")]
+pub async fn readiness_check(
+    self_test: Option<bool>,
+    readiness: &State<Readiness>,
+    queue: &State<QueueState>,
+    model_state: &State<ModelState>,
+    self_tests: &State<SelfTest>,
+    config: &State<Config>,
+) -> (Status, Json<HealthResponse>) {
+    if queue.is_draining() {
+        (Status::ServiceUnavailable, Json(HealthResponse {
+            status: "draining".to_string(),
+            message: "Instance is draining and should be taken out of rotation".to_string(),
+            self_test: None,
+        }))
+    } else if queue.breaker().is_open() {
+        (Status::ServiceUnavailable, Json(HealthResponse {
+            status: "circuit_open".to_string(),
+            message: "Inference is failing repeatedly; the model is being reloaded".to_string(),
+            self_test: None,
+        }))
+    } else if readiness.is_ready() {
+        if self_test != Some(true) {
+            return (Status::Ok, Json(HealthResponse {
+                status: "ready".to_string(),
+                message: "Model loaded and warmed up".to_string(),
+                self_test: None,
+            }));
+        }
+        let result = self_tests.check(model_state, queue, config).await;
+        if result.degraded {
+            (Status::ServiceUnavailable, Json(HealthResponse {
+                status: "degraded".to_string(),
+                message: "Inference self-test was too slow or failed".to_string(),
+                self_test: Some(result),
+            }))
+        } else {
+            (Status::Ok, Json(HealthResponse {
+                status: "ready".to_string(),
+                message: "Model loaded and inference self-test passed".to_string(),
+                self_test: Some(result),
+            }))
+        }
+    } else {
+        (Status::ServiceUnavailable, Json(HealthResponse {
+            status: "not_ready".to_string(),
+            message: "Model is not loaded or still warming up".to_string(),
+            self_test: None,
+        }))
+    }
+}
+
+#[get("/")]
+pub fn index() -> Json<ApiResponse<String>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some("Welcome to Gliner RS API".to_string()),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+#[get("/version")]
+pub fn version() -> Json<ApiResponse<String>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some("0.1.0".to_string()),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+#[get("/metrics")]
+pub async fn get_metrics(
+    metrics: &State<MetricsState>,
+    queue: &State<QueueState>,
+    model_state: &State<ModelState>,
+) -> Json<ApiResponse<MetricsSnapshot>> {
+    let mut snapshot = metrics.snapshot();
+    snapshot.queue_depth = queue.depth();
+    snapshot.queue_capacity = queue.capacity();
+    snapshot.session_pool = model_state.lock().await.as_ref().map(|pool| pool.snapshot());
+
+    Json(ApiResponse {
+        success: true,
+        data: Some(snapshot),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// Where loading the model has got to: pending, downloading (with progress),
+// loading, warming, ready or failed
+#[get("/model/status")]
+pub fn get_model_status(model_load: &State<ModelLoadState>) -> Json<ApiResponse<ModelStatus>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some(model_load.status()),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+#[get("/model")]
+pub async fn get_model_info(info_state: &State<ModelInfoState>) -> ApiResult<ModelInfo> {
+    let info = info_state.lock().await.clone().ok_or(ApiError::ModelNotLoaded)?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(info),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+#[post("/pii/detect?<fields>&<labels>&<min_confidence>&<counts_only>", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii(
+    request: Body<PiiRequest>,
+    fields: Option<&str>,
+    labels: Option<&str>,
+    min_confidence: Option<f32>,
+    counts_only: Option<bool>,
+    request_id: RequestId,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    canary: &State<CanaryState>,
+    shadow: &State<ShadowState>,
+    audit_log: &State<Option<AuditState>>,
+    feedback: &State<FeedbackState>,
+    review: &State<ReviewState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    plugins: &State<PluginsState>,
+    languages: &State<LanguageRouterState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> EncodedResult<serde_json::Value> {
+    let mut stopwatch = Stopwatch::start();
+    let visible = visible_text(&request.text, request.input_format, request.skip_code_blocks, &Normalization::from_config(config))?;
+    validation::validate_text(&visible.text, config)?;
+    let private = privacy::enabled(config, request.privacy_mode);
+    audit.set_private(private);
+    privacy::check_output_format(private, request.output_format)?;
+    if let Some(chars) = request.context_chars {
+        context::validate(chars)?;
+        privacy::check_context(private)?;
+    }
+    let selection = privacy::selection(FieldSelection::resolve(fields, request.fields.as_deref())?, private);
+    let filter = ResponseFilter::parse(labels, min_confidence, counts_only)?;
+    request.recognizers.validate()?;
+    if filter.counts_only && request.output_format != OutputFormat::Json {
+        return Err(ApiError::InvalidInput("counts_only only applies to the json output format".to_string()));
+    }
+    let profile = profiles::resolve(&*profiles.read().await, request.profile.as_deref())?.cloned();
+
+    let validation_ms = stopwatch.lap();
+
+    // Text in a language with a model of its own goes to that model; otherwise,
+    // during a canary rollout, the canary model serves its share of requests
+    let language = (config.language_detection || !languages.is_empty())
+        .then(|| language::detect(&visible.text))
+        .flatten();
+    let routed = language.as_ref().and_then(|language| languages.pick(&language.code, config));
+    let rollout = Option::as_ref(canary.inner());
+    let (serving, served_by) = routed
+        .or_else(|| rollout.filter(|canary| canary.pick()).map(|canary| (canary.model_state(), canary.model())))
+        .unwrap_or((model_state.inner(), config.model_name.as_str()));
+
+    let text = visible.text.clone();
+    let prepass = prepass.inner().clone();
+    let labels = profile_labels(profile.as_ref());
+    // What the model is asked for, with label aliases and prompts resolved
+    let prompts: Vec<String> = {
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let label_map = LabelMap::resolve(&labels, config, request.recognizers.label_prompts.as_ref());
+        label_map.prompts().into_iter().map(str::to_string).collect()
+    };
+    let model_prompts = prompts.clone();
+    let sentences = request.sentences.then(|| sentences::split(&visible.text));
+    let segments = sentences.clone().map(|sentences| (sentences, request.recognizers.clone(), config.inner().clone()));
+    let submitted = std::time::Instant::now();
+    let (entities, model_timings) = run_with_deadline(serving, queue, deadline, move |model, progress| {
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let prompts: Vec<&str> = model_prompts.iter().map(String::as_str).collect();
+        let mut timings = StageTimings {
+            queue_ms: timing::millis(submitted.elapsed()),
+            ..StageTimings::default()
+        };
+        let mut stage = Stopwatch::start();
+
+        // In sentence mode every sentence is a document of one batched run
+        if let Some((sentences, recognizer_options, config)) = &segments {
+            let texts: Vec<&str> = sentences.iter().map(|sentence| &text[sentence.start..sentence.end]).collect();
+            let batch = detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, recognizer_options, config, progress)
+                .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+            timings.inference_ms = stage.lap();
+            let entities = batch
+                .documents
+                .into_iter()
+                .zip(sentences)
+                .flat_map(|(entities, sentence)| {
+                    entities.into_iter().map(move |entity| PiiEntity {
+                        start: sentence.start + entity.start,
+                        end: sentence.start + entity.end,
+                        ..entity
+                    })
+                })
+                .collect();
+            return Ok((Some(entities), timings));
+        }
+
+        // Texts the pre-pass finds nothing in never reach the main model
+        if let Some(prepass) = Option::as_ref(&prepass) {
+            let flags = prepass
+                .candidates(&[&text], &prompts, 1)
+                .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+            timings.prepass_ms = Some(stage.lap());
+            if !flags.iter().any(|&flagged| flagged) {
+                return Ok((None, timings));
+            }
+        }
+
+        // Create text input for GLiNER - using the exact API from the docs
+        let text_input = TextInput::from_str(&[&text], &prompts)
+            .map_err(|e| ApiError::InvalidInput(format!("{}: {}", privacy::MODEL_INPUT_ERROR, e)))?;
+        timings.tokenization_ms = stage.lap();
+
+        // Run inference using the exact API from the docs
+        let output = model
+            .inference(text_input)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+        timings.inference_ms = stage.lap();
+
+        // Extract entities from the output
+        let mut entities = Vec::new();
+        for spans in &output.spans {
+            for span in spans {
+                let (start, end) = span.offsets();
+                entities.push(PiiEntity {
+                    text: span.text().to_string(),
+                    label: span.class().to_string(),
+                    confidence: span.probability(),
+                    start,
+                    end,
+                    attributes: BTreeMap::new(),
+                });
+            }
+        }
+        timings.post_processing_ms = stage.lap();
+        Ok((Some(entities), timings))
+    })
+    .await
+    .map_err(|e| privacy::scrub_error(private, e))?;
+    // Queue wait and model stages were measured by the task
+    stopwatch.lap();
+    let served_by = (rollout.is_some() || !languages.is_empty()).then_some(served_by);
+    if let Some(model) = served_by {
+        metrics.record_served_by(model);
+    }
+
+    let mut entities = entities.unwrap_or_else(|| {
+        metrics.record_prepass_skipped(1);
+        Vec::new()
+    });
+    // The shadow model sees the same text as the model did; only where the two
+    // disagree is recorded, once it has run
+    let recorder = {
+        let (metrics, audit_log, request_id) = (metrics.inner().clone(), audit_log.inner().clone(), request_id.0.clone());
+        move |diff| record_shadow_diff(diff, &request_id, &metrics, audit_log.as_ref())
+    };
+    // Model findings are still under the labels the model was asked for
+    if let ShadowRun::Busy = shadow::compare(shadow, visible.text.clone(), prompts, entities.clone(), recorder) {
+        metrics.record_shadow_busy();
+    }
+    let labels = profile_labels(profile.as_ref());
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    recognizers::apply(&visible.text, &labels, &request.recognizers, config, &mut entities);
+    visible.map_entities(&mut entities);
+    if let Some(profile) = &profile {
+        profile.filter(&mut entities);
+    }
+    if let Some(plugins) = Option::as_ref(plugins.inner()) {
+        plugins
+            .run(&request.text, &mut entities)
+            .map_err(|e| privacy::scrub_error(private, ApiError::InferenceFailed(e.to_string())))?;
+    }
+    // Detections the model was unsure about are kept for fine-tuning, and
+    // uncertain findings wait for a reviewer; never in privacy mode
+    if let Some(feedback) = Option::as_ref(feedback.inner()).filter(|_| !private) {
+        feedback.sample(&request.text, &entities);
+    }
+    if let Some(review) = Option::as_ref(review.inner()).filter(|_| !private) {
+        review.submit(&request_id.0, &request.text, &entities);
+    }
+    tracing::info!(
+        request_id = %request_id,
+        text_sha256 = %logging::text_hash(&request.text),
+        text_chars = request.text.chars().count(),
+        entities = entities.len(),
+        labels = ?logging::label_counts(&entities),
+        "PII detection completed"
+    );
+    audit.findings([&entities]);
+    // Presidio results and SARIF logs have no place for them
+    let debug = request.debug.then(|| StageTimings {
+        validation_ms,
+        post_processing_ms: model_timings.post_processing_ms + stopwatch.lap(),
+        total_ms: timing::millis(stopwatch.started().elapsed()),
+        tokens: chunking::estimate_tokens(&visible.text),
+        labels: profile_labels(profile.as_ref()).len(),
+        ..model_timings
+    });
+
+    // Redaction masks every finding; the response filter only narrows what is
+    // reported
+    let redacted_text = profile
+        .as_ref()
+        .map(|profile| apply_masking(&request.text, &entities, |label| profile.masking_for(label)));
+    filter.apply(&mut entities);
+    if filter.counts_only {
+        let mut result = serde_json::json!({
+            "counts": filters::counts(&entities),
+            "total_entities": entities.len(),
+        });
+        request.reference.echo_into(&mut result);
+        if let Some(model) = served_by {
+            result["model"] = serde_json::json!(model);
+        }
+        if let Some(language) = &language {
+            result["language"] = serde_json::json!(language);
+        }
+        if let Some(debug) = &debug {
+            result["debug"] = serde_json::json!(debug);
+        }
+        return Ok(Encoded(ApiResponse {
+            success: true,
+            data: Some(result),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+
+    if request.output_format == OutputFormat::Html {
+        let marked: Vec<&PiiEntity> = entities.iter().collect();
+        let mut result = serde_json::json!({
+            "html": report::highlight(&request.text, &marked),
+            "total_entities": entities.len(),
+        });
+        request.reference.echo_into(&mut result);
+        if let Some(model) = served_by {
+            result["model"] = serde_json::json!(model);
+        }
+        if let Some(debug) = &debug {
+            result["debug"] = serde_json::json!(debug);
+        }
+        return Ok(Encoded(ApiResponse {
+            success: true,
+            data: Some(result),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+    if request.output_format == OutputFormat::Conll {
+        let mut result = serde_json::json!({
+            "conll": conll::to_conll(&request.text, &entities),
+            "total_entities": entities.len(),
+        });
+        request.reference.echo_into(&mut result);
+        if let Some(model) = served_by {
+            result["model"] = serde_json::json!(model);
+        }
+        if let Some(debug) = &debug {
+            result["debug"] = serde_json::json!(debug);
+        }
+        return Ok(Encoded(ApiResponse {
+            success: true,
+            data: Some(result),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+    if request.output_format == OutputFormat::Presidio {
+        return Ok(Encoded(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!(presidio::results(&request.text, &entities))),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+    if request.output_format == OutputFormat::Sarif {
+        let uri = request.reference.id.as_deref().unwrap_or("input");
+        let findings: Vec<sarif::Finding> = entities
+            .iter()
+            .map(|entity| sarif::Finding {
+                uri,
+                entity,
+                text: Some(&request.text),
+                section: None,
+            })
+            .collect();
+        return Ok(Encoded(ApiResponse {
+            success: true,
+            data: Some(sarif::log(&findings)),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+
+    // Keep the original entity shape, with offsets alongside
+    let offset_index = request.offset_units.then(|| OffsetIndex::new(&request.text));
+    let entity_values: Vec<serde_json::Value> = entities
+        .iter()
+        .map(|entity| {
+            let mut value = serde_json::json!({
+                "text": entity.text,
+                "label": entity.label,
+                "sequence": 0,
+                "probability": entity.confidence,
+                "start": entity.start,
+                "end": entity.end
+            });
+            if !entity.attributes.is_empty() {
+                value["attributes"] = serde_json::json!(entity.attributes);
+            }
+            if let Some(chars) = request.context_chars {
+                value["context"] = serde_json::json!(context::snippet(&request.text, entity.start, entity.end, chars));
+            }
+            if let Some(index) = &offset_index {
+                value["offsets"] = serde_json::json!(index.offsets(entity.start, entity.end));
+            }
+            value
+        })
+        .collect();
+
+    let mut result = serde_json::json!({
+        "text": request.text,
+        "entities": entity_values,
+        "total_entities": entity_values.len(),
+        "message": "PII detection completed successfully"
+    });
+    if let Some(sentences) = &sentences {
+        result["sentences"] = serde_json::json!(group_by_sentence(sentences, &visible, &entities, &entity_values));
+    }
+    if request.groups {
+        let mut groups = grouping::group(&entities);
+        if private {
+            groups.iter_mut().for_each(|group| group.text.clear());
+        }
+        result["groups"] = serde_json::json!(groups);
+    }
+    if let (Some(profile), Some(redacted_text)) = (&profile, redacted_text) {
+        result["profile"] = serde_json::json!(profile.name);
+        result["redacted_text"] = serde_json::json!(redacted_text);
+    }
+    request.reference.echo_into(&mut result);
+    if let Some(selection) = &selection {
+        selection.apply(&mut result);
+    }
+    if private {
+        privacy::omit_text(&mut result);
+    }
+    if let Some(model) = served_by {
+        result["model"] = serde_json::json!(model);
+    }
+    if let Some(language) = &language {
+        result["language"] = serde_json::json!(language);
+    }
+    if let Some(debug) = &debug {
+        result["debug"] = serde_json::json!(debug);
+    }
+
+    Ok(Encoded(ApiResponse {
+        success: true,
+        data: Some(result),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+fn record_shadow_diff(diff: ShadowDiff, request_id: &str, metrics: &Metrics, audit_log: Option<&AuditState>) {
+    metrics.record_shadow(&diff);
+    if !diff.disagrees() {
+        return;
+    }
+    tracing::info!(
+        request_id = %request_id,
+        model = %diff.model,
+        only_primary = ?diff.only_primary,
+        only_shadow = ?diff.only_shadow,
+        "Shadow model disagreed"
+    );
+    if let Some(log) = audit_log {
+        log.record(AuditEvent {
+            timestamp: unix_now(),
+            request_id: request_id.to_string(),
+            method: "POST".to_string(),
+            route: "/v1/pii/detect".to_string(),
+            status: 200,
+            caller: None,
+            latency_ms: diff.latency_ms,
+            findings: None,
+            shadow: Some(diff),
+        });
+    }
+}
+
+// Run detection on annotated texts and score it against the annotations:
+// precision, recall and F1 overall and per label
+#[post("/eval", format = "json", data = "<request>")]
+pub async fn evaluate(
+    request: Json<EvalRequest>,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> ApiResult<EvalReport> {
+    let request = request.into_inner();
+    let found = detect_annotated(&request, deadline, model_state, queue, prepass, config, metrics).await?;
+    let gold: Vec<_> = request.documents.into_iter().map(|document| document.entities).collect();
+    let report = eval::evaluate(&gold, &found, request.span_match, request.threshold);
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Score one detection run on annotated texts at a range of thresholds, with
+// the precision/recall curve and the best-F1 threshold overall and per label
+#[post("/eval/sweep", format = "json", data = "<request>")]
+pub async fn evaluate_sweep(
+    request: Json<SweepRequest>,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> ApiResult<SweepReport> {
+    let SweepRequest { eval: request, thresholds } = request.into_inner();
+    let thresholds = thresholds.filter(|thresholds| !thresholds.is_empty()).unwrap_or_else(|| eval::DEFAULT_SWEEP.to_vec());
+    if thresholds.iter().any(|threshold| !(0.0..=1.0).contains(threshold)) {
+        return Err(ApiError::InvalidInput("thresholds must be between 0 and 1".to_string()));
+    }
+    let found = detect_annotated(&request, deadline, model_state, queue, prepass, config, metrics).await?;
+    let gold: Vec<_> = request.documents.into_iter().map(|document| document.entities).collect();
+    let report = eval::sweep(&gold, &found, request.span_match, &thresholds);
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Batch detection over the texts of an evaluation set, for its labels
+async fn detect_annotated(
+    request: &EvalRequest,
+    deadline: RequestDeadline,
+    model_state: &ModelState,
+    queue: &InferenceQueue,
+    prepass: &PrepassState,
+    config: &Config,
+    metrics: &Metrics,
+) -> Result<Vec<Vec<PiiEntity>>, ApiError> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    validation::validate_batch(request.documents.iter().map(|document| document.text.as_str()), config)?;
+    let labels = request.labels();
+    if labels.is_empty() {
+        return Err(ApiError::InvalidInput("no labels to evaluate: annotate entities or pass labels".to_string()));
+    }
+    let texts: Vec<String> = request.documents.iter().map(|document| document.text.clone()).collect();
+    let prepass = prepass.clone();
+    let config = config.clone();
+
+    let batch = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, &RecognizerOptions::default(), &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))
+    })
+    .await?;
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+    Ok(batch.documents)
+}
+
+// Fake documents with planted PII at known offsets, shaped as POST /eval takes
+// them; the same seed always gives the same documents
+#[cfg(feature = "dev")]
+#[get("/dev/synthetic?<count>&<seed>")]
+pub fn synthetic_documents(count: Option<usize>, seed: Option<u64>) -> ApiResult<Vec<eval::EvalDocument>> {
+    let count = count.unwrap_or(10);
+    if count > MAX_SYNTHETIC_DOCUMENTS {
+        return Err(ApiError::InvalidInput(format!("count must be at most {}", MAX_SYNTHETIC_DOCUMENTS)));
+    }
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(synthetic::documents(count, seed.unwrap_or(0))),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+#[cfg(feature = "dev")]
+const MAX_SYNTHETIC_DOCUMENTS: usize = 10_000;
+
+// Take the instance out of load balancer rotation: readiness turns 503 at once,
+// detection requests are still admitted for the grace period (for traffic the
+// balancer routed before noticing), then refused with 503 "draining"
+#[post("/admin/drain?<grace_seconds>")]
+pub fn start_drain(
+    _admin: AdminAuth,
+    grace_seconds: Option<u64>,
+    queue: &State<QueueState>,
+    config: &State<Config>,
+) -> Json<ApiResponse<DrainStatus>> {
+    let grace = std::time::Duration::from_secs(grace_seconds.unwrap_or(config.drain_grace_secs));
+    queue.drain(grace);
+    tracing::info!(grace_secs = grace.as_secs(), "Draining: readiness is now 503, still accepting new detections");
+
+    Json(ApiResponse {
+        success: true,
+        data: Some(DrainStatus::of(queue)),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// Put a drained instance back into rotation
+#[delete("/admin/drain")]
+pub fn cancel_drain(_admin: AdminAuth, queue: &State<QueueState>) -> Json<ApiResponse<DrainStatus>> {
+    queue.cancel_drain();
+    tracing::info!("Drain cancelled: accepting detections and reporting ready again");
+
+    Json(ApiResponse {
+        success: true,
+        data: Some(DrainStatus::of(queue)),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// Resource usage of this instance, for capacity planning and spotting leaks
+#[get("/admin/stats")]
+pub async fn admin_stats(
+    _admin: AdminAuth,
+    queue: &State<QueueState>,
+    model_state: &State<ModelState>,
+    info_state: &State<ModelInfoState>,
+    model_load: &State<ModelLoadState>,
+) -> Json<ApiResponse<AdminStats>> {
+    let pool = model_state.lock().await.as_ref().map(|pool| pool.snapshot());
+    let info = info_state.lock().await;
+    let stats = AdminStats::of(model_load.status().elapsed_secs, queue, pool, info.as_ref());
+
+    Json(ApiResponse {
+        success: true,
+        data: Some(stats),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// Text detection runs over, read in the request's input format and normalized
+fn visible_text(
+    text: &str,
+    format: InputFormat,
+    skip_code_blocks: bool,
+    normalization: &Normalization,
+) -> Result<VisibleText, ApiError> {
+    let visible = match format {
+        InputFormat::Markdown if skip_code_blocks => markdown::strip_markdown(text, true),
+        _ if skip_code_blocks => {
+            return Err(ApiError::InvalidInput("skip_code_blocks only applies to the markdown input format".to_string()))
+        }
+        _ => VisibleText::new(text, format),
+    };
+    Ok(visible.normalized(normalization))
+}
+
+// Findings of sentence mode per sentence, with offsets into the input
+fn group_by_sentence(
+    sentences: &[sentences::Sentence],
+    visible: &VisibleText,
+    entities: &[PiiEntity],
+    entity_values: &[serde_json::Value],
+) -> Vec<serde_json::Value> {
+    sentences
+        .iter()
+        .enumerate()
+        .map(|(index, sentence)| {
+            let (start, end) = (visible.source_start(sentence.start), visible.source_end(sentence.end));
+            let found: Vec<&serde_json::Value> = entities
+                .iter()
+                .zip(entity_values)
+                .filter(|(entity, _)| entity.start >= start && entity.start < end)
+                .map(|(_, value)| value)
+                .collect();
+            serde_json::json!({
+                "index": index,
+                "start": start,
+                "end": end,
+                "entities": found,
+                "total_entities": found.len(),
+            })
+        })
+        .collect()
+}
+
+// Time the loaded model on generated texts of the requested lengths and batch
+// sizes, for capacity planning. One session is held for the whole run.
+#[post("/admin/benchmark", format = "json", data = "<request>")]
+pub async fn run_benchmark(
+    _admin: AdminAuth,
+    request: Json<BenchmarkRequest>,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    config: &State<Config>,
+) -> ApiResult<BenchmarkReport> {
+    let request = request.into_inner();
+    request.validate()?;
+    let labels = request.labels.clone().filter(|labels| !labels.is_empty()).unwrap_or_else(|| profile_labels(None));
+    let pool = model_state.lock().await.clone().ok_or(ApiError::ModelNotLoaded)?;
+
+    let _permit = queue.try_admit()?;
+    let started = std::time::Instant::now();
+    let cases = {
+        let labels = labels.clone();
+        tokio::task::spawn_blocking(move || {
+            let model = pool.checkout_with(Priority::Batch);
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            let mut cases = Vec::new();
+            for &length in &request.lengths {
+                for &batch_size in &request.batch_sizes {
+                    let case = benchmark::run_case(&model, length, batch_size, request.iterations, &labels, request.seed)
+                        .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+                    tracing::info!(length, batch_size, texts_per_sec = case.texts_per_sec, p50_ms = case.latency.p50_ms, "Benchmark case done");
+                    cases.push(case);
+                }
+            }
+            Ok::<_, ApiError>(cases)
+        })
+        .await
+        .map_err(|e| ApiError::InferenceFailed(e.to_string()))??
+    };
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(BenchmarkReport {
+            model: config.model_name.clone(),
+            labels,
+            cases,
+            total_ms: timing::millis(started.elapsed()),
+        }),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Run texts through two loaded models (the primary, the canary or the shadow)
+// and line up their findings, with score deltas and a summary of where they
+// disagree
+#[post("/admin/compare", format = "json", data = "<request>")]
+pub async fn compare_models(
+    _admin: AdminAuth,
+    request: Json<CompareRequest>,
+    model_state: &State<ModelState>,
+    canary: &State<CanaryState>,
+    shadow: &State<ShadowState>,
+    queue: &State<QueueState>,
+    config: &State<Config>,
+) -> ApiResult<ModelComparison> {
+    let CompareRequest { text, texts, model_a, model_b, labels } = request.into_inner();
+    let texts = text.into_iter().chain(texts).collect();
+    let models = (model_a.as_deref(), model_b.as_deref());
+    run_comparison(texts, models, labels, model_state, canary, shadow, queue, config).await
+}
+
+// The same with a file of texts, one per line
+#[post("/admin/compare?<model_a>&<model_b>", format = "text/plain", data = "<body>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn compare_models_file(
+    _admin: AdminAuth,
+    body: Data<'_>,
+    model_a: Option<&str>,
+    model_b: Option<&str>,
+    model_state: &State<ModelState>,
+    canary: &State<CanaryState>,
+    shadow: &State<ShadowState>,
+    queue: &State<QueueState>,
+    config: &State<Config>,
+) -> ApiResult<ModelComparison> {
+    let body = body
+        .open(ByteUnit::from(config.max_payload_bytes))
+        .into_string()
+        .await
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    if !body.is_complete() {
+        return Err(ApiError::PayloadTooLarge(format!("the file is larger than {} bytes", config.max_payload_bytes)));
+    }
+    let texts = body.lines().filter(|line| !line.trim().is_empty()).map(str::to_string).collect();
+    run_comparison(texts, (model_a, model_b), None, model_state, canary, shadow, queue, config).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_comparison(
+    texts: Vec<String>,
+    (model_a, model_b): (Option<&str>, Option<&str>),
+    labels: Option<Vec<String>>,
+    model_state: &ModelState,
+    canary: &CanaryState,
+    shadow: &ShadowState,
+    queue: &InferenceQueue,
+    config: &Config,
+) -> ApiResult<ModelComparison> {
+    validation::validate_batch(texts.iter().map(String::as_str), config)?;
+    let labels = labels.filter(|labels| !labels.is_empty()).unwrap_or_else(|| profile_labels(None));
+    let canary = Option::as_ref(canary);
+    let shadow = Option::as_ref(shadow);
+    let other = canary.map(Canary::model).or(shadow.map(Shadow::model));
+    let model_a = model_a.unwrap_or(config.model_name.as_str()).to_string();
+    let model_b = match model_b.or(other) {
+        Some(model) => model.to_string(),
+        None => return Err(ApiError::InvalidInput("only one model is loaded; set GLINER_CANARY_MODEL or GLINER_SHADOW_MODEL to compare".to_string())),
+    };
+
+    // The session pool of a model by name, if it is loaded
+    let loaded = |name: &str| {
+        let name = name.to_string();
+        async move {
+            if name == config.model_name {
+                return model_state.lock().await.clone().ok_or(ApiError::ModelNotLoaded);
+            }
+            if let Some(canary) = canary.filter(|canary| canary.model() == name) {
+                return canary.model_state().lock().await.clone().ok_or(ApiError::ModelNotLoaded);
+            }
+            if let Some(shadow) = shadow.filter(|shadow| shadow.model() == name) {
+                return shadow.pool().ok_or(ApiError::ModelNotLoaded);
+            }
+            Err(ApiError::NotFound(format!("model {:?} is not loaded", name)))
+        }
+    };
+    let (pool_a, pool_b) = (loaded(&model_a).await?, loaded(&model_b).await?);
+
+    let _permit = queue.try_admit()?;
+    let label_map = LabelMap::resolve(&labels.iter().map(String::as_str).collect::<Vec<_>>(), config, None);
+    let documents = tokio::task::spawn_blocking(move || {
+        let run = |pool: &ModelPool, text: &str| {
+            detection::model_entities(&pool.checkout_with(Priority::Batch), text, &label_map).map_err(|e| ApiError::InferenceFailed(e.to_string()))
+        };
+        texts
+            .iter()
+            .map(|text| Ok(compare::side_by_side(&run(&pool_a, text)?, &run(&pool_b, text)?)))
+            .collect::<Result<Vec<_>, ApiError>>()
+    })
+    .await
+    .map_err(|e| ApiError::InferenceFailed(e.to_string()))??;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(ModelComparison {
+            summary: compare::summarize(&documents),
+            model_a,
+            model_b,
+            documents,
+        }),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Run texts through the model with a broad set of generic types instead of a
+// PII profile, and summarize which types it finds, to explore a dataset before
+// choosing the labels to detect. Only the model runs: rule-based recognizers
+// and profiles don't apply.
+#[post("/pii/discover", format = "json", data = "<request>")]
+pub async fn discover_entities(
+    request: Json<DiscoverRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> ApiResult<Discovery> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let DiscoverRequest { text, texts, labels, privacy_mode } = request.into_inner();
+    let private = privacy::enabled(config, privacy_mode);
+    audit.set_private(private);
+    let texts: Vec<String> = text.into_iter().chain(texts).collect();
+    if texts.is_empty() {
+        return Err(ApiError::InvalidInput("give a text or texts to explore".to_string()));
+    }
+    validation::validate_batch(texts.iter().map(String::as_str), config)?;
+    let labels = discovery::labels(&config.discovery_labels, &labels);
+
+    let config = config.inner().clone();
+    let run_labels = labels.clone();
+    let batch = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let labels: Vec<&str> = run_labels.iter().map(String::as_str).collect();
+        let model_only = RecognizerOptions {
+            detectors: Some(vec![pipeline::MODEL.to_string()]),
+            ..RecognizerOptions::default()
+        };
+        detection::detect_documents(model, None, &texts, &labels, &model_only, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))
+    })
+    .await
+    .map_err(|e| privacy::scrub_error(private, e))?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    audit.findings(&batch.documents);
+    let mut documents = batch.documents;
+    let types = discovery::summarize(&documents, !private);
+    if private {
+        documents.iter_mut().for_each(|entities| privacy::clear_text(entities));
+    }
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(Discovery { labels, documents, types }),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// The collected feedback examples in GLiNER's fine-tuning format, a JSON array
+// to save as a training file; `drain` removes what was exported
+#[get("/admin/training/export?<drain>")]
+pub fn export_training(_admin: AdminAuth, drain: Option<bool>, feedback: &State<FeedbackState>) -> ApiResult<Vec<TrainingExample>> {
+    let Some(feedback) = Option::as_ref(feedback.inner()) else {
+        return Err(ApiError::InvalidInput("feedback collection is off; set GLINER_FEEDBACK_CAPACITY".to_string()));
+    };
+    let examples = feedback.export(drain.unwrap_or(false));
+    Ok(Json(ApiResponse {
+        success: true,
+        message: Some(format!("{} training examples", examples.len())),
+        data: Some(examples),
+        api_version: API_VERSION,
+    }))
+}
+
+fn review_queue(review: &ReviewState) -> Result<&ReviewQueue, ApiError> {
+    Option::as_ref(review).ok_or_else(|| ApiError::InvalidInput("review is off; set GLINER_REVIEW_CAPACITY".to_string()))
+}
+
+// Findings waiting for a reviewer, oldest first
+#[get("/review/pending?<limit>")]
+pub fn review_pending(_admin: AdminAuth, limit: Option<usize>, review: &State<ReviewState>) -> ApiResult<Vec<ReviewItem>> {
+    let queue = review_queue(review)?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: Some(format!("{} pending", queue.len())),
+        data: Some(queue.pending(limit.unwrap_or(100))),
+        api_version: API_VERSION,
+    }))
+}
+
+#[post("/review/<id>/approve")]
+pub fn review_approve(_admin: AdminAuth, id: u64, review: &State<ReviewState>, feedback: &State<FeedbackState>) -> ApiResult<ReviewOutcome> {
+    let outcome = review_queue(review)?.decide(id, Decision::Approve, Option::as_ref(feedback.inner()))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(outcome),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+#[post("/review/<id>/reject")]
+pub fn review_reject(_admin: AdminAuth, id: u64, review: &State<ReviewState>, feedback: &State<FeedbackState>) -> ApiResult<ReviewOutcome> {
+    let outcome = review_queue(review)?.decide(id, Decision::Reject, Option::as_ref(feedback.inner()))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(outcome),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Drop the model to free its memory, e.g. on a drained instance of a memory-
+// constrained node; detection answers 503 until POST /admin/model/reload
+#[post("/admin/model/unload")]
+pub async fn unload_model(
+    _admin: AdminAuth,
+    model_load: &State<ModelLoadState>,
+    model_state: &State<ModelState>,
+    info_state: &State<ModelInfoState>,
+    readiness: &State<Readiness>,
+) -> ApiResult<ModelStatus> {
+    if !model_load.begin() {
+        return Err(ApiError::InvalidInput("the model is being loaded; unload it once loading is done".to_string()));
+    }
+    loading::unload(model_load, model_state, info_state, readiness).await;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(model_load.status()),
+        message: Some("Model unloaded".to_string()),
+        api_version: API_VERSION,
+    }))
+}
+
+// Load the model again from its files in the background, replacing the current
+// one once warmed up; progress at GET /model/status
+#[post("/admin/model/reload")]
+pub fn reload_model(
+    _admin: AdminAuth,
+    model_load: &State<ModelLoadState>,
+    model_state: &State<ModelState>,
+    info_state: &State<ModelInfoState>,
+    readiness: &State<Readiness>,
+    config: &State<Config>,
+) -> ApiResult<ModelStatus> {
+    if !model_load.begin() {
+        return Err(ApiError::InvalidInput("the model is already being loaded".to_string()));
+    }
+    tokio::spawn(loading::load(
+        config.inner().clone(),
+        model_load.inner().clone(),
+        model_state.inner().clone(),
+        info_state.inner().clone(),
+        readiness.inner().clone(),
+    ));
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(model_load.status()),
+        message: Some("Model reload started".to_string()),
+        api_version: API_VERSION,
+    }))
+}
+
+// Export the replicable configuration (label profiles) for other regions
+#[get("/admin/config")]
+pub async fn export_config(_admin: AdminAuth, profiles: &State<ProfilesState>) -> Json<ApiResponse<ConfigBundle>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some(replication::export(&*profiles.read().await)),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// Most recent audit events, newest first; `since` is a Unix timestamp in seconds.
+// Only events kept in memory are searched, older ones are in the configured sink.
+#[get("/admin/audit?<limit>&<since>")]
+pub fn recent_audit_events(
+    _admin: AdminAuth,
+    limit: Option<usize>,
+    since: Option<u64>,
+    audit: &State<Option<AuditState>>,
+) -> Json<ApiResponse<Vec<AuditEvent>>> {
+    let Some(audit) = audit.inner() else {
+        return Json(ApiResponse {
+            success: true,
+            data: Some(Vec::new()),
+            message: Some("Audit logging is off; set GLINER_AUDIT_SINK to record events".to_string()),
+            api_version: API_VERSION,
+        });
+    };
+    Json(ApiResponse {
+        success: true,
+        data: Some(audit.recent(limit.unwrap_or(100), since)),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// History of stored detection results, newest first, without their findings;
+// `before` is a Unix timestamp in seconds for paging back
+#[cfg(feature = "persistence")]
+#[get("/admin/results?<limit>&<before>")]
+pub async fn list_results(
+    _admin: AdminAuth,
+    limit: Option<usize>,
+    before: Option<u64>,
+    store: &State<Option<ResultStoreState>>,
+) -> ApiResult<Vec<ResultSummary>> {
+    let Some(store) = store.inner() else {
+        return Ok(Json(ApiResponse {
+            success: true,
+            data: Some(Vec::new()),
+            message: Some("Results are not stored; set GLINER_DATABASE_URL to keep them".to_string()),
+            api_version: API_VERSION,
+        }));
+    };
+    let results = store
+        .list(limit.unwrap_or(100).min(1_000), before)
+        .await
+        .map_err(|e| ApiError::StorageFailed(format!("could not read stored results: {}", e)))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(results),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// A stored detection result with its findings, by the request id of the call
+#[cfg(feature = "persistence")]
+#[get("/admin/results/<request_id>")]
+pub async fn get_result(
+    _admin: AdminAuth,
+    request_id: &str,
+    store: &State<Option<ResultStoreState>>,
+) -> ApiResult<StoredResult> {
+    let Some(store) = store.inner() else {
+        return Err(ApiError::NotFound("results are not stored; set GLINER_DATABASE_URL to keep them".to_string()));
+    };
+    let result = store
+        .get(request_id)
+        .await
+        .map_err(|e| ApiError::StorageFailed(format!("could not read stored results: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("no stored result for request {}", request_id)))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(result),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Delete stored results older than `older_than` (seconds, or e.g. "30d")
+#[delete("/admin/results?<older_than>")]
+pub async fn purge_results(
+    _admin: AdminAuth,
+    older_than: Option<&str>,
+    retention: &State<Retention>,
+) -> ApiResult<PurgeReport> {
+    let age = older_than.map(parse_age).transpose()?;
+    let age = age.ok_or_else(|| ApiError::InvalidInput("older_than is required, e.g. older_than=30d".to_string()))?;
+    let cutoff = retention.cutoff(age);
+    let results = retention.purge_results(cutoff).await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: results.is_none().then(|| "Result persistence is off; nothing is stored".to_string()),
+        data: Some(PurgeReport { cutoff, results, audit_events: None }),
+        api_version: API_VERSION,
+    }))
+}
+
+// Register a recurring scan, or replace the schedule of the same name
+#[post("/admin/schedules", data = "<spec>")]
+pub fn register_schedule(_admin: AdminAuth, spec: Json<ScheduleSpec>, scheduler: &State<SchedulerState>) -> ApiResult<ScheduleStatus> {
+    let status = scheduler.register(spec.into_inner())?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(status),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+#[get("/admin/schedules")]
+pub fn list_schedules(_admin: AdminAuth, scheduler: &State<SchedulerState>) -> Json<ApiResponse<Vec<ScheduleStatus>>> {
+    Json(ApiResponse {
+        success: true,
+        data: Some(scheduler.list()),
+        message: None,
+        api_version: API_VERSION,
+    })
+}
+
+// A schedule with its run history
+#[get("/admin/schedules/<name>")]
+pub fn get_schedule(_admin: AdminAuth, name: &str, scheduler: &State<SchedulerState>) -> ApiResult<ScheduleStatus> {
+    let status = scheduler.get(name).ok_or_else(|| ApiError::NotFound(format!("no schedule named {}", name)))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(status),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Objects the schedule's kept runs gave up on after retrying
+#[get("/admin/schedules/<name>/dead-letters")]
+pub fn get_schedule_dead_letters(_admin: AdminAuth, name: &str, scheduler: &State<SchedulerState>) -> ApiResult<Vec<RunDeadLetters>> {
+    let runs = scheduler
+        .dead_letters(name)
+        .ok_or_else(|| ApiError::NotFound(format!("no schedule named {}", name)))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(runs),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Remove a schedule; a run in progress finishes but is not recorded
+#[delete("/admin/schedules/<name>")]
+pub fn delete_schedule(_admin: AdminAuth, name: &str, scheduler: &State<SchedulerState>) -> ApiResult<()> {
+    if !scheduler.remove(name) {
+        return Err(ApiError::NotFound(format!("no schedule named {}", name)));
+    }
+    Ok(Json(ApiResponse {
+        success: true,
+        data: None,
+        message: Some(format!("Schedule {} removed", name)),
+        api_version: API_VERSION,
+    }))
+}
+
+// Start a run of a schedule now, outside its timing
+#[post("/admin/schedules/<name>/run")]
+pub fn run_schedule(_admin: AdminAuth, name: &str, scheduler: &State<SchedulerState>) -> ApiResult<ScheduleStatus> {
+    scheduler.trigger(name)?;
+    let status = scheduler.get(name).ok_or_else(|| ApiError::NotFound(format!("no schedule named {}", name)))?;
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(status),
+        message: Some("Run started".to_string()),
+        api_version: API_VERSION,
+    }))
+}
+
+// Delete audit events older than `older_than`, from memory and every sink that keeps them
+#[delete("/admin/audit?<older_than>")]
+pub async fn purge_audit_events(
+    _admin: AdminAuth,
+    older_than: Option<&str>,
+    retention: &State<Retention>,
+) -> ApiResult<PurgeReport> {
+    let age = older_than.map(parse_age).transpose()?;
+    let age = age.ok_or_else(|| ApiError::InvalidInput("older_than is required, e.g. older_than=90d".to_string()))?;
+    let cutoff = retention.cutoff(age);
+    let audit_events = retention.purge_audit(cutoff).await?;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: audit_events.is_none().then(|| "Audit logging is off; nothing is stored".to_string()),
+        data: Some(PurgeReport { cutoff, results: None, audit_events }),
+        api_version: API_VERSION,
+    }))
+}
+
+// Replace the replicable configuration with an exported bundle
+#[put("/admin/config", data = "<bundle>")]
+pub async fn import_config(
+    _admin: AdminAuth,
+    bundle: Json<ConfigBundle>,
+    profiles: &State<ProfilesState>,
+) -> ApiResult<ConfigBundle> {
+    let registry = replication::import(bundle.into_inner())?;
+    let mut current = profiles.write().await;
+    *current = registry;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(replication::export(&current)),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+#[post("/pii/detect/batch?<fields>", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii_batch(
+    request: Body<BatchRequest>,
+    fields: Option<&str>,
+    request_id: RequestId,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    plugins: &State<PluginsState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> EncodedResult<serde_json::Value> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let private = privacy::enabled(config, request.privacy_mode);
+    audit.set_private(private);
+    let selection = privacy::selection(FieldSelection::resolve(fields, request.fields.as_deref())?, private);
+    let profile = profiles::resolve(&*profiles.read().await, request.profile.as_deref())?.cloned();
+    let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+
+    let request = request.into_inner();
+    let (input_format, skip_code_blocks) = (request.input_format, request.skip_code_blocks);
+    let output_format = request.output_format;
+    if matches!(output_format, OutputFormat::Sarif | OutputFormat::Html) {
+        return Err(ApiError::InvalidInput("batch output_format must be json, conll or presidio".to_string()));
+    }
+    privacy::check_output_format(private, output_format)?;
+    request.recognizers.validate()?;
+    let recognizer_options = request.recognizers;
+    let (texts, mut references): (Vec<String>, Vec<DocumentRef>) = request
+        .documents
+        .into_iter()
+        .map(|document| (document.text, document.reference))
+        .unzip();
+    let normalization = Normalization::from_config(config);
+    let visibles: Vec<VisibleText> = texts
+        .iter()
+        .map(|text| visible_text(text, input_format, skip_code_blocks, &normalization))
+        .collect::<Result<_, _>>()?;
+    validation::validate_batch(visibles.iter().map(|visible| visible.text.as_str()), config)?;
+    // Texts the CoNLL and Presidio formats are built from, before redaction
+    let originals = matches!(output_format, OutputFormat::Conll | OutputFormat::Presidio).then(|| texts.clone());
+    let prepass = prepass.inner().clone();
+    let plugins = plugins.inner().clone();
+    let config = config.inner().clone();
+
+    // Chunks from all documents are scheduled together, then reassembled per document
+    let (mut batch, redacted) = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let visible_texts: Vec<&str> = visibles.iter().map(|visible| visible.text.as_str()).collect();
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+        let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &visible_texts, &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+        let mut redacted: Vec<Option<String>> = Vec::with_capacity(texts.len());
+        for (index, entities) in batch.documents.iter_mut().enumerate() {
+            visibles[index].map_entities(entities);
+            if let Some(plugins) = Option::as_ref(&plugins) {
+                plugins.run(&texts[index], entities).map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+            }
+            redacted.push(apply_profile(profile.as_ref(), &texts[index], entities));
+        }
+        Ok((batch, redacted))
+    })
+    .await
+    .map_err(|e| privacy::scrub_error(private, e))?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+    tracing::info!(
+        request_id = %request_id,
+        documents = batch.documents.len(),
+        chunks = batch.chunks,
+        inference_batches = batch.batches,
+        entities = batch.documents.iter().map(Vec::len).sum::<usize>(),
+        labels = ?logging::label_counts(batch.documents.iter().flatten()),
+        "Batch PII detection completed"
+    );
+    audit.findings(&batch.documents);
+
+    if let Some(texts) = &originals {
+        let result = if output_format == OutputFormat::Conll {
+            let documents = texts.iter().map(String::as_str).zip(batch.documents.iter().map(Vec::as_slice));
+            serde_json::json!({
+                "conll": conll::documents(documents),
+                "total_documents": texts.len(),
+                "total_entities": batch.documents.iter().map(Vec::len).sum::<usize>(),
+            })
+        } else {
+            let results: Vec<Vec<RecognizerResult>> = texts
+                .iter()
+                .zip(&batch.documents)
+                .map(|(text, entities)| presidio::results(text, entities))
+                .collect();
+            serde_json::json!(results)
+        };
+        return Ok(Encoded(ApiResponse {
+            success: true,
+            data: Some(result),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+
+    let results: Vec<DocumentResult> = std::mem::take(&mut batch.documents)
+        .into_iter()
+        .zip(redacted)
+        .enumerate()
+        .map(|(index, (entities, redacted_text))| DocumentResult {
+            index,
+            reference: std::mem::take(&mut references[index]),
+            total_entities: entities.len(),
+            entities,
+            redacted_text,
+        })
+        .collect();
+
+    let response = BatchResponse {
+        total_documents: results.len(),
+        results,
+        profile: profile_name,
+        total_chunks: batch.chunks,
+        escalated_chunks: batch.escalated,
+        inference_batches: batch.batches,
+        padding_efficiency: batch.padding.efficiency(),
+    };
+    Ok(Encoded(ApiResponse {
+        success: true,
+        data: Some(fields::sparse(&response, selection.as_ref())?),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Protobuf variants of detection and batch detection, chosen by
+// `Content-Type: application/x-protobuf`; the JSON handlers forward such bodies
+// here. Results are always JSON-shaped detections, so `output_format` and
+// `fields` are not part of the schema.
+#[cfg(feature = "protobuf")]
+#[post("/pii/detect", format = "application/x-protobuf", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii_protobuf(
+    request: Protobuf<proto::DetectRequest>,
+    request_id: RequestId,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    canary: &State<CanaryState>,
+    shadow: &State<ShadowState>,
+    audit_log: &State<Option<AuditState>>,
+    feedback: &State<FeedbackState>,
+    review: &State<ReviewState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    plugins: &State<PluginsState>,
+    languages: &State<LanguageRouterState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<Protobuf<proto::DetectResponse>, ApiError> {
+    let request = Body(request.into_inner().into());
+    let Encoded(response) = detect_pii(request, None, None, None, None, request_id, audit, deadline, model_state, canary, shadow, audit_log, feedback, review, queue, prepass, profiles, plugins, languages, config, metrics).await?;
+    Ok(Protobuf(proto::from_json(response.data)?))
+}
+
+#[cfg(feature = "protobuf")]
+#[post("/pii/detect/batch", format = "application/x-protobuf", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii_batch_protobuf(
+    request: Protobuf<proto::BatchDetectRequest>,
+    request_id: RequestId,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    plugins: &State<PluginsState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<Protobuf<proto::BatchDetectResponse>, ApiError> {
+    let request = Body(request.into_inner().into());
+    let Encoded(response) = detect_pii_batch(request, None, request_id, audit, deadline, model_state, queue, prepass, profiles, plugins, config, metrics).await?;
+    Ok(Protobuf(proto::from_json(response.data)?))
+}
+
+// JSON Lines variant of batch detection: one `DocumentResult` per line, written
+// as soon as each document's last chunk has been through the model
+#[post("/pii/detect/batch?response_format=jsonl&<fields>", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii_batch_jsonl(
+    request: Body<BatchRequest>,
+    fields: Option<&str>,
+    _audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<(ContentType, TextStream<impl Stream<Item = String>>), ApiError> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let private = privacy::enabled(config, request.privacy_mode);
+    let selection = privacy::selection(FieldSelection::resolve(fields, request.fields.as_deref())?, private);
+    let profile = profiles::resolve(&*profiles.read().await, request.profile.as_deref())?.cloned();
+
+    let request = request.into_inner();
+    let (input_format, skip_code_blocks) = (request.input_format, request.skip_code_blocks);
+    if request.output_format != OutputFormat::Json {
+        return Err(ApiError::InvalidInput("JSON Lines batch results only support output_format json".to_string()));
+    }
+    request.recognizers.validate()?;
+    let recognizer_options = request.recognizers;
+    let (texts, mut references): (Vec<String>, Vec<DocumentRef>) = request
+        .documents
+        .into_iter()
+        .map(|document| (document.text, document.reference))
+        .unzip();
+    let normalization = Normalization::from_config(config);
+    let visibles: Vec<VisibleText> = texts
+        .iter()
+        .map(|text| visible_text(text, input_format, skip_code_blocks, &normalization))
+        .collect::<Result<_, _>>()?;
+    validation::validate_batch(visibles.iter().map(|visible| visible.text.as_str()), config)?;
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let metrics = metrics.inner().clone();
+
+    let stream = stream_with_deadline(model_state, queue, deadline, move |model, progress, sink| {
+        let visible_texts: Vec<&str> = visibles.iter().map(|visible| visible.text.as_str()).collect();
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+        let stats = detection::detect_documents_streaming(
+            model,
+            Option::as_ref(&prepass),
+            &visible_texts,
+            &labels,
+            &recognizer_options,
+            &config,
+            progress,
+            |index, mut entities| {
+                visibles[index].map_entities(&mut entities);
+                let redacted_text = apply_profile(profile.as_ref(), &texts[index], &mut entities);
+                let result = DocumentResult {
+                    index,
+                    reference: std::mem::take(&mut references[index]),
+                    total_entities: entities.len(),
+                    entities,
+                    redacted_text,
+                };
+                match fields::sparse(&result, selection.as_ref()) {
+                    Ok(line) => sink.send(&line),
+                    Err(e) => sink.send(&e.body()),
+                }
+            },
+        )
+        .map_err(|e| privacy::scrub_error(private, ApiError::InferenceFailed(e.to_string())))?;
+
+        metrics.record_batches(stats.batches, stats.padding);
+        metrics.record_prepass_skipped(stats.chunks - stats.escalated);
+        Ok(())
+    })
+    .await?;
+
+    Ok((ContentType::new("application", "jsonl"), stream))
+}
+
+// Entities of one chunk, sent as a "chunk" event by the SSE endpoint
+#[derive(Serialize, Deserialize)]
+pub struct ChunkEvent {
+    pub chunk: usize,
+    pub total_chunks: usize,
+    // Entities first seen in this chunk, at offsets into the whole text
+    pub entities: Vec<PiiEntity>,
+}
+
+// Server-sent events variant of single-text detection for long documents: a
+// "chunk" event with new entities as each chunk comes back from the model, then
+// a "done" event with the final, deduplicated result (recognizers and profile
+// applied). Failures arrive as an "error" event.
+#[post("/pii/detect/sse?<fields>", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii_sse(
+    request: Json<PiiRequest>,
+    fields: Option<&str>,
+    _audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<EventStream<impl Stream<Item = Event>>, ApiError> {
+    let visible = visible_text(&request.text, request.input_format, request.skip_code_blocks, &Normalization::from_config(config))?;
+    validation::validate_text(&visible.text, config)?;
+    let private = privacy::enabled(config, request.privacy_mode);
+    let selection = privacy::selection(FieldSelection::resolve(fields, request.fields.as_deref())?, private);
+    let profile = profiles::resolve(&*profiles.read().await, request.profile.as_deref())?.cloned();
+    request.recognizers.validate()?;
+
+    let request = request.into_inner();
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let metrics = metrics.inner().clone();
+
+    events_with_deadline(model_state, queue, deadline, move |model, progress, sink| {
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let total_chunks = chunking::split_into_chunks(0, &visible.text, config.max_chunk_chars, config.chunk_overlap).len();
+        let label_map = LabelMap::resolve(&labels, &config, request.recognizers.label_prompts.as_ref());
+
+        let mut found: Vec<PiiEntity> = Vec::new();
+        let stats = detection::detect_chunks_in_order(
+            model,
+            Option::as_ref(&prepass),
+            &visible.text,
+            &label_map,
+            &config,
+            progress,
+            |index, _, mut entities| {
+                // Spans already sent for the overlap with the previous chunk are not repeated
+                entities.retain(|entity| {
+                    !found.iter().any(|seen| (seen.start, seen.end, &seen.label) == (entity.start, entity.end, &entity.label))
+                });
+                found.extend(entities.iter().cloned());
+                if let Some(profile) = &profile {
+                    profile.filter(&mut entities);
+                }
+                visible.map_entities(&mut entities);
+                let event = ChunkEvent {
+                    chunk: index,
+                    total_chunks,
+                    entities,
+                };
+                match fields::sparse(&event, selection.as_ref()) {
+                    Ok(event) => sink.send_event("chunk", &event),
+                    Err(e) => sink.send_event("error", &e.body()),
+                }
+            },
+        )
+        .map_err(|e| privacy::scrub_error(private, ApiError::InferenceFailed(e.to_string())))?;
+        metrics.record_batches(stats.batches, stats.padding);
+        metrics.record_prepass_skipped(stats.chunks - stats.escalated);
+
+        detection::dedupe_entities(&mut found);
+        recognizers::apply(&visible.text, &labels, &request.recognizers, &config, &mut found);
+        visible.map_entities(&mut found);
+        let redacted_text = apply_profile(profile.as_ref(), &request.text, &mut found);
+        let result = DocumentResult {
+            index: 0,
+            reference: request.reference,
+            total_entities: found.len(),
+            entities: found,
+            redacted_text,
+        };
+        let result = fields::sparse(&result, selection.as_ref())?;
+        sink.send_event("done", &result);
+        Ok(())
+    })
+    .await
+}
+
+// Interactive detection over one WebSocket, for editors that highlight PII as
+// the user types: each text frame is a document (a JSON batch document, or plain
+// text) and is answered with one result frame, indexed by frame. Profile, fields
+// and locale are fixed when the socket opens; every frame still goes through
+// admission control and gets the deadline of the upgrade request.
+#[get("/ws/detect?<profile>&<fields>&<locale>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn ws_detect(
+    ws: rocket_ws::WebSocket,
+    profile: Option<&str>,
+    fields: Option<&str>,
+    locale: Option<&str>,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<rocket_ws::Channel<'static>, ApiError> {
+    use rocket::futures::{SinkExt, StreamExt};
+    use rocket_ws::Message;
+
+    let session = WsSession {
+        model_state: model_state.inner().clone(),
+        queue: queue.inner().clone(),
+        prepass: prepass.inner().clone(),
+        profile: profiles::resolve(&*profiles.read().await, profile)?.cloned(),
+        selection: privacy::selection(FieldSelection::resolve(fields, None)?, config.privacy_mode),
+        recognizer_options: RecognizerOptions {
+            locale: locale.map(str::to_string),
+            ..Default::default()
+        },
+        config: config.inner().clone(),
+        metrics: metrics.inner().clone(),
+        deadline,
+    };
+
+    Ok(ws.channel(move |mut stream| {
+        Box::pin(async move {
+            let mut index = 0;
+            while let Some(message) = stream.next().await {
+                let frame = match message? {
+                    Message::Text(frame) => frame,
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+                let reply = session.detect(index, frame).await;
+                index += 1;
+                stream.send(Message::Text(reply)).await?;
+            }
+            Ok(())
+        })
+    }))
+}
+
+// What a WebSocket detection session needs for every frame
+struct WsSession {
+    model_state: ModelState,
+    queue: QueueState,
+    prepass: PrepassState,
+    profile: Option<LabelProfile>,
+    selection: Option<FieldSelection>,
+    recognizer_options: RecognizerOptions,
+    config: Config,
+    metrics: MetricsState,
+    deadline: RequestDeadline,
+}
+
+impl WsSession {
+    // Detect in one frame, returning the reply frame: a document result, or an
+    // error body carrying the frame index
+    async fn detect(&self, index: usize, frame: String) -> String {
+        match self.try_detect(index, frame).await {
+            Ok(reply) => reply.to_string(),
+            Err(e) => ndjson_error(index, &e),
+        }
+    }
+
+    async fn try_detect(&self, index: usize, frame: String) -> Result<serde_json::Value, ApiError> {
+        let document = match serde_json::from_str::<BatchDocument>(&frame) {
+            Ok(document) => document,
+            Err(_) if !frame.trim_start().starts_with('{') => BatchDocument {
+                text: frame,
+                reference: DocumentRef::default(),
+            },
+            Err(e) => return Err(ApiError::InvalidInput(format!("invalid frame: {}", e))),
+        };
+        validation::validate_text(&document.text, &self.config)?;
+
+        let (prepass, profile, options, config) = (
+            self.prepass.clone(),
+            self.profile.clone(),
+            self.recognizer_options.clone(),
+            self.config.clone(),
+        );
+        let privacy_mode = self.config.privacy_mode;
+        let (result, batch) = run_with_deadline(&self.model_state, &self.queue, self.deadline, move |model, progress| {
+            let labels = profile_labels(profile.as_ref());
+            let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+            let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &[&document.text], &labels, &options, &config, progress)
+                .map_err(move |e| privacy::scrub_error(privacy_mode, ApiError::InferenceFailed(e.to_string())))?;
+
+            let mut entities = batch.documents.pop().unwrap_or_default();
+            let redacted_text = apply_profile(profile.as_ref(), &document.text, &mut entities);
+            let result = DocumentResult {
+                index,
+                reference: document.reference,
+                total_entities: entities.len(),
+                entities,
+                redacted_text,
+            };
+            Ok((result, batch))
+        })
+        .await?;
+
+        self.metrics.record_batches(batch.batches, batch.padding);
+        self.metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+        fields::sparse(&result, self.selection.as_ref())
+    }
+}
+
+// Streaming NDJSON detection: the body is read one record per line and run
+// through the model a batch at a time, and results go back as NDJSON while the
+// rest of the body is still arriving, so neither side has to hold the whole
+// scan. Each record is a batch document (`text`, optional `id` and `metadata`).
+// The request deadline applies to each batch rather than the whole stream; bad
+// records get an error line carrying their index and the scan continues.
+#[post("/pii/detect/ndjson?<profile>&<fields>&<locale>", data = "<body>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn detect_pii_ndjson<'r>(
+    body: Data<'r>,
+    profile: Option<&str>,
+    fields: Option<&str>,
+    locale: Option<&str>,
+    _audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<(ContentType, TextStream<impl Stream<Item = String> + 'r>), ApiError> {
+    use tokio::io::AsyncBufReadExt;
+
+    let deadline = deadline.or_priority(Priority::Batch);
+    let selection = privacy::selection(FieldSelection::resolve(fields, None)?, config.privacy_mode);
+    let profile = profiles::resolve(&*profiles.read().await, profile)?.cloned();
+    let recognizer_options = RecognizerOptions {
+        locale: locale.map(str::to_string),
+        ..Default::default()
+    };
+    let permit = queue.try_admit()?;
+    let pool = model_state.lock().await.clone().ok_or(ApiError::ModelNotLoaded)?;
+
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let metrics = metrics.inner().clone();
+    let batch_size = config.max_batch_documents.max(1);
+    let mut lines = tokio::io::BufReader::new(body.open(ByteUnit::from(config.max_ndjson_bytes))).lines();
+
+    let stream = TextStream! {
+        let _permit = permit;
+        let mut index = 0;
+        let mut finished = false;
+        while !finished {
+            let mut records = Vec::with_capacity(batch_size);
+            while records.len() < batch_size {
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => {
+                        finished = true;
+                        break;
+                    }
+                    Err(e) => {
+                        let error = ApiError::InvalidInput(format!("failed to read request body: {}", e));
+                        yield format!("{}\n", ndjson_error(index, &error));
+                        finished = true;
+                        break;
+                    }
+                };
+                let record = index;
+                index += 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let document = serde_json::from_str::<BatchDocument>(&line)
+                    .map_err(|e| ApiError::InvalidInput(format!("invalid record: {}", e)))
+                    .and_then(|document| validation::validate_text(&document.text, &config).map(|_| document));
+                match document {
+                    Ok(document) => records.push((record, document)),
+                    Err(e) => yield format!("{}\n", ndjson_error(record, &e)),
+                }
+            }
+            if records.is_empty() {
+                continue;
+            }
+
+            let first = records[0].0;
+            let progress = Arc::new(InferenceProgress::default());
+            let task_progress = progress.clone();
+            let (pool, prepass, profile, selection, recognizer_options, config) = (
+                pool.clone(),
+                prepass.clone(),
+                profile.clone(),
+                selection.clone(),
+                recognizer_options.clone(),
+                config.clone(),
+            );
+            let task = tokio::task::spawn_blocking(move || {
+                let session = pool.checkout_with(deadline.priority());
+                let batch = NdjsonBatch {
+                    records,
+                    profile: profile.as_ref(),
+                    selection: selection.as_ref(),
+                    recognizer_options: &recognizer_options,
+                };
+                detect_ndjson_batch(&session, Option::as_ref(&prepass), batch, &config, &task_progress)
+            });
+            match tokio::time::timeout(deadline.0, task).await {
+                Ok(Ok(Ok((results, stats)))) => {
+                    metrics.record_batches(stats.batches, stats.padding);
+                    metrics.record_prepass_skipped(stats.chunks - stats.escalated);
+                    for line in results {
+                        yield format!("{}\n", line);
+                    }
+                }
+                Ok(Ok(Err(e))) => {
+                    yield format!("{}\n", ndjson_error(first, &e));
+                    break;
+                }
+                Ok(Err(e)) => {
+                    yield format!("{}\n", ndjson_error(first, &ApiError::InferenceFailed(e.to_string())));
+                    break;
+                }
+                Err(_) => {
+                    progress.cancel();
+                    let error = ApiError::Timeout {
+                        timeout_ms: deadline.0.as_millis() as u64,
+                        elapsed_ms: deadline.0.as_millis() as u64,
+                        completed_batches: progress.completed_batches(),
+                        total_batches: progress.total_batches(),
+                    };
+                    yield format!("{}\n", ndjson_error(first, &error));
+                    break;
+                }
+            }
+        }
+    };
+
+    Ok((ContentType::new("application", "x-ndjson"), stream))
+}
+
+// One batch of NDJSON records (with their line indices) and the request's settings
+struct NdjsonBatch<'a> {
+    records: Vec<(usize, BatchDocument)>,
+    profile: Option<&'a LabelProfile>,
+    selection: Option<&'a FieldSelection>,
+    recognizer_options: &'a RecognizerOptions,
+}
+
+// Run one batch of NDJSON records, returning a result line per record in order
+fn detect_ndjson_batch(
+    model: &Model,
+    prepass: Option<&Prepass>,
+    batch: NdjsonBatch<'_>,
+    config: &Config,
+    progress: &InferenceProgress,
+) -> Result<(Vec<String>, detection::DetectionStats), ApiError> {
+    let NdjsonBatch { records, profile, selection, recognizer_options } = batch;
+    let labels = profile_labels(profile);
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let texts: Vec<&str> = records.iter().map(|(_, document)| document.text.as_str()).collect();
+    let batch = detection::detect_documents(model, prepass, &texts, &labels, recognizer_options, config, progress)
+        .map_err(|e| privacy::scrub_error(config.privacy_mode, ApiError::InferenceFailed(e.to_string())))?;
+
+    let lines = records
+        .into_iter()
+        .zip(batch.documents)
+        .map(|((index, document), mut entities)| {
+            let redacted_text = apply_profile(profile, &document.text, &mut entities);
+            let result = DocumentResult {
+                index,
+                reference: document.reference,
+                total_entities: entities.len(),
+                entities,
+                redacted_text,
+            };
+            match fields::sparse(&result, selection) {
+                Ok(line) => line.to_string(),
+                Err(e) => ndjson_error(index, &e),
+            }
+        })
+        .collect();
+    let stats = detection::DetectionStats {
+        chunks: batch.chunks,
+        escalated: batch.escalated,
+        batches: batch.batches,
+        padding: batch.padding,
+    };
+    Ok((lines, stats))
+}
+
+// Error line for one NDJSON record: the usual error body plus the record index
+fn ndjson_error(index: usize, error: &ApiError) -> String {
+    let mut line = serde_json::to_value(error.body()).unwrap_or_default();
+    if let Some(object) = line.as_object_mut() {
+        object.insert("index".to_string(), serde_json::json!(index));
+    }
+    line.to_string()
+}
+
+// Scan an uploaded document: extract its text, run chunked detection over each
+// section (the whole file, each PDF page, or each paragraph) and report where entities were found.
+// Zip and tar archives are unpacked and their supported members scanned, see `scan_archive`.
+#[post("/pii/scan/file", data = "<upload>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_file(
+    upload: Form<FileScanForm<'_>>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<ScanOutput, ApiError> {
+    let upload = upload.into_inner();
+    let selection = upload.fields.as_deref().map(FieldSelection::parse).transpose()?;
+    let selection = privacy::selection(selection, config.privacy_mode);
+    let output_format = file_output_format(upload.output_format.as_deref(), config)?;
+    let filename = upload
+        .file
+        .raw_name()
+        .map(|name| name.dangerous_unsafe_unsanitized_raw().as_str().to_string());
+    let media_type = upload
+        .file
+        .content_type()
+        .map(|content_type| (content_type.top().to_string(), content_type.sub().to_string()));
+    let bytes = read_upload(&upload.file)
+        .await
+        .map_err(|e| ApiError::InvalidInput(format!("failed to read uploaded file: {}", e)))?;
+    let object = ScannedObject {
+        filename,
+        media_type,
+        bytes,
+        profile: upload.profile.as_deref(),
+        selection: selection.as_ref(),
+        output_format,
+    };
+    scan_object(object, audit, deadline, model_state, queue, prepass, profiles, config, metrics).await
+}
+
+// Scan an object stored in S3 or an S3-compatible store, named by bucket and key
+// (signed with the AWS_* credentials of the service, allowed buckets only) or by
+// a presigned url. The object goes through the same extractors as an uploaded file.
+#[post("/pii/scan/s3", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_s3(
+    request: Json<S3ScanRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<ScanOutput, ApiError> {
+    let request = request.into_inner();
+    s3::check_allowed(&request.object, config)?;
+    let fetch = s3::fetch(&request.object, config);
+    scan_stored(fetch, &request.settings, audit, deadline, model_state, queue, prepass, profiles, config, metrics).await
+}
+
+// Scan an object stored in Google Cloud Storage, named by bucket and object name
+// or by a signed url, like an uploaded file
+#[cfg(feature = "gcs")]
+#[post("/pii/scan/gcs", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_gcs(
+    request: Json<GcsScanRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<ScanOutput, ApiError> {
+    let request = request.into_inner();
+    let fetch = gcs::fetch(&request.object, config);
+    scan_stored(fetch, &request.settings, audit, deadline, model_state, queue, prepass, profiles, config, metrics).await
+}
+
+// Scan a blob stored in Azure Blob Storage, named by container and blob name or
+// by a SAS url, like an uploaded file
+#[cfg(feature = "azure")]
+#[post("/pii/scan/azure", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_azure(
+    request: Json<AzureScanRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<ScanOutput, ApiError> {
+    let request = request.into_inner();
+    let fetch = azure::fetch(&request.blob, config);
+    scan_stored(fetch, &request.settings, audit, deadline, model_state, queue, prepass, profiles, config, metrics).await
+}
+
+// Validate the scan settings, then download the object and scan it. Settings
+// are checked first so a bad request costs no download.
+#[allow(clippy::too_many_arguments)]
+async fn scan_stored(
+    fetch: impl std::future::Future<Output = Result<RemoteObject, ApiError>>,
+    settings: &ObjectScanSettings,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &ModelState,
+    queue: &QueueState,
+    prepass: &PrepassState,
+    profiles: &ProfilesState,
+    config: &Config,
+    metrics: &MetricsState,
+) -> Result<ScanOutput, ApiError> {
+    let selection = settings.fields.as_deref().map(FieldSelection::from_list).transpose()?;
+    let selection = privacy::selection(selection, config.privacy_mode);
+    let output_format = file_output_format(settings.output_format.as_deref(), config)?;
+    let fetched = fetch.await?;
+    let object = ScannedObject {
+        filename: fetched.name,
+        media_type: fetched.media_type,
+        bytes: fetched.bytes,
+        profile: settings.profile.as_deref(),
+        selection: selection.as_ref(),
+        output_format,
+    };
+    scan_object(object, audit, deadline, model_state, queue, prepass, profiles, config, metrics).await
+}
+
+// Scan a fetched object outside any request, as scheduled scans do, returning a
+// summary of what was found
+pub(crate) async fn scan_in_background(engine: &ScanEngine, fetched: RemoteObject, profile: Option<&str>) -> Result<AuditFindings, ApiError> {
+    let audit = AuditTrail::default();
+    let deadline = RequestDeadline(std::time::Duration::from_millis(engine.config.max_request_timeout_ms), Some(Priority::Batch));
+    let object = ScannedObject {
+        filename: fetched.name,
+        media_type: fetched.media_type,
+        bytes: fetched.bytes,
+        profile,
+        selection: None,
+        output_format: OutputFormat::Json,
+    };
+    let ScanEngine { model_state, queue, prepass, profiles, config, metrics, .. } = engine;
+    scan_object(object, &audit, deadline, model_state, queue, prepass, profiles, config, metrics).await?;
+    Ok(audit.summary().unwrap_or_default())
+}
+
+// Output format of a file scan, which has no CoNLL or Presidio form
+fn file_output_format(output_format: Option<&str>, config: &Config) -> Result<OutputFormat, ApiError> {
+    let output_format = output_format.map(OutputFormat::parse).transpose()?.unwrap_or_default();
+    if matches!(output_format, OutputFormat::Conll | OutputFormat::Presidio) {
+        return Err(ApiError::InvalidInput(
+            "conll and presidio output are only available for detect and batch requests".to_string(),
+        ));
+    }
+    privacy::check_output_format(config.privacy_mode, output_format)?;
+    Ok(output_format)
+}
+
+// A file to scan, uploaded or fetched from storage, and the scan settings of its request
+struct ScannedObject<'a> {
+    filename: Option<String>,
+    // Type and subtype the file was sent or stored with, used when its name has no known extension
+    media_type: Option<(String, String)>,
+    bytes: Vec<u8>,
+    profile: Option<&'a str>,
+    selection: Option<&'a FieldSelection>,
+    output_format: OutputFormat,
+}
+
+// Scan one file: unpack it if it is an archive, otherwise extract its text and
+// run chunked detection over each section
+#[allow(clippy::too_many_arguments)]
+async fn scan_object(
+    object: ScannedObject<'_>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &ModelState,
+    queue: &QueueState,
+    prepass: &PrepassState,
+    profiles: &ProfilesState,
+    config: &Config,
+    metrics: &MetricsState,
+) -> Result<ScanOutput, ApiError> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let ScannedObject { filename, media_type, bytes, profile, selection, output_format } = object;
+    if let Some(archive) = filename.as_deref().and_then(ArchiveFormat::from_filename) {
+        let upload = ArchiveUpload {
+            filename,
+            archive,
+            profile,
+            selection,
+            output_format,
+            bytes,
+        };
+        return scan_archive(upload, audit, deadline, model_state, queue, prepass, profiles, config, metrics).await;
+    }
+    let format = filename
+        .as_deref()
+        .and_then(DocumentFormat::from_filename)
+        .or_else(|| {
+            let (top, sub) = media_type.as_ref()?;
+            DocumentFormat::from_media_type(top, sub)
+        })
+        .ok_or_else(|| {
+            ApiError::InvalidInput(
+                "unsupported file type, expected .txt, .md, .pdf, .docx, .odt, .eml, an image, .zip, .tar or .tar.gz".to_string(),
+            )
+        })?;
+    let size_bytes = bytes.len();
+
+    // Parsers for binary formats are CPU-bound, and may panic on malformed files
+    let ocr_settings = OcrSettings::from_config(config);
+    let extracted = tokio::task::spawn_blocking(move || ocr::extract_with_ocr(format, bytes, &ocr_settings))
+        .await
+        .map_err(|_| ApiError::InvalidInput(format!("could not parse {} file", format.name())))??;
+    let profile = profiles::resolve(&*profiles.read().await, profile)?.cloned();
+    let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+
+    let prepass = prepass.clone();
+    let config = config.clone();
+    let OcrExtraction { text: ExtractedText { unit, mut sections, parts, encoding }, layouts } = extracted;
+    // SARIF lines and columns refer to the file as uploaded, before any redaction
+    let original = (output_format == OutputFormat::Sarif && unit == SectionUnit::File).then(|| sections.concat());
+    // Reports highlight the text as extracted, before any redaction
+    let report_text = (output_format == OutputFormat::Html).then(|| ExtractedText {
+        unit,
+        sections: sections.clone(),
+        parts: parts.clone(),
+        encoding,
+    });
+    let (batch, entities, sections) = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let recognizer_options = RecognizerOptions::default();
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+        // Blank sections (e.g. image-only pages) are not sent to the model
+        let scanned: Vec<usize> = (0..sections.len()).filter(|&index| !sections[index].trim().is_empty()).collect();
+        let texts: Vec<&str> = scanned.iter().map(|&index| sections[index].as_str()).collect();
+        let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+
+        let mut entities = Vec::new();
+        for (&section, mut found) in scanned.iter().zip(std::mem::take(&mut batch.documents)) {
+            // Boxes are looked up before redaction changes the section's text
+            let layout = layouts[section].as_ref();
+            let boxes: Vec<Option<BoundingBox>> = found
+                .iter()
+                .map(|entity| layout.and_then(|page| page.bounding_box(entity.start, entity.end)))
+                .collect();
+            if let Some(redacted) = apply_profile(profile.as_ref(), &sections[section], &mut found) {
+                sections[section] = redacted;
+            }
+            entities.extend(found.into_iter().zip(boxes).map(|(entity, bbox)| FileEntity {
+                entity,
+                page: (unit == SectionUnit::Page).then_some(section + 1),
+                paragraph: (unit == SectionUnit::Paragraph).then_some(section),
+                part: parts.get(section).cloned(),
+                bbox,
+            }));
+        }
+        Ok((batch, entities, sections))
+    })
+    .await?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+    audit.findings([entities.iter().map(|found| &found.entity)]);
+
+    if output_format == OutputFormat::Sarif {
+        let uri = filename.as_deref().unwrap_or("upload");
+        return Ok(ScanOutput::Json(Json(ApiResponse {
+            success: true,
+            data: Some(sarif::log(&sarif::file_findings(uri, &entities, original.as_deref()))),
+            message: None,
+            api_version: API_VERSION,
+        })));
+    }
+    if let Some(text) = &report_text {
+        let title = filename.as_deref().unwrap_or("upload");
+        return Ok(ScanOutput::Html(RawHtml(report::report(title, &report::file_sections(None, text, &entities)))));
+    }
+
+    let redacted_text = profile_name.as_ref().map(|_| ExtractedText::join(&sections, unit));
+    let response = FileScanResponse {
+        filename,
+        format,
+        encoding,
+        size_bytes,
+        total_entities: entities.len(),
+        entities,
+        profile: profile_name,
+        redacted_text,
+        total_chunks: batch.chunks,
+        inference_batches: batch.batches,
+    };
+    Ok(ScanOutput::Json(Json(ApiResponse {
+        success: true,
+        data: Some(fields::sparse(&response, selection)?),
+        message: None,
+        api_version: API_VERSION,
+    })))
+}
+
+// An archive to scan and the scan settings of its request
+struct ArchiveUpload<'a> {
+    filename: Option<String>,
+    archive: ArchiveFormat,
+    profile: Option<&'a str>,
+    selection: Option<&'a FieldSelection>,
+    output_format: OutputFormat,
+    bytes: Vec<u8>,
+}
+
+// Unpack an archive within the configured limits and scan the sections of all
+// its supported members in one detection run, reporting findings per member
+#[allow(clippy::too_many_arguments)]
+async fn scan_archive(
+    upload: ArchiveUpload<'_>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &ModelState,
+    queue: &QueueState,
+    prepass: &PrepassState,
+    profiles: &ProfilesState,
+    config: &Config,
+    metrics: &MetricsState,
+) -> Result<ScanOutput, ApiError> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let bytes = upload.bytes;
+    let size_bytes = bytes.len();
+    let limits = ArchiveLimits {
+        max_members: config.max_archive_members,
+        max_member_bytes: config.max_upload_bytes,
+        max_total_bytes: config.max_archive_bytes,
+    };
+    let archive = upload.archive;
+    let contents = tokio::task::spawn_blocking(move || archive::read_archive(archive, &bytes, limits))
+        .await
+        .map_err(|_| ApiError::InvalidInput("could not parse archive".to_string()))??;
+    let profile = profiles::resolve(&*profiles.read().await, upload.profile)?.cloned();
+    let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+
+    let prepass = prepass.clone();
+    let config = config.clone();
+    let ArchiveContents { mut members, skipped } = contents;
+    let originals: Vec<Option<String>> = members
+        .iter()
+        .map(|member| {
+            let whole = upload.output_format == OutputFormat::Sarif && member.text.unit == SectionUnit::File;
+            whole.then(|| member.text.sections.concat())
+        })
+        .collect();
+    let report_texts: Vec<ExtractedText> = match upload.output_format {
+        OutputFormat::Html => members.iter().map(|member| member.text.clone()).collect(),
+        _ => Vec::new(),
+    };
+    let (batch, results) = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let recognizer_options = RecognizerOptions::default();
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+        // (member, section) of every non-blank section across the archive
+        let scanned: Vec<(usize, usize)> = members
+            .iter()
+            .enumerate()
+            .flat_map(|(member, found)| {
+                let sections = &found.text.sections;
+                (0..sections.len())
+                    .filter(move |&section| !sections[section].trim().is_empty())
+                    .map(move |section| (member, section))
+            })
+            .collect();
+        let texts: Vec<&str> = scanned
+            .iter()
+            .map(|&(member, section)| members[member].text.sections[section].as_str())
+            .collect();
+        let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+
+        let mut entities: Vec<Vec<FileEntity>> = vec![Vec::new(); members.len()];
+        for (&(member, section), mut found) in scanned.iter().zip(std::mem::take(&mut batch.documents)) {
+            let text = &mut members[member].text;
+            if let Some(redacted) = apply_profile(profile.as_ref(), &text.sections[section], &mut found) {
+                text.sections[section] = redacted;
+            }
+            let unit = text.unit;
+            entities[member].extend(found.into_iter().map(|entity| FileEntity {
+                entity,
+                page: (unit == SectionUnit::Page).then_some(section + 1),
+                paragraph: (unit == SectionUnit::Paragraph).then_some(section),
+                part: text.parts.get(section).cloned(),
+                bbox: None,
+            }));
+        }
+        let results: Vec<ArchiveMemberResult> = members
+            .into_iter()
+            .zip(entities)
+            .map(|(member, entities)| ArchiveMemberResult {
+                redacted_text: profile.as_ref().map(|_| ExtractedText::join(&member.text.sections, member.text.unit)),
+                path: member.path,
+                format: member.format,
+                encoding: member.text.encoding,
+                total_entities: entities.len(),
+                entities,
+            })
+            .collect();
+        Ok((batch, results))
+    })
+    .await?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+
+    audit.findings(results.iter().map(|member| member.entities.iter().map(|found| &found.entity)));
+
+    if upload.output_format == OutputFormat::Sarif {
+        let findings: Vec<sarif::Finding> = results
+            .iter()
+            .zip(&originals)
+            .flat_map(|(member, original)| sarif::file_findings(&member.path, &member.entities, original.as_deref()))
+            .collect();
+        return Ok(ScanOutput::Json(Json(ApiResponse {
+            success: true,
+            data: Some(sarif::log(&findings)),
+            message: None,
+            api_version: API_VERSION,
+        })));
+    }
+    if upload.output_format == OutputFormat::Html {
+        let sections: Vec<report::ReportSection> = results
+            .iter()
+            .zip(&report_texts)
+            .flat_map(|(member, text)| report::file_sections(Some(&member.path), text, &member.entities))
+            .collect();
+        let title = upload.filename.as_deref().unwrap_or("upload");
+        return Ok(ScanOutput::Html(RawHtml(report::report(title, &sections))));
+    }
+
+    let response = ArchiveScanResponse {
+        filename: upload.filename,
+        archive,
+        size_bytes,
+        total_entities: results.iter().map(|member| member.total_entities).sum(),
+        members: results,
+        skipped,
+        profile: profile_name,
+        total_chunks: batch.chunks,
+        inference_batches: batch.batches,
+    };
+    Ok(ScanOutput::Json(Json(ApiResponse {
+        success: true,
+        data: Some(fields::sparse(&response, upload.selection)?),
+        message: None,
+        api_version: API_VERSION,
+    })))
+}
+
+// Profile a CSV table: scan the cells of the selected columns (every cell is its
+// own document, batched across the whole table) and summarise hits per column
+#[post("/pii/scan/csv", data = "<upload>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_csv(
+    upload: Form<CsvScanForm<'_>>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> ApiResult<CsvScanResponse> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let upload = upload.into_inner();
+    let filename = upload
+        .file
+        .raw_name()
+        .map(|name| name.dangerous_unsafe_unsanitized_raw().as_str().to_string());
+    let delimiter = match upload.delimiter.as_deref() {
+        None => b',',
+        Some("\\t") => b'\t',
+        Some(delimiter) if delimiter.len() == 1 => delimiter.as_bytes()[0],
+        Some(delimiter) => {
+            return Err(ApiError::InvalidInput(format!("delimiter must be a single character, got {:?}", delimiter)))
+        }
+    };
+    let options = CsvOptions {
+        has_headers: upload.has_headers.unwrap_or(true),
+        delimiter,
+        columns: upload.columns.clone(),
+        max_cells: config.max_csv_cells,
+    };
+    let profile = profiles::resolve(&*profiles.read().await, upload.profile.as_deref())?.cloned();
+    let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+
+    let bytes = read_upload(&upload.file)
+        .await
+        .map_err(|e| ApiError::InvalidInput(format!("failed to read uploaded file: {}", e)))?;
+    let table = tokio::task::spawn_blocking(move || csv_scan::parse_csv(&bytes, &options))
+        .await
+        .map_err(|e| ApiError::InvalidInput(format!("could not parse CSV file: {}", e)))??;
+
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let (table, batch) = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let recognizer_options = RecognizerOptions::default();
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let texts: Vec<&str> = table.cells.iter().map(|cell| cell.text.as_str()).collect();
+
+        let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+        if let Some(profile) = &profile {
+            batch.documents.iter_mut().for_each(|entities| profile.filter(entities));
+        }
+        if config.privacy_mode {
+            batch.documents.iter_mut().for_each(|entities| privacy::clear_text(entities));
+        }
+        Ok((table, batch))
+    })
+    .await?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+
+    audit.findings(&batch.documents);
+    let (columns, hits) = csv_scan::summarize(&table, batch.documents);
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(CsvScanResponse {
+            filename,
+            rows: table.rows,
+            cells_scanned: table.cells.len(),
+            columns,
+            total_hits: hits.len(),
+            hits,
+            profile: profile_name,
+            inference_batches: batch.batches,
+        }),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Deep-scan a JSON document: every string value is its own document, batched
+// across the whole payload, and findings are reported by JSON Pointer
+#[post("/pii/scan/json", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_json(
+    request: Body<JsonScanRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> EncodedResult<JsonScanResponse> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let request = request.into_inner();
+    let strings = json_scan::collect_strings(&request.document, config.max_json_strings, config.max_text_chars)?;
+    let profile = profiles::resolve(&*profiles.read().await, request.profile.as_deref())?.cloned();
+    request.recognizers.validate()?;
+    let recognizer_options = request.recognizers;
+    let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let (strings, batch) = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let texts: Vec<&str> = strings.iter().map(|string| string.text.as_str()).collect();
+
+        let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+        if let Some(profile) = &profile {
+            batch.documents.iter_mut().for_each(|entities| profile.filter(entities));
+        }
+        if config.privacy_mode {
+            batch.documents.iter_mut().for_each(|entities| privacy::clear_text(entities));
+        }
+        Ok((strings, batch))
+    })
+    .await?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+
+    audit.findings(&batch.documents);
+    let findings = json_scan::findings(&strings, batch.documents);
+    Ok(Encoded(ApiResponse {
+        success: true,
+        data: Some(JsonScanResponse {
+            strings_scanned: strings.len(),
+            total_hits: findings.iter().map(|finding| finding.entities.len()).sum(),
+            findings,
+            profile: profile_name,
+            inference_batches: batch.batches,
+        }),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+// Presidio-compatible analyzer: takes the body of Presidio's POST /analyze and
+// answers with a bare array of RecognizerResults, so Presidio clients can be
+// pointed at this service unchanged. `language` is used as the locale.
+#[post("/analyze", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn presidio_analyze(
+    request: Json<AnalyzeRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<Json<Vec<RecognizerResult>>, ApiError> {
+    let request = request.into_inner();
+    validation::validate_text(&request.text, config)?;
+    let labels = match &request.entities {
+        Some(entity_types) if !entity_types.is_empty() => presidio::labels_for(entity_types),
+        _ => profile_labels(None),
+    };
+    let recognizer_options = RecognizerOptions {
+        locale: Some(request.language.clone()),
+        ..Default::default()
+    };
+
+    let text = request.text.clone();
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let batch = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        detection::detect_documents(model, Option::as_ref(&prepass), &[&text], &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))
+    })
+    .await?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+
+    let mut entities = batch.documents.into_iter().next().unwrap_or_default();
+    let threshold = request.score_threshold.unwrap_or(0.0);
+    let allowed = request.allow_list.unwrap_or_default();
+    entities.retain(|entity| entity.confidence >= threshold && !allowed.contains(&entity.text));
+    audit.findings([&entities]);
+    Ok(Json(presidio::results(&request.text, &entities)))
+}
+
+// Presidio-compatible list of the entity types the default labels and the
+// configured profiles can report. Presidio's `language` parameter is accepted
+// and ignored, as the model is multilingual.
+#[get("/supportedentities")]
+pub async fn presidio_supported_entities(profiles: &State<ProfilesState>) -> Json<Vec<String>> {
+    let mut labels = profile_labels(None);
+    for profile in profiles.read().await.values() {
+        labels.extend(profile.labels.iter().cloned());
+    }
+    Json(presidio::entity_types(&labels))
+}
+
+// Scan a log file streamed as the request body, line by line. Lines are batched
+// into inference calls and every line with findings is answered with an NDJSON
+// `{"line": ..., "entities": [...]}` as its batch completes; the last line is a
+// `{"summary": ...}` with totals and a per-label count. Lines that can't be
+// scanned get an error line with their line number, and the scan carries on.
+#[post("/pii/scan/logs?<profile>&<locale>", data = "<body>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_logs<'r>(
+    body: Data<'r>,
+    profile: Option<&str>,
+    locale: Option<&str>,
+    _audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> Result<(ContentType, TextStream<impl Stream<Item = String> + 'r>), ApiError> {
+    use tokio::io::AsyncBufReadExt;
+
+    let deadline = deadline.or_priority(Priority::Batch);
+    let profile = profiles::resolve(&*profiles.read().await, profile)?.cloned();
+    let recognizer_options = RecognizerOptions {
+        locale: locale.map(str::to_string),
+        ..Default::default()
+    };
+    let permit = queue.try_admit()?;
+    let pool = model_state.lock().await.clone().ok_or(ApiError::ModelNotLoaded)?;
+
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let metrics = metrics.inner().clone();
+    let mut reader = tokio::io::BufReader::new(body.open(ByteUnit::from(config.max_log_bytes)));
+
+    let stream = TextStream! {
+        let _permit = permit;
+        let mut batcher = LineBatcher::new(config.max_batch_documents);
+        let mut summary = LogSummary::default();
+        let mut buffer = Vec::new();
+        let mut finished = false;
+        while !finished {
+            let mut batch = None;
+            while batch.is_none() {
+                buffer.clear();
+                match reader.read_until(b'\n', &mut buffer).await {
+                    Ok(0) => {
+                        finished = true;
+                        break;
+                    }
+                    Ok(_) => {
+                        // Logs are not always valid UTF-8; scan what can be read
+                        let line = String::from_utf8_lossy(buffer.strip_suffix(b"\n").unwrap_or(&buffer[..])).into_owned();
+                        batch = batcher.push(line);
+                    }
+                    Err(e) => {
+                        let error = ApiError::InvalidInput(format!("failed to read request body: {}", e));
+                        yield format!("{}\n", log_error(batcher.lines_read() + 1, &error));
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            let mut lines = Vec::new();
+            for line in batch.unwrap_or_else(|| batcher.finish()) {
+                match validation::validate_text(&line.text, &config) {
+                    Ok(()) => lines.push(line),
+                    Err(e) => {
+                        summary.lines_skipped += 1;
+                        yield format!("{}\n", log_error(line.line, &e));
+                    }
+                }
+            }
+            if lines.is_empty() {
+                continue;
+            }
+
+            let first = lines[0].line;
+            let progress = Arc::new(InferenceProgress::default());
+            let task_progress = progress.clone();
+            let (pool, prepass, profile, recognizer_options, config) = (
+                pool.clone(),
+                prepass.clone(),
+                profile.clone(),
+                recognizer_options.clone(),
+                config.clone(),
+            );
+            let task = tokio::task::spawn_blocking(move || {
+                let session = pool.checkout_with(deadline.priority());
+                detect_log_batch(&session, Option::as_ref(&prepass), lines, profile.as_ref(), &recognizer_options, &config, &task_progress)
+            });
+            match tokio::time::timeout(deadline.0, task).await {
+                Ok(Ok(Ok((results, stats)))) => {
+                    metrics.record_batches(stats.batches, stats.padding);
+                    metrics.record_prepass_skipped(stats.chunks - stats.escalated);
+                    for finding in results {
+                        summary.record(&finding.entities);
+                        if !finding.entities.is_empty() {
+                            yield format!("{}\n", serde_json::to_string(&finding).unwrap_or_default());
+                        }
+                    }
+                }
+                Ok(Ok(Err(e))) => {
+                    yield format!("{}\n", log_error(first, &e));
+                    break;
+                }
+                Ok(Err(e)) => {
+                    yield format!("{}\n", log_error(first, &ApiError::InferenceFailed(e.to_string())));
+                    break;
+                }
+                Err(_) => {
+                    progress.cancel();
+                    let error = ApiError::Timeout {
+                        timeout_ms: deadline.0.as_millis() as u64,
+                        elapsed_ms: deadline.0.as_millis() as u64,
+                        completed_batches: progress.completed_batches(),
+                        total_batches: progress.total_batches(),
+                    };
+                    yield format!("{}\n", log_error(first, &error));
+                    break;
+                }
+            }
+        }
+        summary.lines_read = batcher.lines_read();
+        yield format!("{}\n", serde_json::to_string(&SummaryLine { summary }).unwrap_or_default());
+    };
+
+    Ok((ContentType::new("application", "x-ndjson"), stream))
+}
+
+// Run one batch of log lines, returning every line's findings in order. Each
+// line is its own document, so offsets are relative to the line.
+fn detect_log_batch(
+    model: &Model,
+    prepass: Option<&Prepass>,
+    lines: Vec<LogLine>,
+    profile: Option<&LabelProfile>,
+    recognizer_options: &RecognizerOptions,
+    config: &Config,
+    progress: &InferenceProgress,
+) -> Result<(Vec<LogFinding>, detection::DetectionStats), ApiError> {
+    let labels = profile_labels(profile);
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    let batch = detection::detect_documents(model, prepass, &texts, &labels, recognizer_options, config, progress)
+        .map_err(|e| privacy::scrub_error(config.privacy_mode, ApiError::InferenceFailed(e.to_string())))?;
+
+    let findings = lines
+        .iter()
+        .zip(batch.documents)
+        .map(|(line, mut entities)| {
+            if let Some(profile) = profile {
+                profile.filter(&mut entities);
+            }
+            if config.privacy_mode {
+                privacy::clear_text(&mut entities);
+            }
+            LogFinding { line: line.line, entities }
+        })
+        .collect();
+    let stats = detection::DetectionStats {
+        chunks: batch.chunks,
+        escalated: batch.escalated,
+        batches: batch.batches,
+        padding: batch.padding,
+    };
+    Ok((findings, stats))
+}
+
+// Error line for one log line: the usual error body plus the line number
+fn log_error(line_number: usize, error: &ApiError) -> String {
+    let mut line = serde_json::to_value(error.body()).unwrap_or_default();
+    if let Some(object) = line.as_object_mut() {
+        object.insert("line".to_string(), serde_json::json!(line_number));
+    }
+    line.to_string()
+}
+
+// Scan only the lines a unified diff adds, e.g. from a pre-commit hook, and
+// report findings by file path and line number
+#[post("/pii/scan/diff", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_diff(
+    request: Json<DiffScanRequest>,
+    audit: &AuditTrail,
+    deadline: RequestDeadline,
+    model_state: &State<ModelState>,
+    queue: &State<QueueState>,
+    prepass: &State<PrepassState>,
+    profiles: &State<ProfilesState>,
+    config: &State<Config>,
+    metrics: &State<MetricsState>,
+) -> ApiResult<DiffScanResponse> {
+    let deadline = deadline.or_priority(Priority::Batch);
+    let request = request.into_inner();
+    let blocks = diff::added_blocks(&request.diff);
+    let profile = profiles::resolve(&*profiles.read().await, request.profile.as_deref())?.cloned();
+    request.recognizers.validate()?;
+    let recognizer_options = request.recognizers;
+    let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+
+    let files_scanned = blocks.iter().map(|block| block.path.as_str()).collect::<HashSet<_>>().len();
+    let lines_scanned = blocks.iter().map(|block| block.text.lines().count()).sum();
+    if blocks.is_empty() {
+        audit.findings(&Vec::<Vec<PiiEntity>>::new());
+        return Ok(Json(ApiResponse {
+            success: true,
+            data: Some(DiffScanResponse {
+                files_scanned,
+                lines_scanned,
+                findings: Vec::new(),
+                total_hits: 0,
+                profile: profile_name,
+                inference_batches: 0,
+            }),
+            message: None,
+            api_version: API_VERSION,
+        }));
+    }
+
+    let prepass = prepass.inner().clone();
+    let config = config.inner().clone();
+    let (blocks, batch) = run_with_deadline(model_state, queue, deadline, move |model, progress| {
+        let labels = profile_labels(profile.as_ref());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let texts: Vec<&str> = blocks.iter().map(|block| block.text.as_str()).collect();
+
+        let mut batch = detection::detect_documents(model, Option::as_ref(&prepass), &texts, &labels, &recognizer_options, &config, progress)
+            .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+        if let Some(profile) = &profile {
+            batch.documents.iter_mut().for_each(|entities| profile.filter(entities));
+        }
+        if config.privacy_mode {
+            batch.documents.iter_mut().for_each(|entities| privacy::clear_text(entities));
+        }
+        Ok((blocks, batch))
+    })
+    .await?;
+
+    metrics.record_batches(batch.batches, batch.padding);
+    metrics.record_prepass_skipped(batch.chunks - batch.escalated);
+
+    audit.findings(&batch.documents);
+    let findings = diff::findings(&blocks, batch.documents);
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(DiffScanResponse {
+            files_scanned,
+            lines_scanned,
+            total_hits: findings.len(),
+            findings,
+            profile: profile_name,
+            inference_batches: batch.batches,
+        }),
+        message: None,
+        api_version: API_VERSION,
+    }))
+}
+
+async fn read_upload(file: &TempFile<'_>) -> std::io::Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut bytes = Vec::with_capacity(file.len() as usize);
+    file.open().await?.read_to_end(&mut bytes).await?;
+    Ok(bytes)
+}
+
+#[launch]
+pub async fn rocket() -> Rocket<Build> {
+    rocket_with_clock(clock::system()).await
+}
+
+// Build the server around a given clock; tests pass a TestClock to move drain
+// grace periods, retention ages and scan schedules forward without sleeping
+pub async fn rocket_with_clock(clock: SharedClock) -> Rocket<Build> {
+    let config = Config::from_env();
+    let readiness = Readiness::default();
+
+    // Initialize model at startup, or in the background in lazy mode
+    let model_state: ModelState = Arc::new(Mutex::new(None));
+    let info_state: ModelInfoState = Arc::new(Mutex::new(None));
+    let model_load: ModelLoadState = Arc::new(ModelLoad::new(&config.model_name));
+    let loader = loading::load(config.clone(), model_load.clone(), model_state.clone(), info_state.clone(), readiness.clone());
+    if config.lazy_load {
+        tracing::info!("Loading the model in the background; GET /v1/model/status reports progress");
+        tokio::spawn(loader);
+    } else {
+        loader.await;
+    }
+    
+    // The canary loads after the primary, and takes no traffic until it is ready
+    let canary_state: CanaryState = Arc::new(Canary::from_config(&config));
+    if canary_state.is_some() {
+        let (canary, config) = (canary_state.clone(), config.clone());
+        tokio::spawn(async move {
+            if let Some(canary) = Option::as_ref(&canary) {
+                canary.load(&config).await;
+            }
+        });
+    }
+    let language_state: LanguageRouterState = Arc::new(LanguageRouter::from_config(&config));
+    if !language_state.is_empty() {
+        let (languages, config) = (language_state.clone(), config.clone());
+        tokio::spawn(async move { languages.load(&config).await });
+    }
+    let feedback_state: FeedbackState = Arc::new(FeedbackStore::from_config(&config));
+    let review_state: ReviewState = Arc::new(ReviewQueue::from_config(&config));
+    let shadow_state: ShadowState = Arc::new(Shadow::from_config(&config));
+    if shadow_state.is_some() {
+        let (shadow, config) = (shadow_state.clone(), config.clone());
+        tokio::spawn(async move {
+            if let Some(shadow) = Option::as_ref(&shadow) {
+                shadow.load(&config).await;
+            }
+        });
+    }
+
+    let prepass = match prepass::load_prepass(&config) {
+        Ok(prepass) => prepass,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to initialize pre-pass; every chunk goes to the main model");
+            None
+        }
+    };
+
+    let profiles = match profiles::load_profiles(&config) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load label profiles; continuing with the built-in profiles only");
+            profiles::builtin_profiles()
+                .into_iter()
+                .map(|profile| (profile.name.clone(), profile))
+                .collect()
+        }
+    };
+
+    let profiles_state: ProfilesState = Arc::new(RwLock::new(profiles));
+    if let Some(peer) = &config.config_peer_url {
+        tracing::info!(peer = %peer, interval_secs = config.config_sync_interval_secs, "Syncing configuration from peer");
+        replication::spawn_pull_sync(
+            peer.clone(),
+            config.config_peer_token.clone(),
+            std::time::Duration::from_secs(config.config_sync_interval_secs.max(1)),
+            profiles_state.clone(),
+        );
+    }
+    #[cfg(feature = "persistence")]
+    let result_store = match &config.database_url {
+        Some(url) => match ResultStore::connect(url).await {
+            Ok(store) => Some(store),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to connect to the results database; continuing without persistence");
+                None
+            }
+        },
+        None => None,
+    };
+    #[cfg(feature = "persistence")]
+    let audit_log = crate::persistence::audit_log(&config, result_store.as_ref());
+    #[cfg(not(feature = "persistence"))]
+    let audit_log = crate::audit::AuditLog::from_config(&config);
+    let audit_state: Option<AuditState> = match audit_log {
+        Ok(audit) => audit.map(Arc::new),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to set up audit logging; continuing without it");
+            None
+        }
+    };
+    let sweeper_config = config.clone();
+    let prepass_state: PrepassState = Arc::new(prepass);
+    let plugins_state: PluginsState = Arc::new(match Plugins::from_config(&config) {
+        Ok(plugins) => {
+            if let Some(plugins) = &plugins {
+                tracing::info!(plugins = ?plugins.names(), "Loaded post-processing plugins");
+            }
+            plugins
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load post-processing plugins; continuing without them");
+            None
+        }
+    });
+    let metrics_state: MetricsState = Arc::new(Metrics::default());
+    let queue_state: QueueState = Arc::new(
+        InferenceQueue::with_clock(config.max_queue_depth, clock.clone()).with_breaker(CircuitBreaker::new(BreakerSettings::from_config(&config))),
+    );
+    health::spawn_recovery(queue_state.breaker().clone(), model_state.clone(), config.clone());
+    
+    // Oversized bodies are rejected with 413 before they are parsed. On shutdown,
+    // in-flight detections get as long as their maximum deadline to finish.
+    let figment = rocket::Config::figment()
+        .merge(("limits", Limits::default()
+            .limit("json", ByteUnit::from(config.max_payload_bytes))
+            .limit("file", ByteUnit::from(config.max_upload_bytes))
+            .limit("data-form", ByteUnit::from(config.max_upload_bytes))))
+        .merge(("shutdown.grace", config.max_request_timeout_ms.div_ceil(1000) as u32));
+
+    let scheduler: SchedulerState = Arc::new(Scheduler::new(ScanEngine {
+        model_state: model_state.clone(),
+        queue: queue_state.clone(),
+        prepass: prepass_state.clone(),
+        profiles: profiles_state.clone(),
+        config: config.clone(),
+        metrics: metrics_state.clone(),
+        clock: clock.clone(),
+    }));
+
+    let rocket = rocket::custom(figment)
+        .manage(model_state)
+        .manage(info_state)
+        .manage(model_load)
+        .manage(canary_state)
+        .manage(shadow_state)
+        .manage(feedback_state)
+        .manage(review_state)
+        .manage(prepass_state)
+        .manage(plugins_state)
+        .manage(language_state)
+        .manage(profiles_state)
+        .manage(readiness)
+        .manage(SelfTest::default())
+        .manage(config)
+        .manage(metrics_state)
+        .manage(queue_state)
+        .manage(scheduler)
+        .mount("/", routes![
+            index,
+            health_check,
+            readiness_check,
+            ws_detect,
+            presidio_analyze,
+            presidio_supported_entities
+        ])
+        .mount(V1_BASE, v1_routes())
+        .mount(LEGACY_BASE, v1_routes())
+        .attach(VersionHeaders)
+        .attach(RequestLog)
+        .register("/", catchers![
+            error::bad_request,
+            error::unauthorized,
+            error::payload_too_large,
+            error::unsupported_media_type,
+            error::unprocessable_entity
+        ]);
+    #[cfg(feature = "persistence")]
+    let result_state: Option<ResultStoreState> = result_store.map(Arc::new);
+    let retention = Retention {
+        audit: audit_state.clone(),
+        #[cfg(feature = "persistence")]
+        results: result_state.clone(),
+        clock: clock.clone(),
+    };
+    retention::spawn_sweeper(retention.clone(), sweeper_config);
+    let rocket = rocket.manage(retention);
+
+    #[cfg(feature = "persistence")]
+    let rocket = {
+        let rocket = rocket.manage(result_state.clone());
+        match result_state {
+            Some(_) => rocket.attach(ResultRecorder),
+            None => rocket,
+        }
+    };
+    // Managed even when off, so routes can take it without aborting launch
+    let rocket = rocket.manage(audit_state.clone());
+    match audit_state {
+        Some(_) => rocket.attach(AuditRecorder),
+        None => rocket,
+    }
+}
+
+// Routes of API version 1, mounted under `/v1` and, deprecated, under `/api`.
+// Probes, the WebSocket and the Presidio-compatible endpoints stay unversioned.
+pub fn v1_routes() -> Vec<rocket::Route> {
+    let mut routes = routes![
+        version,
+        get_model_info,
+        get_model_status,
+        get_metrics,
+        detect_pii,
+        detect_pii_batch,
+        detect_pii_batch_jsonl,
+        detect_pii_ndjson,
+        detect_pii_sse,
+        discover_entities,
+        evaluate,
+        evaluate_sweep,
+        scan_file,
+        scan_s3,
+        scan_csv,
+        scan_json,
+        scan_diff,
+        scan_logs,
+        start_drain,
+        cancel_drain,
+        admin_stats,
+        run_benchmark,
+        compare_models,
+        compare_models_file,
+        export_training,
+        review_pending,
+        review_approve,
+        review_reject,
+        unload_model,
+        reload_model,
+        export_config,
+        import_config,
+        recent_audit_events,
+        purge_audit_events,
+        purge_results,
+        register_schedule,
+        list_schedules,
+        get_schedule,
+        get_schedule_dead_letters,
+        delete_schedule,
+        run_schedule
+    ];
+    routes.extend(protobuf_routes());
+    routes.extend(persistence_routes());
+    routes.extend(storage_routes());
+    routes.extend(dev_routes());
+    routes
+}
+
+#[cfg(feature = "dev")]
+fn dev_routes() -> Vec<rocket::Route> {
+    routes![synthetic_documents]
+}
+
+#[cfg(not(feature = "dev"))]
+fn dev_routes() -> Vec<rocket::Route> {
+    Vec::new()
+}
+
+// Object storage backends beyond S3, each behind its own feature
+fn storage_routes() -> Vec<rocket::Route> {
+    #[allow(unused_mut)]
+    let mut routes = Vec::new();
+    #[cfg(feature = "gcs")]
+    routes.extend(routes![scan_gcs]);
+    #[cfg(feature = "azure")]
+    routes.extend(routes![scan_azure]);
+    routes
+}
+
+#[cfg(feature = "persistence")]
+fn persistence_routes() -> Vec<rocket::Route> {
+    routes![list_results, get_result]
+}
+
+#[cfg(not(feature = "persistence"))]
+fn persistence_routes() -> Vec<rocket::Route> {
+    Vec::new()
+}
+
+#[cfg(feature = "protobuf")]
+fn protobuf_routes() -> Vec<rocket::Route> {
+    routes![detect_pii_protobuf, detect_pii_batch_protobuf]
+}
+
+#[cfg(not(feature = "protobuf"))]
+fn protobuf_routes() -> Vec<rocket::Route> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inference;
+    use rocket::http::Status;
+    use rocket::local::blocking::Client;
+
+    fn create_test_client() -> Client {
+        Client::tracked(rocket::execute(rocket())).expect("valid rocket instance")
+    }
+
+    #[test]
+    fn test_health_check_response() {
+        let client = create_test_client();
+        let response = client.get("/health").dispatch();
+        
+        assert_eq!(response.status(), Status::Ok);
+        
+        let health_response: HealthResponse = response.into_json().expect("valid JSON");
+        assert_eq!(health_response.status, "ok");
+        assert_eq!(health_response.message, "API is running");
+    }
+
+    #[test]
+    fn test_readiness_without_model() {
+        let client = create_test_client();
+        let response = client.get("/health/ready").dispatch();
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        let health_response: HealthResponse = response.into_json().expect("valid JSON");
+        assert_eq!(health_response.status, "not_ready");
+
+        // No self-test runs before the instance is ready
+        let response = client.get("/health/ready?self_test=true").dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let health_response: HealthResponse = response.into_json().expect("valid JSON");
+        assert!(health_response.self_test.is_none());
+    }
+
+    #[test]
+    fn test_readiness_flag() {
+        let readiness = Readiness::default();
+        assert!(!readiness.is_ready());
+        readiness.set_ready(true);
+        assert!(readiness.is_ready());
+    }
+
+    #[test]
+    fn test_index_response() {
+        let client = create_test_client();
+        let response = client.get("/").dispatch();
+        
+        assert_eq!(response.status(), Status::Ok);
+        
+        let api_response: ApiResponse<String> = response.into_json().expect("valid JSON");
+        assert!(api_response.success);
+        assert_eq!(api_response.data, Some("Welcome to Gliner RS API".to_string()));
+        assert!(api_response.message.is_none());
+    }
+
+    #[test]
+    fn test_version_response() {
+        let client = create_test_client();
+        let response = client.get("/api/version").dispatch();
+        
+        assert_eq!(response.status(), Status::Ok);
+        
+        let api_response: ApiResponse<String> = response.into_json().expect("valid JSON");
+        assert!(api_response.success);
+        assert_eq!(api_response.data, Some("0.1.0".to_string()));
+        assert!(api_response.message.is_none());
+    }
+
+    #[test]
+    fn test_versioned_and_legacy_paths() {
+        let client = create_test_client();
+        let response = client.get("/v1/version").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.headers().get_one("API-Version"), Some("1"));
+        assert_eq!(response.headers().get_one("Deprecation"), None);
+        let api_response: ApiResponse<String> = response.into_json().expect("valid JSON");
+        assert_eq!(api_response.api_version, 1);
+
+        let response = client.post("/api/pii/detect").header(ContentType::JSON).body(r#"{"text": ""}"#).dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.headers().get_one("Deprecation"), Some("true"));
+        assert_eq!(
+            response.headers().get_one("Link"),
+            Some("</v1/pii/detect>; rel=\"successor-version\"")
+        );
+
+        // Probes stay unversioned and are not deprecated
+        let response = client.get("/health").dispatch();
+        assert_eq!(response.headers().get_one("Deprecation"), None);
+        assert_eq!(client.get("/v1/health").dispatch().status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_request_id_is_returned_and_honored() {
+        use rocket::http::Header;
+
+        let client = create_test_client();
+        let response = client.get("/v1/version").dispatch();
+        let generated = response.headers().get_one("X-Request-Id").expect("request id header").to_string();
+        let response = client.get("/v1/version").dispatch();
+        assert_ne!(response.headers().get_one("X-Request-Id"), Some(generated.as_str()));
+
+        // A caller's id comes back in the header and in error bodies
+        let response = client
+            .post("/v1/pii/detect")
+            .header(ContentType::JSON)
+            .header(Header::new("X-Request-Id", "checkout-42"))
+            .body(r#"{"text": ""}"#)
+            .dispatch();
+        assert_eq!(response.headers().get_one("X-Request-Id"), Some("checkout-42"));
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error_response.request_id.as_deref(), Some("checkout-42"));
+
+        // Ids that could break a log line are replaced
+        let response = client.get("/v1/version").header(Header::new("X-Request-Id", "a b")).dispatch();
+        assert_ne!(response.headers().get_one("X-Request-Id"), Some("a b"));
+    }
+
+    #[test]
+    fn test_audit_endpoint_when_audit_is_off() {
+        let client = create_test_client();
+        let response = client.get("/v1/admin/audit?limit=10").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let api_response: ApiResponse<Vec<crate::audit::AuditEvent>> = response.into_json().expect("valid JSON");
+        assert_eq!(api_response.data.map(|events| events.len()), Some(0));
+        assert!(api_response.message.is_some());
+    }
+
+    #[test]
+    fn test_purge_needs_an_age() {
+        let client = create_test_client();
+        let response = client.delete("/v1/admin/results").dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let response = client.delete("/v1/admin/audit?older_than=7w").dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client.delete("/v1/admin/audit?older_than=90d").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let api_response: ApiResponse<PurgeReport> = response.into_json().expect("valid JSON");
+        assert!(api_response.data.unwrap().audit_events.is_none());
+    }
+
+    #[test]
+    fn test_s3_scan_rejects_unnamed_and_foreign_objects() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/pii/scan/s3")
+            .header(ContentType::JSON)
+            .body(r#"{"bucket": "lake"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        // Not in GLINER_S3_ALLOWED_BUCKETS, so the service's credentials aren't used
+        let response = client
+            .post("/v1/pii/scan/s3")
+            .header(ContentType::JSON)
+            .body(r#"{"bucket": "payroll", "key": "2026/salaries.csv"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(error_response.message.unwrap_or_default().contains("GLINER_S3_ALLOWED_BUCKETS"));
+
+        let response = client
+            .post("/v1/pii/scan/s3")
+            .header(ContentType::JSON)
+            .body(r#"{"url": "http://169.254.169.254/latest/meta-data?X-Amz-Signature=abc"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error_response.error_code, "invalid_input");
+    }
+
+    #[test]
+    fn test_schedules_are_validated_and_listed() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/admin/schedules")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "too-often", "every": "10s", "target": {"type": "s3", "bucket": "exports"}}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client.get("/v1/admin/schedules").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let api_response: ApiResponse<Vec<ScheduleStatus>> = response.into_json().expect("valid JSON");
+        assert!(api_response.data.unwrap().is_empty());
+
+        let response = client.get("/v1/admin/schedules/nightly").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client.get("/v1/admin/schedules/nightly/dead-letters").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_model_info_without_model() {
+        let client = create_test_client();
+        let response = client.get("/api/model").dispatch();
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(!error_response.success);
+        assert_eq!(error_response.error_code, "model_not_loaded");
+    }
+
+    #[test]
+    fn test_model_status_reports_failed_load() {
+        let client = create_test_client();
+        let response = client.get("/v1/model/status").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let api_response: ApiResponse<ModelStatus> = response.into_json().expect("valid JSON");
+        let status = api_response.data.unwrap();
+        assert!(matches!(status.phase, loading::LoadPhase::Failed { .. }));
+        assert_eq!(client.get("/api/model/status").dispatch().status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_admin_stats() {
+        let client = create_test_client();
+        let response = client.get("/v1/admin/stats").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let stats = response.into_json::<ApiResponse<AdminStats>>().expect("valid JSON").data.unwrap();
+        assert_eq!(stats.in_flight_inferences, 0);
+        assert_eq!(stats.queue_depth, 0);
+        assert!(stats.model_memory_bytes.is_none());
+        assert!(stats.queue_capacity > 0);
+    }
+
+    #[test]
+    fn test_benchmark_needs_a_model() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/admin/benchmark")
+            .header(ContentType::JSON)
+            .body(r#"{"batch_sizes": [0]}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client.post("/v1/admin/benchmark").header(ContentType::JSON).body("{}").dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_eval_validates_before_detecting() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/eval")
+            .header(ContentType::JSON)
+            .body(r#"{"documents": []}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/v1/eval")
+            .header(ContentType::JSON)
+            .body(r#"{"documents": [{"text": "Call Jane Roe", "entities": [{"label": "person", "start": 5, "end": 13}]}], "match": "partial"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_sweep_rejects_bad_thresholds() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/eval/sweep")
+            .header(ContentType::JSON)
+            .body(r#"{"documents": [{"text": "Call Jane Roe", "entities": [{"label": "person", "start": 5, "end": 13}]}], "thresholds": [0.5, 1.5]}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[cfg(feature = "dev")]
+    #[test]
+    fn test_synthetic_documents() {
+        let client = create_test_client();
+        let response = client.get("/v1/dev/synthetic?count=3&seed=9").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let documents = response.into_json::<ApiResponse<Vec<eval::EvalDocument>>>().expect("valid JSON").data.unwrap();
+        assert_eq!(documents.len(), 3);
+
+        assert_eq!(client.get("/v1/dev/synthetic?count=100000").dispatch().status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_compare_needs_two_models() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/admin/compare")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Call Jane Roe"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/v1/admin/compare?model_b=gliner-next")
+            .header(ContentType::Plain)
+            .body("Call Jane Roe\nMail jane@example.com\n")
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_discover_needs_text() {
+        let client = create_test_client();
+        let response = client.post("/v1/pii/discover").header(ContentType::JSON).body(r#"{"texts": []}"#).dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_training_export_needs_collection() {
+        let client = create_test_client();
+        let response = client.get("/v1/admin/training/export").dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_review_needs_enabling() {
+        let client = create_test_client();
+        assert_eq!(client.get("/v1/review/pending").dispatch().status(), Status::UnprocessableEntity);
+        assert_eq!(client.post("/v1/review/1/approve").dispatch().status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_unload_model() {
+        let client = create_test_client();
+        let response = client.post("/v1/admin/model/unload").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let api_response: ApiResponse<ModelStatus> = response.into_json().expect("valid JSON");
+        assert_eq!(api_response.data.unwrap().phase, loading::LoadPhase::Unloaded);
+
+        assert_eq!(client.get("/health/ready").dispatch().status(), Status::ServiceUnavailable);
+        let response = client
+            .post("/v1/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Call Jane Roe"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_metrics_endpoint() {
+        let client = create_test_client();
+        let response = client.get("/api/metrics").dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let api_response: ApiResponse<MetricsSnapshot> = response.into_json().expect("valid JSON");
+        assert!(api_response.success);
+        assert_eq!(api_response.data.unwrap().inference_batches, 0);
+    }
+
+    #[test]
+    fn test_404_for_unknown_route() {
+        let client = create_test_client();
+        let response = client.get("/unknown-route").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_health_response_serialization() {
+        let health = HealthResponse {
+            status: "ok".to_string(),
+            message: "test".to_string(),
+            self_test: None,
+        };
+        
+        let json = serde_json::to_string(&health).expect("serialization should work");
+        let deserialized: HealthResponse = serde_json::from_str(&json).expect("deserialization should work");
+        
+        assert_eq!(health.status, deserialized.status);
+        assert_eq!(health.message, deserialized.message);
+    }
+
+    #[test]
+    fn test_api_response_serialization() {
+        let api_response = ApiResponse {
+            success: true,
+            data: Some("test data".to_string()),
+            message: Some("test message".to_string()),
+            api_version: API_VERSION,
+        };
+        
+        let json = serde_json::to_string(&api_response).expect("serialization should work");
+        let deserialized: ApiResponse<String> = serde_json::from_str(&json).expect("deserialization should work");
+        
+        assert_eq!(api_response.success, deserialized.success);
+        assert_eq!(api_response.data, deserialized.data);
+        assert_eq!(api_response.message, deserialized.message);
+    }
+
+    #[test]
+    fn test_pii_entities_endpoint() {
+        let client = create_test_client();
+        let response = client.get("/api/pii/entities").dispatch();
+        
+        assert_eq!(response.status(), Status::Ok);
+        
+        let api_response: ApiResponse<Vec<String>> = response.into_json().expect("valid JSON");
+        assert!(api_response.success);
+        assert!(api_response.data.is_some());
+        
+        let entities = api_response.data.unwrap();
+        assert!(entities.contains(&"person".to_string()));
+        assert!(entities.contains(&"email".to_string()));
+        assert!(entities.contains(&"phone".to_string()));
+    }
+
+    #[test]
+    fn test_pii_detect_without_model() {
+        let client = create_test_client();
+        let request_body = serde_json::json!({
+            "text": "My name is John Doe and my email is john@example.com",
+            "threshold": 0.5
+        });
+        
+        let response = client
+            .post("/api/pii/detect")
+            .header(rocket::http::ContentType::JSON)
+            .body(request_body.to_string())
+            .dispatch();
+        
+        // Should return an error since model is not loaded
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(!error_response.success);
+        assert_eq!(error_response.error_code, "model_not_loaded");
+        assert!(error_response.message.unwrap().contains("model not loaded"));
+    }
+
+    #[test]
+    fn test_pii_detect_batch_without_model() {
+        let client = create_test_client();
+        let request_body = serde_json::json!({
+            "documents": [
+                {"text": "My name is John Doe"},
+                {"text": "Reach me at jane@example.com"}
+            ]
+        });
+
+        let response = client
+            .post("/api/pii/detect/batch")
+            .header(rocket::http::ContentType::JSON)
+            .body(request_body.to_string())
+            .dispatch();
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error_response.error_code, "model_not_loaded");
+    }
+
+    #[test]
+    fn test_malformed_json_uses_error_shape() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(rocket::http::ContentType::JSON)
+            .body("{\"txt\": 42}")
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error_response.error_code, "invalid_input");
+    }
+
+    #[test]
+    fn test_pii_detect_rejects_blank_text() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(rocket::http::ContentType::JSON)
+            .body(serde_json::json!({"text": "   "}).to_string())
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error_response.error_code, "invalid_input");
+    }
+
+    #[test]
+    fn test_invalid_timeout_header() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(rocket::http::ContentType::JSON)
+            .header(rocket::http::Header::new(inference::TIMEOUT_HEADER, "soon"))
+            .body(serde_json::json!({"text": "My name is John Doe"}).to_string())
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_pii_detect_batch_jsonl_without_model() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect/batch?response_format=jsonl")
+            .header(rocket::http::ContentType::JSON)
+            .body(serde_json::json!({"documents": [{"text": "My name is John Doe"}]}).to_string())
+            .dispatch();
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_pii_detect_unknown_profile() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(rocket::http::ContentType::JSON)
+            .body(serde_json::json!({"text": "My name is John Doe", "profile": "sox"}).to_string())
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(error_response.message.unwrap().contains("unknown profile"));
+    }
+
+    fn multipart_upload(filename: &str, content: &str) -> (ContentType, String) {
+        let boundary = "gliner-test-boundary";
+        let body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{f}\"\r\nContent-Type: application/octet-stream\r\n\r\n{c}\r\n--{b}--\r\n",
+            b = boundary,
+            f = filename,
+            c = content
+        );
+        (ContentType::new("multipart", "form-data").with_params(("boundary", boundary)), body)
+    }
+
+    #[test]
+    fn test_scan_file_without_model() {
+        let client = create_test_client();
+        let (content_type, body) = multipart_upload("notes.txt", "Call John Doe at 555-0100");
+        let response = client.post("/api/pii/scan/file").header(content_type).body(body).dispatch();
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let error: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error.error_code, "model_not_loaded");
+    }
+
+    #[test]
+    fn test_scan_file_rejects_unsupported_type() {
+        let client = create_test_client();
+        let (content_type, body) = multipart_upload("scan.exe", "MZ");
+        let response = client.post("/api/pii/scan/file").header(content_type).body(body).dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error.error_code, "invalid_input");
+    }
+
+    #[test]
+    #[cfg(not(feature = "ocr"))]
+    fn test_scan_image_needs_ocr_feature() {
+        let client = create_test_client();
+        let (content_type, body) = multipart_upload("scan.png", "not really a png");
+        let response = client.post("/api/pii/scan/file").header(content_type).body(body).dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(error.message.unwrap().contains("OCR"));
+    }
+
+    #[test]
+    fn test_scan_file_rejects_corrupt_archive() {
+        let client = create_test_client();
+        let (content_type, body) = multipart_upload("export.zip", "not a zip file");
+        let response = client.post("/api/pii/scan/file").header(content_type).body(body).dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(error.message.unwrap().contains("zip archive"));
+    }
+
+    #[test]
+    fn test_pii_detect_rejects_unknown_field_selection() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect?fields=label,ssn")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "John Doe"}"#)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let error: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert!(error.message.unwrap().contains("ssn"));
+    }
+
+    #[test]
+    fn test_pii_detect_context_limits() {
+        let client = create_test_client();
+        for body in [
+            r#"{"text": "John Doe", "context_chars": 20, "privacy_mode": true}"#,
+            r#"{"text": "John Doe", "context_chars": 5000}"#,
+        ] {
+            let response = client.post("/api/pii/detect").header(ContentType::JSON).body(body).dispatch();
+            assert_eq!(response.status(), Status::UnprocessableEntity, "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_pii_detect_rejects_bad_response_filters() {
+        let client = create_test_client();
+        for uri in [
+            "/api/pii/detect?min_confidence=1.5",
+            "/api/pii/detect?labels=,",
+        ] {
+            let response = client.post(uri).header(ContentType::JSON).body(r#"{"text": "John Doe"}"#).dispatch();
+            assert_eq!(response.status(), Status::UnprocessableEntity, "{}", uri);
+        }
+
+        let response = client
+            .post("/api/pii/detect?counts_only=true")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "John Doe", "output_format": "html"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_pii_detect_rejects_unknown_recognizer_pack() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "John Doe", "recognizer_packs": ["uk", "atlantis"]}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_pii_detect_rejects_markup_without_text() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "<div><img src=\"a.png\"></div>", "input_format": "html"}"#)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_pii_detect_skip_code_blocks_needs_markdown() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Call Jane", "input_format": "html", "skip_code_blocks": true}"#)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_drain_flips_readiness() {
+        let client = create_test_client();
+        let response = client.post("/api/admin/drain?grace_seconds=60").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let status: ApiResponse<DrainStatus> = response.into_json().expect("valid JSON");
+        let status = status.data.unwrap();
+        assert!(status.draining);
+        assert!(status.accepting);
+
+        let response = client.get("/health/ready").dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let health: HealthResponse = response.into_json().expect("valid JSON");
+        assert_eq!(health.status, "draining");
+
+        let response = client.delete("/api/admin/drain").dispatch();
+        let status: ApiResponse<DrainStatus> = response.into_json().expect("valid JSON");
+        assert!(!status.data.unwrap().draining);
+    }
+
+    #[test]
+    fn test_schedules_are_timed_by_the_server_clock() {
+        use crate::clock::Clock;
+
+        let clock = crate::clock::TestClock::new();
+        // A week ahead of the system clock, so timestamps show which one was read
+        clock.advance(std::time::Duration::from_secs(7 * 86_400));
+        let rocket = rocket::execute(rocket_with_clock(clock.clone()));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+        let body = r#"{"name": "nightly", "every": "1d", "at": "02:00", "target": {"type": "s3", "bucket": "exports"}}"#;
+        let spec: ScheduleSpec = serde_json::from_str(body).unwrap();
+        let response = client.post("/v1/admin/schedules").header(ContentType::JSON).body(body).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let status = response.into_json::<ApiResponse<ScheduleStatus>>().expect("valid JSON").data.unwrap();
+        assert_eq!(status.next_run, crate::schedule::plan(&spec, clock.unix_now()).unwrap().1);
+
+        let response = client.post("/v1/admin/schedules/nightly/run").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let status = client.get("/v1/admin/schedules/nightly").dispatch();
+        let status = status.into_json::<ApiResponse<ScheduleStatus>>().expect("valid JSON").data.unwrap();
+        assert_eq!(status.runs[0].started_at, clock.unix_now());
+    }
+
+    #[test]
+    fn test_drain_grace_expires_with_test_clock() {
+        let clock = crate::clock::TestClock::new();
+        let rocket = rocket::execute(rocket_with_clock(clock.clone()));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+        client.post("/api/admin/drain?grace_seconds=30").dispatch();
+
+        clock.advance(std::time::Duration::from_secs(31));
+        // Draining again keeps the original deadline and reports the current state
+        let response = client.post("/api/admin/drain").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let status: ApiResponse<DrainStatus> = response.into_json().expect("valid JSON");
+        let status = status.data.unwrap();
+        assert!(status.draining);
+        assert!(!status.accepting);
+        assert_eq!(status.grace_remaining_seconds, 0);
+
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Jane Roe"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let error_response: error::ErrorResponse = response.into_json().expect("valid JSON");
+        assert_eq!(error_response.error_code, "draining");
+    }
+
+    #[test]
+    fn test_sse_validates_before_streaming() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect/sse")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "   "}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/api/pii/detect/sse")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Jane Roe lives in Springfield"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_ws_detect_rejects_unknown_profile() {
+        use rocket::http::Header;
+
+        let client = create_test_client();
+        let response = client
+            .get("/ws/detect?profile=nope")
+            .header(Header::new("Connection", "Upgrade"))
+            .header(Header::new("Upgrade", "websocket"))
+            .header(Header::new("Sec-WebSocket-Version", "13"))
+            .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        // Without a WebSocket handshake the route does not apply
+        assert_eq!(client.get("/ws/detect").dispatch().status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_ndjson_requires_model() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect/ndjson")
+            .header(ContentType::new("application", "x-ndjson"))
+            .body("{\"text\": \"Jane Roe\"}\n{\"text\": \"John Doe\"}\n")
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+
+        let response = client.post("/api/pii/detect/ndjson?profile=nope").body("").dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_ndjson_error_line_carries_index() {
+        let line: serde_json::Value =
+            serde_json::from_str(&ndjson_error(7, &ApiError::InvalidInput("bad".to_string()))).unwrap();
+        assert_eq!(line["index"], 7);
+        assert_eq!(line["error_code"], "invalid_input");
+    }
+
+    #[test]
+    fn test_scan_json_validates_before_model() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/scan/json")
+            .header(ContentType::JSON)
+            .body(r#"{"document": {"customers": [{"notes": "Jane Roe"}]}, "profile": "nope"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/api/pii/scan/json")
+            .header(ContentType::JSON)
+            .body(r#"{"document": {"customers": [{"notes": "Jane Roe"}]}}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_scan_diff_without_added_lines() {
+        let client = create_test_client();
+        let diff = "--- a/notes.txt\n+++ b/notes.txt\n@@ -1 +0,0 @@\n-Jane Roe\n";
+        let response = client
+            .post("/api/pii/scan/diff")
+            .header(ContentType::JSON)
+            .body(serde_json::json!({ "diff": diff }).to_string())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: ApiResponse<DiffScanResponse> = response.into_json().expect("valid JSON");
+        let data = body.data.unwrap();
+        assert_eq!((data.files_scanned, data.lines_scanned, data.total_hits), (0, 0, 0));
+
+        let diff = "--- a/notes.txt\n+++ b/notes.txt\n@@ -0,0 +1 @@\n+Jane Roe\n";
+        let response = client
+            .post("/api/pii/scan/diff")
+            .header(ContentType::JSON)
+            .body(serde_json::json!({ "diff": diff }).to_string())
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_scan_logs_validates_before_model() {
+        let client = create_test_client();
+        let response = client.post("/api/pii/scan/logs?profile=nope").body("login jane@example.com\n").dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client.post("/api/pii/scan/logs").body("login jane@example.com\n").dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_batch_rejects_single_document_formats() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect/batch")
+            .header(ContentType::JSON)
+            .body(r#"{"documents": [{"text": "Jane Roe"}], "output_format": "sarif"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/api/pii/detect/batch")
+            .header(ContentType::JSON)
+            .body(r#"{"documents": [{"text": "Jane Roe"}], "output_format": "conll"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_privacy_mode_rejects_text_reproducing_formats() {
+        let client = create_test_client();
+        let response = client
+            .post("/v1/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Jane Roe", "output_format": "html", "privacy_mode": true}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body = response.into_string().unwrap();
+        assert!(body.contains("privacy mode"));
+        assert!(!body.contains("Jane"));
+
+        let response = client
+            .post("/v1/pii/detect/batch")
+            .header(ContentType::JSON)
+            .body(r#"{"documents": [{"text": "Jane Roe"}], "output_format": "conll", "privacy_mode": true}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_presidio_compatible_endpoints() {
+        let client = create_test_client();
+        let response = client.get("/supportedentities?language=en").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let types: Vec<String> = response.into_json().expect("valid JSON");
+        assert!(types.contains(&"EMAIL_ADDRESS".to_string()));
+        assert!(types.contains(&"CREDIT_CARD".to_string()));
+
+        let response = client
+            .post("/analyze")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "  ", "language": "en"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .post("/analyze")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Call Jane Roe", "language": "en", "entities": ["PERSON"], "correlation_id": "abc"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_detect_accepts_msgpack_body() {
+        let client = create_test_client();
+        let body = rmp_serde::to_vec_named(&serde_json::json!({"text": "  "})).unwrap();
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::MsgPack)
+            .header(rocket::http::Accept::MsgPack)
+            .body(body)
+            .dispatch();
+        // The body was decoded and reached validation; errors stay JSON
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        assert!(response.into_string().unwrap().contains("must not be empty"));
+
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::new("application", "cbor"))
+            .body(vec![0xff])
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    #[cfg(feature = "protobuf")]
+    fn test_detect_accepts_protobuf_body() {
+        use prost::Message;
+        let client = create_test_client();
+        let body = proto::DetectRequest {
+            text: "  ".to_string(),
+            ..proto::DetectRequest::default()
+        };
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::new("application", "x-protobuf"))
+            .body(body.encode_to_vec())
+            .dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert!(response.into_string().unwrap().contains("must not be empty"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "protobuf"))]
+    fn test_protobuf_needs_feature() {
+        let client = create_test_client();
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::new("application", "x-protobuf"))
+            .body(vec![0x0a, 0x00])
+            .dispatch();
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+    }
+
+    #[test]
+    fn test_config_export_import() {
+        let client = create_test_client();
+        let response = client.get("/api/admin/config").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let exported: ApiResponse<ConfigBundle> = response.into_json().expect("valid JSON");
+        let mut bundle = exported.data.unwrap();
+        assert!(bundle.profiles.iter().any(|profile| profile.name == "hipaa"));
+
+        let mut custom = bundle.profiles[0].clone();
+        custom.name = "hr".to_string();
+        bundle.profiles.push(custom);
+        bundle.checksum = replication::checksum(&bundle.profiles);
+        let response = client
+            .put("/api/admin/config")
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&bundle).unwrap())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/api/pii/detect")
+            .header(ContentType::JSON)
+            .body(r#"{"text": "Jane Roe", "profile": "hr"}"#)
+            .dispatch();
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_pii_request_serialization() {
+        let pii_request = PiiRequest {
+            text: "Test text".to_string(),
+            ..Default::default()
+        };
+        
+        let json = serde_json::to_string(&pii_request).expect("serialization should work");
+        let deserialized: PiiRequest = serde_json::from_str(&json).expect("deserialization should work");
+        
+        assert_eq!(pii_request.text, deserialized.text);
+    }
+
+    #[test]
+    fn test_document_ref_round_trip() {
+        let request: BatchDocument = serde_json::from_value(serde_json::json!({
+            "text": "Call 555-0100",
+            "id": "ticket-42",
+            "metadata": {"source": "zendesk", "priority": 2}
+        }))
+        .expect("valid document");
+        assert_eq!(request.reference.id.as_deref(), Some("ticket-42"));
+
+        let result = DocumentResult {
+            index: 0,
+            reference: request.reference,
+            entities: Vec::new(),
+            total_entities: 0,
+            redacted_text: None,
+        };
+        let json = serde_json::to_value(&result).expect("serialization should work");
+        assert_eq!(json["id"], "ticket-42");
+        assert_eq!(json["metadata"]["source"], "zendesk");
+
+        let untagged = serde_json::to_value(DocumentResult {
+            index: 1,
+            reference: DocumentRef::default(),
+            entities: Vec::new(),
+            total_entities: 0,
+            redacted_text: None,
+        })
+        .expect("serialization should work");
+        assert!(untagged.get("id").is_none());
+    }
+
+    #[test]
+    fn test_document_ref_echo_into_result() {
+        let reference = DocumentRef {
+            id: Some("doc-1".to_string()),
+            metadata: None,
+        };
+        let mut result = serde_json::json!({"entities": []});
+        reference.echo_into(&mut result);
+        assert_eq!(result["id"], "doc-1");
+        assert!(result.get("metadata").is_none());
+    }
+
+    #[test]
+    fn test_pii_entity_serialization() {
+        let pii_entity = PiiEntity {
+            text: "John Doe".to_string(),
+            label: "person".to_string(),
+            confidence: 0.95,
+            start: 0,
+            end: 8,
+            attributes: BTreeMap::new(),
+        };
+        
+        let json = serde_json::to_string(&pii_entity).expect("serialization should work");
+        let deserialized: PiiEntity = serde_json::from_str(&json).expect("deserialization should work");
+        
+        assert_eq!(pii_entity.text, deserialized.text);
+        assert_eq!(pii_entity.label, deserialized.label);
+        assert_eq!(pii_entity.confidence, deserialized.confidence);
+        assert_eq!(pii_entity.start, deserialized.start);
+        assert_eq!(pii_entity.end, deserialized.end);
+    }
+}
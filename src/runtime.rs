@@ -0,0 +1,160 @@
+use orp::params::RuntimeParameters;
+use ort::execution_providers::{
+    ArenaExtendStrategy, CPUExecutionProvider, CUDAExecutionProvider, CoreMLExecutionProvider,
+    DirectMLExecutionProvider, ExecutionProviderDispatch, TensorRTExecutionProvider,
+};
+use std::str::FromStr;
+
+use crate::config::Config;
+
+// Execution providers that can be requested through GLINER_EXECUTION_PROVIDERS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    Cpu,
+    Cuda,
+    TensorRt,
+    CoreMl,
+    DirectMl,
+}
+
+impl FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cpu" => Ok(ProviderKind::Cpu),
+            "cuda" => Ok(ProviderKind::Cuda),
+            "tensorrt" | "trt" => Ok(ProviderKind::TensorRt),
+            "coreml" => Ok(ProviderKind::CoreMl),
+            "directml" | "dml" => Ok(ProviderKind::DirectMl),
+            other => Err(format!(
+                "unknown execution provider {:?}, expected cpu, cuda, tensorrt, coreml or directml",
+                other
+            )),
+        }
+    }
+}
+
+impl ProviderKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProviderKind::Cpu => "cpu",
+            ProviderKind::Cuda => "cuda",
+            ProviderKind::TensorRt => "tensorrt",
+            ProviderKind::CoreMl => "coreml",
+            ProviderKind::DirectMl => "directml",
+        }
+    }
+
+    fn build(&self, config: &Config) -> ExecutionProviderDispatch {
+        match self {
+            ProviderKind::Cpu => {
+                let provider = CPUExecutionProvider::default();
+                if config.cpu_arena {
+                    provider.with_arena_allocator().build()
+                } else {
+                    provider.build()
+                }
+            }
+            ProviderKind::Cuda => {
+                let mut provider = CUDAExecutionProvider::default()
+                    .with_device_id(config.gpu_device_id)
+                    .with_arena_extend_strategy(ArenaExtendStrategy::SameAsRequested);
+                if let Some(limit) = config.gpu_memory_limit {
+                    provider = provider.with_memory_limit(limit);
+                }
+                provider.build()
+            }
+            ProviderKind::TensorRt => TensorRTExecutionProvider::default()
+                .with_device_id(config.gpu_device_id)
+                .build(),
+            ProviderKind::CoreMl => CoreMLExecutionProvider::default().build(),
+            ProviderKind::DirectMl => DirectMLExecutionProvider::default()
+                .with_device_id(config.gpu_device_id)
+                .build(),
+        }
+    }
+}
+
+// Providers in order of preference. ONNX Runtime falls back down the list when
+// one is unavailable, so CPU is always appended last.
+pub fn provider_kinds(config: &Config) -> Result<Vec<ProviderKind>, String> {
+    let mut kinds = config
+        .execution_providers
+        .iter()
+        .map(|name| name.parse())
+        .collect::<Result<Vec<ProviderKind>, String>>()?;
+
+    if config.deterministic && kinds.iter().any(|kind| *kind != ProviderKind::Cpu) {
+        tracing::warn!(providers = ?config.execution_providers, "Deterministic mode only runs on the CPU execution provider; ignoring other providers");
+        kinds.clear();
+    }
+    if !kinds.contains(&ProviderKind::Cpu) {
+        kinds.push(ProviderKind::Cpu);
+    }
+    Ok(kinds)
+}
+
+// ONNX Runtime settings for every session this service creates
+pub fn runtime_parameters(config: &Config) -> Result<RuntimeParameters, String> {
+    let providers: Vec<ExecutionProviderDispatch> = provider_kinds(config)?
+        .iter()
+        .map(|kind| kind.build(config))
+        .collect();
+    let params = RuntimeParameters::default().with_execution_providers(providers);
+
+    if config.deterministic {
+        // A single intra-op thread fixes the reduction order, so identical input
+        // gives identical scores across runs on the same machine. orp builds the
+        // session itself with all graph optimizations on and exposes no other
+        // settings; sessions run their nodes sequentially, so no inter-op pool
+        // is involved, but optimized kernels can still differ between CPUs
+        return Ok(params.with_threads(1));
+    }
+    match config.threads {
+        Some(threads) => Ok(params.with_threads(threads)),
+        None => Ok(params),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(providers: &[&str]) -> Config {
+        Config {
+            execution_providers: providers.iter().map(|name| name.to_string()).collect(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_provider_names() {
+        assert_eq!("CUDA".parse::<ProviderKind>(), Ok(ProviderKind::Cuda));
+        assert_eq!("trt".parse::<ProviderKind>(), Ok(ProviderKind::TensorRt));
+        assert!("tpu".parse::<ProviderKind>().is_err());
+    }
+
+    #[test]
+    fn test_cpu_is_always_the_fallback() {
+        assert_eq!(provider_kinds(&config(&[])).unwrap(), vec![ProviderKind::Cpu]);
+        assert_eq!(
+            provider_kinds(&config(&["tensorrt", "cuda"])).unwrap(),
+            vec![ProviderKind::TensorRt, ProviderKind::Cuda, ProviderKind::Cpu]
+        );
+    }
+
+    #[test]
+    fn test_deterministic_forces_cpu() {
+        let deterministic = Config {
+            deterministic: true,
+            ..config(&["cuda"])
+        };
+        assert_eq!(provider_kinds(&deterministic).unwrap(), vec![ProviderKind::Cpu]);
+    }
+
+    #[test]
+    fn test_unknown_provider_is_an_error() {
+        assert!(runtime_parameters(&config(&["cuda", "quantum"])).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::service::{FileEntity, PiiEntity};
+
+// One finding to report, with where it was made
+pub struct Finding<'a> {
+    pub uri: &'a str,
+    pub entity: &'a PiiEntity,
+    // The artifact's text, when the entity's offsets are relative to all of it,
+    // so lines and columns can be given
+    pub text: Option<&'a str>,
+    // Page, paragraph or part the offsets are relative to otherwise
+    pub section: Option<String>,
+}
+
+// Findings for an uploaded or scanned file. Lines are only reported for formats
+// scanned as a whole, where `text` is the file's text.
+pub fn file_findings<'a>(uri: &'a str, entities: &'a [FileEntity], text: Option<&'a str>) -> Vec<Finding<'a>> {
+    entities
+        .iter()
+        .map(|found| {
+            let section = match (found.page, found.paragraph, &found.part) {
+                (Some(page), _, _) => Some(format!("page {}", page)),
+                (_, Some(paragraph), _) => Some(format!("paragraph {}", paragraph)),
+                (_, _, Some(part)) => Some(part.clone()),
+                _ => None,
+            };
+            Finding {
+                uri,
+                entity: &found.entity,
+                text: if section.is_none() { text } else { None },
+                section,
+            }
+        })
+        .collect()
+}
+
+// 1-based line and column (in characters) of a byte offset
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+// Rule id for a label, e.g. "pii/credit-card-number"
+fn rule_id(label: &str) -> String {
+    let slug: Vec<String> = label.split_whitespace().map(str::to_ascii_lowercase).collect();
+    format!("pii/{}", slug.join("-"))
+}
+
+// Build a SARIF 2.1.0 log with one rule per label. Results carry the label,
+// confidence and location but never the matched text, so the log itself can be
+// uploaded to shared dashboards.
+pub fn log(findings: &[Finding<'_>]) -> Value {
+    let mut rules: BTreeMap<String, &str> = BTreeMap::new();
+    for finding in findings {
+        rules.entry(rule_id(&finding.entity.label)).or_insert(finding.entity.label.as_str());
+    }
+    let rule_index: BTreeMap<&str, usize> = rules.keys().enumerate().map(|(index, id)| (id.as_str(), index)).collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let entity = finding.entity;
+            let id = rule_id(&entity.label);
+            let region = match finding.text {
+                Some(text) => {
+                    let (start_line, start_column) = line_column(text, entity.start);
+                    let (end_line, end_column) = line_column(text, entity.end);
+                    json!({
+                        "startLine": start_line,
+                        "startColumn": start_column,
+                        "endLine": end_line,
+                        "endColumn": end_column,
+                        "byteOffset": entity.start,
+                        "byteLength": entity.end - entity.start,
+                    })
+                }
+                // Offsets are section-relative, so only point at the file
+                None => json!({ "startLine": 1 }),
+            };
+            let mut properties = json!({
+                "label": entity.label,
+                "confidence": entity.confidence,
+            });
+            if let Some(section) = &finding.section {
+                properties["section"] = json!(section);
+                properties["sectionOffset"] = json!({ "start": entity.start, "end": entity.end });
+            }
+            if !entity.attributes.is_empty() {
+                properties["attributes"] = json!(entity.attributes);
+            }
+            json!({
+                "ruleId": id,
+                "ruleIndex": rule_index[id.as_str()],
+                "level": "warning",
+                "message": { "text": format!("Possible {} (confidence {:.2})", entity.label, entity.confidence) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.uri },
+                        "region": region,
+                    }
+                }],
+                "properties": properties,
+            })
+        })
+        .collect();
+
+    let rules: Vec<Value> = rules
+        .iter()
+        .map(|(id, label)| {
+            json!({
+                "id": id,
+                "name": label,
+                "shortDescription": { "text": format!("Possible {} in content", label) },
+                "defaultConfiguration": { "level": "warning" },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity_in;
+
+    #[test]
+    fn test_line_column() {
+        let text = "first\nsécond Jane";
+        assert_eq!(line_column(text, 0), (1, 1));
+        assert_eq!(line_column(text, text.find("Jane").unwrap()), (2, 8));
+    }
+
+    #[test]
+    fn test_log_has_rules_and_regions_but_no_matched_text() {
+        let text = "name: Jane Roe\nmail: jane@example.com";
+        let entities = [entity_in(text, "Jane Roe", "person", 0.9), entity_in(text, "jane@example.com", "email address", 0.9)];
+        let findings: Vec<Finding> = entities
+            .iter()
+            .map(|entity| Finding {
+                uri: "config/users.yml",
+                entity,
+                text: Some(text),
+                section: None,
+            })
+            .collect();
+        let log = log(&findings);
+
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "pii/email-address");
+        assert_eq!(run["results"][0]["ruleId"], "pii/person");
+        assert_eq!(run["results"][0]["ruleIndex"], 1);
+        let region = &run["results"][1]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 2);
+        assert_eq!(region["startColumn"], 7);
+        assert!(!log.to_string().contains("jane@example.com"));
+    }
+}
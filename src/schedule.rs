@@ -0,0 +1,519 @@
+// Recurring scans registered by admins, e.g. every object under an S3 prefix
+// nightly, run in the background instead of by an external orchestrator. Each
+// schedule keeps a history of its runs, and failed runs are reported to a
+// notification url. Objects that fail for a transient reason are retried with
+// backoff; those that still fail are kept in the run's dead-letter list. Schedules live in memory: like profiles imported at
+// runtime they are lost on restart and not shared between replicas.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+
+use crate::archive::ArchiveFormat;
+use crate::audit::AuditFindings;
+use crate::clock::SharedClock;
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::extract::DocumentFormat;
+use crate::remote;
+use crate::retention::parse_age;
+use crate::s3::{self, S3Object};
+use crate::routes::scan_in_background;
+use crate::{MetricsState, ModelState, PrepassState, ProfilesState, QueueState};
+
+// Shortest interval between runs of a schedule
+pub const MIN_INTERVAL_SECS: u64 = 60;
+// How often due schedules are looked for
+const TICK: Duration = Duration::from_secs(15);
+// Longest wait between retries of an object
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+// A recurring scan as registered by an admin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleSpec {
+    pub name: String,
+    // Interval between runs, in seconds or with an s, m, h or d suffix
+    pub every: String,
+    // Time of day of the first run, "HH:MM" in UTC; right away when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<String>,
+    pub target: ScheduleTarget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    // Receives a JSON POST when a run fails, instead of GLINER_SCHEDULE_NOTIFY_URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_url: Option<String>,
+}
+
+// What a schedule scans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTarget {
+    // Every supported object under a prefix of an S3 bucket
+    S3 {
+        bucket: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+// One run of a schedule. Findings are counted, never kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub started_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    pub status: RunStatus,
+    pub objects_scanned: usize,
+    // Objects of unsupported types
+    pub objects_skipped: usize,
+    pub objects_failed: usize,
+    pub total_entities: usize,
+    pub label_counts: BTreeMap<String, usize>,
+    // The listing error, or the first object that failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // Objects given up on, in the order they failed
+    #[serde(default)]
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+// An object that failed on every attempt, with its last error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub key: String,
+    pub error: String,
+    pub attempts: u32,
+}
+
+// The dead letters of one run, as listed by GET /admin/schedules/<name>/dead-letters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunDeadLetters {
+    pub started_at: u64,
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+impl RunRecord {
+    fn started(now: u64) -> Self {
+        RunRecord {
+            started_at: now,
+            finished_at: None,
+            status: RunStatus::Running,
+            objects_scanned: 0,
+            objects_skipped: 0,
+            objects_failed: 0,
+            total_entities: 0,
+            label_counts: BTreeMap::new(),
+            error: None,
+            dead_letters: Vec::new(),
+        }
+    }
+
+    fn fail(&mut self, key: String, error: ApiError, attempts: u32) {
+        self.objects_failed += 1;
+        self.error.get_or_insert_with(|| format!("{}: {}", key, error));
+        self.dead_letters.push(DeadLetter {
+            key,
+            error: error.to_string(),
+            attempts,
+        });
+    }
+}
+
+// A schedule with its timing and most recent runs, newest last
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub spec: ScheduleSpec,
+    pub interval_secs: u64,
+    // Unix seconds
+    pub next_run: u64,
+    pub runs: VecDeque<RunRecord>,
+}
+
+impl ScheduleStatus {
+    fn running(&self) -> bool {
+        self.runs.back().is_some_and(|run| run.status == RunStatus::Running)
+    }
+}
+
+// Validate a spec, returning its interval in seconds and its first run time
+pub fn plan(spec: &ScheduleSpec, now: u64) -> Result<(u64, u64), ApiError> {
+    if spec.name.trim().is_empty() || !spec.name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+        return Err(ApiError::InvalidInput("schedule name must be letters, digits, '-', '_' or '.'".to_string()));
+    }
+    let interval = parse_age(&spec.every)?;
+    if interval < MIN_INTERVAL_SECS {
+        return Err(ApiError::InvalidInput(format!("schedules run at most every {} seconds", MIN_INTERVAL_SECS)));
+    }
+    let ScheduleTarget::S3 { bucket, .. } = &spec.target;
+    if bucket.is_empty() {
+        return Err(ApiError::InvalidInput("an s3 target needs a bucket".to_string()));
+    }
+    let first = match &spec.at {
+        None => now,
+        Some(at) => next_time_of_day(at, now)?,
+    };
+    Ok((interval, first))
+}
+
+// Next Unix time at "HH:MM" UTC, today or tomorrow
+fn next_time_of_day(at: &str, now: u64) -> Result<u64, ApiError> {
+    let invalid = || ApiError::InvalidInput(format!("invalid time of day {:?}, expected HH:MM in UTC", at));
+    let (hours, minutes) = at.split_once(':').ok_or_else(invalid)?;
+    let (hours, minutes): (u64, u64) = (hours.parse().map_err(|_| invalid())?, minutes.parse().map_err(|_| invalid())?);
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    let today = now - now % 86_400 + hours * 3_600 + minutes * 60;
+    Ok(if today >= now { today } else { today + 86_400 })
+}
+
+// The states a scan outside a request runs with, the same the handlers get, and
+// the clock schedules are timed by
+#[derive(Clone)]
+pub struct ScanEngine {
+    pub model_state: ModelState,
+    pub queue: QueueState,
+    pub prepass: PrepassState,
+    pub profiles: ProfilesState,
+    pub config: Config,
+    pub metrics: MetricsState,
+    pub clock: SharedClock,
+}
+
+pub struct Scheduler {
+    schedules: Mutex<BTreeMap<String, ScheduleStatus>>,
+    engine: ScanEngine,
+    // Due schedules are only looked for once the first one is registered
+    ticker: Once,
+}
+
+pub type SchedulerState = Arc<Scheduler>;
+
+impl Scheduler {
+    pub fn new(engine: ScanEngine) -> Self {
+        Scheduler {
+            schedules: Mutex::new(BTreeMap::new()),
+            engine,
+            ticker: Once::new(),
+        }
+    }
+
+    // Add a schedule, or replace the one with the same name keeping its history
+    pub fn register(self: &Arc<Self>, spec: ScheduleSpec) -> Result<ScheduleStatus, ApiError> {
+        let (interval_secs, next_run) = plan(&spec, self.engine.clock.unix_now())?;
+        self.ticker.call_once(|| spawn_ticker(self.clone()));
+        let mut schedules = self.lock();
+        let runs = schedules.remove(&spec.name).map(|old| old.runs).unwrap_or_default();
+        let status = ScheduleStatus { spec, interval_secs, next_run, runs };
+        schedules.insert(status.spec.name.clone(), status.clone());
+        Ok(status)
+    }
+
+    pub fn list(&self) -> Vec<ScheduleStatus> {
+        self.lock().values().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<ScheduleStatus> {
+        self.lock().get(name).cloned()
+    }
+
+    // Dead letters of the schedule's kept runs that have any, oldest first
+    pub fn dead_letters(&self, name: &str) -> Option<Vec<RunDeadLetters>> {
+        let schedules = self.lock();
+        let status = schedules.get(name)?;
+        Some(
+            status
+                .runs
+                .iter()
+                .filter(|run| !run.dead_letters.is_empty())
+                .map(|run| RunDeadLetters {
+                    started_at: run.started_at,
+                    dead_letters: run.dead_letters.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        self.lock().remove(name).is_some()
+    }
+
+    // Start a run now, unless one is already running
+    pub fn trigger(self: &Arc<Self>, name: &str) -> Result<(), ApiError> {
+        let started = {
+            let mut schedules = self.lock();
+            let status = schedules
+                .get_mut(name)
+                .ok_or_else(|| ApiError::NotFound(format!("no schedule named {}", name)))?;
+            if status.running() {
+                return Err(ApiError::InvalidInput(format!("schedule {} is already running", name)));
+            }
+            self.start(status)
+        };
+        let (spec, started_at) = started;
+        self.spawn_run(spec, started_at);
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, ScheduleStatus>> {
+        self.schedules.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Record a new run and move the schedule's next run on, returning what to
+    // run and when the run started
+    fn start(&self, status: &mut ScheduleStatus) -> (ScheduleSpec, u64) {
+        let now = self.engine.clock.unix_now();
+        status.runs.push_back(RunRecord::started(now));
+        while status.runs.len() > self.engine.config.schedule_history.max(1) {
+            status.runs.pop_front();
+        }
+        while status.next_run <= now {
+            status.next_run += status.interval_secs;
+        }
+        (status.spec.clone(), now)
+    }
+
+    // Start every due schedule that is not still running its previous run
+    fn start_due(self: &Arc<Self>) {
+        let now = self.engine.clock.unix_now();
+        let due: Vec<(ScheduleSpec, u64)> = {
+            let mut schedules = self.lock();
+            schedules
+                .values_mut()
+                .filter(|status| status.next_run <= now && !status.running())
+                .map(|status| self.start(status))
+                .collect()
+        };
+        for (spec, started_at) in due {
+            self.spawn_run(spec, started_at);
+        }
+    }
+
+    fn spawn_run(self: &Arc<Self>, spec: ScheduleSpec, started_at: u64) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let record = scheduler.run(&spec, started_at).await;
+            if record.status == RunStatus::Failed {
+                tracing::warn!(schedule = %spec.name, error = record.error.as_deref().unwrap_or_default(), "Scheduled scan failed");
+                scheduler.notify(&spec, &record).await;
+            } else {
+                tracing::info!(
+                    schedule = %spec.name,
+                    objects = record.objects_scanned,
+                    entities = record.total_entities,
+                    "Scheduled scan finished"
+                );
+            }
+            // The schedule may have been removed or replaced while it ran
+            if let Some(status) = scheduler.lock().get_mut(&spec.name) {
+                if let Some(last) = status.runs.back_mut().filter(|run| run.started_at == record.started_at) {
+                    *last = record;
+                }
+            }
+        });
+    }
+
+    async fn run(&self, spec: &ScheduleSpec, started_at: u64) -> RunRecord {
+        let config = &self.engine.config;
+        let mut record = RunRecord::started(started_at);
+        let ScheduleTarget::S3 { bucket, prefix } = &spec.target;
+        match s3::list_objects(bucket, prefix, config.schedule_max_objects, config).await {
+            Err(e) => record.error = Some(format!("could not list s3://{}/{}: {}", bucket, prefix, e)),
+            Ok(keys) => {
+                for key in keys {
+                    if !scannable(&key) {
+                        record.objects_skipped += 1;
+                        continue;
+                    }
+                    match self.scan_with_retries(spec, bucket, &key).await {
+                        Ok(findings) => {
+                            record.objects_scanned += 1;
+                            record.total_entities += findings.total_entities;
+                            for (label, count) in findings.label_counts {
+                                *record.label_counts.entry(label).or_default() += count;
+                            }
+                        }
+                        Err((e, attempts)) => record.fail(key, e, attempts),
+                    }
+                }
+            }
+        }
+        record.finished_at = Some(self.engine.clock.unix_now());
+        record.status = if record.error.is_some() { RunStatus::Failed } else { RunStatus::Succeeded };
+        record
+    }
+
+    // Fetch and scan one object, retrying transient failures with backoff;
+    // gives back the last error and the number of attempts made
+    async fn scan_with_retries(&self, spec: &ScheduleSpec, bucket: &str, key: &str) -> Result<AuditFindings, (ApiError, u32)> {
+        let config = &self.engine.config;
+        let object = S3Object {
+            bucket: Some(bucket.to_string()),
+            key: Some(key.to_string()),
+            url: None,
+        };
+        let mut attempt = 1;
+        loop {
+            let result = match s3::fetch(&object, config).await {
+                Ok(fetched) => scan_in_background(&self.engine, fetched, spec.profile.as_deref()).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(findings) => return Ok(findings),
+                Err(e) if attempt <= config.schedule_retries && retryable(&e) => {
+                    let delay = retry_delay(config.schedule_retry_backoff_ms, attempt);
+                    tracing::warn!(schedule = %spec.name, key, attempt, error = %e, "Retrying scheduled object");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err((e, attempt)),
+            }
+        }
+    }
+
+    // POST the failed run to the schedule's notification url; failures to
+    // deliver are only logged
+    async fn notify(&self, spec: &ScheduleSpec, record: &RunRecord) {
+        let Some(url) = spec.notify_url.as_ref().or(self.engine.config.schedule_notify_url.as_ref()) else {
+            return;
+        };
+        let notice = serde_json::json!({ "schedule": spec.name, "run": record });
+        let sent = reqwest::Client::new()
+            .post(url)
+            .timeout(Duration::from_secs(10))
+            .json(&notice)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        if let Err(e) = sent {
+            tracing::error!(schedule = %spec.name, error = %e, "Failed to send schedule failure notification");
+        }
+    }
+}
+
+// Whether a failure may go away on its own: storage and inference errors, a
+// busy or reloading model. Bad or oversized objects fail the same way again.
+fn retryable(error: &ApiError) -> bool {
+    matches!(
+        error,
+        ApiError::StorageFailed(_)
+            | ApiError::InferenceFailed(_)
+            | ApiError::Timeout { .. }
+            | ApiError::Overloaded { .. }
+            | ApiError::ModelNotLoaded
+            | ApiError::CircuitOpen
+    )
+}
+
+// Wait before retry `attempt` (1-based): the backoff, doubled each time
+fn retry_delay(backoff_ms: u64, attempt: u32) -> Duration {
+    let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+    Duration::from_millis(backoff_ms.saturating_mul(factor)).min(MAX_RETRY_DELAY)
+}
+
+// Whether an object's name has a type the file extractors read
+fn scannable(key: &str) -> bool {
+    remote::object_name(key).is_some_and(|name| DocumentFormat::from_filename(&name).is_some() || ArchiveFormat::from_filename(&name).is_some())
+}
+
+// Start due schedules until the server stops
+fn spawn_ticker(scheduler: SchedulerState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(TICK);
+        loop {
+            ticker.tick().await;
+            scheduler.start_due();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(every: &str, at: Option<&str>) -> ScheduleSpec {
+        ScheduleSpec {
+            name: "nightly-exports".to_string(),
+            every: every.to_string(),
+            at: at.map(str::to_string),
+            target: ScheduleTarget::S3 {
+                bucket: "exports".to_string(),
+                prefix: "2026/".to_string(),
+            },
+            profile: None,
+            notify_url: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_first_run() {
+        // 2026-10-16 09:12:03 UTC
+        let now = 1_792_141_923;
+        assert_eq!(plan(&spec("1d", None), now).unwrap(), (86_400, now));
+        let (_, first) = plan(&spec("1d", Some("02:00")), now).unwrap();
+        assert_eq!(first, 1_792_202_400);
+        let (_, first) = plan(&spec("1d", Some("23:30")), now).unwrap();
+        assert_eq!(first, 1_792_193_400);
+    }
+
+    #[test]
+    fn test_plan_rejects_bad_specs() {
+        let now = 1_792_141_923;
+        assert!(plan(&spec("30s", None), now).is_err());
+        assert!(plan(&spec("1d", Some("25:00")), now).is_err());
+        assert!(plan(&ScheduleSpec { name: "a b".to_string(), ..spec("1d", None) }, now).is_err());
+    }
+
+    #[test]
+    fn test_spec_json_shape() {
+        let spec: ScheduleSpec = serde_json::from_str(
+            r#"{"name": "nightly", "every": "24h", "at": "02:00", "target": {"type": "s3", "bucket": "exports", "prefix": "2026/"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(spec.target, ScheduleTarget::S3 { ref prefix, .. } if prefix == "2026/"));
+        assert!(serde_json::from_str::<ScheduleSpec>(r#"{"name": "x", "every": "1d", "target": {"type": "ftp"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        assert!(retryable(&ApiError::StorageFailed("connection reset".to_string())));
+        assert!(retryable(&ApiError::Overloaded { queue_depth: 4, capacity: 4 }));
+        assert!(!retryable(&ApiError::PayloadTooLarge("too big".to_string())));
+        assert!(!retryable(&ApiError::NotFound("gone".to_string())));
+
+        assert_eq!(retry_delay(1_000, 1), Duration::from_secs(1));
+        assert_eq!(retry_delay(1_000, 3), Duration::from_secs(4));
+        assert_eq!(retry_delay(1_000, 40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_failed_objects_are_dead_lettered() {
+        let mut record = RunRecord::started(1_792_141_923);
+        record.fail("2026/a.pdf".to_string(), ApiError::StorageFailed("timed out".to_string()), 3);
+        record.fail("2026/b.pdf".to_string(), ApiError::NotFound("gone".to_string()), 1);
+        assert_eq!(record.objects_failed, 2);
+        assert!(record.error.as_deref().unwrap().starts_with("2026/a.pdf: "));
+        assert_eq!(record.dead_letters.len(), 2);
+        assert_eq!(record.dead_letters[0].attempts, 3);
+        assert_eq!(record.dead_letters[1].key, "2026/b.pdf");
+    }
+
+    #[test]
+    fn test_scannable() {
+        assert!(scannable("2026/q3/contract.pdf"));
+        assert!(scannable("2026/q3/batch.tar.gz"));
+        assert!(!scannable("2026/q3/data.parquet"));
+        assert!(!scannable("2026/q3/"));
+    }
+}
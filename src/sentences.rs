@@ -0,0 +1,110 @@
+// Rule-based sentence segmentation for per-sentence detection: a sentence ends
+// at a line break, or at ., ! or ? (after any closing quotes or brackets)
+// followed by whitespace and not lowercase text. A period after a common
+// abbreviation or a single-letter initial does not end a sentence. CJK full
+// stops end a sentence without whitespace after them.
+
+// Words that usually take a period without ending the sentence (compared
+// lowercase, without the period)
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "ave", "rd", "no", "vs", "etc", "e.g", "i.e", "inc", "ltd", "co",
+    "corp", "dept", "approx", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+];
+
+const CLOSING: &[char] = &['"', '\'', ')', ']', '}', '»', '”', '’'];
+
+// A sentence's byte offsets, without surrounding whitespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sentence {
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn split(text: &str) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut index = 0;
+    while index < chars.len() {
+        let (offset, c) = chars[index];
+        let end = match c {
+            '\n' | '\r' => Some((offset, index + 1)),
+            '。' | '！' | '？' => Some((offset + c.len_utf8(), index + 1)),
+            '.' | '!' | '?' | '…' => {
+                let mut next = index + 1;
+                while next < chars.len() && CLOSING.contains(&chars[next].1) {
+                    next += 1;
+                }
+                let end = chars.get(next).map_or(text.len(), |&(offset, _)| offset);
+                let followed_by_space = next == chars.len() || chars[next].1.is_whitespace();
+                let lowercase_next = chars[next..].iter().find(|(_, c)| !c.is_whitespace()).is_some_and(|(_, c)| c.is_lowercase());
+                let breaks = followed_by_space && !lowercase_next && !(c == '.' && abbreviated(&text[start..offset]));
+                breaks.then_some((end, next))
+            }
+            _ => None,
+        };
+        match end {
+            Some((end, next)) => {
+                push(text, start, end, &mut sentences);
+                start = end;
+                index = next;
+            }
+            None => index += 1,
+        }
+    }
+    push(text, start, text.len(), &mut sentences);
+    sentences
+}
+
+// Whether the text before a period ends with an abbreviation or an initial
+fn abbreviated(before: &str) -> bool {
+    let word = before.rsplit(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("");
+    let mut letters = word.chars();
+    let initial = matches!((letters.next(), letters.next()), (Some(c), None) if c.is_uppercase());
+    initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+// Add `text[start..end]` trimmed, unless it is blank
+fn push(text: &str, start: usize, end: usize, sentences: &mut Vec<Sentence>) {
+    let span = &text[start..end];
+    let trimmed = span.trim_start();
+    let start = start + (span.len() - trimmed.len());
+    let end = start + trimmed.trim_end().len();
+    if end > start {
+        sentences.push(Sentence { start, end });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(text: &str) -> Vec<&str> {
+        split(text).into_iter().map(|sentence| &text[sentence.start..sentence.end]).collect()
+    }
+
+    #[test]
+    fn test_splits_on_terminators_and_line_breaks() {
+        assert_eq!(
+            texts("Hi there! Is this Jane Roe? \"Yes.\" Call me\nat 555-0100."),
+            vec!["Hi there!", "Is this Jane Roe?", "\"Yes.\"", "Call me", "at 555-0100."]
+        );
+        assert!(split("  \n \n").is_empty());
+    }
+
+    #[test]
+    fn test_abbreviations_and_initials_do_not_split() {
+        assert_eq!(
+            texts("Dr. Smith met J. Doe at 12 Elm St. on Monday. Bring e.g. the form. Version 2.5 is out."),
+            vec!["Dr. Smith met J. Doe at 12 Elm St. on Monday.", "Bring e.g. the form.", "Version 2.5 is out."]
+        );
+    }
+
+    #[test]
+    fn test_offsets_are_bytes() {
+        let text = "Grüße an Jörg. 你好。再见";
+        let sentences = split(text);
+        assert_eq!(texts(text), vec!["Grüße an Jörg.", "你好。", "再见"]);
+        assert_eq!(sentences[1].start, text.find('你').unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+// Detection core without any HTTP types: model loading, detection and
+// redaction. The Rocket server in `routes` is built on top of this, and other
+// programs can embed it directly (build without the default `server` feature to
+// leave out the web framework).
+
+use gliner::{
+    model::params::Parameters,
+    model::input::text::TextInput,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::detection::{self, DetectionError};
+use crate::error::ApiError;
+use crate::inference::InferenceProgress;
+use crate::masking::apply_masking;
+use crate::model_info::ModelInfo;
+use crate::ocr::BoundingBox;
+use crate::pool::{Model, ModelPool};
+use crate::prepass::{self, Prepass};
+use crate::packs;
+use crate::profiles::{self, LabelProfile, ProfileRegistry};
+use crate::recognizers::RecognizerOptions;
+use crate::runtime;
+
+// Labels used for inference when the request does not specify any
+pub const DEFAULT_LABELS: &[&str] = &["person", "email", "phone", "address", "organization"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiEntity {
+    // Left out of responses when empty, as in privacy mode
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    pub label: String,
+    // Single-text detection responses call this "probability"
+    #[serde(alias = "probability")]
+    pub confidence: f32,
+    pub start: usize,
+    pub end: usize,
+    // Extra facts about the finding from recognizers, e.g. an IP address's scope
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+// An entity found in an uploaded file, with where in the file it was found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntity {
+    #[serde(flatten)]
+    pub entity: PiiEntity,
+    // 1-based page number for paged formats (PDF)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    // 0-based paragraph index for word processor documents (DOCX, ODT)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paragraph: Option<usize>,
+    // Email header, body part or attachment, e.g. "header:from" or "body:1.2:text/html"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>,
+    // Approximate pixel box of text read with OCR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BoundingBox>,
+}
+
+// Global model state: the session pool, None until loading succeeds
+pub type ModelState = Arc<Mutex<Option<Arc<ModelPool>>>>;
+
+// Labels to run inference with: the profile's, or the defaults
+pub fn profile_labels(profile: Option<&LabelProfile>) -> Vec<String> {
+    match profile {
+        Some(profile) => {
+            let mut labels = profile.labels.clone();
+            for label in packs::labels(&profile.packs) {
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+            labels
+        }
+        None => DEFAULT_LABELS.iter().map(|label| label.to_string()).collect(),
+    }
+}
+
+// Apply a profile to one document's findings, returning the redacted text
+pub fn apply_profile(profile: Option<&LabelProfile>, text: &str, entities: &mut Vec<PiiEntity>) -> Option<String> {
+    let profile = profile?;
+    profile.filter(entities);
+    Some(apply_masking(text, entities, |label| profile.masking_for(label)))
+}
+
+// Initialize the model from the runtime configuration. Progress is logged, which
+// goes to stderr so CLI output on stdout stays machine-readable.
+pub async fn init_model(config: &Config) -> Result<(ModelPool, ModelInfo), Box<dyn std::error::Error + Send + Sync>> {
+    let model_name = &config.model_name;
+
+    // For now, we'll use local paths - in production you'd download from HuggingFace
+    let tokenizer_path = config.tokenizer_path();
+    let onnx_path = config.onnx_path();
+
+    tracing::info!(model = %model_name, tokenizer = %tokenizer_path, onnx = %onnx_path, "Loading GLiNER model");
+
+    if config.deterministic {
+        tracing::info!("Deterministic inference enabled: single-threaded ONNX execution");
+    }
+    let providers: Vec<&str> = runtime::provider_kinds(config)?.iter().map(|kind| kind.name()).collect();
+    tracing::info!(providers = %providers.join(", "), "Execution providers selected");
+
+    // Each session holds its own copy of the weights. Parameters is not Clone, so
+    // every session gets fresh defaults.
+    let params = Parameters::default();
+    let mut sessions = Vec::with_capacity(config.sessions);
+    for _ in 0..config.sessions {
+        sessions.push(Model::new(
+            Parameters::default(),
+            runtime::runtime_parameters(config)?,
+            &tokenizer_path,
+            &onnx_path,
+        )?);
+    }
+
+    #[cfg(not(feature = "mock-model"))]
+    let info = ModelInfo::collect(config, &params)?;
+    #[cfg(feature = "mock-model")]
+    let info = crate::mock::model_info(config, &params);
+
+    tracing::info!(sessions = sessions.len(), onnx_sha256 = %info.onnx.sha256, "Model loaded");
+    Ok((ModelPool::new(sessions).reserve_interactive(config.interactive_sessions), info))
+}
+
+// Run throwaway inferences so the first real request doesn't pay ONNX session
+// initialization and allocation costs
+pub fn warmup_model(model: &Model, text: &str, runs: usize) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    for run in 1..=runs {
+        let started = std::time::Instant::now();
+        let input = TextInput::from_str(&[text], DEFAULT_LABELS)?;
+        model.inference(input)?;
+        tracing::info!(run, runs, latency_ms = started.elapsed().as_secs_f64() * 1000.0, "Warmup inference");
+    }
+    Ok(())
+}
+
+
+// Entities found in one text, and its redacted form when a profile was applied
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub entities: Vec<PiiEntity>,
+    pub redacted_text: Option<String>,
+}
+
+// Everything needed to run detection in-process: the model sessions, the
+// optional pre-pass and the label profiles. Detection blocks while the model
+// runs, so async callers should call it from a blocking task.
+pub struct PiiService {
+    pool: ModelPool,
+    prepass: Option<Prepass>,
+    profiles: ProfileRegistry,
+    config: Config,
+}
+
+impl PiiService {
+    pub fn new(pool: ModelPool, prepass: Option<Prepass>, profiles: ProfileRegistry, config: Config) -> Self {
+        PiiService { pool, prepass, profiles, config }
+    }
+
+    // Load the model, pre-pass and label profiles the configuration names
+    pub async fn load(config: Config) -> Result<Self, DetectionError> {
+        let profiles = profiles::load_profiles(&config)?;
+        let (pool, _) = init_model(&config).await?;
+        let prepass = prepass::load_prepass(&config)?;
+        Ok(PiiService::new(pool, prepass, profiles, config))
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&LabelProfile>, ApiError> {
+        profiles::resolve(&self.profiles, name)
+    }
+
+    pub fn detect(&self, text: &str, profile: Option<&str>, recognizers: &RecognizerOptions) -> Result<Detection, ApiError> {
+        let mut detections = self.detect_batch(&[text], profile, recognizers)?;
+        Ok(detections.remove(0))
+    }
+
+    // Detect over several texts in one scheduling run, filtering and redacting
+    // with the named profile when one is given
+    pub fn detect_batch(
+        &self,
+        texts: &[&str],
+        profile: Option<&str>,
+        recognizers: &RecognizerOptions,
+    ) -> Result<Vec<Detection>, ApiError> {
+        let profile = self.profile(profile)?;
+        let labels = profile_labels(profile);
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let model = self.pool.checkout();
+        let batch = detection::detect_documents(
+            &model,
+            self.prepass.as_ref(),
+            texts,
+            &labels,
+            recognizers,
+            &self.config,
+            &InferenceProgress::default(),
+        )
+        .map_err(|e| ApiError::InferenceFailed(e.to_string()))?;
+
+        Ok(texts
+            .iter()
+            .zip(batch.documents)
+            .map(|(text, mut entities)| {
+                let redacted_text = apply_profile(profile, text, &mut entities);
+                Detection { entities, redacted_text }
+            })
+            .collect())
+    }
+}
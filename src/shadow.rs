@@ -0,0 +1,182 @@
+// Shadow mode: a second model, GLINER_SHADOW_MODEL, is run on the same texts as
+// single-text detections once the response is on its way, and only where the
+// two models disagree is recorded (metrics, the log and the audit trail). It
+// never changes a response, so a candidate model can be judged on live traffic
+// before a canary rollout.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use crate::aliases::LabelMap;
+use crate::canary::take_share;
+use crate::config::Config;
+use crate::detection;
+use crate::loading;
+use crate::pool::{ModelPool, Priority};
+use crate::timing;
+use crate::PiiEntity;
+
+// Entities one model found and the other did not, counted per label. Findings
+// match when label and offsets are the same; scores are not compared.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShadowDiff {
+    pub model: String,
+    pub only_primary: BTreeMap<String, usize>,
+    pub only_shadow: BTreeMap<String, usize>,
+    pub latency_ms: f64,
+}
+
+impl ShadowDiff {
+    pub fn between(model: &str, primary: &[PiiEntity], shadow: &[PiiEntity]) -> Self {
+        let spans = |entities: &[PiiEntity]| -> HashSet<(String, usize, usize)> {
+            entities.iter().map(|entity| (entity.label.clone(), entity.start, entity.end)).collect()
+        };
+        let (primary, shadow) = (spans(primary), spans(shadow));
+        let count = |only: HashSet<&(String, usize, usize)>| {
+            let mut counts = BTreeMap::new();
+            for (label, _, _) in only {
+                *counts.entry(label.clone()).or_default() += 1;
+            }
+            counts
+        };
+        ShadowDiff {
+            model: model.to_string(),
+            only_primary: count(primary.difference(&shadow).collect()),
+            only_shadow: count(shadow.difference(&primary).collect()),
+            latency_ms: 0.0,
+        }
+    }
+
+    pub fn disagrees(&self) -> bool {
+        !self.only_primary.is_empty() || !self.only_shadow.is_empty()
+    }
+}
+
+pub enum ShadowRun {
+    // Outside GLINER_SHADOW_PERCENT, or the shadow model is not loaded
+    NotSampled,
+    // Every shadow session was in use; the comparison is dropped rather than queued
+    Busy,
+    Started,
+}
+
+pub struct Shadow {
+    model: String,
+    percent: f64,
+    pool: OnceLock<Arc<ModelPool>>,
+    requests: AtomicU64,
+    running: AtomicUsize,
+}
+
+pub type ShadowState = Arc<Option<Shadow>>;
+
+impl Shadow {
+    pub fn new(model: &str, percent: f64) -> Self {
+        Shadow {
+            model: model.to_string(),
+            percent: percent.clamp(0.0, 100.0),
+            pool: OnceLock::new(),
+            requests: AtomicU64::new(0),
+            running: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .shadow_model
+            .as_deref()
+            .map(|model| Shadow::new(model, config.shadow_percent))
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    // The shadow sessions, once loaded
+    pub fn pool(&self) -> Option<Arc<ModelPool>> {
+        self.pool.get().cloned()
+    }
+
+    // Load and warm up the shadow model with the primary's settings
+    pub async fn load(&self, config: &Config) {
+        match loading::load_secondary(config, &self.model).await {
+            Ok(pool) => {
+                let _ = self.pool.set(pool);
+                tracing::info!(model = %self.model, percent = self.percent, "Shadow model ready");
+            }
+            Err(e) => tracing::error!(model = %self.model, error = %e, "Failed to load the shadow model; shadow comparisons are off"),
+        }
+    }
+}
+
+// Run the shadow model on `text` in the background and hand the differences
+// from the primary model's `primary` findings to `record`
+pub fn compare<F>(state: &ShadowState, text: String, labels: Vec<String>, primary: Vec<PiiEntity>, record: F) -> ShadowRun
+where
+    F: FnOnce(ShadowDiff) + Send + 'static,
+{
+    let Some(shadow) = Option::as_ref(&**state) else {
+        return ShadowRun::NotSampled;
+    };
+    let Some(pool) = shadow.pool() else {
+        return ShadowRun::NotSampled;
+    };
+    if !take_share(&shadow.requests, shadow.percent) {
+        return ShadowRun::NotSampled;
+    }
+    if shadow.running.fetch_add(1, Ordering::AcqRel) >= pool.size() {
+        shadow.running.fetch_sub(1, Ordering::AcqRel);
+        return ShadowRun::Busy;
+    }
+
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let Some(shadow) = Option::as_ref(&*state) else {
+            return;
+        };
+        let started = Instant::now();
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let outcome = detection::model_entities(&pool.checkout_with(Priority::Batch), &text, &LabelMap::new(&labels, &[]));
+        shadow.running.fetch_sub(1, Ordering::AcqRel);
+        match outcome {
+            Ok(entities) => record(ShadowDiff {
+                latency_ms: timing::millis(started.elapsed()),
+                ..ShadowDiff::between(&shadow.model, &primary, &entities)
+            }),
+            Err(e) => tracing::warn!(model = %shadow.model, error = %e, "Shadow inference failed"),
+        }
+    });
+    ShadowRun::Started
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_diff_counts_unmatched_findings_per_label() {
+        let primary = vec![entity("person", 0, 8, 0.9), entity("email", 20, 36, 0.9), entity("phone", 40, 52, 0.9)];
+        let shadow = vec![entity("person", 0, 8, 0.9), entity("email", 20, 30, 0.9), entity("address", 60, 75, 0.9)];
+        let diff = ShadowDiff::between("gliner-next", &primary, &shadow);
+        assert!(diff.disagrees());
+        assert_eq!(diff.only_primary, BTreeMap::from([("email".to_string(), 1), ("phone".to_string(), 1)]));
+        assert_eq!(diff.only_shadow, BTreeMap::from([("address".to_string(), 1), ("email".to_string(), 1)]));
+    }
+
+    #[test]
+    fn test_same_findings_agree() {
+        let found = vec![entity("person", 0, 8, 0.9)];
+        assert!(!ShadowDiff::between("gliner-next", &found, &found).disagrees());
+    }
+
+    #[tokio::test]
+    async fn test_nothing_runs_until_loaded() {
+        let state: ShadowState = Arc::new(Some(Shadow::new("gliner-next", 100.0)));
+        let run = compare(&state, "Call Jane Roe".to_string(), vec!["person".to_string()], Vec::new(), |_| {});
+        assert!(matches!(run, ShadowRun::NotSampled));
+    }
+}
@@ -0,0 +1,171 @@
+// Streaming responses: detection work running on the blocking pool emits
+// results that are forwarded to the client as JSON lines or server-sent events
+
+use rocket::futures::{stream, Stream, StreamExt};
+use rocket::response::stream::{Event, EventStream, TextStream};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+use crate::error::ApiError;
+use crate::inference::{InferenceProgress, InferenceQueue, RequestDeadline};
+use crate::pool::Model;
+use crate::service::ModelState;
+
+// One item of a streaming run: a JSON payload and, for server-sent events, the
+// event name it is sent under
+pub struct StreamItem {
+    pub event: Option<&'static str>,
+    pub data: String,
+}
+
+impl StreamItem {
+    fn error(error: &ApiError) -> Self {
+        StreamItem {
+            event: Some("error"),
+            data: serde_json::to_string(&error.body()).unwrap_or_default(),
+        }
+    }
+}
+
+// Output side of a streaming run: each item becomes one JSON line (or event).
+// Sending fails once the client has gone away, which cancels the run.
+pub struct LineSink {
+    sender: mpsc::Sender<StreamItem>,
+    progress: Arc<InferenceProgress>,
+}
+
+impl LineSink {
+    pub fn send<T: Serialize>(&self, item: &T) {
+        self.send_item(None, item);
+    }
+
+    // Send an item under an event name; plain JSON line streams ignore the name
+    pub fn send_event<T: Serialize>(&self, event: &'static str, item: &T) {
+        self.send_item(Some(event), item);
+    }
+
+    fn send_item<T: Serialize>(&self, event: Option<&'static str>, item: &T) {
+        let data = match serde_json::to_string(item) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize streamed result");
+                return;
+            }
+        };
+        if self.sender.blocking_send(StreamItem { event, data }).is_err() {
+            self.progress.cancel();
+        }
+    }
+}
+
+// Streaming counterpart of `run_with_deadline`: the work emits JSON lines as
+// results become available and they are forwarded to the client immediately.
+// Admission and model availability are checked up front so those failures still
+// get a proper status; once streaming has started, errors and an expired
+// deadline are reported as a final error line.
+pub async fn stream_with_deadline<F>(
+    model_state: &ModelState,
+    queue: &InferenceQueue,
+    deadline: RequestDeadline,
+    work: F,
+) -> Result<TextStream<impl Stream<Item = String>>, ApiError>
+where
+    F: FnOnce(&Model, &InferenceProgress, &LineSink) -> Result<(), ApiError> + Send + 'static,
+{
+    let items = start_stream(model_state, queue, deadline, work).await?;
+    Ok(TextStream::from(items.map(|item| format!("{}\n", item.data))))
+}
+
+// Server-sent events variant of `stream_with_deadline`: every item is one event,
+// named by the work (errors are sent as "error" events)
+pub async fn events_with_deadline<F>(
+    model_state: &ModelState,
+    queue: &InferenceQueue,
+    deadline: RequestDeadline,
+    work: F,
+) -> Result<EventStream<impl Stream<Item = Event>>, ApiError>
+where
+    F: FnOnce(&Model, &InferenceProgress, &LineSink) -> Result<(), ApiError> + Send + 'static,
+{
+    let items = start_stream(model_state, queue, deadline, work).await?;
+    Ok(EventStream::from(items.map(|item| {
+        let event = Event::data(item.data);
+        match item.event {
+            Some(name) => event.event(name),
+            None => event,
+        }
+    })))
+}
+
+async fn start_stream<F>(
+    model_state: &ModelState,
+    queue: &InferenceQueue,
+    deadline: RequestDeadline,
+    work: F,
+) -> Result<impl Stream<Item = StreamItem>, ApiError>
+where
+    F: FnOnce(&Model, &InferenceProgress, &LineSink) -> Result<(), ApiError> + Send + 'static,
+{
+    let permit = queue.try_admit()?;
+    if model_state.lock().await.is_none() {
+        return Err(ApiError::ModelNotLoaded);
+    }
+
+    let started = Instant::now();
+    let deadline_at = tokio::time::Instant::now() + deadline.0;
+    let progress = Arc::new(InferenceProgress::default());
+    let (sender, receiver) = mpsc::channel::<StreamItem>(16);
+    let sink = LineSink {
+        sender,
+        progress: progress.clone(),
+    };
+    let state = model_state.clone();
+    let priority = deadline.priority();
+    let breaker = queue.breaker().clone();
+
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let pool = state.blocking_lock().clone();
+        let result = match pool {
+            Some(pool) => {
+                let session = pool.checkout_with(priority);
+                if sink.progress.is_cancelled() {
+                    return;
+                }
+                work(&session, &sink.progress, &sink)
+            }
+            None => Err(ApiError::ModelNotLoaded),
+        };
+        breaker.observe(&result);
+        if let Err(e) = result {
+            if !sink.progress.is_cancelled() {
+                sink.send_event("error", &e.body());
+            }
+        }
+    });
+
+    // Forward items until the work is done or the deadline passes; the receiver
+    // is dropped after a timeout so the stream ends with the error item
+    Ok(stream::unfold(Some(receiver), move |receiver| {
+        let progress = progress.clone();
+        async move {
+            let mut receiver = receiver?;
+            match tokio::time::timeout_at(deadline_at, receiver.recv()).await {
+                Ok(Some(item)) => Some((item, Some(receiver))),
+                Ok(None) => None,
+                Err(_) => {
+                    progress.cancel();
+                    let error = ApiError::Timeout {
+                        timeout_ms: deadline.0.as_millis() as u64,
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                        completed_batches: progress.completed_batches(),
+                        total_batches: progress.total_batches(),
+                    };
+                    Some((StreamItem::error(&error), None))
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,375 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use rocket::fairing::AdHoc;
+use rocket::{Build, Rocket};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, Stdin};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::watch;
+
+// Set on processes started by the supervisor so they run as workers instead of
+// supervising again
+pub const WORKER_ENV: &str = "GLINER_SUPERVISED_WORKER";
+
+// Control protocol: a worker prints READY and its private port on stdout once
+// its model is loaded and it is listening, and the supervisor writes SHUTDOWN
+// to its stdin when it should drain and exit
+const SHUTDOWN: &str = "shutdown";
+const READY: &str = "gliner-worker-ready";
+
+// A primary that dies sooner than this after taking over is treated as a crash
+// loop, and the next failover is delayed
+const MIN_UPTIME: Duration = Duration::from_secs(10);
+const CRASH_LOOP_DELAY: Duration = Duration::from_secs(2);
+
+// How long an accepted connection waits for a primary to forward it to
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn is_worker() -> bool {
+    std::env::var_os(WORKER_ENV).is_some()
+}
+
+async fn wait_for_line(lines: &mut Lines<BufReader<Stdin>>, expected: &str) -> io::Result<()> {
+    while let Some(line) = lines.next_line().await? {
+        if line.trim() == expected {
+            return Ok(());
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "supervisor closed the control channel"))
+}
+
+// Entry point of a supervised worker, given the fully initialised (model loaded
+// and warmed) instance: serve on a private loopback port, report it, and serve
+// until told to shut down. The supervisor owns the public port and forwards
+// connections to whichever worker is primary. A graceful shutdown lets
+// in-flight requests finish first.
+pub async fn run_worker(rocket: Rocket<Build>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let figment = rocket.figment().clone().merge(("address", Ipv4Addr::LOCALHOST.to_string())).merge(("port", 0));
+    let rocket = rocket
+        .configure(figment)
+        .attach(AdHoc::on_liftoff("Supervisor ready report", |rocket| {
+            Box::pin(async move { println!("{} {}", READY, rocket.config().port) })
+        }))
+        .ignite()
+        .await?;
+    let shutdown = rocket.shutdown();
+    tokio::spawn(async move {
+        // If the supervisor goes away the worker keeps serving
+        if wait_for_line(&mut lines, SHUTDOWN).await.is_ok() {
+            shutdown.notify();
+        }
+    });
+
+    let _ = rocket.launch().await?;
+    Ok(())
+}
+
+// Connect to the current primary, waiting while there is none (between a crash
+// and the standby taking over) and retrying when the primary changes
+async fn connect_primary(backend: &mut watch::Receiver<Option<u16>>) -> io::Result<TcpStream> {
+    let closed = || io::Error::new(io::ErrorKind::BrokenPipe, "supervisor stopped");
+    let connect = async {
+        loop {
+            let port = backend.wait_for(Option::is_some).await.map_err(|_| closed())?.unwrap_or_default();
+            match TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => tracing::debug!(error = %e, port, "Supervisor: primary refused a connection; waiting for the next one"),
+            }
+            backend.changed().await.map_err(|_| closed())?;
+        }
+    };
+    tokio::time::timeout(BACKEND_TIMEOUT, connect)
+        .await
+        .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "no primary worker available")))
+}
+
+// Accept connections on the public port and relay each one to the primary
+// worker. The listener lives as long as the supervisor, so switching primaries
+// never refuses a connection: it is sent to the worker that is primary when it
+// is accepted, and waits in the meantime if there is none.
+async fn forward(listener: TcpListener, backend: watch::Receiver<Option<u16>>) {
+    loop {
+        let (mut client, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!(error = %e, "Supervisor: failed to accept a connection");
+                continue;
+            }
+        };
+        let mut backend = backend.clone();
+        tokio::spawn(async move {
+            let mut upstream = match connect_primary(&mut backend).await {
+                Ok(upstream) => upstream,
+                Err(e) => {
+                    tracing::warn!(error = %e, %peer, "Supervisor: dropping connection");
+                    return;
+                }
+            };
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+        });
+    }
+}
+
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    port: watch::Receiver<Option<u16>>,
+    promoted_at: Option<Instant>,
+}
+
+impl Worker {
+    // Re-run the binary on disk with the same arguments and environment as a
+    // worker. It loads and warms the model straight away, then listens on a
+    // private port. Its output is passed through, apart from the ready marker.
+    fn spawn() -> io::Result<Self> {
+        let mut child = Command::new(std::env::current_exe()?)
+            .args(std::env::args_os().skip(1))
+            .env(WORKER_ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let missing = || io::Error::other("worker stdio not captured");
+        let stdin = child.stdin.take().ok_or_else(missing)?;
+        let stdout = child.stdout.take().ok_or_else(missing)?;
+
+        let (port_tx, port) = watch::channel(None);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match line.strip_prefix(READY).map(|port| port.trim().parse::<u16>()) {
+                    Some(Ok(port)) => {
+                        port_tx.send_replace(Some(port));
+                    }
+                    _ => println!("{}", line),
+                }
+            }
+        });
+
+        tracing::info!(pid = ?child.id(), "Supervisor: started worker");
+        Ok(Worker { child, stdin, port, promoted_at: None })
+    }
+
+    async fn send(&mut self, line: &str) -> io::Result<()> {
+        self.stdin.write_all(format!("{}\n", line).as_bytes()).await?;
+        self.stdin.flush().await
+    }
+
+    // The private port, once the worker has loaded the model and is listening
+    async fn wait_ready(&mut self) -> io::Result<u16> {
+        self.port
+            .wait_for(Option::is_some)
+            .await
+            .map(|port| port.unwrap_or_default())
+            .map_err(|_| io::Error::new(io::ErrorKind::UnexpectedEof, "worker exited before becoming ready"))
+    }
+
+    // Route new connections to this worker
+    fn make_primary(&mut self, port: u16, backend: &watch::Sender<Option<u16>>) {
+        backend.send_replace(Some(port));
+        self.promoted_at = Some(Instant::now());
+        tracing::info!(pid = ?self.child.id(), port, "Supervisor: worker is now primary");
+    }
+}
+
+// How long to wait before promoting the standby after the primary exited
+pub fn failover_delay(primary_uptime: Duration) -> Duration {
+    if primary_uptime < MIN_UPTIME {
+        CRASH_LOOP_DELAY
+    } else {
+        Duration::ZERO
+    }
+}
+
+// Promote `standby`, falling back to a cold worker if it has died in the meantime
+async fn promote(mut standby: Worker, backend: &watch::Sender<Option<u16>>) -> io::Result<Worker> {
+    match standby.wait_ready().await {
+        Ok(port) => {
+            standby.make_primary(port, backend);
+            Ok(standby)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Supervisor: standby could not be promoted; starting a cold worker");
+            let mut worker = Worker::spawn()?;
+            let port = worker.wait_ready().await?;
+            worker.make_primary(port, backend);
+            Ok(worker)
+        }
+    }
+}
+
+// Resolves with the private port of the worker being upgraded to, if any
+async fn upgrade_ready(upgrading: &mut Option<Worker>) -> io::Result<u16> {
+    match upgrading {
+        Some(worker) => worker.wait_ready().await,
+        None => std::future::pending().await,
+    }
+}
+
+// SIGHUP where available; other platforms have no upgrade trigger
+struct UpgradeSignal {
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl UpgradeSignal {
+    fn new() -> io::Result<Self> {
+        Ok(UpgradeSignal {
+            #[cfg(unix)]
+            hangup: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.hangup.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+// Keep one primary worker serving and one warm standby with the model already
+// loaded. The supervisor holds the public port and forwards connections to the
+// primary, so it never closes. Native faults in ONNX Runtime can take down a
+// whole process, so when the primary exits abnormally the standby takes over
+// and a new standby is started behind it. A clean primary exit stops the
+// supervisor.
+//
+// SIGHUP performs a binary upgrade: a worker is started from the binary now on
+// disk, and once it has loaded the model new connections go to it while the old
+// primary is shut down gracefully (finishing in-flight requests). Failover keeps
+// working while the new worker loads.
+pub async fn run() -> io::Result<()> {
+    tracing::info!("Supervisor mode: running a primary worker with a warm standby");
+
+    let config: rocket::Config = rocket::Config::figment().extract().map_err(io::Error::other)?;
+    let listener = TcpListener::bind(SocketAddr::new(config.address, config.port)).await?;
+    let (backend, backend_rx) = watch::channel(None);
+    tokio::spawn(forward(listener, backend_rx));
+
+    let mut upgrade = UpgradeSignal::new()?;
+
+    let mut primary = promote(Worker::spawn()?, &backend).await?;
+    let mut standby = Worker::spawn()?;
+    let mut upgrading: Option<Worker> = None;
+
+    loop {
+        tokio::select! {
+            status = primary.child.wait() => {
+                let status: ExitStatus = status?;
+                if status.success() {
+                    tracing::info!("Supervisor: primary exited cleanly, shutting down");
+                    standby.child.kill().await.ok();
+                    if let Some(mut worker) = upgrading.take() {
+                        worker.child.kill().await.ok();
+                    }
+                    return Ok(());
+                }
+
+                backend.send_replace(None);
+                let uptime = primary.promoted_at.map(|at| at.elapsed()).unwrap_or_default();
+                tracing::error!(%status, uptime_secs = uptime.as_secs_f64(), "Supervisor: primary exited; failing over to warm standby");
+                tokio::time::sleep(failover_delay(uptime)).await;
+
+                let replacement = Worker::spawn()?;
+                primary = promote(std::mem::replace(&mut standby, replacement), &backend).await?;
+            }
+            status = standby.child.wait() => {
+                tracing::warn!(status = %status?, "Supervisor: standby exited; starting a new one");
+                standby = Worker::spawn()?;
+            }
+            ready = upgrade_ready(&mut upgrading) => {
+                let Some(mut worker) = upgrading.take() else { continue };
+                let port = match ready {
+                    Ok(port) => port,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Supervisor: upgrade aborted");
+                        continue;
+                    }
+                };
+
+                // Route new connections to the new worker first, then let the old
+                // primary drain in-flight requests and exit on its own
+                worker.make_primary(port, &backend);
+                let mut old = std::mem::replace(&mut primary, worker);
+                old.send(SHUTDOWN).await.ok();
+                tokio::spawn(async move {
+                    if let Ok(status) = old.child.wait().await {
+                        tracing::info!(%status, "Supervisor: previous primary exited");
+                    }
+                });
+
+                // The existing standby runs the old binary; replace it
+                standby.child.kill().await.ok();
+                standby = Worker::spawn()?;
+            }
+            _ = upgrade.recv() => {
+                tracing::info!("Supervisor: upgrade requested, starting a worker from the current binary");
+                if let Some(mut stale) = upgrading.replace(Worker::spawn()?) {
+                    stale.child.kill().await.ok();
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("Supervisor: interrupted, stopping workers");
+                primary.child.kill().await.ok();
+                standby.child.kill().await.ok();
+                if let Some(mut worker) = upgrading.take() {
+                    worker.child.kill().await.ok();
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_crash_loop_delays_failover() {
+        assert_eq!(failover_delay(Duration::from_secs(1)), CRASH_LOOP_DELAY);
+        assert_eq!(failover_delay(Duration::from_secs(600)), Duration::ZERO);
+    }
+
+    // Stand-in for a worker: answers every connection with its name
+    async fn fake_worker(name: &'static str) -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                stream.write_all(name.as_bytes()).await.ok();
+            }
+        });
+        port
+    }
+
+    async fn read_reply(port: u16) -> String {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await.unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        reply
+    }
+
+    #[rocket::async_test]
+    async fn test_forwarding_switches_primary_without_closing_the_port() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let public = listener.local_addr().unwrap().port();
+        let (backend, backend_rx) = watch::channel(None);
+        tokio::spawn(forward(listener, backend_rx));
+
+        // Accepted while there is no primary, answered once one takes over
+        let pending = tokio::spawn(read_reply(public));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        backend.send_replace(Some(fake_worker("old").await));
+        assert_eq!(pending.await.unwrap(), "old");
+
+        backend.send_replace(Some(fake_worker("new").await));
+        assert_eq!(read_reply(public).await, "new");
+    }
+}
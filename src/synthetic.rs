@@ -0,0 +1,135 @@
+// Fake documents with planted PII at known offsets, for load tests and for
+// checking the pipeline end to end (the output is an evaluation set POST
+// /api/eval accepts as is). Generation is seeded, so a seed always gives the
+// same documents. Every name, address and number is made up.
+
+use crate::eval::{EvalDocument, GoldEntity};
+
+const FIRST_NAMES: &[&str] = &["Jane", "Omar", "Lucia", "Wei", "Priya", "Tomasz", "Amara", "Kenji", "Sofia", "Daniel"];
+const LAST_NAMES: &[&str] = &["Roe", "Haddad", "Moreno", "Chen", "Raman", "Kowalski", "Okafor", "Tanaka", "Lindqvist", "Brooks"];
+const STREETS: &[&str] = &["Maple Street", "Harbor Road", "Elm Avenue", "Station Lane", "Quarry Drive", "Willow Court"];
+const CITIES: &[&str] = &["Springfield", "Riverton", "Lakeside", "Fairview", "Ashford", "Millbrook"];
+const ORGANIZATIONS: &[&str] = &["Northwind Traders", "Acme Logistics", "Blue Harbor Bank", "Contoso Health", "Fabrikam Labs", "Globex Insurance"];
+const DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+
+// Sentences with slots: {person}, {email}, {phone}, {address} and {organization}
+const TEMPLATES: &[&str] = &[
+    "Please contact {person} at {email} about the renewal.",
+    "{person} called from {phone} to update the delivery address to {address}.",
+    "The invoice was issued by {organization} and sent to {email}.",
+    "Our records show {person} moved to {address} last spring.",
+    "For questions, reach {organization} support on {phone}.",
+    "Meeting notes: {person} ({organization}) will follow up by phone at {phone}.",
+    "Ship the replacement part to {person}, {address}.",
+    "The account manager at {organization} is {person}, email {email}.",
+    "No action is needed for this ticket.",
+    "The quarterly report is attached for review.",
+];
+
+// SplitMix64: small, fast and good enough for picking words
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+fn fill(slot: &str, rng: &mut SeededRng) -> String {
+    match slot {
+        "person" => format!("{} {}", rng.pick(FIRST_NAMES), rng.pick(LAST_NAMES)),
+        "email" => format!(
+            "{}.{}@{}",
+            rng.pick(FIRST_NAMES).to_lowercase(),
+            rng.pick(LAST_NAMES).to_lowercase(),
+            rng.pick(DOMAINS)
+        ),
+        // 555-01xx numbers are reserved for fiction
+        "phone" => format!("(555) 01{:02}-{:04}", rng.below(100), rng.below(10_000)),
+        "address" => format!("{} {}, {}", 1 + rng.below(9_999), rng.pick(STREETS), rng.pick(CITIES)),
+        _ => rng.pick(ORGANIZATIONS).to_string(),
+    }
+}
+
+// One document of `sentences` sentences, with the offsets (bytes) of every
+// planted entity
+pub fn document(rng: &mut SeededRng, sentences: usize) -> EvalDocument {
+    let mut text = String::new();
+    let mut entities = Vec::new();
+    for index in 0..sentences {
+        if index > 0 {
+            text.push(' ');
+        }
+        let mut rest = TEMPLATES[rng.below(TEMPLATES.len())];
+        while let Some(open) = rest.find('{') {
+            let close = open + rest[open..].find('}').expect("template slots are closed");
+            text.push_str(&rest[..open]);
+            let label = &rest[open + 1..close];
+            let value = fill(label, rng);
+            entities.push(GoldEntity {
+                label: label.to_string(),
+                start: text.len(),
+                end: text.len() + value.len(),
+            });
+            text.push_str(&value);
+            rest = &rest[close + 1..];
+        }
+        text.push_str(rest);
+    }
+    EvalDocument { text, entities }
+}
+
+// `count` documents of one to four sentences each
+pub fn documents(count: usize, seed: u64) -> Vec<EvalDocument> {
+    let mut rng = SeededRng::new(seed);
+    (0..count)
+        .map(|_| {
+            let sentences = 1 + rng.below(4);
+            document(&mut rng, sentences)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_point_at_planted_values() {
+        for document in documents(50, 7) {
+            for entity in &document.entities {
+                let value = &document.text[entity.start..entity.end];
+                match entity.label.as_str() {
+                    "email" => assert!(value.contains('@'), "{}", value),
+                    "phone" => assert!(value.starts_with("(555) 01"), "{}", value),
+                    _ => assert!(!value.is_empty()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_documents() {
+        let first: Vec<String> = documents(5, 42).into_iter().map(|document| document.text).collect();
+        let again: Vec<String> = documents(5, 42).into_iter().map(|document| document.text).collect();
+        let other: Vec<String> = documents(5, 43).into_iter().map(|document| document.text).collect();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use crate::audit::AuditEvent;
+use crate::PiiEntity;
+
+// Fixtures shared by the unit tests of the post-processing and output modules
+
+// A finding of `label` over `start..end`, for tests that only look at spans;
+// its text is a placeholder of the same length
+pub fn entity(label: &str, start: usize, end: usize, confidence: f32) -> PiiEntity {
+    entity_at(&"x".repeat(end - start), label, start, confidence)
+}
+
+// A finding of `text` starting at byte `start` of the document
+pub fn entity_at(text: &str, label: &str, start: usize, confidence: f32) -> PiiEntity {
+    PiiEntity {
+        text: text.to_string(),
+        label: label.to_string(),
+        confidence,
+        start,
+        end: start + text.len(),
+        attributes: BTreeMap::new(),
+    }
+}
+
+// The finding of `needle` at its first occurrence in `text`
+pub fn entity_in(text: &str, needle: &str, label: &str, confidence: f32) -> PiiEntity {
+    let start = text.find(needle).expect("needle in text");
+    entity_at(needle, label, start, confidence)
+}
+
+// A successful detection call recorded at `timestamp`, without findings
+pub fn audit_event(timestamp: u64) -> AuditEvent {
+    AuditEvent {
+        timestamp,
+        request_id: timestamp.to_string(),
+        method: "POST".to_string(),
+        route: "/v1/pii/detect".to_string(),
+        status: 200,
+        caller: None,
+        latency_ms: 1.0,
+        findings: None,
+        shadow: None,
+    }
+}
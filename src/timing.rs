@@ -0,0 +1,71 @@
+// Per-stage timings of a detection, returned as `debug` in the response when a
+// request asks for them, to tell model latency from the work around it
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageTimings {
+    // Checking the input and resolving the profile and field selection
+    pub validation_ms: f64,
+    // Waiting for a queue slot and a free model session
+    pub queue_ms: f64,
+    // The pre-pass model, when one is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prepass_ms: Option<f64>,
+    // Splitting the text into words and preparing the labels
+    pub tokenization_ms: f64,
+    // The model run, which includes its subword tokenization
+    pub inference_ms: f64,
+    // Building entities, recognizers, profile filtering and the response
+    pub post_processing_ms: f64,
+    pub total_ms: f64,
+    // Words the model sees, as the GLiNER splitter counts them
+    pub tokens: usize,
+    pub labels: usize,
+}
+
+// Measures consecutive stages: each lap is the time since the previous one
+pub struct Stopwatch {
+    started: Instant,
+    last: Instant,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Stopwatch { started: now, last: now }
+    }
+
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    pub fn lap(&mut self) -> f64 {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        millis(elapsed)
+    }
+}
+
+// Milliseconds to the microsecond
+pub fn millis(duration: Duration) -> f64 {
+    (duration.as_micros() as f64) / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_millis_keeps_microseconds() {
+        assert_eq!(millis(Duration::from_micros(1_234_567)), 1234.567);
+    }
+
+    #[test]
+    fn test_prepass_is_left_out_when_not_run() {
+        let value = serde_json::to_value(StageTimings::default()).unwrap();
+        assert!(value.get("prepass_ms").is_none());
+        assert_eq!(value["tokens"], 0);
+    }
+}
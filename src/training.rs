@@ -0,0 +1,70 @@
+// GLiNER's fine-tuning format: one object per example with the text split into
+// words the way GLiNER splits it, and each entity as inclusive word indices and
+// a label, e.g. {"tokenized_text": ["Call", "Jane", "Roe"], "ner": [[1, 2, "person"]]}
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::PiiEntity;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingExample {
+    pub tokenized_text: Vec<String>,
+    pub ner: Vec<(usize, usize, String)>,
+}
+
+fn word_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\w+(?:[-_]\w+)*|\S").expect("valid word pattern"))
+}
+
+// Words of `text` with their byte spans, as GLiNER's splitter produces them
+pub fn words(text: &str) -> Vec<(&str, usize, usize)> {
+    word_pattern()
+        .find_iter(text)
+        .map(|word| (word.as_str(), word.start(), word.end()))
+        .collect()
+}
+
+// Convert a text and its findings (byte offsets) to a training example. An
+// entity covers every word it overlaps; entities covering no word are dropped.
+pub fn example(text: &str, entities: &[PiiEntity]) -> TrainingExample {
+    let words = words(text);
+    let mut ner: Vec<(usize, usize, String)> = entities
+        .iter()
+        .filter_map(|entity| {
+            let first = words.iter().position(|&(_, _, end)| end > entity.start)?;
+            let last = words.iter().rposition(|&(_, start, _)| start < entity.end)?;
+            (first <= last).then(|| (first, last, entity.label.clone()))
+        })
+        .collect();
+    ner.sort();
+    ner.dedup();
+    TrainingExample {
+        tokenized_text: words.into_iter().map(|(word, _, _)| word.to_string()).collect(),
+        ner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::entity;
+
+    #[test]
+    fn test_words_match_the_gliner_splitter() {
+        let found: Vec<&str> = words("Mail jean-luc@example.com, now!").into_iter().map(|(word, _, _)| word).collect();
+        assert_eq!(found, vec!["Mail", "jean-luc", "@", "example", ".", "com", ",", "now", "!"]);
+    }
+
+    #[test]
+    fn test_entities_become_word_indices() {
+        let text = "Call Jane Roe at jane@example.com";
+        let example = example(text, &[entity("person", 5, 13, 0.6), entity("email", 17, 33, 0.6)]);
+        assert_eq!(example.tokenized_text[1..3], ["Jane".to_string(), "Roe".to_string()]);
+        assert_eq!(example.ner, vec![(1, 2, "person".to_string()), (4, 8, "email".to_string())]);
+
+        let json = serde_json::to_value(&example).unwrap();
+        assert_eq!(json["ner"][0], serde_json::json!([1, 2, "person"]));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::config::Config;
+use crate::error::ApiError;
+
+// Reject text the model should never see: empty input, or input longer than the
+// configured limit (which would otherwise hold the model lock for a long time)
+pub fn validate_text(text: &str, config: &Config) -> Result<(), ApiError> {
+    if text.trim().is_empty() {
+        return Err(ApiError::InvalidInput("text must not be empty or whitespace-only".to_string()));
+    }
+
+    let length = text.chars().count();
+    if length > config.max_text_chars {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "text is {} characters long, the limit is {}",
+            length, config.max_text_chars
+        )));
+    }
+
+    Ok(())
+}
+
+// Validate every document of a batch, reporting the index of the first bad one
+pub fn validate_batch<'a>(texts: impl ExactSizeIterator<Item = &'a str>, config: &Config) -> Result<(), ApiError> {
+    if texts.len() == 0 {
+        return Err(ApiError::InvalidInput("documents must not be empty".to_string()));
+    }
+    if texts.len() > config.max_batch_documents {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "batch has {} documents, the limit is {}",
+            texts.len(),
+            config.max_batch_documents
+        )));
+    }
+
+    for (index, text) in texts.enumerate() {
+        validate_text(text, config).map_err(|e| match e {
+            ApiError::InvalidInput(message) => ApiError::InvalidInput(format!("document {}: {}", index, message)),
+            ApiError::PayloadTooLarge(message) => ApiError::PayloadTooLarge(format!("document {}: {}", index, message)),
+            other => other,
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            max_text_chars: 10,
+            max_batch_documents: 2,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_rejects_blank_text() {
+        assert_eq!(validate_text(" \n\t", &config()).unwrap_err().error_code(), "invalid_input");
+        assert_eq!(validate_text("", &config()).unwrap_err().error_code(), "invalid_input");
+    }
+
+    #[test]
+    fn test_rejects_long_text_by_characters() {
+        assert!(validate_text("ééééééééé", &config()).is_ok());
+        assert_eq!(validate_text("12345678901", &config()).unwrap_err().error_code(), "payload_too_large");
+    }
+
+    #[test]
+    fn test_batch_limits() {
+        assert!(validate_batch(["a", "b"].into_iter(), &config()).is_ok());
+        assert_eq!(validate_batch(["a", "b", "c"].into_iter(), &config()).unwrap_err().error_code(), "payload_too_large");
+
+        let error = validate_batch(["a", " "].into_iter(), &config()).unwrap_err();
+        assert!(error.to_string().contains("document 1"));
+    }
+}
@@ -0,0 +1,71 @@
+// API versions. Versioned routes are grouped per version and mounted under
+// `/v{N}`; the pre-versioning `/api/...` paths serve the v1 group as deprecated
+// aliases. A breaking change to a response shape ships as a new group mounted
+// under `/v2`, leaving `/v1` as it is.
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::{Request, Response};
+
+// Version every `ApiResponse` built by the v1 handlers reports
+pub const API_VERSION: u32 = 1;
+
+// Mount point of the v1 route group
+pub const V1_BASE: &str = "/v1";
+
+// Mount point of the deprecated unversioned aliases of the v1 routes
+pub const LEGACY_BASE: &str = "/api";
+
+// Adds `API-Version` to versioned responses and marks responses served from a
+// legacy path with `Deprecation` and a `Link` to the `/v1` successor
+pub struct VersionHeaders;
+
+#[rocket::async_trait]
+impl Fairing for VersionHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "API version headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let path = request.uri().path();
+        if let Some(rest) = path.as_str().strip_prefix(LEGACY_BASE).filter(|rest| rest.is_empty() || rest.starts_with('/')) {
+            response.set_header(Header::new("API-Version", API_VERSION.to_string()));
+            response.set_header(Header::new("Deprecation", "true"));
+            response.set_header(Header::new("Link", successor_link(rest)));
+        } else if let Some(version) = path_version(path.as_str()) {
+            response.set_header(Header::new("API-Version", version.to_string()));
+        }
+    }
+}
+
+// `Link` header value pointing a legacy path's remainder at its v1 route
+pub fn successor_link(rest: &str) -> String {
+    format!("<{}{}>; rel=\"successor-version\"", V1_BASE, rest)
+}
+
+// Version named by a path's first segment, e.g. 1 for `/v1/pii/detect`
+pub fn path_version(path: &str) -> Option<u32> {
+    let segment = path.trim_start_matches('/').split('/').next()?;
+    segment.strip_prefix('v')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_version() {
+        assert_eq!(path_version("/v1/pii/detect"), Some(1));
+        assert_eq!(path_version("/v2"), Some(2));
+        assert_eq!(path_version("/api/pii/detect"), None);
+        assert_eq!(path_version("/version"), None);
+    }
+
+    #[test]
+    fn test_successor_link() {
+        assert_eq!(successor_link("/pii/detect"), "</v1/pii/detect>; rel=\"successor-version\"");
+    }
+}
@@ -6,7 +6,7 @@ use serde_json::Value;
 use gliner_rs_api::rocket;
 
 fn create_test_client() -> Client {
-    Client::tracked(rocket()).expect("valid rocket instance")
+    Client::tracked(rocket::execute(rocket())).expect("valid rocket instance")
 }
 
 #[test]
@@ -0,0 +1,181 @@
+// Detection end to end on the scripted mock model (`mock-model` feature)
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+use serde_json::Value;
+
+use gliner_rs_api::rocket;
+
+// Every test sets the same values, so tests running in parallel agree
+fn create_test_client() -> Client {
+    std::env::set_var("GLINER_MODEL", "mock");
+    std::env::set_var("GLINER_MAX_CHUNK_CHARS", "120");
+    std::env::set_var("GLINER_CHUNK_OVERLAP", "20");
+    std::env::set_var("GLINER_INFERENCE_BATCH_SIZE", "2");
+    Client::tracked(rocket::execute(rocket())).expect("valid rocket instance")
+}
+
+fn post(client: &Client, uri: &str, body: Value) -> Value {
+    let response = client.post(uri).header(ContentType::JSON).body(body.to_string()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    response.into_json().expect("valid JSON")
+}
+
+fn spans(entities: &Value, label: &str) -> Vec<(u64, u64)> {
+    entities
+        .as_array()
+        .expect("entity list")
+        .iter()
+        .filter(|entity| entity["label"] == label)
+        .map(|entity| (entity["start"].as_u64().unwrap(), entity["end"].as_u64().unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_model_is_ready() {
+    let client = create_test_client();
+    assert_eq!(client.get("/health/ready").dispatch().status(), Status::Ok);
+}
+
+#[test]
+fn test_detect_reports_scripted_entities() {
+    let client = create_test_client();
+    let text = "Please call Jane Roe at 555-0100 or mail jane@example.com";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text }));
+
+    let entities = &body["data"]["entities"];
+    assert_eq!(spans(entities, "person"), vec![(12, 20)]);
+    assert_eq!(spans(entities, "email"), vec![(41, 57)]);
+    assert_eq!(entities[0]["text"], "Jane Roe");
+}
+
+#[test]
+fn test_zero_width_characters_do_not_hide_findings() {
+    let client = create_test_client();
+    let text = "Mail ja\u{200b}ne@example.com today";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text }));
+
+    let entities = &body["data"]["entities"];
+    assert_eq!(spans(entities, "email"), vec![(5, 24)]);
+    assert_eq!(entities[0]["text"], "jane@example.com");
+}
+
+#[test]
+fn test_offsets_in_every_unit() {
+    let client = create_test_client();
+    let text = "👋🏽 Café owner Jane Roe";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text, "offset_units": true }));
+
+    let offsets = &body["data"]["entities"][0]["offsets"];
+    assert_eq!(offsets["utf8"], serde_json::json!({"start": 21, "end": 29}));
+    assert_eq!(offsets["utf16"], serde_json::json!({"start": 16, "end": 24}));
+    assert_eq!(offsets["chars"], serde_json::json!({"start": 14, "end": 22}));
+}
+
+#[test]
+fn test_discovery_suggests_types() {
+    let client = create_test_client();
+    let texts = ["Jane Roe joined Acme Logistics", "Acme Logistics hired John Smith"];
+    let body = post(&client, "/api/pii/discover", serde_json::json!({ "texts": texts }));
+
+    let types = body["data"]["types"].as_array().expect("type list");
+    let labels: Vec<&str> = types.iter().map(|found| found["label"].as_str().unwrap()).collect();
+    assert_eq!(labels, vec!["organization", "person"]);
+    assert_eq!(types[0]["documents"], 2);
+    assert_eq!(types[0]["examples"], serde_json::json!(["Acme Logistics"]));
+    assert_eq!(spans(&body["data"]["documents"][0], "person"), vec![(0, 8)]);
+}
+
+#[test]
+fn test_context_snippets() {
+    let client = create_test_client();
+    let text = "Our records show Jane Roe moved last spring";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text, "context_chars": 8 }));
+
+    let context = &body["data"]["entities"][0]["context"];
+    assert_eq!(context["text"], "ds show Jane Roe moved l");
+    assert_eq!((context["start"].as_u64(), context["end"].as_u64()), (Some(8), Some(16)));
+}
+
+#[test]
+fn test_sentence_mode_groups_findings() {
+    let client = create_test_client();
+    let text = "Dr. Smith called. Jane Roe answered!\nShe gave 555-0100 and jane@example.com.";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text, "sentences": true }));
+
+    let sentences = body["data"]["sentences"].as_array().expect("sentences");
+    assert_eq!(sentences.len(), 3);
+    assert_eq!(sentences[0]["total_entities"], 0);
+    assert_eq!(sentences[1]["start"], 18);
+    assert_eq!(spans(&sentences[1]["entities"], "person"), vec![(18, 26)]);
+    assert_eq!(sentences[2]["total_entities"], 2);
+    assert_eq!(body["data"]["total_entities"], 3);
+}
+
+#[test]
+fn test_repeated_mentions_are_grouped() {
+    let client = create_test_client();
+    let text = "Jane Roe wrote to jane@example.com. Jane Roe signed.";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text, "groups": true }));
+
+    let groups = body["data"]["groups"].as_array().expect("groups");
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0]["label"], "person");
+    assert_eq!(groups[0]["mentions"], serde_json::json!([{"start": 0, "end": 8}, {"start": 36, "end": 44}]));
+    assert_eq!(groups[1]["mentions"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_profile_redacts_findings() {
+    let client = create_test_client();
+    let text = "Jane Roe wrote from jane@example.com";
+    let body = post(&client, "/api/pii/detect", serde_json::json!({ "text": text, "profile": "gdpr" }));
+
+    let redacted = body["data"]["redacted_text"].as_str().expect("redacted text");
+    assert!(!redacted.contains("Jane Roe"), "{}", redacted);
+    assert!(!redacted.contains("jane@example.com"), "{}", redacted);
+}
+
+#[test]
+fn test_response_filters() {
+    let client = create_test_client();
+    let text = "Jane Roe (Acme Logistics) at jane@example.com or 555-0100";
+    let body = post(&client, "/api/pii/detect?labels=email,organization&min_confidence=0.7", serde_json::json!({ "text": text }));
+    let entities = &body["data"]["entities"];
+    assert_eq!(entities.as_array().unwrap().len(), 1);
+    assert_eq!(entities[0]["label"], "email");
+
+    // Counts carry no text, and redaction still masks what the filter leaves out
+    let body = post(&client, "/api/pii/detect?counts_only=true&labels=person,phone", serde_json::json!({ "text": text }));
+    assert_eq!(body["data"]["counts"], serde_json::json!({"person": 1, "phone": 1}));
+    assert_eq!(body["data"]["total_entities"], 2);
+    assert!(body["data"].get("entities").is_none());
+    assert!(body["data"].get("text").is_none());
+
+    let body = post(&client, "/api/pii/detect?labels=phone", serde_json::json!({ "text": text, "profile": "gdpr" }));
+    assert!(!body["data"]["redacted_text"].as_str().unwrap().contains("Jane Roe"));
+}
+
+#[test]
+fn test_batch_chunks_long_documents() {
+    let client = create_test_client();
+    let filler = "The quarterly report is attached for review. ".repeat(6);
+    let long = format!("{}Jane Roe signed it. {}Ask John Smith.", filler, filler);
+    let body = post(&client, "/api/pii/detect/batch", serde_json::json!({
+        "documents": [{"text": "Nothing to see here"}, {"text": long}, {"text": "Acme Logistics called"}]
+    }));
+
+    let data = &body["data"];
+    assert_eq!(data["total_documents"], 3);
+    assert!(data["total_chunks"].as_u64().unwrap() > 3);
+    assert!(data["inference_batches"].as_u64().unwrap() > 1);
+
+    let results = data["results"].as_array().unwrap();
+    assert_eq!(results[0]["total_entities"], 0);
+    // Offsets are into the whole document, not the chunk, and overlap duplicates are gone
+    let start = filler.len() as u64;
+    let people = spans(&results[1]["entities"], "person");
+    assert_eq!(people.len(), 2);
+    assert_eq!(people[0], (start, start + 8));
+    assert_eq!(&long[people[1].0 as usize..people[1].1 as usize], "John Smith");
+    assert_eq!(spans(&results[2]["entities"], "organization"), vec![(0, 14)]);
+}